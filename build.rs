@@ -0,0 +1,12 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // The gRPC interface is optional, so protoc is only needed with the `grpc` feature
+    #[cfg(feature = "grpc")]
+    {
+        let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR")?);
+        tonic_build::configure()
+            .file_descriptor_set_path(out_dir.join("wasmic_descriptor.bin"))
+            .compile_protos(&["proto/wasmic.proto"], &["proto"])?;
+    }
+    println!("cargo:rerun-if-changed=proto/wasmic.proto");
+    Ok(())
+}
@@ -0,0 +1,199 @@
+//! Tool latency measurement
+//!
+//! Invokes a single tool repeatedly and summarizes latency percentiles, throughput and the
+//! fuel and memory each call costs, to compare component builds and engine settings.
+
+use crate::error::{Result, WasiMcpError};
+use crate::executor::WasmExecutor;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Summary of a benchmark run
+#[derive(Debug, Clone)]
+pub struct BenchReport {
+    pub tool: String,
+    /// Latency of every measured call, sorted ascending
+    pub latencies: Vec<Duration>,
+    /// Wall-clock time of all measured calls together
+    pub total: Duration,
+    /// Fuel consumed by each measured call, when the context meters fuel
+    pub fuel: Vec<u64>,
+    /// Linear memory held by the component after the run
+    pub memory_bytes: usize,
+    /// Linear memory the component grew by during the measured calls
+    pub memory_growth: usize,
+}
+
+impl BenchReport {
+    /// Latency at percentile `p` (0-100), using the nearest-rank method
+    pub fn percentile(&self, p: f64) -> Duration {
+        nearest_rank(&self.latencies, p)
+    }
+
+    /// Measured calls per second
+    pub fn throughput(&self) -> f64 {
+        if self.total.is_zero() {
+            return 0.0;
+        }
+        self.latencies.len() as f64 / self.total.as_secs_f64()
+    }
+
+    /// Average fuel consumed per call, when fuel was metered
+    pub fn fuel_per_call(&self) -> Option<u64> {
+        if self.fuel.is_empty() {
+            return None;
+        }
+        Some(self.fuel.iter().sum::<u64>() / self.fuel.len() as u64)
+    }
+
+    pub fn to_json(&self) -> Value {
+        let micros = |d: Duration| d.as_micros() as u64;
+        serde_json::json!({
+            "tool": self.tool,
+            "iterations": self.latencies.len(),
+            "latency_us": {
+                "min": self.latencies.first().copied().map(micros),
+                "p50": micros(self.percentile(50.0)),
+                "p95": micros(self.percentile(95.0)),
+                "p99": micros(self.percentile(99.0)),
+                "max": self.latencies.last().copied().map(micros),
+            },
+            "throughput_per_sec": self.throughput(),
+            "fuel_per_call": self.fuel_per_call(),
+            "memory_bytes": self.memory_bytes,
+            "memory_growth_bytes": self.memory_growth,
+        })
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} ({} calls)", self.tool, self.latencies.len())?;
+        writeln!(
+            f,
+            "  latency     p50 {:?}  p95 {:?}  p99 {:?}  max {:?}",
+            self.percentile(50.0),
+            self.percentile(95.0),
+            self.percentile(99.0),
+            self.latencies.last().copied().unwrap_or_default(),
+        )?;
+        writeln!(f, "  throughput  {:.1} calls/s", self.throughput())?;
+        if let Some(fuel) = self.fuel_per_call() {
+            writeln!(f, "  fuel        {fuel} per call")?;
+        }
+        write!(
+            f,
+            "  memory      {} bytes (+{} during run)",
+            self.memory_bytes, self.memory_growth
+        )
+    }
+}
+
+fn nearest_rank(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Call `tool` `warmup` times unmeasured, then `iterations` times measured
+///
+/// Any failing call aborts the run, since timings of error paths are rarely what is wanted.
+pub async fn run(
+    executor: &mut WasmExecutor,
+    tool: &str,
+    arguments: HashMap<String, Value>,
+    iterations: usize,
+    warmup: usize,
+) -> Result<BenchReport> {
+    let (component_name, _) = tool.split_once('.').ok_or_else(|| {
+        WasiMcpError::InvalidArguments(format!(
+            "Tool name must be in format 'component.function', got: {tool}"
+        ))
+    })?;
+    let usage = |executor: &WasmExecutor| {
+        executor
+            .component_usage(component_name)
+            .ok_or_else(|| WasiMcpError::ComponentNotFound(component_name.to_string()))
+    };
+
+    for _ in 0..warmup {
+        executor.execute_function(tool, arguments.clone()).await?;
+    }
+
+    let initial = usage(executor)?;
+    let mut latencies = Vec::with_capacity(iterations);
+    let mut fuel = Vec::with_capacity(iterations);
+    let run_start = Instant::now();
+    for _ in 0..iterations {
+        let before = usage(executor)?;
+        let start = Instant::now();
+        executor.execute_function(tool, arguments.clone()).await?;
+        latencies.push(start.elapsed());
+        if let (Some(before), Some(after)) = (before.fuel_consumed, usage(executor)?.fuel_consumed)
+        {
+            fuel.push(after - before);
+        }
+    }
+    let total = run_start.elapsed();
+    let last = usage(executor)?;
+
+    latencies.sort_unstable();
+    Ok(BenchReport {
+        tool: tool.to_string(),
+        latencies,
+        total,
+        fuel,
+        memory_bytes: last.memory_bytes,
+        memory_growth: last.memory_bytes.saturating_sub(initial.memory_bytes),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(latencies_ms: &[u64]) -> BenchReport {
+        BenchReport {
+            tool: "test.run".to_string(),
+            latencies: latencies_ms
+                .iter()
+                .copied()
+                .map(Duration::from_millis)
+                .collect(),
+            total: Duration::from_millis(latencies_ms.iter().sum()),
+            fuel: vec![100, 200],
+            memory_bytes: 65536,
+            memory_growth: 0,
+        }
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let hundred = report(&(1..=100).collect::<Vec<_>>());
+        assert_eq!(hundred.percentile(50.0), Duration::from_millis(50));
+        assert_eq!(hundred.percentile(95.0), Duration::from_millis(95));
+        assert_eq!(hundred.percentile(99.0), Duration::from_millis(99));
+        assert_eq!(hundred.percentile(100.0), Duration::from_millis(100));
+        assert_eq!(hundred.percentile(0.0), Duration::from_millis(1));
+
+        let single = report(&[7]);
+        assert_eq!(single.percentile(99.0), Duration::from_millis(7));
+        assert_eq!(nearest_rank(&[], 50.0), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_throughput_and_fuel() {
+        let report = report(&[250, 250, 250, 250]);
+        assert!((report.throughput() - 4.0).abs() < f64::EPSILON);
+        assert_eq!(report.fuel_per_call(), Some(150));
+
+        let json = report.to_json();
+        assert_eq!(json["iterations"], 4);
+        assert_eq!(json["latency_us"]["p50"], 250_000);
+        assert_eq!(json["fuel_per_call"], 150);
+    }
+}
@@ -0,0 +1,463 @@
+use crate::config::CachePolicy;
+use crate::error::Result;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::instrument;
+use wasmtime::Engine;
+use wasmtime::component::Component;
+
+/// Get the XDG cache directory for wasmic
+pub fn cache_dir() -> Result<PathBuf> {
+    let cache_dir = dirs::cache_dir()
+        .ok_or_else(|| {
+            crate::WasiMcpError::InvalidArguments("Could not determine cache directory".to_string())
+        })?
+        .join("wasmic");
+
+    Ok(cache_dir)
+}
+
+/// File in the cache directory recording when each cached artifact was last used
+const INDEX_FILE: &str = "index.json";
+
+/// Serializes read-modify-write cycles of the index within this process
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+/// A cached artifact (pulled `.wasm` or compiled `.cwasm`)
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    /// Path relative to the cache directory
+    pub name: String,
+    pub size: u64,
+    pub last_access: SystemTime,
+}
+
+/// Last-access index over everything in the cache directory, used for eviction
+pub struct CacheIndex {
+    cache_dir: PathBuf,
+}
+
+impl CacheIndex {
+    /// Open the index of the XDG cache directory
+    pub fn new() -> Result<Self> {
+        Ok(Self::with_dir(cache_dir()?))
+    }
+
+    /// Open the index of a specific cache directory
+    pub fn with_dir(cache_dir: PathBuf) -> Self {
+        Self { cache_dir }
+    }
+
+    /// Record that a cached artifact was just used
+    ///
+    /// Failures are logged and otherwise ignored: the index only guides eviction.
+    pub fn touch(&self, path: &Path) {
+        let Ok(name) = path.strip_prefix(&self.cache_dir) else {
+            return;
+        };
+        let name = name.to_string_lossy().to_string();
+
+        let _guard = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut index = self.read_index();
+        index.insert(name, unix_secs(SystemTime::now()));
+        if let Err(e) = self.write_index(&index) {
+            tracing::debug!("Failed to update cache index: {}", e);
+        }
+    }
+
+    /// All cached artifacts, least recently used first
+    pub fn entries(&self) -> Result<Vec<CacheEntry>> {
+        let index = self.read_index();
+        let mut entries = Vec::new();
+
+        for dir in [self.cache_dir.clone(), self.cache_dir.join("compiled")] {
+            let Ok(read_dir) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for dir_entry in read_dir {
+                let path = dir_entry?.path();
+                let is_artifact = matches!(
+                    path.extension().and_then(|e| e.to_str()),
+                    Some("wasm" | "cwasm")
+                );
+                if !is_artifact {
+                    continue;
+                }
+
+                let metadata = fs::metadata(&path)?;
+                let name = path
+                    .strip_prefix(&self.cache_dir)
+                    .unwrap_or(&path)
+                    .to_string_lossy()
+                    .to_string();
+                // Artifacts cached before the index existed fall back to their mtime
+                let last_access = match index.get(&name) {
+                    Some(secs) => UNIX_EPOCH + Duration::from_secs(*secs),
+                    None => metadata.modified()?,
+                };
+                entries.push(CacheEntry {
+                    name,
+                    size: metadata.len(),
+                    last_access,
+                });
+            }
+        }
+
+        entries.sort_by_key(|entry| entry.last_access);
+        Ok(entries)
+    }
+
+    /// Evict artifacts older than `max_age_days`, then the least recently used ones until the
+    /// cache fits in `max_size`. Returns the evicted entries.
+    pub fn prune(&self, policy: &CachePolicy) -> Result<Vec<CacheEntry>> {
+        let max_size = policy.max_size.as_deref().map(parse_size).transpose()?;
+        let max_age = policy
+            .max_age_days
+            .map(|days| Duration::from_secs(days * 24 * 60 * 60));
+
+        let _guard = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let entries = self.entries()?;
+        let mut total: u64 = entries.iter().map(|entry| entry.size).sum();
+        let now = SystemTime::now();
+
+        let mut evicted = Vec::new();
+        for entry in entries {
+            let expired = max_age.is_some_and(|max_age| {
+                now.duration_since(entry.last_access)
+                    .is_ok_and(|age| age > max_age)
+            });
+            let over_size = max_size.is_some_and(|max_size| total > max_size);
+            if !expired && !over_size {
+                continue;
+            }
+
+            self.remove_artifact(&entry.name)?;
+            total -= entry.size;
+            evicted.push(entry);
+        }
+
+        let mut index = self.read_index();
+        for entry in &evicted {
+            index.remove(&entry.name);
+        }
+        self.write_index(&index)?;
+
+        Ok(evicted)
+    }
+
+    /// Remove every cached artifact and the index
+    pub fn clear(&self) -> Result<Vec<CacheEntry>> {
+        let _guard = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let entries = self.entries()?;
+        for entry in &entries {
+            self.remove_artifact(&entry.name)?;
+        }
+        if let Err(e) = fs::remove_file(self.cache_dir.join(INDEX_FILE))
+            && e.kind() != std::io::ErrorKind::NotFound
+        {
+            return Err(e.into());
+        }
+        Ok(entries)
+    }
+
+    /// Remove an artifact together with the digest, annotations and verification recorded for
+    /// pulled artifacts
+    fn remove_artifact(&self, name: &str) -> Result<()> {
+        let path = self.cache_dir.join(name);
+        fs::remove_file(&path)?;
+        let _ = fs::remove_file(path.with_extension("digest"));
+        let _ = fs::remove_file(path.with_extension("annotations"));
+        let _ = fs::remove_file(path.with_extension("verified"));
+        Ok(())
+    }
+
+    fn read_index(&self) -> HashMap<String, u64> {
+        fs::read(self.cache_dir.join(INDEX_FILE))
+            .ok()
+            .and_then(|content| serde_json::from_slice(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn write_index(&self, index: &HashMap<String, u64>) -> Result<()> {
+        fs::create_dir_all(&self.cache_dir)?;
+        let index_path = self.cache_dir.join(INDEX_FILE);
+        let tmp_path = index_path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_vec(index)?)?;
+        fs::rename(&tmp_path, &index_path)?;
+        Ok(())
+    }
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// Parse a size such as `500M`, `2G` or `1048576` into bytes
+pub fn parse_size(size: &str) -> Result<u64> {
+    let size = size.trim();
+    let (number, multiplier) = match size.char_indices().last() {
+        Some((i, unit)) if unit.is_ascii_alphabetic() => {
+            let multiplier: u64 = match unit.to_ascii_uppercase() {
+                'K' => 1 << 10,
+                'M' => 1 << 20,
+                'G' => 1 << 30,
+                'T' => 1 << 40,
+                _ => {
+                    return Err(crate::WasiMcpError::InvalidArguments(format!(
+                        "Invalid size '{size}': unknown unit '{unit}'"
+                    )));
+                }
+            };
+            (&size[..i], multiplier)
+        }
+        _ => (size, 1),
+    };
+
+    let number: u64 = number
+        .trim()
+        .parse()
+        .map_err(|_| crate::WasiMcpError::InvalidArguments(format!("Invalid size '{size}'")))?;
+    Ok(number * multiplier)
+}
+
+/// Persistent cache of compiled components (`.cwasm`)
+///
+/// Artifacts are keyed by the SHA-256 of the component file and the engine's compatibility
+/// hash, which covers the wasmtime version and the compilation settings.
+pub struct ComponentCache {
+    cache_dir: PathBuf,
+    index: CacheIndex,
+}
+
+impl ComponentCache {
+    /// Create a compiled-component cache in the XDG cache directory
+    pub fn new() -> Result<Self> {
+        Self::with_dir(cache_dir()?.join("compiled"))
+    }
+
+    /// Create a compiled-component cache in a specific directory
+    pub fn with_dir(cache_dir: PathBuf) -> Result<Self> {
+        fs::create_dir_all(&cache_dir)?;
+        let index_dir = cache_dir.parent().unwrap_or(&cache_dir).to_path_buf();
+        Ok(Self {
+            cache_dir,
+            index: CacheIndex::with_dir(index_dir),
+        })
+    }
+
+    /// Load a component, reusing a previously compiled artifact when one is available
+    pub fn load(&self, engine: &Engine, path: &Path) -> Result<Component> {
+        self.load_verified(engine, path, None)
+    }
+
+    /// Load a component after checking the file against an expected SHA-256 (hex, optionally
+    /// prefixed with `sha256:`)
+    #[instrument(level = "debug", skip(self, engine), fields(cached, duration_ms))]
+    pub fn load_verified(
+        &self,
+        engine: &Engine,
+        path: &Path,
+        sha256: Option<&str>,
+    ) -> Result<Component> {
+        let start_time = std::time::Instant::now();
+        let bytes = fs::read(path)?;
+        let file_hash = format!("{:x}", Sha256::digest(&bytes));
+
+        if let Some(expected) = sha256 {
+            let expected = expected.strip_prefix("sha256:").unwrap_or(expected);
+            if !expected.eq_ignore_ascii_case(&file_hash) {
+                return Err(crate::WasiMcpError::InvalidArguments(format!(
+                    "Checksum mismatch for {path:?}: expected sha256:{expected}, got sha256:{file_hash}"
+                )));
+            }
+        }
+
+        let cached_path = self.cached_path(engine, &file_hash);
+
+        if cached_path.exists() {
+            // SAFETY: files in the cache directory are only written by `store` from
+            // `Component::serialize`, and the key includes the engine compatibility hash.
+            match unsafe { Component::deserialize_file(engine, &cached_path) } {
+                Ok(component) => {
+                    self.index.touch(&cached_path);
+                    tracing::Span::current().record("cached", true);
+                    tracing::Span::current()
+                        .record("duration_ms", start_time.elapsed().as_millis());
+                    return Ok(component);
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "Ignoring unusable compiled component {:?}: {}",
+                        cached_path,
+                        e
+                    );
+                }
+            }
+        }
+
+        let bytes = if crate::componentize::is_core_module(&bytes) {
+            tracing::info!("Turning core module {:?} into a component", path);
+            crate::componentize::componentize(&bytes)?
+        } else {
+            bytes
+        };
+        let component = Component::new(engine, &bytes)?;
+        match self.store(&cached_path, &component) {
+            Ok(()) => self.index.touch(&cached_path),
+            Err(e) => tracing::warn!(
+                "Failed to cache compiled component {:?}: {}",
+                cached_path,
+                e
+            ),
+        }
+
+        tracing::Span::current().record("cached", false);
+        tracing::Span::current().record("duration_ms", start_time.elapsed().as_millis());
+        Ok(component)
+    }
+
+    /// Path of the compiled artifact for the given component bytes
+    fn cached_path(&self, engine: &Engine, file_hash: &str) -> PathBuf {
+        let mut hasher = StableHasher::default();
+        engine.precompile_compatibility_hash().hash(&mut hasher);
+
+        self.cache_dir
+            .join(format!("{file_hash}-{:016x}.cwasm", hasher.finish()))
+    }
+
+    /// Serialize a compiled component, writing to a temporary file first so readers never
+    /// observe a partially written artifact
+    fn store(&self, cached_path: &Path, component: &Component) -> Result<()> {
+        let serialized = component.serialize()?;
+        let tmp_path = cached_path.with_extension("cwasm.tmp");
+        fs::write(&tmp_path, serialized)?;
+        fs::rename(&tmp_path, cached_path)?;
+
+        tracing::debug!("Cached compiled component: {:?}", cached_path);
+        Ok(())
+    }
+}
+
+/// Hasher over SHA-256, so cache keys stay the same across Rust releases unlike with
+/// `DefaultHasher`
+#[derive(Default)]
+struct StableHasher(Sha256);
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        let digest = self.0.clone().finalize();
+        u64::from_be_bytes(
+            digest[..8]
+                .try_into()
+                .expect("SHA-256 digests have 32 bytes"),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_compiled_component_is_reused() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let component_path = temp_dir.path().join("empty.wat");
+        std::fs::write(&component_path, "(component)").unwrap();
+
+        let engine = Engine::default();
+        let cache = ComponentCache::with_dir(temp_dir.path().join("compiled")).unwrap();
+        cache.load(&engine, &component_path).unwrap();
+
+        let artifacts: Vec<_> = fs::read_dir(temp_dir.path().join("compiled"))
+            .unwrap()
+            .collect();
+        assert_eq!(artifacts.len(), 1);
+
+        // The second load deserializes the artifact written by the first
+        cache.load(&engine, &component_path).unwrap();
+    }
+
+    #[test]
+    fn test_checksum_mismatch_is_rejected() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let component_path = temp_dir.path().join("empty.wat");
+        std::fs::write(&component_path, "(component)").unwrap();
+
+        let engine = Engine::default();
+        let cache = ComponentCache::with_dir(temp_dir.path().join("compiled")).unwrap();
+        let checksum = format!("sha256:{:x}", Sha256::digest(b"(component)"));
+        cache
+            .load_verified(&engine, &component_path, Some(&checksum))
+            .unwrap();
+
+        let wrong = format!("{:x}", Sha256::digest(b"(component) "));
+        assert!(
+            cache
+                .load_verified(&engine, &component_path, Some(&wrong))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1024").unwrap(), 1024);
+        assert_eq!(parse_size("500M").unwrap(), 500 << 20);
+        assert_eq!(parse_size("2g").unwrap(), 2 << 30);
+        assert!(parse_size("10X").is_err());
+        assert!(parse_size("G").is_err());
+    }
+
+    #[test]
+    fn test_prune_evicts_least_recently_used() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let index = CacheIndex::with_dir(temp_dir.path().to_path_buf());
+
+        for name in ["old.wasm", "new.wasm"] {
+            let path = temp_dir.path().join(name);
+            fs::write(&path, [0u8; 100]).unwrap();
+        }
+        fs::write(temp_dir.path().join("notes.txt"), "not an artifact").unwrap();
+        index
+            .write_index(&HashMap::from([
+                ("old.wasm".to_string(), 1),
+                ("new.wasm".to_string(), unix_secs(SystemTime::now())),
+            ]))
+            .unwrap();
+
+        let entries = index.entries().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].name, "old.wasm");
+
+        let policy = CachePolicy {
+            max_size: Some("150".to_string()),
+            max_age_days: None,
+        };
+        let evicted = index.prune(&policy).unwrap();
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].name, "old.wasm");
+        assert!(temp_dir.path().join("new.wasm").exists());
+
+        index.clear().unwrap();
+        assert!(index.entries().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_stable_hasher() {
+        let mut hasher = StableHasher::default();
+        hasher.write(b"abc");
+        // First eight bytes of SHA-256("abc")
+        assert_eq!(hasher.finish(), 0xba78_16bf_8f01_cfea);
+    }
+}
@@ -0,0 +1,174 @@
+//! Per-call environment and working-directory overrides
+//!
+//! A call may carry a reserved `_ctx` argument, or the same object under the `wasmic/ctx`
+//! key of the MCP request's `_meta`, to set environment variables and select the working
+//! directory for that call alone:
+//!
+//! ```json
+//! {"_ctx": {"env": {"PROJECT": "billing"}, "cwd": "/srv/workspaces/billing"}}
+//! ```
+//!
+//! Only what the component's `call_context` settings permit is accepted. The WASI context of
+//! a store is fixed once built, so such calls run in a fresh instance of the component.
+
+use crate::config::{CallContextConfig, ComponentConfig};
+use crate::error::{Result, WasiMcpError};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Reserved argument carrying the overrides
+pub const CTX_ARGUMENT: &str = "_ctx";
+
+/// Key of the overrides in the `_meta` of an MCP tool call
+pub const CTX_META_KEY: &str = "wasmic/ctx";
+
+/// Overrides requested by one call
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CallContext {
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub cwd: Option<String>,
+}
+
+impl CallContext {
+    /// Remove the `_ctx` argument from `arguments`, parsing it
+    pub fn take(arguments: &mut HashMap<String, Value>) -> Result<Option<Self>> {
+        let Some(value) = arguments.remove(CTX_ARGUMENT) else {
+            return Ok(None);
+        };
+        serde_json::from_value(value)
+            .map(Some)
+            .map_err(|e| WasiMcpError::InvalidArguments(format!("Invalid {CTX_ARGUMENT}: {e}")))
+    }
+
+    /// `config` with the overrides applied, failing when `config` doesn't permit them
+    pub fn apply(&self, component: &str, config: &ComponentConfig) -> Result<ComponentConfig> {
+        let permitted = config.call_context.clone().unwrap_or_default();
+        let mut config = config.clone();
+
+        for (key, value) in &self.env {
+            if !allows_env(&permitted, key) {
+                return Err(WasiMcpError::InvalidArguments(format!(
+                    "Component '{component}' doesn't allow calls to set environment variable '{key}'"
+                )));
+            }
+            config.env.insert(key.clone(), value.clone());
+        }
+
+        if let Some(cwd) = &self.cwd {
+            if !allows_cwd(&permitted, Path::new(cwd)) {
+                return Err(WasiMcpError::InvalidArguments(format!(
+                    "Component '{component}' doesn't allow calls to use '{cwd}' as working directory"
+                )));
+            }
+            config.cwd = Some(cwd.clone());
+        }
+        Ok(config)
+    }
+}
+
+/// Schema of the `_ctx` argument, advertised by components that permit overrides
+pub fn ctx_schema(permitted: &CallContextConfig) -> Value {
+    let mut properties = serde_json::Map::new();
+    if !permitted.env.is_empty() {
+        properties.insert(
+            "env".to_string(),
+            serde_json::json!({
+                "type": "object",
+                "additionalProperties": {"type": "string"},
+                "description": format!("Environment variables for this call: {}", permitted.env.join(", "))
+            }),
+        );
+    }
+    if !permitted.cwd.is_empty() {
+        properties.insert(
+            "cwd".to_string(),
+            serde_json::json!({
+                "type": "string",
+                "description": format!("Working directory for this call, within: {}", permitted.cwd.join(", "))
+            }),
+        );
+    }
+    serde_json::json!({
+        "type": "object",
+        "properties": properties,
+        "additionalProperties": false
+    })
+}
+
+fn allows_env(permitted: &CallContextConfig, key: &str) -> bool {
+    permitted.env.iter().any(|name| name == "*" || name == key)
+}
+
+/// Whether `cwd` is one of the permitted directories or inside one, after resolving `..`
+/// and symlinks
+fn allows_cwd(permitted: &CallContextConfig, cwd: &Path) -> bool {
+    let Ok(cwd) = cwd.canonicalize() else {
+        return false;
+    };
+    permitted.cwd.iter().any(|allowed| {
+        Path::new(allowed)
+            .canonicalize()
+            .is_ok_and(|allowed| cwd.starts_with(allowed))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_take() {
+        let mut arguments = HashMap::from([
+            ("query".to_string(), json!("rust")),
+            (CTX_ARGUMENT.to_string(), json!({"env": {"PROJECT": "a"}})),
+        ]);
+        let context = CallContext::take(&mut arguments).unwrap().unwrap();
+        assert_eq!(context.env["PROJECT"], "a");
+        assert!(!arguments.contains_key(CTX_ARGUMENT));
+        assert_eq!(CallContext::take(&mut arguments).unwrap(), None);
+
+        let mut arguments = HashMap::from([(CTX_ARGUMENT.to_string(), json!({"vars": {}}))]);
+        assert!(CallContext::take(&mut arguments).is_err());
+    }
+
+    #[test]
+    fn test_apply() {
+        let workspaces = tempfile::tempdir().unwrap();
+        let project = workspaces.path().join("billing");
+        std::fs::create_dir(&project).unwrap();
+        let config = ComponentConfig {
+            call_context: Some(CallContextConfig {
+                env: vec!["PROJECT".to_string()],
+                cwd: vec![workspaces.path().display().to_string()],
+            }),
+            ..ComponentConfig::default()
+        };
+
+        let context = CallContext {
+            env: HashMap::from([("PROJECT".to_string(), "billing".to_string())]),
+            cwd: Some(project.display().to_string()),
+        };
+        let applied = context.apply("app", &config).unwrap();
+        assert_eq!(applied.env["PROJECT"], "billing");
+        assert_eq!(applied.cwd, context.cwd);
+
+        let context = CallContext {
+            env: HashMap::from([("HOME".to_string(), "/".to_string())]),
+            cwd: None,
+        };
+        assert!(context.apply("app", &config).is_err());
+
+        let context = CallContext {
+            env: HashMap::new(),
+            cwd: Some(std::env::temp_dir().display().to_string()),
+        };
+        assert!(context.apply("app", &config).is_err());
+        assert!(CallContext::default().apply("app", &config).is_ok());
+    }
+}
@@ -1,6 +1,11 @@
 use std::path::PathBuf;
 
-use clap::{Parser, Subcommand, command};
+use clap::{Parser, Subcommand, ValueEnum, command};
+use serde_json::Value;
+
+use crate::WasiMcpError;
+use crate::error::Result;
+use crate::utils::transform::result_error;
 
 #[derive(Parser)]
 #[command(name = "wasi-mcp")]
@@ -11,20 +16,107 @@ pub struct Cli {
     #[arg(short, long, global = true)]
     pub config: Option<PathBuf>,
 
+    /// Log the component values converted from JSON arguments and the raw values returned
+    /// before they are converted to JSON
+    #[arg(long, global = true)]
+    pub debug_conversions: bool,
+
     #[command(subcommand)]
     pub command: Commands,
 }
 
 #[derive(Subcommand, Debug)]
 pub enum Commands {
+    /// Create a starter configuration file
+    Init {
+        /// Overwrite an existing configuration file
+        #[arg(long)]
+        force: bool,
+    },
     /// Run the WASM component as an MCP server
+    #[command(alias = "serve")]
     Mcp {
         /// Use HTTP transport with host:port (e.g., "127.0.0.1:8080" or ":8080")
         #[arg(long, default_value = "127.0.0.1:8080")]
         http: String,
+
+        /// Reload components when their local .wasm file changes
+        #[arg(long)]
+        watch: bool,
+
+        /// Re-resolve mutable OCI tags and download newer artifacts before starting
+        #[arg(long)]
+        refresh: bool,
+
+        /// Seconds running tool calls may take to finish after Ctrl-C or SIGTERM
+        #[arg(long, default_value_t = 30)]
+        grace_period: u64,
+
+        /// Also serve every tool at `POST /tools/{component}/{function}`
+        #[arg(long)]
+        rest: bool,
+
+        /// Also serve the gRPC interface on this address (requires the `grpc` feature)
+        #[arg(long, value_name = "ADDRESS")]
+        grpc: Option<std::net::SocketAddr>,
     },
     /// Directly call a WASM method
     Call {
+        /// Function name in format 'component.function'
+        #[arg(short, long, required_unless_present = "batch")]
+        function: Option<String>,
+
+        /// Arguments as JSON string
+        #[arg(short, long, default_value = "{}")]
+        args: String,
+
+        /// How to print the result
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Pretty)]
+        output: OutputFormat,
+
+        /// Read `{"tool": ..., "args": {...}}` lines from stdin and write one JSON result per line
+        #[arg(long, conflicts_with_all = ["function", "args", "output"])]
+        batch: bool,
+
+        /// Number of calls to run concurrently in batch mode, each with its own instances
+        #[arg(long, default_value_t = 1, requires = "batch")]
+        concurrency: usize,
+
+        /// Resolve the tool and convert the arguments to its WIT types without running it,
+        /// then print what would be called; `json` and `yaml` output print it as data
+        #[arg(long, conflicts_with = "batch")]
+        dry_run: bool,
+    },
+    /// Run a component once, e.g. to try it before serving it
+    ///
+    /// Runs the component's `wasi:cli/run` with the arguments after `--`, or calls one of its
+    /// exports with `--export`, and prints what it produced.
+    Run {
+        /// Configured component name, path to a .wasm file or OCI reference
+        component: String,
+
+        /// Call this export with `--args` instead of running `wasi:cli/run`
+        #[arg(short, long)]
+        export: Option<String>,
+
+        /// Arguments of the export as JSON string
+        #[arg(short, long, default_value = "{}", requires = "export")]
+        args: String,
+
+        /// File passed to the program on stdin; `-` passes wasmic's own stdin
+        #[arg(long, conflicts_with = "export")]
+        stdin: Option<PathBuf>,
+
+        /// How to print the result of an export
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Pretty, requires = "export")]
+        output: OutputFormat,
+
+        /// Command-line arguments of the program
+        #[arg(last = true, conflicts_with = "export")]
+        argv: Vec<String>,
+    },
+    /// Measure the latency of repeated calls to a function
+    Bench {
         /// Function name in format 'component.function'
         #[arg(short, long)]
         function: String,
@@ -32,7 +124,286 @@ pub enum Commands {
         /// Arguments as JSON string
         #[arg(short, long, default_value = "{}")]
         args: String,
+
+        /// Number of measured calls
+        #[arg(short = 'n', long, default_value_t = 100)]
+        iterations: usize,
+
+        /// Number of unmeasured calls made first
+        #[arg(long, default_value_t = 10)]
+        warmup: usize,
+
+        /// Meter the fuel each call consumes (slows execution down)
+        #[arg(long)]
+        fuel: bool,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print the tools as function-calling schemas for an LLM API
+    Export {
+        /// Schema format of the target API
+        #[arg(long, value_enum)]
+        format: ExportFormat,
+
+        /// Export only the tools of this profile
+        #[arg(long)]
+        profile: Option<String>,
+    },
+    /// Print an OpenAPI 3.1 document describing the REST gateway
+    Openapi {
+        /// Describe only the tools of this profile
+        #[arg(long)]
+        profile: Option<String>,
+    },
+    /// Show the calls recorded in the history database, newest first
+    History {
+        /// Only calls of this tool (`component.function`)
+        #[arg(long)]
+        tool: Option<String>,
+
+        /// Only failed calls
+        #[arg(long)]
+        failed: bool,
+
+        /// Number of calls shown
+        #[arg(short = 'n', long, default_value_t = 50)]
+        limit: usize,
+
+        /// Print the calls as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Re-execute recorded calls with the clocks, random numbers and HTTP responses they saw
+    ///
+    /// Reports every call whose outcome differs from the recording and exits with a non-zero
+    /// code if any does.
+    Replay {
+        /// Recording files written by a server with `recording` configured, or directories
+        /// of them
+        #[arg(required = true)]
+        recordings: Vec<PathBuf>,
+
+        /// Replay against this build of the recorded component instead of the configured
+        /// one: a .wasm file or an OCI reference
+        #[arg(long)]
+        candidate: Option<String>,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Run the test cases of the configuration and report which pass
+    ///
+    /// Exits with a non-zero code when a test fails, for use in CI.
+    Test {
+        /// Also run the test cases of this YAML file
+        #[arg(long)]
+        file: Option<PathBuf>,
+
+        /// Only run tests whose name or tool contains this text
+        #[arg(long)]
+        filter: Option<String>,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show call statistics of a running MCP server
+    Stats {
+        /// Address of the server's HTTP listener
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        server: String,
+
+        /// Bearer token of a client configured in the server's policy
+        #[arg(long, env = "WASMIC_TOKEN")]
+        token: Option<String>,
+
+        /// Print the statistics as JSON
+        #[arg(long)]
+        json: bool,
     },
     /// List available functions in a WASM component
+    List {
+        /// Print the tools as JSON, exactly as MCP clients see them
+        #[arg(long)]
+        json: bool,
+
+        /// Only list the tools of this component
+        #[arg(long)]
+        component: Option<String>,
+    },
+    /// Print the full contract of one tool: its WIT signature, schemas, parameter docs,
+    /// configured defaults and an example call
+    Explain {
+        /// Tool name as advertised, or as `component.function`
+        tool: String,
+
+        /// Print the contract as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print the imports and exports of a component (local path or OCI reference)
+    Inspect {
+        /// Path to a .wasm file or an OCI reference
+        component: String,
+
+        /// Print JSON instead of WIT
+        #[arg(long)]
+        json: bool,
+    },
+    /// Download all OCI components into the cache without starting the server
+    Pull {},
+    /// List the tags of an OCI repository with their annotations
+    Tags {
+        /// Repository, e.g. "ghcr.io/dineshdb/wasi-components/fetch"
+        repository: String,
+
+        /// Maximum number of tags to show, newest first
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Re-resolve mutable OCI tags (e.g. `:latest`) and replace stale cached artifacts
+    Update {},
+    /// Resolve and compile all configured components into the cache
+    Precompile {},
+    /// Inspect and clean up the component cache
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommand,
+    },
+    /// Work with the configuration file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Print the JSON Schema of the configuration file
+    Schema {},
+    /// Load every component and check that no two tools share a name
+    Validate {},
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CacheCommand {
+    /// List cached artifacts, least recently used first
     List {},
+    /// Evict artifacts by age and total size (defaults to the `cache` policy in the config)
+    Prune {
+        /// Maximum total cache size, e.g. "2G" or "500M"
+        #[arg(long)]
+        max_size: Option<String>,
+
+        /// Evict artifacts not used for this many days
+        #[arg(long)]
+        max_age_days: Option<u64>,
+    },
+    /// Remove every cached artifact
+    Clear {},
+}
+
+/// Output format of `call` results
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Compact JSON on a single line
+    Json,
+    /// YAML
+    Yaml,
+    /// Indented JSON
+    #[default]
+    Pretty,
+    /// Only the unwrapped value: strings without quotes, `result` values without their
+    /// `ok` wrapper (an `err` fails the command), everything else as compact JSON
+    Raw,
+}
+
+impl OutputFormat {
+    /// Render a call result for printing
+    pub fn render(self, value: &Value) -> Result<String> {
+        match self {
+            OutputFormat::Json => Ok(serde_json::to_string(value)?),
+            OutputFormat::Pretty => Ok(serde_json::to_string_pretty(value)?),
+            OutputFormat::Yaml => serde_yaml::to_string(value)
+                .map(|yaml| yaml.trim_end().to_string())
+                .map_err(|e| WasiMcpError::Execution(format!("Failed to render YAML: {e}"))),
+            OutputFormat::Raw => match unwrap_result(value)? {
+                Value::String(string) => Ok(string.clone()),
+                value => Ok(serde_json::to_string(value)?),
+            },
+        }
+    }
+}
+
+/// Function-calling schema format of `export`
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// OpenAI `tools` with `type: function`
+    Openai,
+    /// Anthropic `tools` with `input_schema`
+    Anthropic,
+}
+
+impl ExportFormat {
+    /// Convert a tool list into this format
+    pub fn convert(self, tools: &[rmcp::model::Tool]) -> Value {
+        match self {
+            ExportFormat::Openai => crate::export::openai(tools),
+            ExportFormat::Anthropic => crate::export::anthropic(tools),
+        }
+    }
+}
+
+/// Unwrap the `{"result": "ok", "value": ...}` encoding of WIT `result` values
+fn unwrap_result(value: &Value) -> Result<&Value> {
+    if let Some(error) = result_error(value) {
+        return Err(WasiMcpError::Execution(match error {
+            Value::String(message) => message.clone(),
+            error => error.to_string(),
+        }));
+    }
+    let Some(object) = value.as_object() else {
+        return Ok(value);
+    };
+    match (object.len(), object.get("result"), object.get("value")) {
+        (2, Some(Value::String(result)), Some(inner)) if result == "ok" => Ok(inner),
+        _ => Ok(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::transform::wasm_to_json;
+    use serde_json::json;
+    use wasmtime::component::Val;
+
+    #[test]
+    fn test_output_formats() {
+        let value = json!({"result": "ok", "value": "hello"});
+        assert_eq!(
+            OutputFormat::Json.render(&value).unwrap(),
+            r#"{"result":"ok","value":"hello"}"#
+        );
+        assert_eq!(
+            OutputFormat::Yaml.render(&value).unwrap(),
+            "result: ok\nvalue: hello"
+        );
+        assert_eq!(OutputFormat::Raw.render(&value).unwrap(), "hello");
+        assert_eq!(OutputFormat::Raw.render(&json!([1, 2])).unwrap(), "[1,2]");
+
+        // The encoding the converter gives the results of calls
+        let ok = wasm_to_json(&Val::Result(Ok(Some(Box::new(Val::U32(7)))))).unwrap();
+        assert_eq!(OutputFormat::Raw.render(&ok).unwrap(), "7");
+        let err = Val::Result(Err(Some(Box::new(Val::String("not found".to_string())))));
+        let err = wasm_to_json(&err).unwrap();
+        match OutputFormat::Raw.render(&err) {
+            Err(WasiMcpError::Execution(message)) => assert_eq!(message, "not found"),
+            other => panic!("expected the guest's error, got {other:?}"),
+        }
+        assert!(OutputFormat::Pretty.render(&err).is_ok());
+    }
 }
@@ -0,0 +1,173 @@
+//! Command components as tools
+//!
+//! Components whose only export is `wasi:cli/run` are programs rather than libraries. They
+//! are exposed as a single `run` tool: every call runs the program in a fresh store with the
+//! given arguments and stdin, and returns what it wrote to stdout and stderr along with its
+//! exit code.
+
+use crate::config::ComponentConfig;
+use crate::error::{Result, WasiMcpError};
+use crate::state::ComponentRunStates;
+use crate::wasm::{WasmComponent, WasmContext};
+use rmcp::model::Tool;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use wasmtime_wasi::p2::bindings::Command;
+use wasmtime_wasi::p2::pipe::{MemoryInputPipe, MemoryOutputPipe};
+
+/// Name of the tool of a command component
+pub const RUN_TOOL: &str = "run";
+
+/// Interface exported by command components, without its version
+pub const RUN_INTERFACE: &str = "wasi:cli/run";
+
+/// Most bytes of stdout or stderr kept per run
+const MAX_OUTPUT: usize = 1024 * 1024;
+
+/// Arguments of the `run` tool
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RunArguments {
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    stdin: String,
+}
+
+/// Whether an exported interface is `wasi:cli/run`, in any version
+pub fn is_run_interface(name: &str) -> bool {
+    name.split('@').next() == Some(RUN_INTERFACE)
+}
+
+/// The `run` tool of a command component
+pub fn run_tool(description: Option<&str>) -> Tool {
+    let input_schema = serde_json::json!({
+        "type": "object",
+        "properties": {
+            "args": {
+                "type": "array",
+                "items": {"type": "string"},
+                "description": "Command-line arguments, without the program name"
+            },
+            "stdin": {
+                "type": "string",
+                "description": "Text passed to the program on standard input"
+            }
+        },
+        "required": [],
+        "additionalProperties": false
+    });
+    let output_schema = serde_json::json!({
+        "type": "object",
+        "properties": {
+            "stdout": {"type": "string"},
+            "stderr": {"type": "string"},
+            "exit_code": {"type": "integer"}
+        },
+        "required": ["stdout", "stderr", "exit_code"],
+        "additionalProperties": false
+    });
+    Tool {
+        name: RUN_TOOL.into(),
+        title: None,
+        description: Some(description.unwrap_or_default().to_string().into()),
+        input_schema: Arc::new(input_schema.as_object().cloned().unwrap_or_default()),
+        output_schema: Some(Arc::new(
+            output_schema.as_object().cloned().unwrap_or_default(),
+        )),
+        annotations: None,
+        icons: None,
+    }
+}
+
+/// Run a command component once in a fresh store set up from `config`
+pub(crate) async fn run(
+    context: &WasmContext,
+    component: &WasmComponent,
+    config: &ComponentConfig,
+    call_id: u64,
+    arguments: &HashMap<String, Value>,
+) -> Result<Value> {
+    let arguments: RunArguments = serde_json::from_value(Value::Object(
+        arguments
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect(),
+    ))
+    .map_err(|e| WasiMcpError::InvalidArguments(format!("Invalid arguments of run: {e}")))?;
+
+    let stdout = MemoryOutputPipe::new(MAX_OUTPUT);
+    let stderr = MemoryOutputPipe::new(MAX_OUTPUT);
+    let mut argv = Vec::with_capacity(arguments.args.len() + 1);
+    argv.push(component.name.clone());
+    argv.extend(arguments.args);
+    let state = command_states(
+        config,
+        &argv,
+        arguments.stdin,
+        stdout.clone(),
+        stderr.clone(),
+    )?;
+
+    let mut store = context.new_store(&component.name, state)?;
+    store.data().stdio.set_call_id(call_id);
+    let instance = context
+        .instantiate(&mut store, &component.name, config, &component.component)
+        .await?;
+    let command = Command::new(&mut store, &instance)?;
+    let exit_code = match command.wasi_cli_run().call_run(&mut store).await {
+        Ok(Ok(())) => 0,
+        Ok(Err(())) => 1,
+        Err(e) => match e.downcast_ref::<wasmtime_wasi::I32Exit>() {
+            Some(wasmtime_wasi::I32Exit(code)) => *code,
+            None => return Err(e.into()),
+        },
+    };
+
+    Ok(serde_json::json!({
+        "stdout": String::from_utf8_lossy(&stdout.contents()),
+        "stderr": String::from_utf8_lossy(&stderr.contents()),
+        "exit_code": exit_code,
+    }))
+}
+
+/// States of one run: the component's settings with the call's arguments and stdin, and
+/// output captured instead of logged
+fn command_states(
+    config: &ComponentConfig,
+    argv: &[String],
+    stdin: String,
+    stdout: MemoryOutputPipe,
+    stderr: MemoryOutputPipe,
+) -> Result<ComponentRunStates> {
+    ComponentRunStates::with_stdio(config, |builder, _stdio| {
+        builder
+            .args(argv)
+            .stdin(MemoryInputPipe::new(stdin))
+            .stdout(stdout)
+            .stderr(stderr);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_interface() {
+        assert!(is_run_interface("wasi:cli/run@0.2.3"));
+        assert!(is_run_interface("wasi:cli/run"));
+        assert!(!is_run_interface("wasi:cli/runner@0.2.3"));
+    }
+
+    #[test]
+    fn test_run_arguments() {
+        let arguments: RunArguments =
+            serde_json::from_value(serde_json::json!({"args": ["-n", "3"]})).unwrap();
+        assert_eq!(arguments.args, ["-n", "3"]);
+        assert!(arguments.stdin.is_empty());
+        assert!(serde_json::from_value::<RunArguments>(serde_json::json!({"argv": []})).is_err());
+    }
+}
@@ -0,0 +1,87 @@
+//! Wrapping core wasm modules into components
+//!
+//! Modules built for `wasm32-wasip1` by older toolchains are turned into components at load
+//! time with the WASI preview1 adapter, the equivalent of `wasm-tools component new --adapt`.
+//! Modules exporting `_start` get the command adapter and export `wasi:cli/run`; all others
+//! get the reactor adapter.
+
+use crate::error::{Result, WasiMcpError};
+use wasi_preview1_component_adapter_provider::{
+    WASI_SNAPSHOT_PREVIEW1_ADAPTER_NAME, WASI_SNAPSHOT_PREVIEW1_COMMAND_ADAPTER,
+    WASI_SNAPSHOT_PREVIEW1_REACTOR_ADAPTER,
+};
+
+/// Preamble of a core module: the magic number and binary format version 1
+const CORE_MODULE_HEADER: &[u8] = b"\0asm\x01\0\0\0";
+
+/// Whether `bytes` hold a core wasm module rather than a component
+pub fn is_core_module(bytes: &[u8]) -> bool {
+    bytes.starts_with(CORE_MODULE_HEADER)
+}
+
+/// Wrap a wasip1 core module into a component
+pub fn componentize(module: &[u8]) -> Result<Vec<u8>> {
+    let adapter = if exports_start(module)? {
+        WASI_SNAPSHOT_PREVIEW1_COMMAND_ADAPTER
+    } else {
+        WASI_SNAPSHOT_PREVIEW1_REACTOR_ADAPTER
+    };
+    wit_component::ComponentEncoder::default()
+        .validate(true)
+        .module(module)
+        .and_then(|encoder| encoder.adapter(WASI_SNAPSHOT_PREVIEW1_ADAPTER_NAME, adapter))
+        .and_then(|mut encoder| encoder.encode())
+        .map_err(|e| {
+            WasiMcpError::InvalidArguments(format!(
+                "Failed to turn the core module into a component: {e:#}"
+            ))
+        })
+}
+
+/// Whether the module exports a command entry point
+fn exports_start(module: &[u8]) -> Result<bool> {
+    let invalid = |e: wasmparser::BinaryReaderError| {
+        WasiMcpError::InvalidArguments(format!("Invalid core module: {e}"))
+    };
+    for payload in wasmparser::Parser::new(0).parse_all(module) {
+        if let wasmparser::Payload::ExportSection(exports) = payload.map_err(invalid)? {
+            for export in exports {
+                if export.map_err(invalid)?.name == "_start" {
+                    return Ok(true);
+                }
+            }
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_core_module() {
+        let module = wat::parse_str("(module)").unwrap();
+        assert!(is_core_module(&module));
+        let component = wat::parse_str("(component)").unwrap();
+        assert!(!is_core_module(&component));
+    }
+
+    #[test]
+    fn test_exports_start() {
+        let command = wat::parse_str(r#"(module (func (export "_start")))"#).unwrap();
+        assert!(exports_start(&command).unwrap());
+        let reactor = wat::parse_str(r#"(module (func (export "add")))"#).unwrap();
+        assert!(!exports_start(&reactor).unwrap());
+    }
+
+    #[test]
+    fn test_componentize() {
+        let module =
+            wat::parse_str(r#"(module (memory (export "memory") 1) (func (export "_start")))"#)
+                .unwrap();
+        let component = componentize(&module).unwrap();
+        assert!(!is_core_module(&component));
+        wasmtime::component::Component::new(&wasmtime::Engine::default(), &component).unwrap();
+    }
+}
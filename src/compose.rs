@@ -0,0 +1,73 @@
+use crate::WasiMcpError;
+use crate::error::Result;
+use crate::state::ComponentRunStates;
+use wasmtime::component::types::ComponentItem;
+use wasmtime::component::{Component, Func, Linker, LinkerInstance};
+use wasmtime::{Engine, Store};
+
+/// Instantiate a plug component and define its exports in `linker`
+///
+/// Every exported function (top-level or inside an exported interface) is registered under
+/// the same name, forwarding calls to the plug instance. Components instantiated afterwards
+/// with this linker get their matching imports satisfied by the plug, like `wac plug`.
+pub async fn link_plug(
+    linker: &mut Linker<ComponentRunStates>,
+    store: &mut Store<ComponentRunStates>,
+    engine: &Engine,
+    plug: &Component,
+) -> Result<()> {
+    let instance = linker.instantiate_async(&mut *store, plug).await?;
+    let ty = plug.component_type();
+
+    for (name, item) in ty.exports(engine) {
+        match item {
+            ComponentItem::ComponentFunc(_) => {
+                let func = instance
+                    .get_func(&mut *store, name)
+                    .ok_or_else(|| WasiMcpError::FunctionNotFound(name.to_string()))?;
+                forward_func(&mut linker.root(), name, func)?;
+            }
+            ComponentItem::ComponentInstance(interface) => {
+                let interface_idx = instance
+                    .get_export_index(&mut *store, None, name)
+                    .ok_or_else(|| WasiMcpError::InterfaceNotFound(name.to_string()))?;
+                let mut linker_instance = linker.instance(name)?;
+
+                for (func_name, func_item) in interface.exports(engine) {
+                    if !matches!(func_item, ComponentItem::ComponentFunc(_)) {
+                        continue;
+                    }
+                    let func = instance
+                        .get_export_index(&mut *store, Some(&interface_idx), func_name)
+                        .and_then(|idx| instance.get_func(&mut *store, idx))
+                        .ok_or_else(|| {
+                            WasiMcpError::FunctionNotFound(format!("{name}.{func_name}"))
+                        })?;
+                    forward_func(&mut linker_instance, func_name, func)?;
+                }
+                tracing::debug!("Plugged interface {}", name);
+            }
+            // Types and resources can't be forwarded through dynamic host functions
+            _ => {
+                tracing::debug!("Skipping non-function plug export {}", name);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Define a host function that forwards calls to a function of another instance
+fn forward_func(
+    linker_instance: &mut LinkerInstance<'_, ComponentRunStates>,
+    name: &str,
+    func: Func,
+) -> Result<()> {
+    linker_instance.func_new_async(name, move |mut store, params, results| {
+        Box::new(async move {
+            func.call_async(&mut store, params, results).await?;
+            Ok(())
+        })
+    })?;
+    Ok(())
+}
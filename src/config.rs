@@ -1,15 +1,20 @@
 use crate::WasiMcpError;
 use crate::error::Result;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Configuration file structure
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct Config {
     /// Components configuration
     pub components: HashMap<String, ComponentConfig>,
 
+    /// Settings every component inherits unless it sets them itself
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub defaults: Option<ComponentConfig>,
+
     /// Prompts configuration
     #[serde(default, skip_serializing_if = "std::collections::HashMap::is_empty")]
     pub prompts: HashMap<String, Prompt>,
@@ -17,10 +22,155 @@ pub struct Config {
     /// Optional description of the configuration
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+
+    /// Named secrets that component `env` values can reference as `${secret:NAME}`
+    #[serde(default, skip_serializing_if = "std::collections::HashMap::is_empty")]
+    pub secrets: HashMap<String, SecretSource>,
+
+    /// Credentials for OCI registries keyed by registry host (e.g. `ghcr.io`); takes
+    /// precedence over `~/.docker/config.json`
+    #[serde(default, skip_serializing_if = "std::collections::HashMap::is_empty")]
+    pub registries: HashMap<String, RegistryConfig>,
+
+    /// Eviction policy for the component cache, applied when the server starts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache: Option<CachePolicy>,
+
+    /// Proxy for registry traffic; defaults to `HTTPS_PROXY`/`HTTP_PROXY`/`NO_PROXY`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<ProxyConfig>,
+
+    /// Signature verification of OCI components
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification: Option<VerificationConfig>,
+
+    /// Which clients may call which tools (default: every client may call every tool)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<PolicyConfig>,
+
+    /// Session handling of the streamable HTTP transport
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http: Option<HttpConfig>,
+
+    /// Named subsets of the components, each served by its own executor at `/mcp/<profile>`
+    /// or with an `X-Wasmic-Profile` header
+    #[serde(default, skip_serializing_if = "std::collections::HashMap::is_empty")]
+    pub profiles: HashMap<String, ProfileConfig>,
+
+    /// Tool calls triggered by `POST /hooks/<name>` requests, keyed by name
+    #[serde(default, skip_serializing_if = "std::collections::HashMap::is_empty")]
+    pub webhooks: HashMap<String, WebhookConfig>,
+
+    /// Compilation and runtime settings of the wasmtime engine; unset options keep
+    /// wasmtime's defaults
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub engine: Option<EngineConfig>,
+
+    /// Record the calls the MCP server executes in a SQLite database, queried with
+    /// `wasmic history`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history: Option<HistoryConfig>,
+
+    /// Record every call the MCP server executes, with what the guest observed, so it can be
+    /// re-executed deterministically with `wasmic replay`; calls run in fresh instances
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recording: Option<RecordingConfig>,
+
+    /// Tool calls the MCP server makes periodically, keyed by name
+    #[serde(default, skip_serializing_if = "std::collections::HashMap::is_empty")]
+    pub schedules: HashMap<String, ScheduleConfig>,
+
+    /// Encode 64-bit integers in tool results as JSON strings so clients that parse numbers
+    /// as doubles don't lose precision; tool schemas then describe results as strings and
+    /// accept strings as arguments (default: false)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub int64_as_string: bool,
+
+    /// Include the guest backtrace in the result of tool calls that trap, not only in the
+    /// logs (default: false)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub trap_backtraces: bool,
+
+    /// How tool names are built from component and function names (default:
+    /// `component.function`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_names: Option<ToolNamingConfig>,
+
+    /// Periodically re-resolve the tags of OCI components and upgrade the components whose
+    /// artifact changed, without interrupting calls (default: off)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updates: Option<UpdateConfig>,
+
+    /// Example calls with their expected outcomes, run by `wasmic test`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tests: Vec<TestCase>,
+}
+
+/// Automatic upgrades of OCI components while the server runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct UpdateConfig {
+    /// Seconds between checks of the registries (default: 3600)
+    #[serde(default = "UpdateConfig::default_interval_secs")]
+    pub interval_secs: u64,
+}
+
+impl UpdateConfig {
+    fn default_interval_secs() -> u64 {
+        3600
+    }
+}
+
+/// An example call of a tool and what it must return
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct TestCase {
+    /// Name shown in the report (default: the tool)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Tool to call, as `component.function`
+    pub tool: String,
+    /// Arguments of the call
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub args: HashMap<String, serde_json::Value>,
+    /// Result the call must return exactly
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expect: Option<serde_json::Value>,
+    /// Checks of parts of the result
+    #[serde(default, rename = "assert", skip_serializing_if = "Vec::is_empty")]
+    pub assertions: Vec<Assertion>,
+    /// Error code the call must fail with, e.g. `validation` or `trap`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expect_error: Option<String>,
+}
+
+impl TestCase {
+    /// Name of the test in reports
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.tool)
+    }
+}
+
+/// A check of the value at one place in a tool result; every set condition must hold
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Assertion {
+    /// JSON pointer to the checked value, e.g. `/items/0/name` (default: the whole result)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub path: String,
+    /// Whether a value exists at the path
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exists: Option<bool>,
+    /// Value the checked value must equal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub equals: Option<serde_json::Value>,
+    /// Substring of a string value, or element of an array value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contains: Option<serde_json::Value>,
+    /// JSON type of the value: `null`, `boolean`, `number`, `string`, `array` or `object`
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
 }
 
 /// Prompt configuration for use-case-specific guidance
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Prompt {
     /// Human-readable name for the prompt
     pub name: String,
@@ -30,8 +180,334 @@ pub struct Prompt {
     pub content: String,
 }
 
+/// Where the value of a named secret comes from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretSource {
+    /// Entry in the OS keyring (macOS Keychain or Secret Service) under the `wasmic` service
+    Keyring(String),
+    /// File containing the secret; trailing newlines are trimmed
+    File(String),
+    /// Command printing the secret to stdout, e.g. `["sops", "-d", "--extract", ...]`
+    Command(Vec<String>),
+    /// Environment variable of the wasmic process
+    Env(String),
+}
+
+/// Credentials for a single OCI registry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct RegistryConfig {
+    pub username: String,
+    /// Password or access token; may reference `${secret:NAME}`
+    pub password: String,
+}
+
+/// HTTP(S) proxy used to reach OCI registries
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ProxyConfig {
+    /// Proxy URL, e.g. `http://proxy.corp:3128`
+    pub url: String,
+    /// Comma-separated hosts that bypass the proxy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub no_proxy: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    /// Proxy password; may reference `${secret:NAME}`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+}
+
+/// Size and age limits for the component cache
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CachePolicy {
+    /// Maximum total size, e.g. `2G` or `500M`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_size: Option<String>,
+    /// Evict artifacts not used for this many days
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age_days: Option<u64>,
+}
+
+/// Session handling of the streamable HTTP transport
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct HttpConfig {
+    /// Serve every request on its own, without `Mcp-Session-Id` sessions, so requests can be
+    /// load balanced without session affinity. Clients then receive no list-changed
+    /// notifications.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stateless: bool,
+    /// Close sessions that have been idle for this many seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_timeout_secs: Option<u64>,
+    /// Interval of keep-alive pings on SSE streams; `0` disables them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sse_keep_alive_secs: Option<u64>,
+    /// Serve `/admin/components` to anyone when no `policy` is configured; with a policy,
+    /// only clients marked `admin` may use it (default: false)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub unauthenticated_admin: bool,
+}
+
+/// How tool names are built from component and function names
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ToolNamingConfig {
+    /// Text between the component and the function name (default: `.`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub separator: Option<String>,
+    /// Replace every character other than ASCII letters, digits, `_` and `-` in the
+    /// component and function names with `_`, e.g. the `:`, `/` and `@` of interface names
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub sanitize: bool,
+    /// Shorten longer names to this many characters, ending in a hash of the full name so
+    /// they stay distinct
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<usize>,
+    /// Leave the interface name out of the names of interface functions (`web.handle`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub short: bool,
+    /// What to do when several tools end up with the same name (default: `error`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collisions: Option<CollisionPolicy>,
+}
+
+/// Handling of tools that end up with the same name
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CollisionPolicy {
+    /// Refuse to load the component that introduces the collision
+    #[default]
+    Error,
+    /// Keep the name for the first tool in `component.function` order and append a hash
+    /// of `component.function` to the others
+    Disambiguate,
+}
+
+/// Components served to the clients of one profile
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ProfileConfig {
+    /// Names of the components in this profile
+    pub components: Vec<String>,
+}
+
+/// A tool call triggered by an incoming webhook
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct WebhookConfig {
+    /// Tool to call, as `component.function`
+    pub tool: String,
+    /// Arguments of the call; `{{ path.to.field }}` is replaced by that field of the JSON
+    /// payload, keeping its type when it is the whole value
+    #[serde(default, skip_serializing_if = "std::collections::HashMap::is_empty")]
+    pub args: HashMap<String, serde_json::Value>,
+    /// HMAC-SHA256 secret the sender signs payloads with; may reference `${secret:NAME}`.
+    /// Unsigned requests are accepted when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    /// How the signature is transmitted (default: github)
+    #[serde(default)]
+    pub signature: WebhookSignature,
+}
+
+/// Tuning of the wasmtime engine shared by all components
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct EngineConfig {
+    /// Compile functions on multiple threads (default: true)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parallel_compilation: Option<bool>,
+    /// Cranelift optimization level (default: speed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub opt_level: Option<OptLevel>,
+    /// Emit DWARF debug info so native debuggers and backtraces see guest source locations
+    /// (default: false)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub debug_info: Option<bool>,
+    /// Resolve guest backtrace frames to source files and lines, when components carry
+    /// DWARF (default: only with `WASMTIME_BACKTRACE_DETAILS=1`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backtrace_details: Option<bool>,
+    /// Stack space guest code may use, in bytes, before trapping (default: 512 KiB)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_wasm_stack: Option<usize>,
+    /// Enable the relaxed-simd proposal (default: true)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relaxed_simd: Option<bool>,
+    /// Make relaxed-simd instructions produce the same results on every platform
+    /// (default: false)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relaxed_simd_deterministic: Option<bool>,
+    /// Canonicalize NaN values produced by float operations for deterministic results
+    /// (default: false)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nan_canonicalization: Option<bool>,
+    /// Let running guests yield to other tasks every this many milliseconds, so a CPU-bound
+    /// component doesn't starve other sessions; also the resolution of call timeouts
+    /// (default: never yield, check timeouts every 10 ms)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub yield_interval_ms: Option<u64>,
+    /// Preallocate instance slots so instantiation only reuses memory instead of mapping it;
+    /// `pooling: {}` enables it with wasmtime's default limits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pooling: Option<PoolingConfig>,
+    /// Save a core dump of the guest, with the tool and arguments hash, whenever a call
+    /// traps; `coredump: {}` saves them in the wasmic data directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coredump: Option<CoreDumpConfig>,
+}
+
+/// Limits of the pooling instance allocator; unset limits keep wasmtime's defaults
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PoolingConfig {
+    /// Component instances alive at once (default: 1000)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_component_instances: Option<u32>,
+    /// Core module instances alive at once, across all component instances (default: 1000)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_core_instances: Option<u32>,
+    /// Linear memories alive at once (default: 1000)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_memories: Option<u32>,
+    /// Tables alive at once (default: 1000)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_tables: Option<u32>,
+    /// Fiber stacks for async calls alive at once (default: 1000)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_stacks: Option<u32>,
+    /// Largest linear memory a slot holds, in bytes (default: 4 GiB)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_memory_size: Option<usize>,
+}
+
+/// Where core dumps of trapped guests are saved
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CoreDumpConfig {
+    /// Directory of the dumps (default: `coredumps` in the wasmic data directory)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dir: Option<PathBuf>,
+    /// Most dumps kept; the oldest are removed first (default: 20)
+    #[serde(default = "CoreDumpConfig::default_max_dumps")]
+    pub max_dumps: usize,
+}
+
+impl CoreDumpConfig {
+    fn default_max_dumps() -> usize {
+        20
+    }
+}
+
+impl Default for CoreDumpConfig {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            max_dumps: Self::default_max_dumps(),
+        }
+    }
+}
+
+/// Cranelift optimization level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OptLevel {
+    None,
+    Speed,
+    SpeedAndSize,
+}
+
+/// Location of the call history database
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct HistoryConfig {
+    /// Database file (default: `history.sqlite` in the wasmic data directory)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+}
+
+/// Where recorded calls are saved
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct RecordingConfig {
+    /// Directory of the recordings (default: `recordings` in the wasmic data directory)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dir: Option<PathBuf>,
+}
+
+/// A tool call made on a cron schedule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ScheduleConfig {
+    /// Five-field cron expression in UTC (`minute hour day month weekday`), or one of
+    /// `@hourly`, `@daily`, `@weekly`, `@monthly` and `@yearly`
+    pub cron: String,
+    /// Tool to call, as `component.function`
+    pub tool: String,
+    /// Arguments of the call
+    #[serde(default, skip_serializing_if = "std::collections::HashMap::is_empty")]
+    pub args: HashMap<String, serde_json::Value>,
+    /// File every run's result is appended to as a JSON line
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<PathBuf>,
+}
+
+/// Signature scheme of a webhook sender
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookSignature {
+    /// `X-Hub-Signature-256: sha256=<hex>` over the body, as sent by GitHub
+    #[default]
+    Github,
+    /// `Stripe-Signature: t=<timestamp>,v1=<hex>` over `<timestamp>.<body>`
+    Stripe,
+}
+
+/// Cosign signature verification policy for OCI components
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct VerificationConfig {
+    /// Refuse components from registries without a trust policy
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub require_signed: bool,
+    /// Trusted signers keyed by registry host (e.g. `ghcr.io`)
+    #[serde(default)]
+    pub registries: HashMap<String, TrustPolicy>,
+}
+
+/// Who is trusted to sign artifacts of a registry: a public key, or a keyless identity
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct TrustPolicy {
+    /// Path to a cosign public key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    /// Certificate identity of keyless signatures (e.g. a GitHub workflow URL)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<String>,
+    /// OIDC issuer of keyless signatures
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<String>,
+}
+
+/// Per-client tool authorization policy
+///
+/// Tool patterns are `component.function`, `component.*` or `*`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PolicyConfig {
+    /// Known clients by name
+    #[serde(default)]
+    pub clients: HashMap<String, ClientPolicy>,
+    /// Tools callable by requests without a known bearer token (default: none)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub anonymous: Vec<String>,
+}
+
+/// Identity and allowed tools of a single client
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ClientPolicy {
+    /// Bearer token the client sends in the `Authorization` header
+    pub token: String,
+    /// Tools this client may list and call
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Whether the client may enable and disable components at `/admin/components`
+    /// (default: false)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub admin: bool,
+}
+
 /// Volume mount configuration for WASI filesystem access
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct VolumeMount {
     /// Host path to mount (absolute path)
     pub host_path: String,
@@ -42,15 +518,174 @@ pub struct VolumeMount {
     pub read_only: bool,
 }
 
+/// A component whose exports are plugged into the imports of the enclosing component
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PlugConfig {
+    /// Path to the local WASM component file (mutually exclusive with oci)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// OCI reference for the WASM component (mutually exclusive with path)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oci: Option<String>,
+}
+
+/// Overrides for how a single function is advertised as a tool
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct FunctionConfig {
+    /// Tool description, replacing the component description
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Per-parameter overrides keyed by parameter name
+    #[serde(default, skip_serializing_if = "std::collections::HashMap::is_empty")]
+    pub params: HashMap<String, ParamConfig>,
+    /// Reuse results of earlier calls with the same arguments; only for deterministic functions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache: Option<ResultCacheConfig>,
+}
+
+/// Result caching of a deterministic function
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ResultCacheConfig {
+    /// Seconds a result is reused for (default: 300)
+    #[serde(default = "ResultCacheConfig::default_ttl_secs")]
+    pub ttl_secs: u64,
+    /// Most results kept; the oldest is dropped first (default: 100)
+    #[serde(default = "ResultCacheConfig::default_max_entries")]
+    pub max_entries: usize,
+}
+
+impl ResultCacheConfig {
+    fn default_ttl_secs() -> u64 {
+        300
+    }
+
+    fn default_max_entries() -> usize {
+        100
+    }
+}
+
+impl Default for ResultCacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl_secs: Self::default_ttl_secs(),
+            max_entries: Self::default_max_entries(),
+        }
+    }
+}
+
+/// Re-instantiation of a component whose calls keep failing in the guest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct RecoveryConfig {
+    /// Consecutive failed calls after which the component gets a new instance; 0 disables
+    /// recovery (default: 3)
+    #[serde(default = "RecoveryConfig::default_max_failures")]
+    pub max_failures: u32,
+    /// Milliseconds before a component that keeps failing is restarted again, doubled by
+    /// every restart until a call succeeds (default: 1000)
+    #[serde(default = "RecoveryConfig::default_backoff_ms")]
+    pub backoff_ms: u64,
+    /// Upper bound of the restart backoff in milliseconds (default: 60000)
+    #[serde(default = "RecoveryConfig::default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+}
+
+impl RecoveryConfig {
+    fn default_max_failures() -> u32 {
+        3
+    }
+
+    fn default_backoff_ms() -> u64 {
+        1000
+    }
+
+    fn default_max_backoff_ms() -> u64 {
+        60_000
+    }
+}
+
+impl Default for RecoveryConfig {
+    fn default() -> Self {
+        Self {
+            max_failures: Self::default_max_failures(),
+            backoff_ms: Self::default_backoff_ms(),
+            max_backoff_ms: Self::default_max_backoff_ms(),
+        }
+    }
+}
+
+/// Limits on what one instance of a component may consume before it is evicted
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct WatchdogConfig {
+    /// Linear memory in MiB the instance may grow to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_memory_mb: Option<u64>,
+    /// Wall-clock seconds the instance may spend in calls, summed over all calls since it was
+    /// created; time a call waits on I/O or the host counts too
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_call_time_secs: Option<u64>,
+    /// What happens to an instance over a limit (default: recycle)
+    #[serde(default)]
+    pub action: WatchdogAction,
+}
+
+/// What the watchdog does with an instance over its limits
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchdogAction {
+    /// Replace the instance with a fresh one
+    #[default]
+    Recycle,
+    /// Replace the instance and disable the component until it is enabled again
+    Disable,
+}
+
+/// What happens when a component's `init` function fails
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum InitFailure {
+    /// The component fails to load
+    #[default]
+    Fail,
+    /// The failure is logged and the component is served anyway
+    Warn,
+}
+
+/// Overrides for a single tool parameter
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ParamConfig {
+    /// Description added to the parameter's input schema
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Example values added to the parameter's input schema
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<serde_json::Value>,
+    /// Value used when the caller omits the parameter; makes it optional
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<serde_json::Value>,
+    /// Value always passed for the parameter; hidden from the input schema
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<serde_json::Value>,
+}
+
 /// Individual component configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct ComponentConfig {
+    /// Whether the component is loaded and served; a disabled component keeps its
+    /// configuration but its tools are not listed and calls are refused (default: true)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    /// Name of another component whose settings this one inherits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
     /// Path to the local WASM component file (mutually exclusive with oci)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub path: Option<String>,
     /// OCI reference for the WASM component (mutually exclusive with path)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub oci: Option<String>,
+    /// Expected SHA-256 of the component file (hex); loading fails on mismatch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
     /// Optional configuration data for the component
     pub config: Option<serde_json::Value>,
     /// Volume mounts for filesystem access
@@ -59,22 +694,192 @@ pub struct ComponentConfig {
     /// Current working directory for the component
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub cwd: Option<String>,
-    /// Environment variables for the component
+    /// Environment variables for the component; values may reference `${secret:NAME}`
     #[serde(default, skip_serializing_if = "std::collections::HashMap::is_empty")]
     pub env: HashMap<String, String>,
+    /// Environment variables read from OS keyring entries (variable name to entry name)
+    #[serde(default, skip_serializing_if = "std::collections::HashMap::is_empty")]
+    pub env_from_keyring: HashMap<String, String>,
+    /// Dotenv-style files (`KEY=VALUE` lines) to read environment variables from
+    ///
+    /// Relative paths are resolved against the configuration file's directory, and a leading
+    /// `~` is expanded to the home directory.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env_from_file: Vec<String>,
     /// Optional description of the component
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    /// Functions exposed as tools (a trailing `*` matches a prefix); all when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expose: Option<Vec<String>>,
+    /// Functions never exposed or callable, even when matched by `expose`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hide: Vec<String>,
+    /// Per-function overrides of the advertised tool, keyed by function name
+    #[serde(default, skip_serializing_if = "std::collections::HashMap::is_empty")]
+    pub functions: HashMap<String, FunctionConfig>,
+    /// Components instantiated first whose exports satisfy this component's imports
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub plugs: Vec<PlugConfig>,
+    /// Allow TCP/UDP sockets and name lookups through wasi:sockets (default: false)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<bool>,
+    /// Pass the host's command-line arguments to the guest (default: false)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inherit_args: Option<bool>,
+    /// Pass the host's environment variables to the guest, below those set in `env`
+    /// (default: false)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inherit_env: Option<bool>,
+    /// Connect the guest to the host's stdin, stdout and stderr instead of logging its
+    /// output (default: false)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inherit_stdio: Option<bool>,
+    /// Address ranges (CIDR or single IP) sockets may connect or bind to; any when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_networks: Vec<String>,
+    /// Hosts outgoing HTTP requests may target (`*.example.com` matches subdomains).
+    /// Unrestricted when unset; an empty list denies all outgoing HTTP.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_hosts: Option<Vec<String>>,
+    /// Function called once after the component is loaded, e.g. to load a model or open a
+    /// connection (also accepted as `on_load`); the initialized state is kept for every call
+    /// and the function is never exposed
+    #[serde(default, alias = "on_load", skip_serializing_if = "Option::is_none")]
+    pub init: Option<String>,
+    /// Whether a failing `init` function fails loading the component (default) or is only
+    /// logged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub init_failure: Option<InitFailure>,
+    /// Function called before the component is unloaded or the server stops, e.g. to close
+    /// connections; failures are logged and the function is never exposed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_shutdown: Option<String>,
+    /// Milliseconds a call may run before it is interrupted (default: no limit)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// Environment variables and working directories a call may override with its `_ctx`
+    /// argument (default: none)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub call_context: Option<CallContextConfig>,
+    /// Mount a private, initially empty temporary directory at `/tmp`, removed with the
+    /// instance (default: false)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scratch: Option<bool>,
+    /// Calls made right after the component is loaded, so caches and lazily opened
+    /// resources are ready before the first real call; failures are logged
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warmup: Vec<WarmupCall>,
+    /// Calls a new version of the component must answer without error before it replaces
+    /// the running one on a reload or update
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub smoke_calls: Vec<WarmupCall>,
+    /// When to re-instantiate the component after repeated failures (default: after 3
+    /// consecutive failed calls)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recovery: Option<RecoveryConfig>,
+    /// Memory and call time limits of an instance, checked after every call (default: none)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub watchdog: Option<WatchdogConfig>,
+    /// SQLite databases the component may use through `wasmic:sql/sql`, keyed by the name
+    /// it opens them with
+    #[serde(default, skip_serializing_if = "std::collections::HashMap::is_empty")]
+    pub databases: HashMap<String, DatabaseConfig>,
 }
 
+/// An SQLite database a component may query
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct DatabaseConfig {
+    /// Path of the database file, relative to the configuration file; created on first use
+    /// unless read-only
+    pub path: String,
+    /// Only allow statements that don't write (default: false)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub read_only: bool,
+}
+
+/// A call made when a component is loaded, to warm it up or to check a new version
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct WarmupCall {
+    /// Exposed function to call
+    pub function: String,
+    /// Named arguments of the call
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub args: HashMap<String, serde_json::Value>,
+}
+
+/// What a tool call may override through its `_ctx` argument
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CallContextConfig {
+    /// Environment variables a call may set; `*` allows any
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env: Vec<String>,
+    /// Host directories a call may select as its working directory, subdirectories included
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cwd: Vec<String>,
+}
+
+/// Commented starter configuration written by `wasmic init`
+pub const STARTER_CONFIG: &str = r#"# wasmic configuration
+# Reference: https://github.com/dineshdb/wasmic#configuration
+# Editor completion: run `wasmic config schema > wasmic.schema.json` and add
+# `# yaml-language-server: $schema=./wasmic.schema.json` at the top of this file.
+
+description: "My wasmic components"
+
+components:
+  # A component pulled from an OCI registry and cached locally
+  time:
+    description: "time-related functions including timezone conversion"
+    oci: ghcr.io/dineshdb/wasi-components/time:latest
+    # Exposed to the guest through wasi:config/store
+    config:
+      timezone: "UTC"
+
+  # A component built locally; uncomment and adjust the path
+  # my_component:
+  #   description: "what my component does"
+  #   path: target/wasm32-wasip2/release/my_component.wasm
+  #   env:
+  #     API_KEY: "${API_KEY}"
+  #   volumes:
+  #     - host_path: /tmp/data
+  #       guest_path: /data
+  #       read_only: true
+
+prompts: {}
+"#;
+
 impl Config {
+    /// Write the starter configuration to `path`, creating its directory
+    ///
+    /// An existing file is only replaced when `force` is set.
+    pub fn write_starter(path: &Path, force: bool) -> Result<()> {
+        if path.exists() && !force {
+            return Err(WasiMcpError::InvalidArguments(format!(
+                "Configuration file {path:?} already exists (use --force to overwrite)"
+            )));
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, STARTER_CONFIG)?;
+        Ok(())
+    }
+
     /// Load configuration from a YAML file
     pub fn from_file(path: &PathBuf) -> Result<Self> {
         let content = std::fs::read_to_string(path)?;
-
-        let config: Config = serde_yaml::from_str(&content).map_err(|e| {
-            WasiMcpError::InvalidArguments(format!("Invalid YAML configuration: {e}",))
-        })?;
+        let mut config = Self::parse(&content, |name| std::env::var(name).ok())?;
+        let base = path.parent().unwrap_or(Path::new("."));
+        for component in config.components.values_mut() {
+            for env_file in &mut component.env_from_file {
+                *env_file = resolve_path(env_file, base).display().to_string();
+            }
+            for database in component.databases.values_mut() {
+                database.path = resolve_path(&database.path, base).display().to_string();
+            }
+        }
 
         tracing::debug!(
             prompts = config.prompts.len(),
@@ -84,4 +889,707 @@ impl Config {
 
         Ok(config)
     }
+
+    /// JSON Schema of the configuration file, for editor completion and CI validation
+    pub fn json_schema() -> serde_json::Value {
+        serde_json::to_value(schemars::schema_for!(Config))
+            .expect("JSON Schema should serialize to JSON")
+    }
+
+    /// Parse a YAML configuration, expanding `${VAR}` and `${VAR:-default}` in string values
+    ///
+    /// Prompts are left untouched since their content is free text. `$${` produces a literal
+    /// `${`, and references that are not variable names (like `${secret:NAME}`) are kept.
+    pub fn parse(content: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let invalid = |e: serde_yaml::Error| {
+            WasiMcpError::InvalidArguments(format!("Invalid YAML configuration: {e}",))
+        };
+
+        let mut value: serde_yaml::Value = serde_yaml::from_str(content).map_err(invalid)?;
+        if let serde_yaml::Value::Mapping(mapping) = &mut value {
+            for (key, value) in mapping.iter_mut() {
+                if key.as_str() != Some("prompts") {
+                    interpolate_value(value, &lookup);
+                }
+            }
+        }
+
+        let mut config: Config = serde_yaml::from_value(value).map_err(invalid)?;
+        config.resolve_inheritance()?;
+        Ok(config)
+    }
+
+    /// The configuration served to clients of profile `name`
+    pub fn profile(&self, name: &str) -> Result<Config> {
+        let profile = self
+            .profiles
+            .get(name)
+            .ok_or_else(|| WasiMcpError::InvalidArguments(format!("Unknown profile '{name}'")))?;
+        if let Some(missing) = profile
+            .components
+            .iter()
+            .find(|component| !self.components.contains_key(*component))
+        {
+            return Err(WasiMcpError::InvalidArguments(format!(
+                "Profile '{name}' refers to unknown component '{missing}'"
+            )));
+        }
+
+        let mut config = self.clone();
+        config.profiles.clear();
+        config
+            .components
+            .retain(|component, _| profile.components.contains(component));
+        Ok(config)
+    }
+
+    /// Merge `extends` chains and the top-level `defaults` into every component
+    fn resolve_inheritance(&mut self) -> Result<()> {
+        let names: Vec<String> = self.components.keys().cloned().collect();
+        self.resolve_inheritance_of(&names)
+    }
+
+    /// Merge `extends` chains and the top-level `defaults` into the components `names`, e.g.
+    /// ones added after the file was read
+    pub(crate) fn resolve_inheritance_of(&mut self, names: &[String]) -> Result<()> {
+        let declared = self.components.clone();
+
+        for name in names {
+            let Some(component) = self.components.get_mut(name) else {
+                continue;
+            };
+            let mut chain = vec![name.clone()];
+            let mut parent = component.extends.clone();
+
+            while let Some(parent_name) = parent {
+                if chain.contains(&parent_name) {
+                    chain.push(parent_name);
+                    return Err(WasiMcpError::InvalidArguments(format!(
+                        "Component inheritance cycle: {}",
+                        chain.join(" -> ")
+                    )));
+                }
+                let base = declared.get(&parent_name).ok_or_else(|| {
+                    WasiMcpError::InvalidArguments(format!(
+                        "Component '{name}' extends unknown component '{parent_name}'"
+                    ))
+                })?;
+                component.inherit(base);
+                parent = base.extends.clone();
+                chain.push(parent_name);
+            }
+
+            if let Some(defaults) = &self.defaults {
+                component.inherit(defaults);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ComponentConfig {
+    /// Whether the component is loaded and served
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+
+    /// Whether a function of this component may be listed and called as a tool
+    pub fn exposes(&self, function: &str) -> bool {
+        let matches = |pattern: &String| match pattern.strip_suffix('*') {
+            Some(prefix) => function.starts_with(prefix),
+            None => pattern == function,
+        };
+
+        self.expose
+            .as_ref()
+            .is_none_or(|expose| expose.iter().any(matches))
+            && !self.hide.iter().any(matches)
+            && self.init.as_deref() != Some(function)
+            && self.on_shutdown.as_deref() != Some(function)
+    }
+
+    /// Fill in everything this component leaves unset from `base`
+    ///
+    /// Maps (`env`, `config` objects) are merged with this component's entries winning,
+    /// volumes are added unless this component mounts the same guest path, and switches such
+    /// as `network` keep this component's value when it sets one, so it can turn off a
+    /// capability `base` grants.
+    pub fn inherit(&mut self, base: &ComponentConfig) {
+        if self.path.is_none() && self.oci.is_none() {
+            self.path = base.path.clone();
+            self.oci = base.oci.clone();
+            self.sha256 = self.sha256.take().or_else(|| base.sha256.clone());
+        }
+
+        match (&mut self.config, &base.config) {
+            (Some(serde_json::Value::Object(config)), Some(serde_json::Value::Object(base))) => {
+                for (key, value) in base {
+                    config.entry(key.clone()).or_insert_with(|| value.clone());
+                }
+            }
+            (None, Some(base)) => self.config = Some(base.clone()),
+            _ => {}
+        }
+
+        for volume in &base.volumes {
+            if !self
+                .volumes
+                .iter()
+                .any(|v| v.guest_path == volume.guest_path)
+            {
+                self.volumes.push(volume.clone());
+            }
+        }
+
+        for (key, value) in &base.env {
+            self.env.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+        for (key, entry) in &base.env_from_keyring {
+            self.env_from_keyring
+                .entry(key.clone())
+                .or_insert_with(|| entry.clone());
+        }
+        for path in &base.env_from_file {
+            if !self.env_from_file.contains(path) {
+                self.env_from_file.push(path.clone());
+            }
+        }
+
+        if self.cwd.is_none() {
+            self.cwd = base.cwd.clone();
+        }
+        if self.description.is_none() {
+            self.description = base.description.clone();
+        }
+        if self.expose.is_none() {
+            self.expose = base.expose.clone();
+        }
+        for function in &base.hide {
+            if !self.hide.contains(function) {
+                self.hide.push(function.clone());
+            }
+        }
+        for (function, overrides) in &base.functions {
+            self.functions
+                .entry(function.clone())
+                .or_insert_with(|| overrides.clone());
+        }
+        if self.plugs.is_empty() {
+            self.plugs = base.plugs.clone();
+        }
+        self.network = self.network.or(base.network);
+        self.inherit_args = self.inherit_args.or(base.inherit_args);
+        self.inherit_env = self.inherit_env.or(base.inherit_env);
+        self.inherit_stdio = self.inherit_stdio.or(base.inherit_stdio);
+        if self.allowed_networks.is_empty() {
+            self.allowed_networks = base.allowed_networks.clone();
+        }
+        if self.allowed_hosts.is_none() {
+            self.allowed_hosts = base.allowed_hosts.clone();
+        }
+        if self.enabled.is_none() {
+            self.enabled = base.enabled;
+        }
+        if self.init.is_none() {
+            self.init = base.init.clone();
+        }
+        if self.init_failure.is_none() {
+            self.init_failure = base.init_failure;
+        }
+        if self.on_shutdown.is_none() {
+            self.on_shutdown = base.on_shutdown.clone();
+        }
+        if self.timeout_ms.is_none() {
+            self.timeout_ms = base.timeout_ms;
+        }
+        if self.call_context.is_none() {
+            self.call_context = base.call_context.clone();
+        }
+        self.scratch = self.scratch.or(base.scratch);
+        if self.warmup.is_empty() {
+            self.warmup = base.warmup.clone();
+        }
+        if self.smoke_calls.is_empty() {
+            self.smoke_calls = base.smoke_calls.clone();
+        }
+        if self.recovery.is_none() {
+            self.recovery = base.recovery.clone();
+        }
+        if self.watchdog.is_none() {
+            self.watchdog = base.watchdog.clone();
+        }
+        for (name, database) in &base.databases {
+            self.databases
+                .entry(name.clone())
+                .or_insert_with(|| database.clone());
+        }
+    }
+}
+
+/// Expand environment variables in every string of a YAML value
+fn interpolate_value(value: &mut serde_yaml::Value, lookup: &impl Fn(&str) -> Option<String>) {
+    match value {
+        serde_yaml::Value::String(string) if string.contains('$') => {
+            *string = interpolate(string, lookup);
+        }
+        serde_yaml::Value::Sequence(sequence) => {
+            for value in sequence {
+                interpolate_value(value, lookup);
+            }
+        }
+        serde_yaml::Value::Mapping(mapping) => {
+            for (_, value) in mapping.iter_mut() {
+                interpolate_value(value, lookup);
+            }
+        }
+        serde_yaml::Value::Tagged(tagged) => interpolate_value(&mut tagged.value, lookup),
+        _ => {}
+    }
+}
+
+/// Expand `${VAR}` and `${VAR:-default}` in a string
+///
+/// Unset variables without a default expand to an empty string, with a warning.
+fn interpolate(input: &str, lookup: &impl Fn(&str) -> Option<String>) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find('$') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if let Some(after) = rest.strip_prefix("$${") {
+            output.push_str("${");
+            rest = after;
+            continue;
+        }
+
+        let Some((name, default, after)) = parse_reference(rest) else {
+            output.push('$');
+            rest = &rest[1..];
+            continue;
+        };
+
+        match (lookup(name), default) {
+            (Some(value), Some(default)) if value.is_empty() => output.push_str(default),
+            (Some(value), _) => output.push_str(&value),
+            (None, Some(default)) => output.push_str(default),
+            (None, None) => {
+                tracing::warn!("Environment variable '{}' is not set", name);
+            }
+        }
+        rest = after;
+    }
+    output.push_str(rest);
+
+    output
+}
+
+/// Parse `${NAME}` or `${NAME:-default}` at the start of `input`, returning the name, the
+/// default and the remaining input
+fn parse_reference(input: &str) -> Option<(&str, Option<&str>, &str)> {
+    let body = input.strip_prefix("${")?;
+    let name_len = body
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(body.len());
+    let name = &body[..name_len];
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let tail = &body[name_len..];
+    if let Some(after) = tail.strip_prefix('}') {
+        return Some((name, None, after));
+    }
+    let default_and_rest = tail.strip_prefix(":-")?;
+    let end = default_and_rest.find('}')?;
+    Some((
+        name,
+        Some(&default_and_rest[..end]),
+        &default_and_rest[end + 1..],
+    ))
+}
+
+/// Resolve a path of the configuration file, expanding `~` and joining relative paths to `base`
+fn resolve_path(path: &str, base: &Path) -> PathBuf {
+    let home = || dirs::home_dir().unwrap_or_default();
+    if path == "~" {
+        home()
+    } else if let Some(rest) = path.strip_prefix("~/") {
+        home().join(rest)
+    } else {
+        base.join(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "HOME" => Some("/home/user".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_env_files_relative_to_config() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.yaml");
+        std::fs::write(
+            &config_path,
+            r#"
+components:
+  search:
+    path: search.wasm
+    env_from_file:
+      - brave.env
+      - /run/secrets/search.env
+      - ~/search.env
+    databases:
+      notes:
+        path: notes.sqlite
+      shared:
+        path: /var/lib/wasmic/shared.sqlite
+"#,
+        )
+        .unwrap();
+
+        let config = Config::from_file(&config_path).unwrap();
+        let home = dirs::home_dir().unwrap_or_default();
+        assert_eq!(
+            config.components["search"].env_from_file,
+            vec![
+                temp_dir.path().join("brave.env").display().to_string(),
+                "/run/secrets/search.env".to_string(),
+                home.join("search.env").display().to_string(),
+            ]
+        );
+        let databases = &config.components["search"].databases;
+        assert_eq!(
+            databases["notes"].path,
+            temp_dir.path().join("notes.sqlite").display().to_string()
+        );
+        assert_eq!(databases["shared"].path, "/var/lib/wasmic/shared.sqlite");
+    }
+
+    #[test]
+    fn test_starter_config_is_valid() {
+        let config = Config::parse(STARTER_CONFIG, lookup).unwrap();
+        assert!(config.components.contains_key("time"));
+
+        let temp_dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+        let path = temp_dir.path().join("wasmic").join("config.yaml");
+        Config::write_starter(&path, false).unwrap();
+        assert!(Config::write_starter(&path, false).is_err());
+        Config::write_starter(&path, true).unwrap();
+    }
+
+    #[test]
+    fn test_json_schema() {
+        let schema = Config::json_schema();
+        let properties = &schema["properties"];
+        assert!(properties.get("components").is_some());
+        assert!(properties.get("prompts").is_some());
+        assert!(schema.to_string().contains("guest_path"));
+    }
+
+    #[test]
+    fn test_tool_naming_config() {
+        let yaml = r#"
+components: {}
+tool_names:
+  separator: __
+  sanitize: true
+  max_length: 64
+  collisions: disambiguate
+"#;
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        let naming = config.tool_names.unwrap();
+        assert_eq!(naming.separator.as_deref(), Some("__"));
+        assert!(naming.sanitize);
+        assert_eq!(naming.max_length, Some(64));
+        assert_eq!(naming.collisions, Some(CollisionPolicy::Disambiguate));
+    }
+
+    #[test]
+    fn test_updates_config() {
+        let yaml = r#"
+components:
+  fetch:
+    oci: ghcr.io/dineshdb/wasi-components/fetch:latest
+    smoke_calls:
+      - function: fetch
+        args: {url: "https://example.com"}
+updates: {}
+"#;
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(config.updates.unwrap().interval_secs, 3600);
+        let smoke_calls = &config.components["fetch"].smoke_calls;
+        assert_eq!(smoke_calls.len(), 1);
+        assert_eq!(smoke_calls[0].function, "fetch");
+    }
+
+    #[test]
+    fn test_test_cases() {
+        let yaml = r#"
+components: {}
+tests:
+  - tool: time.now
+  - name: greets by name
+    tool: hello.greet
+    args: {name: World}
+    expect: "Hello, World!"
+  - tool: db.query
+    args: {sql: "select 1"}
+    assert:
+      - path: /rows/0
+        equals: [1]
+      - path: /columns
+        type: array
+  - tool: db.query
+    expect_error: validation
+"#;
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(config.tests.len(), 4);
+        assert_eq!(config.tests[0].display_name(), "time.now");
+        assert_eq!(config.tests[1].display_name(), "greets by name");
+        assert_eq!(config.tests[2].assertions[1].kind.as_deref(), Some("array"));
+        assert_eq!(config.tests[3].expect_error.as_deref(), Some("validation"));
+    }
+
+    #[test]
+    fn test_engine_config() {
+        let yaml = r#"
+engine:
+  opt_level: speed_and_size
+  max_wasm_stack: 4194304
+  parallel_compilation: false
+  yield_interval_ms: 10
+  pooling:
+    total_component_instances: 8
+    total_core_instances: 64
+    total_memories: 64
+    total_tables: 64
+    total_stacks: 8
+    max_memory_size: 67108864
+  coredump:
+    dir: /var/lib/wasmic/coredumps
+components: {}
+"#;
+        let config = Config::parse(yaml, lookup).unwrap();
+        let engine = config.engine.unwrap();
+        assert_eq!(engine.opt_level, Some(OptLevel::SpeedAndSize));
+        assert_eq!(
+            engine.coredump.as_ref().unwrap().dir.as_deref(),
+            Some(Path::new("/var/lib/wasmic/coredumps"))
+        );
+        assert_eq!(engine.coredump.as_ref().unwrap().max_dumps, 20);
+        assert!(crate::wasm::WasmContext::from_config(&engine, false).is_ok());
+    }
+
+    #[test]
+    fn test_extends_and_defaults() {
+        let yaml = r#"
+defaults:
+  env:
+    LOG_LEVEL: info
+    REGION: eu
+  volumes:
+    - host_path: /srv/shared
+      guest_path: /shared
+components:
+  base:
+    oci: ghcr.io/dineshdb/wasi-components/fetch:latest
+    config:
+      timeout: 30
+      retries: 3
+    allowed_hosts: ["api.example.com"]
+    inherit_env: true
+    warmup:
+      - function: fetch
+        args: {url: "https://api.example.com/health"}
+    databases:
+      catalog: {path: /srv/catalog.db, read_only: true}
+      cache: {path: /srv/base-cache.db}
+  child:
+    extends: base
+    config:
+      timeout: 5
+    env:
+      REGION: us
+    databases:
+      cache: {path: /srv/child-cache.db}
+"#;
+        let config = Config::parse(yaml, lookup).unwrap();
+        let child = &config.components["child"];
+        assert_eq!(
+            child.oci.as_deref(),
+            Some("ghcr.io/dineshdb/wasi-components/fetch:latest")
+        );
+        assert_eq!(
+            child.config,
+            Some(serde_json::json!({"timeout": 5, "retries": 3}))
+        );
+        assert_eq!(child.env["REGION"], "us");
+        assert_eq!(child.env["LOG_LEVEL"], "info");
+        assert_eq!(child.volumes.len(), 1);
+        assert_eq!(
+            child.allowed_hosts,
+            Some(vec!["api.example.com".to_string()])
+        );
+        assert_eq!(config.components["base"].env["REGION"], "eu");
+        // Host capabilities are only granted when asked for
+        assert_eq!(child.inherit_env, Some(true));
+        assert!(!child.inherit_args.unwrap_or_default());
+        assert!(!child.inherit_stdio.unwrap_or_default());
+        assert_eq!(child.warmup.len(), 1);
+        assert_eq!(child.warmup[0].function, "fetch");
+        assert_eq!(
+            child.warmup[0].args["url"],
+            serde_json::json!("https://api.example.com/health")
+        );
+        assert!(child.databases["catalog"].read_only);
+        assert_eq!(child.databases["cache"].path, "/srv/child-cache.db");
+        assert!(!child.databases["cache"].read_only);
+    }
+
+    #[test]
+    fn test_child_revokes_capabilities() {
+        let yaml = r#"
+defaults:
+  network: true
+  scratch: true
+components:
+  base:
+    path: ./fetch.wasm
+    inherit_env: true
+  child:
+    extends: base
+    network: false
+    inherit_env: false
+  sibling:
+    path: ./other.wasm
+"#;
+        let config = Config::parse(yaml, |_| None).unwrap();
+        let child = &config.components["child"];
+        assert_eq!(child.network, Some(false));
+        assert_eq!(child.inherit_env, Some(false));
+        assert_eq!(child.scratch, Some(true));
+        assert_eq!(config.components["base"].network, Some(true));
+        assert_eq!(config.components["base"].inherit_env, Some(true));
+        assert_eq!(config.components["sibling"].network, Some(true));
+    }
+
+    #[test]
+    fn test_expose_and_hide() {
+        let component = ComponentConfig {
+            expose: Some(vec!["read_*".to_string(), "list".to_string()]),
+            hide: vec!["read_secret".to_string()],
+            ..Default::default()
+        };
+        assert!(component.exposes("read_file"));
+        assert!(component.exposes("list"));
+        assert!(!component.exposes("read_secret"));
+        assert!(!component.exposes("delete_file"));
+
+        let everything = ComponentConfig::default();
+        assert!(everything.exposes("delete_file"));
+
+        // The init function only runs at load
+        let initialized = ComponentConfig {
+            init: Some("load_model".to_string()),
+            ..Default::default()
+        };
+        assert!(!initialized.exposes("load_model"));
+        assert!(initialized.exposes("classify"));
+
+        // So does the shutdown function, and `on_load` names the init function
+        let hooks: ComponentConfig =
+            serde_yaml::from_str("on_load: connect\non_shutdown: disconnect\ninit_failure: warn")
+                .unwrap();
+        assert_eq!(hooks.init.as_deref(), Some("connect"));
+        assert_eq!(hooks.init_failure, Some(InitFailure::Warn));
+        assert!(!hooks.exposes("connect"));
+        assert!(!hooks.exposes("disconnect"));
+    }
+
+    #[test]
+    fn test_profile() {
+        let yaml = r#"
+components:
+  time:
+    oci: ghcr.io/dineshdb/wasi-components/time:latest
+  fetch:
+    oci: ghcr.io/dineshdb/wasi-components/fetch:latest
+profiles:
+  tenant-a:
+    components: [time]
+  broken:
+    components: [time, missing]
+"#;
+        let config = Config::parse(yaml, lookup).unwrap();
+        let tenant = config.profile("tenant-a").unwrap();
+        assert_eq!(tenant.components.keys().collect::<Vec<_>>(), ["time"]);
+        assert!(tenant.profiles.is_empty());
+
+        assert!(config.profile("broken").is_err());
+        assert!(config.profile("unknown").is_err());
+    }
+
+    #[test]
+    fn test_extends_cycle_is_rejected() {
+        let yaml = r#"
+components:
+  a:
+    extends: b
+  b:
+    extends: a
+"#;
+        assert!(Config::parse(yaml, lookup).is_err());
+
+        let unknown = "components:\n  a:\n    extends: missing\n";
+        assert!(Config::parse(unknown, lookup).is_err());
+    }
+
+    #[test]
+    fn test_interpolate() {
+        assert_eq!(interpolate("${HOME}/data", &lookup), "/home/user/data");
+        assert_eq!(interpolate("${MISSING:-fallback}", &lookup), "fallback");
+        assert_eq!(interpolate("${EMPTY:-fallback}", &lookup), "fallback");
+        assert_eq!(interpolate("${MISSING}", &lookup), "");
+        assert_eq!(
+            interpolate("$${HOME} costs $5", &lookup),
+            "${HOME} costs $5"
+        );
+        // Secret references are resolved later and must survive interpolation
+        assert_eq!(interpolate("${secret:token}", &lookup), "${secret:token}");
+    }
+
+    #[test]
+    fn test_parse_interpolates_components_but_not_prompts() {
+        let yaml = r#"
+components:
+  time:
+    oci: ghcr.io/dineshdb/wasi-components/time:${TAG:-latest}
+    env:
+      DATA_DIR: ${HOME}/data
+prompts:
+  shell:
+    name: Shell
+    description: Shell help
+    content: echo ${HOME}
+"#;
+        let config = Config::parse(yaml, lookup).unwrap();
+        let time = &config.components["time"];
+        assert_eq!(
+            time.oci.as_deref(),
+            Some("ghcr.io/dineshdb/wasi-components/time:latest")
+        );
+        assert_eq!(time.env["DATA_DIR"], "/home/user/data");
+        assert_eq!(config.prompts["shell"].content, "echo ${HOME}");
+    }
 }
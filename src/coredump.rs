@@ -0,0 +1,153 @@
+//! Core dumps of guests that trapped
+//!
+//! With `engine.coredump` configured, wasmtime captures the guest's stack, memories and
+//! globals when a call traps. The executor saves them as a `.coredump` file in the standard
+//! Wasm core dump format, next to a `.json` file naming the tool, the hash of the arguments
+//! and the time of the call, so traps seen in production can be inspected offline, e.g.
+//! with `wasmgdb`. Only the newest `max_dumps` dumps are kept.
+
+use crate::config::CoreDumpConfig;
+use crate::error::{Result, WasiMcpError};
+use crate::utils::unix_now;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// What was called when a core dump was captured
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CoreDumpMetadata {
+    pub tool: String,
+    /// SHA-256 of the arguments, as recorded in the call history
+    pub args_hash: String,
+    pub call_id: u64,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trap: Option<String>,
+}
+
+impl CoreDumpMetadata {
+    pub fn new(tool: &str, args_hash: String, call_id: u64, trap: Option<String>) -> Self {
+        Self {
+            tool: tool.to_string(),
+            args_hash,
+            call_id,
+            timestamp: unix_now(),
+            trap,
+        }
+    }
+}
+
+/// Directory core dumps are saved in, by default in the user's data directory
+pub fn directory(config: &CoreDumpConfig) -> Result<PathBuf> {
+    match &config.dir {
+        Some(dir) => Ok(dir.clone()),
+        None => Ok(dirs::data_dir()
+            .ok_or_else(|| {
+                WasiMcpError::InvalidArguments("Could not determine data directory".to_string())
+            })?
+            .join("wasmic")
+            .join("coredumps")),
+    }
+}
+
+/// Core dump attached to the error of a trapped call, serialized from the store it ran in
+pub fn capture(
+    error: &anyhow::Error,
+    store: impl wasmtime::AsContextMut,
+    name: &str,
+) -> Option<Vec<u8>> {
+    let dump = error.downcast_ref::<wasmtime::WasmCoreDump>()?;
+    Some(dump.serialize(store, name))
+}
+
+/// Write a core dump and its metadata to `dir`, then remove the oldest dumps beyond
+/// `max_dumps`; returns the path of the dump
+pub fn save(
+    dir: &Path,
+    metadata: &CoreDumpMetadata,
+    dump: &[u8],
+    max_dumps: usize,
+) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let stem = format!(
+        "{}-{}-{}",
+        metadata.timestamp,
+        metadata.call_id,
+        crate::utils::file_safe(&metadata.tool)
+    );
+    let path = dir.join(format!("{stem}.coredump"));
+    std::fs::write(&path, dump)?;
+    std::fs::write(
+        dir.join(format!("{stem}.json")),
+        serde_json::to_vec_pretty(metadata)?,
+    )?;
+    prune(dir, max_dumps)?;
+    Ok(path)
+}
+
+/// Remove the oldest dumps in `dir`, and their metadata, until at most `max_dumps` are left
+fn prune(dir: &Path, max_dumps: usize) -> Result<()> {
+    let mut dumps = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "coredump")
+        {
+            // Named after the time of the call, so older dumps sort first
+            let timestamp = path
+                .file_stem()
+                .and_then(|stem| stem.to_str()?.split('-').next()?.parse::<u64>().ok())
+                .unwrap_or_default();
+            dumps.push((timestamp, path));
+        }
+    }
+    dumps.sort();
+    let excess = dumps.len().saturating_sub(max_dumps);
+    for (_, path) in dumps.into_iter().take(excess) {
+        std::fs::remove_file(&path)?;
+        let _ = std::fs::remove_file(path.with_extension("json"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_with_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let metadata = CoreDumpMetadata {
+            tool: "db.wasi:cli/run@0.2.0.run".to_string(),
+            args_hash: "abc".to_string(),
+            call_id: 7,
+            timestamp: 1_700_000_000,
+            trap: Some("unreachable".to_string()),
+        };
+
+        let path = save(dir.path(), &metadata, b"\0asm", 2).unwrap();
+        assert_eq!(
+            path,
+            dir.path()
+                .join("1700000000-7-db.wasi_cli_run_0.2.0.run.coredump")
+        );
+        assert_eq!(std::fs::read(&path).unwrap(), b"\0asm");
+        let saved: CoreDumpMetadata =
+            serde_json::from_slice(&std::fs::read(path.with_extension("json")).unwrap()).unwrap();
+        assert_eq!(saved, metadata);
+
+        // Only the newest dumps are kept
+        for (timestamp, call_id) in [(1_700_000_001, 8), (1_700_000_002, 9)] {
+            let metadata = CoreDumpMetadata {
+                timestamp,
+                call_id,
+                ..metadata.clone()
+            };
+            save(dir.path(), &metadata, b"\0asm", 2).unwrap();
+        }
+        assert!(!path.exists());
+        assert!(!path.with_extension("json").exists());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 4);
+    }
+}
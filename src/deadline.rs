@@ -0,0 +1,196 @@
+//! Epoch ticks and per-call deadlines
+//!
+//! A single background thread advances the engine's epoch. Every store checks in with the
+//! [`DeadlineManager`] on each tick: a call past its timeout, or one that was cancelled,
+//! is interrupted with a trap, and otherwise the guest keeps running (or yields to the
+//! async runtime, when `yield_interval_ms` is configured).
+
+use crate::state::ComponentRunStates;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use wasmtime::{Engine, Store, Trap, UpdateDeadline};
+
+/// Epoch tick when no yield interval is configured; the resolution of call timeouts
+pub const DEFAULT_TICK: Duration = Duration::from_millis(10);
+
+/// Deadlines of running calls, checked by every store on each epoch tick
+#[derive(Clone)]
+pub struct DeadlineManager {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    tick: Duration,
+    yield_on_tick: bool,
+    calls: Mutex<HashMap<u64, CallDeadline>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CallDeadline {
+    expires: Option<Instant>,
+    cancelled: bool,
+}
+
+impl DeadlineManager {
+    /// Advance the epoch of `engine` every `tick` until the engine is dropped
+    ///
+    /// The engine must have epoch interruption enabled.
+    pub fn start(engine: &Engine, tick: Duration, yield_on_tick: bool) -> std::io::Result<Self> {
+        let engine = engine.weak();
+        std::thread::Builder::new()
+            .name("wasmic-epoch".to_string())
+            .spawn(move || {
+                loop {
+                    std::thread::sleep(tick);
+                    match engine.upgrade() {
+                        Some(engine) => engine.increment_epoch(),
+                        None => break,
+                    }
+                }
+            })?;
+        Ok(Self {
+            inner: Arc::new(Inner {
+                tick,
+                yield_on_tick,
+                calls: Mutex::default(),
+            }),
+        })
+    }
+
+    /// Time between epoch ticks
+    pub fn tick(&self) -> Duration {
+        self.inner.tick
+    }
+
+    /// Check the deadline of the store's current call on every tick
+    pub fn configure(&self, store: &mut Store<ComponentRunStates>) {
+        let manager = self.clone();
+        store.set_epoch_deadline(1);
+        store.epoch_deadline_callback(move |context| manager.check(context.data().stdio.call_id()));
+    }
+
+    /// Track the call `call_id` until the returned guard is dropped, interrupting it once
+    /// `timeout` has passed
+    pub fn begin(&self, call_id: u64, timeout: Option<Duration>) -> DeadlineGuard {
+        self.calls().insert(
+            call_id,
+            CallDeadline {
+                expires: timeout.map(|timeout| Instant::now() + timeout),
+                cancelled: false,
+            },
+        );
+        DeadlineGuard {
+            manager: self.clone(),
+            call_id,
+        }
+    }
+
+    /// Interrupt the call `call_id` at the next tick; false when it isn't running
+    pub fn cancel(&self, call_id: u64) -> bool {
+        match self.calls().get_mut(&call_id) {
+            Some(call) => {
+                call.cancelled = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Interrupt every running call at the next tick, returning how many were running
+    pub fn cancel_all(&self) -> usize {
+        let mut calls = self.calls();
+        for call in calls.values_mut() {
+            call.cancelled = true;
+        }
+        calls.len()
+    }
+
+    /// Number of calls being tracked
+    pub fn running(&self) -> usize {
+        self.calls().len()
+    }
+
+    fn calls(&self) -> std::sync::MutexGuard<'_, HashMap<u64, CallDeadline>> {
+        // The map stays consistent even if a holder panicked
+        self.inner
+            .calls
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn check(&self, call_id: u64) -> anyhow::Result<UpdateDeadline> {
+        let interrupted = self.calls().get(&call_id).is_some_and(|call| {
+            call.cancelled
+                || call
+                    .expires
+                    .is_some_and(|expires| Instant::now() >= expires)
+        });
+        if interrupted {
+            return Err(Trap::Interrupt.into());
+        }
+        Ok(if self.inner.yield_on_tick {
+            // Hand the thread back to the runtime, then keep running
+            UpdateDeadline::Yield(1)
+        } else {
+            UpdateDeadline::Continue(1)
+        })
+    }
+}
+
+/// Stops tracking a call's deadline when dropped
+pub struct DeadlineGuard {
+    manager: DeadlineManager,
+    call_id: u64,
+}
+
+impl Drop for DeadlineGuard {
+    fn drop(&mut self) {
+        self.manager.calls().remove(&self.call_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager() -> DeadlineManager {
+        let mut config = wasmtime::Config::new();
+        config.epoch_interruption(true);
+        let engine = Engine::new(&config).unwrap();
+        DeadlineManager::start(&engine, DEFAULT_TICK, false).unwrap()
+    }
+
+    fn interrupted(manager: &DeadlineManager, call_id: u64) -> bool {
+        manager.check(call_id).is_err()
+    }
+
+    #[test]
+    fn test_timeout() {
+        let manager = manager();
+        let _expired = manager.begin(1, Some(Duration::ZERO));
+        let _running = manager.begin(2, Some(Duration::from_secs(60)));
+        let _unlimited = manager.begin(3, None);
+        assert!(interrupted(&manager, 1));
+        assert!(!interrupted(&manager, 2));
+        assert!(!interrupted(&manager, 3));
+        // Untracked calls, such as initialization, are never interrupted
+        assert!(!interrupted(&manager, 4));
+    }
+
+    #[test]
+    fn test_cancel() {
+        let manager = manager();
+        let guard = manager.begin(1, None);
+        let _other = manager.begin(2, None);
+        assert!(manager.cancel(1));
+        assert!(interrupted(&manager, 1));
+        assert!(!interrupted(&manager, 2));
+
+        drop(guard);
+        assert_eq!(manager.running(), 1);
+        assert!(!manager.cancel(1));
+        assert_eq!(manager.cancel_all(), 1);
+        assert!(interrupted(&manager, 2));
+    }
+}
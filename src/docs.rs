@@ -0,0 +1,147 @@
+//! Documentation embedded in components
+//!
+//! cargo-component and wit-bindgen keep the doc comments of the WIT a component was built
+//! from in its `package-docs` custom section. A function's docs become its tool description,
+//! and lines documenting a parameter, `- name: text` or ``- `name`: text``, become that
+//! parameter's description.
+
+use std::collections::HashMap;
+use std::path::Path;
+use wit_parser::{Function, Resolve, WorldItem};
+
+/// Docs of one exported function
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FunctionDocs {
+    /// The doc comment without the parameter lines
+    pub description: Option<String>,
+    /// Parameter descriptions by parameter name
+    pub params: HashMap<String, String>,
+}
+
+/// Docs of a component's exported functions, keyed like [`crate::wasm::FunctionInfo::name`]
+#[derive(Debug, Clone, Default)]
+pub struct ComponentDocs {
+    functions: HashMap<String, FunctionDocs>,
+}
+
+impl ComponentDocs {
+    /// Docs of the component in `bytes`; empty when it carries none or isn't a component
+    pub fn decode(bytes: &[u8]) -> Self {
+        let (resolve, world) = match wit_component::decode(bytes) {
+            Ok(wit_component::DecodedWasm::Component(resolve, world)) => (resolve, world),
+            Ok(wit_component::DecodedWasm::WitPackage(..)) => return Self::default(),
+            Err(e) => {
+                tracing::debug!("No WIT docs: {e:#}");
+                return Self::default();
+            }
+        };
+
+        let mut functions = HashMap::new();
+        for (key, item) in &resolve.worlds[world].exports {
+            match item {
+                WorldItem::Function(function) => {
+                    insert(&mut functions, function.name.clone(), function);
+                }
+                WorldItem::Interface { id, .. } => {
+                    let interface = resolve.name_world_key(key);
+                    for function in resolve.interfaces[*id].functions.values() {
+                        insert(
+                            &mut functions,
+                            format!("{interface}.{}", function.name),
+                            function,
+                        );
+                    }
+                }
+                WorldItem::Type(_) => {}
+            }
+        }
+        Self { functions }
+    }
+
+    /// Docs of the component file at `path`
+    pub fn read(path: &Path) -> Self {
+        match std::fs::read(path) {
+            Ok(bytes) => Self::decode(&bytes),
+            Err(e) => {
+                tracing::debug!("No WIT docs for {path:?}: {e}");
+                Self::default()
+            }
+        }
+    }
+
+    /// Docs of the function `name`, `interface.function` for interface functions
+    pub fn function(&self, name: &str) -> Option<&FunctionDocs> {
+        self.functions.get(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+}
+
+fn insert(functions: &mut HashMap<String, FunctionDocs>, name: String, function: &Function) {
+    let Some(contents) = &function.docs.contents else {
+        return;
+    };
+    let params: Vec<&str> = function
+        .params
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect();
+    functions.insert(name, parse(contents, &params));
+}
+
+/// Split a doc comment into the description and the descriptions of `params`
+pub fn parse(contents: &str, params: &[&str]) -> FunctionDocs {
+    let mut docs = FunctionDocs::default();
+    let mut description = Vec::new();
+    for line in contents.lines() {
+        match param_line(line, params) {
+            Some((name, text)) => {
+                docs.params.insert(name.to_string(), text.to_string());
+            }
+            None => description.push(line),
+        }
+    }
+    let description = description.join("\n");
+    let description = description.trim();
+    if !description.is_empty() {
+        docs.description = Some(description.to_string());
+    }
+    docs
+}
+
+/// `- name: text` or ``* `name`: text`` for a known parameter
+fn param_line<'a>(line: &'a str, params: &[&str]) -> Option<(&'a str, &'a str)> {
+    let item = line.trim().strip_prefix(['-', '*'])?.trim_start();
+    let (name, text) = item.split_once(':')?;
+    let name = name.trim().trim_matches('`');
+    let text = text.trim();
+    (params.contains(&name) && !text.is_empty()).then_some((name, text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let docs = parse(
+            "Search the index.\n\n- `query`: Words to look for\n* limit: Most results returned\n- note: not a parameter",
+            &["query", "limit"],
+        );
+        assert_eq!(
+            docs.description.as_deref(),
+            Some("Search the index.\n\n- note: not a parameter")
+        );
+        assert_eq!(docs.params["query"], "Words to look for");
+        assert_eq!(docs.params["limit"], "Most results returned");
+    }
+
+    #[test]
+    fn test_decode_without_docs() {
+        let component = wat::parse_str("(component)").unwrap();
+        assert!(ComponentDocs::decode(&component).is_empty());
+        assert!(ComponentDocs::decode(b"not wasm").is_empty());
+    }
+}
@@ -0,0 +1,111 @@
+//! Dry runs of tool calls
+//!
+//! A dry run goes through everything a call does before the guest runs: resolving the tool
+//! name, applying configured argument values, schema validation, mapping named arguments to
+//! positions and converting them to component values of the function's WIT types. It reports
+//! what would be executed, or the error the call would fail with.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+
+/// Metadata key of an MCP `tools/call` request asking for a dry run instead of the call
+pub const DRY_RUN_META_KEY: &str = "wasmic/dryRun";
+
+/// What a call would execute
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DryRun {
+    /// Internal name of the tool, `component.function`
+    pub tool: String,
+    pub component: String,
+    /// Export that would be called
+    pub function: String,
+    /// Whether the call would get a fresh instance, because of per-call overrides or
+    /// because the component is a command
+    pub fresh_instance: bool,
+    /// Arguments in the order they are passed to the export
+    pub arguments: Vec<DryRunArgument>,
+    /// JSON schemas of the results
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub results: Vec<Value>,
+}
+
+/// An argument as the guest would receive it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DryRunArgument {
+    pub name: String,
+    /// WIT type of the parameter; unset for the arguments of commands
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wit_type: Option<String>,
+    /// Value after configured values and defaults were applied
+    pub value: Value,
+    /// The converted component value; unset for the arguments of commands
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub converted: Option<String>,
+}
+
+impl fmt::Display for DryRun {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Would call {} of component {}",
+            self.function, self.component
+        )?;
+        if self.fresh_instance {
+            write!(f, " in a fresh instance")?;
+        }
+        writeln!(f)?;
+        for argument in &self.arguments {
+            write!(f, "  {}", argument.name)?;
+            if let Some(wit_type) = &argument.wit_type {
+                write!(f, ": {wit_type}")?;
+            }
+            write!(f, " = {}", argument.value)?;
+            if let Some(converted) = &argument.converted {
+                write!(f, " -> {converted}")?;
+            }
+            writeln!(f)?;
+        }
+        for result in &self.results {
+            writeln!(f, "Returns {result}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let dry_run = DryRun {
+            tool: "db.query".to_string(),
+            component: "db".to_string(),
+            function: "query".to_string(),
+            fresh_instance: false,
+            arguments: vec![
+                DryRunArgument {
+                    name: "sql".to_string(),
+                    wit_type: Some("string".to_string()),
+                    value: Value::from("select 1"),
+                    converted: Some(r#"String("select 1")"#.to_string()),
+                },
+                DryRunArgument {
+                    name: "limit".to_string(),
+                    wit_type: Some("option<u32>".to_string()),
+                    value: Value::Null,
+                    converted: Some("Option(None)".to_string()),
+                },
+            ],
+            results: vec![serde_json::json!({"type": "string"})],
+        };
+        assert_eq!(
+            dry_run.to_string(),
+            "Would call query of component db\n  \
+             sql: string = \"select 1\" -> String(\"select 1\")\n  \
+             limit: option<u32> = null -> Option(None)\n\
+             Returns {\"type\":\"string\"}\n"
+        );
+    }
+}
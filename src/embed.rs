@@ -0,0 +1,311 @@
+//! Builder-style API for embedding wasmic in another application
+//!
+//! ```no_run
+//! # async fn example() -> wasmic::Result<()> {
+//! use std::collections::HashMap;
+//! use wasmic::Wasmic;
+//!
+//! let wasmic = Wasmic::builder()
+//!     .component("time", "time.wasm")
+//!     .oci("fetch", "ghcr.io/dineshdb/wasmic/fetch:latest")
+//!     .http("127.0.0.1", 8080)
+//!     .build()
+//!     .await?;
+//! let now = wasmic.call("time.now", HashMap::new()).await?;
+//! wasmic.serve().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::cache::cache_dir;
+use crate::config::{ComponentConfig, Config};
+use crate::error::{Result, WasiMcpError};
+use crate::hooks::CallHook;
+use crate::host::HostExtension;
+use crate::mcp::WasmMcpServer;
+use crate::server::{McpTransport, ServerManager};
+use crate::wasm::WasmContext;
+use rmcp::model::Tool;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Where a component added to the builder comes from
+enum Source {
+    Config(ComponentConfig),
+    Bytes(Vec<u8>),
+}
+
+/// Collects components, transports and host interfaces for a [`Wasmic`] instance
+pub struct WasmicBuilder {
+    config: Config,
+    components: Vec<(String, Source)>,
+    extensions: Vec<Box<dyn FnOnce(&mut WasmContext) -> anyhow::Result<()> + Send>>,
+    hooks: Vec<Arc<dyn CallHook>>,
+    transport: McpTransport,
+    grace_period: Duration,
+    grpc: Option<SocketAddr>,
+}
+
+impl Default for WasmicBuilder {
+    fn default() -> Self {
+        Self {
+            // Parsed like a file, so settings get the defaults serde fills in
+            config: Config::parse("{}", |_| None).expect("an empty configuration is valid"),
+            components: Vec::new(),
+            extensions: Vec::new(),
+            hooks: Vec::new(),
+            transport: McpTransport::Http {
+                host: "127.0.0.1".to_string(),
+                port: 8080,
+                rest: false,
+            },
+            grace_period: Duration::from_secs(30),
+            grpc: None,
+        }
+    }
+}
+
+impl WasmicBuilder {
+    /// Start from an existing configuration, e.g. one read with [`Config::from_file`];
+    /// components added to the builder are merged into it
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Add a component from a local `.wasm` file
+    pub fn component(self, name: impl Into<String>, path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref().to_string_lossy();
+        self.component_config(name, component_config(json!({ "path": path })))
+    }
+
+    /// Add a component pulled from an OCI registry
+    pub fn oci(self, name: impl Into<String>, reference: impl Into<String>) -> Self {
+        self.component_config(name, component_config(json!({ "oci": reference.into() })))
+    }
+
+    /// Add a component from its binary, e.g. one embedded with `include_bytes!`
+    pub fn component_bytes(mut self, name: impl Into<String>, bytes: impl Into<Vec<u8>>) -> Self {
+        self.components
+            .push((name.into(), Source::Bytes(bytes.into())));
+        self
+    }
+
+    /// Add a component with every setting of the configuration file available
+    ///
+    /// Like components of the file, it inherits from the component it `extends` and from the
+    /// configuration's `defaults`.
+    pub fn component_config(mut self, name: impl Into<String>, config: ComponentConfig) -> Self {
+        self.components.push((name.into(), Source::Config(config)));
+        self
+    }
+
+    /// Provide additional host interfaces to every component
+    pub fn extension(mut self, extension: impl HostExtension + 'static) -> Self {
+        self.extensions
+            .push(Box::new(move |context: &mut WasmContext| {
+                context.register_extension(extension)
+            }));
+        self
+    }
+
+    /// Run `hook` around every tool call
+    pub fn hook(mut self, hook: impl CallHook + 'static) -> Self {
+        self.hooks.push(Arc::new(hook));
+        self
+    }
+
+    /// Serve MCP over streamable HTTP on `host:port` (default: `127.0.0.1:8080`)
+    pub fn http(mut self, host: impl Into<String>, port: u16) -> Self {
+        let McpTransport::Http { rest, .. } = self.transport;
+        self.transport = McpTransport::Http {
+            host: host.into(),
+            port,
+            rest,
+        };
+        self
+    }
+
+    /// Also serve every tool at `POST /tools/{component}/{function}`
+    pub fn rest(mut self, enabled: bool) -> Self {
+        let McpTransport::Http { rest, .. } = &mut self.transport;
+        *rest = enabled;
+        self
+    }
+
+    /// Also serve the gRPC interface on `address` (requires the `grpc` feature)
+    pub fn grpc(mut self, address: SocketAddr) -> Self {
+        self.grpc = Some(address);
+        self
+    }
+
+    /// How long running tool calls may take to finish on shutdown (default: 30 seconds)
+    pub fn grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
+        self
+    }
+
+    /// Load every component
+    pub async fn build(self) -> Result<Wasmic> {
+        let mut context =
+            WasmContext::from_config(&self.config.engine.clone().unwrap_or_default(), false)?;
+        for register in self.extensions {
+            register(&mut context)?;
+        }
+
+        let mut components = Vec::with_capacity(self.components.len());
+        for (name, source) in self.components {
+            let component = match source {
+                Source::Config(component) => component,
+                Source::Bytes(bytes) => {
+                    let (path, sha256) = store_bytes(&cache_dir()?.join("embedded"), &bytes)?;
+                    let path = path.to_string_lossy();
+                    component_config(json!({ "path": path, "sha256": sha256 }))
+                }
+            };
+            components.push((name, component));
+        }
+        let mut config = self.config;
+        add_components(&mut config, components)?;
+
+        let server = ServerManager::build_server(config, context).await?;
+        for hook in self.hooks {
+            server.add_hook(hook).await;
+        }
+        Ok(Wasmic {
+            server,
+            transport: self.transport,
+            grace_period: self.grace_period,
+            grpc: self.grpc,
+        })
+    }
+}
+
+/// A component configuration as the configuration file would give it, with the defaults
+/// serde fills in for everything `value` leaves out
+fn component_config(value: Value) -> ComponentConfig {
+    serde_json::from_value(value).expect("component settings built by the builder are valid")
+}
+
+/// Merge the builder's components into `config` and resolve their inheritance
+fn add_components(config: &mut Config, components: Vec<(String, ComponentConfig)>) -> Result<()> {
+    let mut names = Vec::with_capacity(components.len());
+    for (name, component) in components {
+        if config.components.insert(name.clone(), component).is_some() {
+            return Err(WasiMcpError::InvalidArguments(format!(
+                "Component '{name}' is added more than once"
+            )));
+        }
+        names.push(name);
+    }
+    config.resolve_inheritance_of(&names)
+}
+
+/// Write a component binary to `dir`, named by its SHA-256 so the compiled-component cache
+/// can be reused across runs
+fn store_bytes(dir: &Path, bytes: &[u8]) -> Result<(PathBuf, String)> {
+    let sha256 = format!("{:x}", Sha256::digest(bytes));
+    let path = dir.join(format!("{sha256}.wasm"));
+    if !path.exists() {
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(&path, bytes)?;
+    }
+    Ok((path, sha256))
+}
+
+/// Loaded components, ready to be called directly or served over MCP
+pub struct Wasmic {
+    server: WasmMcpServer,
+    transport: McpTransport,
+    grace_period: Duration,
+    grpc: Option<SocketAddr>,
+}
+
+impl Wasmic {
+    pub fn builder() -> WasmicBuilder {
+        WasmicBuilder::default()
+    }
+
+    /// Tools of all loaded components
+    pub async fn tools(&self) -> Result<Vec<Tool>> {
+        self.server.executor.lock().await.get_all_tools()
+    }
+
+    /// Call a tool, named `component.function`
+    pub async fn call(&self, tool: &str, args: HashMap<String, Value>) -> Result<Value> {
+        self.server
+            .executor
+            .lock()
+            .await
+            .execute_function(tool, args)
+            .await
+    }
+
+    /// Serve the components on the configured transports until Ctrl-C or SIGTERM
+    pub async fn serve(self) -> Result<()> {
+        ServerManager::serve(self.server, self.transport, self.grace_period, self.grpc).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let (path, sha256) = store_bytes(dir.path(), b"\0asm").unwrap();
+        assert_eq!(path, dir.path().join(format!("{sha256}.wasm")));
+        assert_eq!(std::fs::read(&path).unwrap(), b"\0asm");
+
+        // The same binary maps to the same file
+        assert_eq!(store_bytes(dir.path(), b"\0asm").unwrap().0, path);
+    }
+
+    #[test]
+    fn test_added_components_inherit_defaults() {
+        let mut config = Config::parse(
+            "defaults:\n  network: true\ncomponents:\n  base:\n    path: base.wasm\n    env: {LEVEL: debug}\n",
+            |_| None,
+        )
+        .unwrap();
+        let derived = ComponentConfig {
+            extends: Some("base".to_string()),
+            ..component_config(json!({ "path": "derived.wasm" }))
+        };
+        add_components(
+            &mut config,
+            vec![
+                (
+                    "time".to_string(),
+                    component_config(json!({ "path": "time.wasm" })),
+                ),
+                ("derived".to_string(), derived),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(config.components["time"].network, Some(true));
+        assert_eq!(config.components["derived"].network, Some(true));
+        assert_eq!(config.components["derived"].env["LEVEL"], "debug");
+        assert_eq!(
+            config.components["derived"].path.as_deref(),
+            Some("derived.wasm")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_component() {
+        let result = Wasmic::builder()
+            .component("time", "time.wasm")
+            .oci("time", "ghcr.io/example/time:latest")
+            .build()
+            .await;
+        assert!(matches!(result, Err(WasiMcpError::InvalidArguments(_))));
+    }
+}
@@ -1,3 +1,5 @@
+use crate::validation::Violation;
+use serde::Serialize;
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, WasiMcpError>;
@@ -25,18 +27,179 @@ pub enum WasiMcpError {
     #[error("Component not found: {0}")]
     ComponentNotFound(String),
 
+    #[error("Component '{0}' is disabled")]
+    ComponentDisabled(String),
+
     #[error("Execution error: {0}")]
     Execution(String),
 
     #[error("Invalid arguments: {0}")]
     InvalidArguments(String),
 
+    #[error("Invalid arguments: {}", join_violations(.0))]
+    ArgumentViolations(Vec<Violation>),
+
     #[error("Expected {0}, got: {1}")]
     UnexpectedExpected(String, String),
+
+    #[error("OCI registry error: {0}")]
+    Oci(String),
+
+    #[error("Component exited with code {0}")]
+    Exit(i32),
+}
+
+/// Stable, machine-readable category of a [`WasiMcpError`], for clients that branch on the
+/// kind of failure instead of parsing messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// Malformed tool names, arguments or configuration
+    Validation,
+    /// Unknown component, interface or function
+    NotFound,
+    /// The guest trapped
+    Trap,
+    /// The call was interrupted for taking too long
+    Timeout,
+    /// The guest ran out of fuel, stack or memory
+    ResourceLimit,
+    /// Pulling or resolving an OCI artifact failed
+    Oci,
+    /// A command-style component exited with a non-zero code
+    Exit,
+    /// The component is disabled by the configuration or an operator
+    Disabled,
+    Io,
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::Validation => "validation",
+            ErrorCode::NotFound => "not_found",
+            ErrorCode::Trap => "trap",
+            ErrorCode::Timeout => "timeout",
+            ErrorCode::ResourceLimit => "resource_limit",
+            ErrorCode::Oci => "oci",
+            ErrorCode::Exit => "exit",
+            ErrorCode::Disabled => "disabled",
+            ErrorCode::Io => "io",
+            ErrorCode::Internal => "internal",
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl WasiMcpError {
+    /// Category of the error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            WasiMcpError::Component(e) => match e.downcast_ref::<wasmtime::Trap>() {
+                Some(wasmtime::Trap::Interrupt) => ErrorCode::Timeout,
+                Some(wasmtime::Trap::OutOfFuel | wasmtime::Trap::StackOverflow) => {
+                    ErrorCode::ResourceLimit
+                }
+                Some(_) => ErrorCode::Trap,
+                None if e.downcast_ref::<std::io::Error>().is_some() => ErrorCode::Io,
+                None => ErrorCode::Internal,
+            },
+            WasiMcpError::Io(_) => ErrorCode::Io,
+            WasiMcpError::Json(_)
+            | WasiMcpError::InvalidArguments(_)
+            | WasiMcpError::ArgumentViolations(_)
+            | WasiMcpError::UnexpectedExpected(_, _) => ErrorCode::Validation,
+            WasiMcpError::FunctionNotFound(_)
+            | WasiMcpError::InterfaceNotFound(_)
+            | WasiMcpError::ComponentNotFound(_) => ErrorCode::NotFound,
+            WasiMcpError::Oci(_) => ErrorCode::Oci,
+            WasiMcpError::Exit(_) => ErrorCode::Exit,
+            WasiMcpError::ComponentDisabled(_) => ErrorCode::Disabled,
+            WasiMcpError::Mcp(_) | WasiMcpError::Execution(_) => ErrorCode::Internal,
+        }
+    }
+
+    /// The error as `{"error": message, "code": category}`, plus `exit_code` for exits and
+    /// `violations` for arguments that don't match the input schema
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut json = serde_json::json!({ "error": self.to_string(), "code": self.code() });
+        match self {
+            WasiMcpError::Exit(code) => json["exit_code"] = (*code).into(),
+            WasiMcpError::ArgumentViolations(violations) => {
+                json["violations"] = serde_json::json!(violations);
+            }
+            _ => {}
+        }
+        json
+    }
+}
+
+fn join_violations(violations: &[Violation]) -> String {
+    violations
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
 }
 
 impl From<WasiMcpError> for rmcp::ErrorData {
     fn from(err: WasiMcpError) -> Self {
-        rmcp::ErrorData::internal_error(err.to_string(), None)
+        rmcp::ErrorData::internal_error(
+            err.to_string(),
+            Some(serde_json::json!({ "code": err.code() })),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes() {
+        assert_eq!(
+            WasiMcpError::ComponentDisabled("db".to_string()).code(),
+            ErrorCode::Disabled
+        );
+        let trap = |trap: wasmtime::Trap| WasiMcpError::Component(wasmtime::Error::new(trap));
+        assert_eq!(
+            trap(wasmtime::Trap::UnreachableCodeReached).code(),
+            ErrorCode::Trap
+        );
+        assert_eq!(trap(wasmtime::Trap::Interrupt).code(), ErrorCode::Timeout);
+        assert_eq!(
+            trap(wasmtime::Trap::OutOfFuel).code(),
+            ErrorCode::ResourceLimit
+        );
+        assert_eq!(
+            WasiMcpError::FunctionNotFound("f".to_string()).code(),
+            ErrorCode::NotFound
+        );
+
+        let json = WasiMcpError::Exit(2).to_json();
+        assert_eq!(json["code"], "exit");
+        assert_eq!(json["exit_code"], 2);
+
+        let json = WasiMcpError::InvalidArguments("x".to_string()).to_json();
+        assert_eq!(json["code"], "validation");
+        assert_eq!(json["error"], "Invalid arguments: x");
+
+        let json = WasiMcpError::ArgumentViolations(vec![Violation {
+            pointer: "/limit".to_string(),
+            message: "\"ten\" is not of type \"integer\"".to_string(),
+        }])
+        .to_json();
+        assert_eq!(json["code"], "validation");
+        assert_eq!(
+            json["error"],
+            "Invalid arguments: /limit: \"ten\" is not of type \"integer\""
+        );
+        assert_eq!(json["violations"][0]["pointer"], "/limit");
     }
 }
@@ -1,16 +1,63 @@
-use crate::config::{ComponentConfig, Config};
+use crate::call_context::{self, CTX_ARGUMENT, CallContext};
+use crate::command::{self, RUN_TOOL};
+use crate::config::{
+    ComponentConfig, Config, CoreDumpConfig, FunctionConfig, Prompt, WatchdogAction,
+};
+use crate::coredump::{self, CoreDumpMetadata};
+use crate::deadline::DeadlineManager;
+use crate::dry_run::{DryRun, DryRunArgument};
 use crate::error::{Result, WasiMcpError};
-use crate::utils::transform::{convert_args_to_wasm_values, convert_wasm_results_to_json};
-use crate::wasm::{FunctionInfo, WasmComponent, WasmContext};
+use crate::explain::{self, Explanation, ParameterContract};
+use crate::history::{HistoryEntry, HistoryRecorder, arguments_hash};
+use crate::hooks::CallHook;
+use crate::naming::ToolNames;
+use crate::recovery::Recovery;
+use crate::replay::{Recording, Tape};
+use crate::resources::{ComponentInventory, Inventory};
+use crate::result_cache::ResultCache;
+use crate::stats::{RuntimeStats, StatsHandle};
+use crate::utils::transform::{
+    ConversionOptions, convert_args_to_wasm_values, convert_wasm_results_to_json, result_error,
+    structure_results,
+};
+use crate::validation::ArgumentValidator;
+use crate::wasm::{FunctionInfo, ResourceUsage, TrapDetails, WasmComponent, WasmContext};
+use crate::watchdog::Watchdog;
 use serde_json::Value;
-use std::collections::HashMap;
-use std::time::Instant;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
 use tracing::instrument;
+use wasmtime::component::Val;
+
+/// Source of the IDs tagging guest output with the call that produced it
+static NEXT_CALL_ID: AtomicU64 = AtomicU64::new(1);
 
 pub struct WasmExecutor {
     context: WasmContext,
     components: HashMap<String, WasmComponent>,
     config: Config,
+    stats: StatsHandle,
+    results: ResultCache,
+    hooks: Vec<Arc<dyn CallHook>>,
+    history: Option<HistoryRecorder>,
+    /// Directory calls are recorded in for replay
+    recording: Option<PathBuf>,
+    /// Compiled input schemas by tool name
+    validators: HashMap<String, Option<ArgumentValidator>>,
+    /// Public names of the tools of every component
+    tool_names: ToolNames,
+    /// Failure streaks of components, for re-instantiating them
+    recovery: Recovery,
+    /// Call time of the current instances, for evicting them
+    watchdog: Watchdog,
+    /// Loaded components an operator or the watchdog disabled at runtime
+    disabled: HashSet<String>,
+    /// Signalled when the executor changes its tool list by itself
+    tools_changed: Arc<Notify>,
 }
 
 impl WasmExecutor {
@@ -19,22 +66,338 @@ impl WasmExecutor {
             context,
             components: HashMap::new(),
             config,
+            stats: StatsHandle::default(),
+            results: ResultCache::default(),
+            hooks: Vec::new(),
+            history: None,
+            recording: None,
+            validators: HashMap::new(),
+            tool_names: ToolNames::default(),
+            recovery: Recovery::default(),
+            watchdog: Watchdog::default(),
+            disabled: HashSet::new(),
+            tools_changed: Arc::default(),
         })
     }
 
     #[instrument(level = "debug", skip(self, config), fields(name, tools))]
     pub async fn add_component(&mut self, name: String, config: ComponentConfig) -> Result<()> {
-        let component = WasmComponent::new(
-            name.clone(),
-            self.context.engine.clone(),
-            config,
-            &mut self.context.linker,
-        )
-        .await?;
-        self.components.insert(name, component);
+        let component = WasmComponent::new(name.clone(), config, &self.context).await?;
+        self.install_component(name.clone(), component).await?;
+        self.warm_up(&name).await;
         Ok(())
     }
 
+    /// Swap in a loaded component, e.g. one staged by [`WasmExecutor::stage_component`]
+    ///
+    /// Calls hold the executor, so no call is running on the instance this replaces; its
+    /// shutdown function runs after the swap.
+    pub async fn install_component(
+        &mut self,
+        name: String,
+        component: WasmComponent,
+    ) -> Result<()> {
+        self.forget_instance(&name);
+        let replaced = self.components.insert(name.clone(), component);
+        // A component whose tools collide with loaded ones is refused
+        if let Err(e) = self.index_tool_names() {
+            match replaced {
+                Some(replaced) => self.components.insert(name, replaced),
+                None => self.components.remove(&name),
+            };
+            self.index_tool_names()?;
+            return Err(e);
+        }
+        if let Some(mut replaced) = replaced {
+            replaced.shutdown().await;
+        }
+        Ok(())
+    }
+
+    /// Apply a reloaded configuration: drop the `removed` components, swap in the `staged`
+    /// ones and use `config` from now on
+    ///
+    /// The tool names of the resulting components are checked first, so a collision leaves
+    /// the executor as it was.
+    pub async fn reload(
+        &mut self,
+        config: Config,
+        removed: &[String],
+        staged: Vec<(String, WasmComponent)>,
+    ) -> Result<()> {
+        let kept = self.components.iter().filter(|(name, _)| {
+            !removed.contains(*name) && !staged.iter().any(|(staged, _)| staged == *name)
+        });
+        name_tools(
+            &config,
+            kept.chain(staged.iter().map(|(name, component)| (name, component))),
+        )?;
+
+        for name in removed {
+            self.disabled.remove(name);
+            self.forget_instance(name);
+            if let Some(mut component) = self.components.remove(name) {
+                component.shutdown().await;
+                tracing::info!("Removed component '{}'", name);
+            }
+        }
+        for (name, component) in staged {
+            self.forget_instance(&name);
+            if let Some(mut replaced) = self.components.insert(name, component) {
+                replaced.shutdown().await;
+            }
+        }
+        self.set_config(config);
+        Ok(())
+    }
+
+    /// Forget the recovery, watchdog, cached results and validators of a component's instance
+    fn forget_instance(&mut self, name: &str) {
+        self.recovery.forget(name);
+        self.watchdog.forget(name);
+        self.results.invalidate_component(name);
+        self.forget_validators(name);
+    }
+
+    /// An empty executor sharing this one's engine, deadlines and configuration, for loading
+    /// a new version of a component while this one keeps serving calls
+    pub fn staging(&self) -> Result<WasmExecutor> {
+        WasmExecutor::new(self.fork_context()?, self.config.clone())
+    }
+
+    /// A context sharing this executor's engine and deadlines, for another set of instances
+    pub fn fork_context(&self) -> Result<WasmContext> {
+        Ok(self.context.fork()?)
+    }
+
+    /// Run this executor's hooks around the calls of a profile's executor, record them in the
+    /// same history and recordings, and signal its tool list changes to the same listeners
+    pub fn attach_profile(&self, profile: &mut WasmExecutor) {
+        for hook in &self.hooks {
+            profile.add_hook(hook.clone());
+        }
+        profile.history = self.history.clone();
+        profile.recording = self.recording.clone();
+        profile.tools_changed = self.tools_changed.clone();
+    }
+
+    /// Signalled when the executor changes its tool list by itself, e.g. when the watchdog
+    /// disables a component
+    pub fn tools_changed(&self) -> Arc<Notify> {
+        self.tools_changed.clone()
+    }
+
+    /// Load a component into this staging executor, make its warm-up and smoke calls, and
+    /// hand out the checked instance for [`WasmExecutor::install_component`]
+    pub async fn stage_component(
+        mut self,
+        name: &str,
+        config: ComponentConfig,
+    ) -> Result<WasmComponent> {
+        let smoke_calls = config.smoke_calls.clone();
+        self.add_component(name.to_string(), config).await?;
+        for call in smoke_calls {
+            let tool_name = format!("{name}.{}", call.function);
+            let value = self
+                .run_function(&tool_name, call.args)
+                .await
+                .map_err(|e| {
+                    WasiMcpError::Execution(format!("Smoke call of {tool_name} failed: {e}"))
+                })?;
+            // A guest reporting an error fails the check as much as one that traps
+            if let Some(error) = result_error(&value) {
+                return Err(WasiMcpError::Execution(format!(
+                    "Smoke call of {tool_name} returned an error: {error}"
+                )));
+            }
+        }
+        self.components
+            .remove(name)
+            .ok_or_else(|| WasiMcpError::ComponentNotFound(name.to_string()))
+    }
+
+    /// Make the configured warm-up calls of a freshly loaded component
+    ///
+    /// They bypass hooks, statistics, history and the result cache, so they don't show up as
+    /// client calls.
+    async fn warm_up(&mut self, name: &str) {
+        let warmup = match self.components.get(name) {
+            Some(component) => component.config.warmup.clone(),
+            None => return,
+        };
+        for call in warmup {
+            let start_time = Instant::now();
+            let tool_name = format!("{name}.{}", call.function);
+            match self.run_function(&tool_name, call.args).await {
+                Ok(_) => tracing::debug!(
+                    tool_name,
+                    duration_ms = start_time.elapsed().as_millis(),
+                    "Warm-up call finished"
+                ),
+                Err(e) => tracing::warn!("Warm-up call of {} failed: {}", tool_name, e),
+            }
+        }
+    }
+
+    /// Remove a component after running its shutdown function, returning whether it was
+    /// loaded
+    pub async fn remove_component(&mut self, name: &str) -> bool {
+        self.disabled.remove(name);
+        self.forget_instance(name);
+        let removed = self.components.remove(name);
+        if let Err(e) = self.index_tool_names() {
+            tracing::warn!("Keeping the previous tool names: {}", e);
+        }
+        match removed {
+            Some(mut component) => {
+                component.shutdown().await;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Enable or disable a loaded component without unloading it; a disabled component's
+    /// tools are not listed and calls to it are refused
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> Result<()> {
+        if !self.components.contains_key(name) {
+            return match self.get_component_config(name) {
+                Some(config) if !config.is_enabled() => {
+                    Err(WasiMcpError::InvalidArguments(format!(
+                        "Component '{name}' is disabled in the configuration; set `enabled: true` and reload"
+                    )))
+                }
+                _ => Err(WasiMcpError::ComponentNotFound(name.to_string())),
+            };
+        }
+        if enabled {
+            self.disabled.remove(name);
+        } else {
+            self.disabled.insert(name.to_string());
+            self.results.invalidate_component(name);
+        }
+        Ok(())
+    }
+
+    /// Whether calls to a component are refused, by the configuration or at runtime
+    pub fn is_disabled(&self, name: &str) -> bool {
+        self.disabled.contains(name)
+            || self
+                .get_component_config(name)
+                .is_some_and(|config| !config.is_enabled())
+    }
+
+    /// Run the shutdown function of every loaded component
+    pub async fn shutdown(&mut self) {
+        for component in self.components.values_mut() {
+            component.shutdown().await;
+        }
+    }
+
+    /// Name the exposed tools of every component, mapping the names back to functions
+    fn index_tool_names(&mut self) -> Result<()> {
+        self.tool_names = name_tools(&self.config, &self.components)?;
+        Ok(())
+    }
+
+    /// `component.function` of the tool named `tool_name`; names that aren't public names
+    /// are taken as `component.function` already
+    /// The `component.function` a tool name refers to: advertised names are mapped back,
+    /// internal names are kept; policies are checked against this name
+    pub fn resolve_tool_name(&self, tool_name: &str) -> String {
+        self.tool_names
+            .internal(tool_name)
+            .unwrap_or(tool_name)
+            .to_string()
+    }
+
+    fn forget_validators(&mut self, component_name: &str) {
+        self.validators.retain(|tool_name, _| {
+            tool_name
+                .split_once('.')
+                .is_none_or(|(component, _)| component != component_name)
+        });
+    }
+
+    /// Replace the configuration used for descriptions and result encoding
+    pub fn set_config(&mut self, config: Config) {
+        self.results.clear();
+        // Input schemas depend on the configured encoding
+        self.validators.clear();
+        self.config = config;
+        if let Err(e) = self.index_tool_names() {
+            tracing::warn!("Keeping the previous tool names: {}", e);
+        }
+    }
+
+    /// Run `hook` around every call made from now on
+    pub fn add_hook(&mut self, hook: Arc<dyn CallHook>) {
+        self.hooks.push(hook);
+    }
+
+    /// Deadlines of running calls, for cancelling them without waiting for the executor
+    pub fn deadlines(&self) -> DeadlineManager {
+        self.context.deadlines().clone()
+    }
+
+    /// Record every call made from now on in the history database
+    pub fn set_history(&mut self, history: HistoryRecorder) {
+        self.history = Some(history);
+    }
+
+    /// Record every call made from now on for replay, in `dir`
+    pub fn set_recording(&mut self, dir: PathBuf) {
+        self.recording = Some(dir);
+    }
+
+    /// Run a recorded call again in a fresh instance that observes what `tape` serves
+    ///
+    /// The recorded arguments are the ones the caller sent; the configured values are applied
+    /// again as for a new call.
+    pub async fn replay(&mut self, recording: &Recording, tape: Arc<Tape>) -> Result<Value> {
+        let Some((component_name, function_name)) = recording.tool.split_once('.') else {
+            return Err(WasiMcpError::InvalidArguments(format!(
+                "Tool name must be in format 'component.function', got: {}",
+                recording.tool
+            )));
+        };
+        let component = self
+            .components
+            .get(component_name)
+            .ok_or_else(|| WasiMcpError::ComponentNotFound(component_name.to_string()))?;
+        let function_info = component
+            .get_function_info(function_name)
+            .ok_or_else(|| WasiMcpError::FunctionNotFound(function_name.to_string()))?;
+
+        let mut arguments = recording.arguments.clone();
+        if let Some(overrides) = self
+            .get_component_config(component_name)
+            .and_then(|config| config.functions.get(function_name))
+        {
+            apply_configured_arguments(&mut arguments, overrides)?;
+        }
+        let positional_args = self.map_named_to_positional_arguments(function_info, &arguments)?;
+        let args = convert_args_to_wasm_values(&positional_args, function_info)?;
+        let mut results = vec![Val::String(String::new()); function_info.results.len()];
+
+        let call_id = NEXT_CALL_ID.fetch_add(1, Ordering::Relaxed);
+        let timeout = component.config.timeout_ms.map(Duration::from_millis);
+        let _deadline = self.context.deadlines().begin(call_id, timeout);
+        let called = component
+            .call_in_fresh_instance(
+                &self.context,
+                &component.config,
+                call_id,
+                &function_info.name,
+                &args,
+                &mut results,
+                Some(tape),
+                &mut None,
+            )
+            .await;
+        call_result(called, &results, &ConversionOptions::from(&self.config))
+    }
+
     /// Get component configuration for a specific component
     fn get_component_config(&self, component_name: &str) -> Option<&ComponentConfig> {
         self.config.components.get(component_name)
@@ -45,21 +408,53 @@ impl WasmExecutor {
         let mut all_tools = Vec::new();
 
         for (name, component) in &self.components {
-            let config = self.get_component_config(name);
-            let description = config.and_then(|config| config.description.as_deref());
-            let mut tools = component.get_tools(&self.context.engine, description)?;
-
-            // Prefix tool names with component name to avoid conflicts
-            for tool in &mut tools {
-                tool.name = format!("{name}.{}", tool.name).into();
+            if self.is_disabled(name) {
+                continue;
             }
-
-            all_tools.extend(tools);
+            let config = self.get_component_config(name);
+            let description = config
+                .and_then(|config| config.description.as_deref())
+                .or(component.metadata.description.as_deref());
+            all_tools.extend(component_tools(
+                name,
+                component.get_tools(description, ConversionOptions::from(&self.config)),
+                config,
+                &self.tool_names,
+            ));
         }
 
         Ok(all_tools)
     }
 
+    /// [`Self::get_all_tools`] paired with the `component.function` each tool calls
+    pub fn tools_by_internal_name(&self) -> Result<Vec<(String, rmcp::model::Tool)>> {
+        Ok(self
+            .get_all_tools()?
+            .into_iter()
+            .map(|tool| (self.resolve_tool_name(&tool.name), tool))
+            .collect())
+    }
+
+    /// Compiled input schema of a function, or `None` when it cannot be compiled and only
+    /// conversion checks the arguments
+    fn validator(
+        &mut self,
+        tool_name: &str,
+        function_info: &FunctionInfo,
+    ) -> Option<&ArgumentValidator> {
+        let options = ConversionOptions::from(&self.config);
+        self.validators
+            .entry(tool_name.to_string())
+            .or_insert_with(|| {
+                let tool =
+                    rmcp::model::Tool::from(&function_info.with_conversion_options(&options));
+                ArgumentValidator::new(&tool.input_schema)
+                    .inspect_err(|e| tracing::warn!(tool_name, "Skipping validation: {}", e))
+                    .ok()
+            })
+            .as_ref()
+    }
+
     /// Map named arguments to positional arguments based on function signature
     fn map_named_to_positional_arguments(
         &self,
@@ -75,9 +470,11 @@ impl WasmExecutor {
             .map(|p| (p.name.as_str(), p.position))
             .collect();
 
-        // Check for missing required arguments
+        // Check for missing required arguments (option parameters default to `none`)
         for param_info in &function_info.params {
-            if !named_args.contains_key(&param_info.name) {
+            if !named_args.contains_key(&param_info.name)
+                && !matches!(param_info.wasm_type, wasmtime::component::Type::Option(_))
+            {
                 return Err(WasiMcpError::InvalidArguments(format!(
                     "Missing required argument: '{}' (position: {})",
                     param_info.name, param_info.position
@@ -110,20 +507,521 @@ impl WasmExecutor {
     }
 
     /// Execute a function from any of the managed components with named arguments (async with direct handles)
-    #[instrument(level = "debug", skip(self), fields(tool_name, arguments, duration_ms))]
+    #[instrument(
+        level = "debug",
+        skip(self),
+        fields(
+            tool_name,
+            arguments,
+            component,
+            function,
+            args_bytes,
+            result_bytes,
+            trap,
+            error_code,
+            call_id,
+            duration_ms
+        )
+    )]
     pub async fn execute_function(
         &mut self,
         tool_name: &str,
-        arguments: HashMap<String, serde_json::Value>,
+        mut arguments: HashMap<String, serde_json::Value>,
     ) -> Result<Value> {
         let start_time = Instant::now();
+        let tool_name = &self.resolve_tool_name(tool_name);
+        let span = tracing::Span::current();
+        if let Some((component, function)) = tool_name.split_once('.') {
+            span.record("component", component);
+            span.record("function", function);
+        }
+        if !span.is_disabled() {
+            span.record("args_bytes", json_size(&arguments));
+        }
+        let hooks = self.hooks.clone();
+        let args_hash = self.history.as_ref().map(|_| arguments_hash(&arguments));
+        let mut result = match hooks
+            .iter()
+            .try_for_each(|hook| hook.before_call(tool_name, &mut arguments))
+        {
+            Ok(()) => self.call_cached(tool_name, arguments).await,
+            Err(e) => Err(e),
+        };
+        for hook in &hooks {
+            result = result.and_then(|mut value| {
+                hook.after_call(tool_name, &mut value)?;
+                Ok(value)
+            });
+        }
+        if let Err(WasiMcpError::Component(e)) = &result {
+            let details = TrapDetails::of(e);
+            if let Some(trap) = &details.trap {
+                span.record("trap", trap.as_str());
+            }
+            if !details.is_empty() {
+                tracing::error!(
+                    tool = tool_name,
+                    trap = details.trap.as_deref().unwrap_or("unknown"),
+                    "Guest trapped, backtrace:\n  {}",
+                    details.backtrace.join("\n  ")
+                );
+            }
+        }
+        if let Ok(value) = &result
+            && !span.is_disabled()
+        {
+            span.record("result_bytes", json_size(value));
+        }
+        if let Err(e) = &result {
+            span.record("error_code", e.code().as_str());
+            for hook in &hooks {
+                hook.on_error(tool_name, e);
+            }
+        }
+
+        // Calls to unknown components are not counted, so probing can't grow the tables
+        let component_name = tool_name.split_once('.').map(|(component, _)| component);
+        if let Some(component_name) = component_name
+            && self.components.contains_key(component_name)
+        {
+            let error = result.as_ref().err().map(ToString::to_string);
+            self.stats.lock().unwrap_or_else(|e| e.into_inner()).record(
+                tool_name,
+                start_time.elapsed(),
+                error.as_deref(),
+            );
+            self.recover(component_name, &result).await;
+            self.watch(component_name, start_time.elapsed()).await;
+        }
+
+        if let (Some(history), Some(args_hash)) = (&self.history, args_hash) {
+            history.record(HistoryEntry::new(
+                tool_name,
+                args_hash,
+                &result,
+                start_time.elapsed(),
+            ));
+        }
+
+        span.record("duration_ms", start_time.elapsed().as_millis());
+        result
+    }
+
+    /// Track failures of the guest and re-instantiate a component that keeps failing
+    ///
+    /// Only errors raised by the guest or the runtime count; invalid arguments and the like
+    /// say nothing about the instance. Calls that trap or exit already replaced the instance,
+    /// so the streak catches instances that keep failing without trapping.
+    async fn recover(&mut self, component_name: &str, result: &Result<Value>) {
+        let failed = match result {
+            Ok(_) => false,
+            Err(WasiMcpError::Component(_) | WasiMcpError::Exit(_)) => true,
+            Err(_) => return,
+        };
+        let config = self
+            .get_component_config(component_name)
+            .and_then(|config| config.recovery.clone())
+            .unwrap_or_default();
+        if !self
+            .recovery
+            .record(component_name, failed, &config, Instant::now())
+        {
+            return;
+        }
+
+        tracing::warn!(
+            "Re-instantiating '{}' after repeated failed calls",
+            component_name
+        );
+        let succeeded = match self.reinstantiate(component_name).await {
+            Ok(mut failing) => {
+                failing.shutdown().await;
+                true
+            }
+            Err(e) => {
+                tracing::error!("Failed to re-instantiate '{}': {}", component_name, e);
+                false
+            }
+        };
+        self.recovery
+            .restarted(component_name, succeeded, &config, Instant::now());
+    }
+
+    /// Check a component's instance against its watchdog limits after a call, and recycle it,
+    /// or disable the component, when it is over one
+    async fn watch(&mut self, component_name: &str, elapsed: Duration) {
+        let Some(config) = self
+            .get_component_config(component_name)
+            .and_then(|config| config.watchdog.clone())
+        else {
+            return;
+        };
+        // Command components get a new instance for every call
+        let Some(component) = self
+            .components
+            .get(component_name)
+            .filter(|component| !component.command)
+        else {
+            return;
+        };
+        let usage = component.usage();
+        let Some(eviction) = self
+            .watchdog
+            .check(component_name, elapsed, &usage, &config)
+        else {
+            return;
+        };
+
+        tracing::warn!(
+            component = component_name,
+            reason = %eviction,
+            action = ?config.action,
+            "Evicting instance"
+        );
+        match self.reinstantiate(component_name).await {
+            Ok(mut evicted) => evicted.shutdown().await,
+            Err(e) => tracing::error!("Failed to recycle '{}': {}", component_name, e),
+        }
+        if config.action == WatchdogAction::Disable {
+            // Disabled like an operator would, so it can be enabled again without a reload
+            match self.set_enabled(component_name, false) {
+                Ok(()) => self.tools_changed.notify_one(),
+                Err(e) => tracing::error!("Failed to disable '{}': {}", component_name, e),
+            }
+        }
+    }
+
+    /// Give a component a new instance right away, after a call left the old one unusable
+    async fn replace_instance(&mut self, component_name: &str) {
+        tracing::warn!(
+            "Re-instantiating '{}' after its call ended the instance",
+            component_name
+        );
+        match self.reinstantiate(component_name).await {
+            Ok(mut ended) => {
+                self.recovery.forget(component_name);
+                ended.shutdown().await;
+            }
+            Err(e) => tracing::error!("Failed to re-instantiate '{}': {}", component_name, e),
+        }
+    }
+
+    /// Replace a component's instance with a fresh one from the same configuration,
+    /// returning the old one
+    async fn reinstantiate(&mut self, component_name: &str) -> Result<WasmComponent> {
+        let config = self
+            .components
+            .get(component_name)
+            .map(|component| component.config.clone())
+            .ok_or_else(|| WasiMcpError::ComponentNotFound(component_name.to_string()))?;
+        let component =
+            WasmComponent::new(component_name.to_string(), config, &self.context).await?;
+        self.watchdog.forget(component_name);
+        self.stats
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .record_restart(component_name);
+        self.components
+            .insert(component_name.to_string(), component)
+            .ok_or_else(|| WasiMcpError::ComponentNotFound(component_name.to_string()))
+    }
+
+    /// Run a call, or answer it from the result cache when the function opts in
+    async fn call_cached(
+        &mut self,
+        tool_name: &str,
+        arguments: HashMap<String, serde_json::Value>,
+    ) -> Result<Value> {
+        // Cached results of disabled or hidden functions aren't served either
+        if let Some((component, function)) = tool_name.split_once('.') {
+            self.check_callable(component, function)?;
+        }
+        let cache_config = tool_name.split_once('.').and_then(|(component, function)| {
+            self.get_component_config(component)?
+                .functions
+                .get(function)?
+                .cache
+                .clone()
+        });
+        if cache_config.is_some()
+            && let Some(cached) = self.results.get(tool_name, &arguments)
+        {
+            tracing::debug!(tool_name, "Serving cached result");
+            self.stats
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .record_cache_hit(tool_name);
+            return Ok(cached);
+        }
+
+        match cache_config {
+            Some(cache_config) => {
+                let result = self.run_function(tool_name, arguments.clone()).await;
+                if let Ok(value) = &result {
+                    self.results
+                        .insert(tool_name, &arguments, value.clone(), &cache_config);
+                }
+                result
+            }
+            None => self.run_function(tool_name, arguments).await,
+        }
+    }
+
+    /// Snapshot of the call counters collected so far
+    pub fn stats(&self) -> RuntimeStats {
+        self.stats
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .snapshot()
+    }
+
+    /// Shared handle to the call counters, readable without locking the executor
+    pub fn stats_handle(&self) -> StatsHandle {
+        self.stats.clone()
+    }
+
+    /// Resolve a call and convert its arguments as [`Self::execute_function`] would, without
+    /// running the guest, hooks or anything that records calls
+    pub fn dry_run(
+        &mut self,
+        tool_name: &str,
+        mut arguments: HashMap<String, serde_json::Value>,
+    ) -> Result<DryRun> {
+        let tool_name = self.resolve_tool_name(tool_name);
+        let Some((component_name, function_name)) = tool_name.split_once('.') else {
+            return Err(WasiMcpError::InvalidArguments(format!(
+                "Tool name must be in format 'component.function', got: {tool_name}",
+            )));
+        };
+        self.check_callable(component_name, function_name)?;
+
+        let call_context = CallContext::take(&mut arguments)?;
+        let component = self
+            .components
+            .get(component_name)
+            .ok_or_else(|| WasiMcpError::ComponentNotFound(component_name.to_string()))?;
+        if let Some(call_context) = &call_context {
+            call_context.apply(component_name, &component.config)?;
+        }
+        if let Some(overrides) = component.config.functions.get(function_name) {
+            apply_configured_arguments(&mut arguments, overrides)?;
+        }
+
+        // Commands get their arguments as argv and stdin, without conversion
+        if function_name == RUN_TOOL && component.command {
+            let mut arguments: Vec<_> = arguments.into_iter().collect();
+            arguments.sort_by(|(a, _), (b, _)| a.cmp(b));
+            return Ok(DryRun {
+                tool: tool_name.to_string(),
+                component: component_name.to_string(),
+                function: "wasi:cli/run".to_string(),
+                fresh_instance: true,
+                arguments: arguments
+                    .into_iter()
+                    .map(|(name, value)| DryRunArgument {
+                        name,
+                        wit_type: None,
+                        value,
+                        converted: None,
+                    })
+                    .collect(),
+                results: Vec::new(),
+            });
+        }
+
+        let function_info = component
+            .get_function_info(function_name)
+            .ok_or_else(|| WasiMcpError::FunctionNotFound(function_name.to_string()))?
+            .clone();
+        self.validator(&tool_name, &function_info)
+            .map_or(Ok(()), |validator| validator.validate(&arguments))?;
+        let positional_args = self.map_named_to_positional_arguments(&function_info, &arguments)?;
+        let converted = convert_args_to_wasm_values(&positional_args, &function_info)?;
+
+        let mut params: Vec<_> = function_info.params.iter().collect();
+        params.sort_by_key(|param| param.position);
+        Ok(DryRun {
+            tool: tool_name.to_string(),
+            component: component_name.to_string(),
+            function: function_info.name.clone(),
+            fresh_instance: call_context.is_some() || self.recording.is_some(),
+            arguments: params
+                .into_iter()
+                .zip(positional_args.into_iter().zip(&converted))
+                .map(|(param, (value, converted))| DryRunArgument {
+                    name: param.name.clone(),
+                    wit_type: Some(crate::inspect::wit_type(&param.wasm_type)),
+                    value,
+                    converted: Some(format!("{converted:?}")),
+                })
+                .collect(),
+            results: function_info.results.clone(),
+        })
+    }
+
+    /// The contract of a tool, looked up by its advertised or internal name
+    pub fn explain(&self, tool_name: &str) -> Result<Explanation> {
+        let tool_name = self.resolve_tool_name(tool_name);
+        let Some((component_name, function_name)) = tool_name.split_once('.') else {
+            return Err(WasiMcpError::InvalidArguments(format!(
+                "Tool name must be in format 'component.function', got: {tool_name}",
+            )));
+        };
+        self.check_callable(component_name, function_name)?;
+        let component = self
+            .components
+            .get(component_name)
+            .ok_or_else(|| WasiMcpError::ComponentNotFound(component_name.to_string()))?;
+
+        let public_name = self.tool_names.public(component_name, function_name);
+        let tool = self
+            .get_all_tools()?
+            .into_iter()
+            .find(|tool| tool.name == public_name)
+            .ok_or_else(|| WasiMcpError::FunctionNotFound(function_name.to_string()))?;
+        let input_schema = Value::Object((*tool.input_schema).clone());
+        let properties = input_schema.get("properties").and_then(Value::as_object);
+        let required = input_schema
+            .get("required")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        let overrides = component.config.functions.get(function_name);
+        let parameter = |name: &str, wit_type: Option<String>| {
+            let configured = overrides.and_then(|overrides| overrides.params.get(name));
+            ParameterContract {
+                name: name.to_string(),
+                wit_type,
+                required: required
+                    .iter()
+                    .any(|required| required.as_str() == Some(name)),
+                description: properties
+                    .and_then(|properties| properties.get(name))
+                    .and_then(|schema| schema.get("description"))
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+                    // Fixed parameters are not in the schema
+                    .or_else(|| configured.and_then(|param| param.description.clone())),
+                default: configured.and_then(|param| param.default.clone()),
+                value: configured.and_then(|param| param.value.clone()),
+            }
+        };
+
+        let (function, signature, parameters) = match component.get_function_info(function_name) {
+            Some(function_info) => {
+                let mut params: Vec<_> = function_info.params.iter().collect();
+                params.sort_by_key(|param| param.position);
+                let types: Vec<_> = params
+                    .iter()
+                    .map(|param| {
+                        (
+                            param.name.clone(),
+                            crate::inspect::wit_type(&param.wasm_type),
+                        )
+                    })
+                    .collect();
+                let results: Vec<_> = function_info
+                    .result_types
+                    .iter()
+                    .map(crate::inspect::wit_type)
+                    .collect();
+                let parameters = types
+                    .iter()
+                    .map(|(name, ty)| parameter(name, Some(ty.clone())))
+                    .collect();
+                (
+                    function_info.name.clone(),
+                    Some(explain::signature(&types, &results)),
+                    parameters,
+                )
+            }
+            // Commands take named arguments as flags rather than typed parameters
+            None => (
+                "wasi:cli/run".to_string(),
+                None,
+                properties
+                    .into_iter()
+                    .flat_map(|properties| properties.keys())
+                    .map(|name| parameter(name, None))
+                    .collect(),
+            ),
+        };
+
+        Ok(Explanation {
+            tool: public_name,
+            component: component_name.to_string(),
+            function,
+            signature,
+            description: tool.description.as_deref().map(str::to_string),
+            parameters,
+            example: explain::example_arguments(&input_schema),
+            input_schema,
+            output_schema: tool
+                .output_schema
+                .as_deref()
+                .map(|schema| Value::Object(schema.clone())),
+        })
+    }
+
+    /// Refuse calls to disabled components and hidden functions
+    fn check_callable(&self, component_name: &str, function_name: &str) -> Result<()> {
+        if self.is_disabled(component_name) {
+            return Err(WasiMcpError::ComponentDisabled(component_name.to_string()));
+        }
+
+        // Hidden functions are reported as missing so they can't be discovered by probing
+        if self
+            .get_component_config(component_name)
+            .is_some_and(|config| !config.exposes(function_name))
+        {
+            return Err(WasiMcpError::FunctionNotFound(function_name.to_string()));
+        }
+        Ok(())
+    }
+
+    async fn run_function(
+        &mut self,
+        tool_name: &str,
+        mut arguments: HashMap<String, serde_json::Value>,
+    ) -> Result<Value> {
         let Some((component_name, function_name)) = tool_name.split_once(".") else {
             return Err(WasiMcpError::InvalidArguments(format!(
                 "Tool name must be in format 'component.function', got: {tool_name}",
             )));
         };
 
-        // Get function info first
+        self.check_callable(component_name, function_name)?;
+
+        let call_context = CallContext::take(&mut arguments)?;
+        // Recordings keep what the caller sent; configured values may hold secrets and are
+        // applied again on replay
+        let supplied = self.recording.is_some().then(|| arguments.clone());
+        if let Some(overrides) = self
+            .get_component_config(component_name)
+            .and_then(|config| config.functions.get(function_name))
+        {
+            apply_configured_arguments(&mut arguments, overrides)?;
+        }
+
+        // Programs run in a fresh store per call, with the arguments as argv and stdin; the
+        // `run` tool is not one of their functions
+        if function_name == RUN_TOOL
+            && let Some(component) = self
+                .components
+                .get(component_name)
+                .filter(|component| component.command)
+        {
+            let call_id = NEXT_CALL_ID.fetch_add(1, Ordering::Relaxed);
+            tracing::Span::current().record("call_id", call_id);
+            let config = match &call_context {
+                Some(call_context) => call_context.apply(component_name, &component.config)?,
+                None => component.config.clone(),
+            };
+            let timeout = component.config.timeout_ms.map(Duration::from_millis);
+            let _deadline = self.context.deadlines().begin(call_id, timeout);
+            return command::run(&self.context, component, &config, call_id, &arguments).await;
+        }
+
         let function_info = {
             let component = self
                 .components
@@ -136,10 +1034,14 @@ impl WasmExecutor {
                 .clone()
         };
 
+        // Report every schema violation at once, before conversion stops at the first
+        self.validator(tool_name, &function_info)
+            .map_or(Ok(()), |validator| validator.validate(&arguments))?;
+
         let positional_args = self.map_named_to_positional_arguments(&function_info, &arguments)?;
         let mut results = Vec::new();
         for _ in 0..function_info.results.len() {
-            results.push(wasmtime::component::Val::String(String::new()));
+            results.push(Val::String(String::new()));
         }
 
         let args = convert_args_to_wasm_values(&positional_args, &function_info)?;
@@ -153,19 +1055,1053 @@ impl WasmExecutor {
             return Err(WasiMcpError::FunctionNotFound(function_info.name));
         };
 
-        component.call_async(&func, &args, &mut results).await?;
-        let result = if results.is_empty() {
-            Value::String("Successfully executed (no return value)".to_string())
+        let call_id = NEXT_CALL_ID.fetch_add(1, Ordering::Relaxed);
+        component.store.data().stdio.set_call_id(call_id);
+        tracing::Span::current().record("call_id", call_id);
+        let timeout = component.config.timeout_ms.map(Duration::from_millis);
+        let _deadline = self.context.deadlines().begin(call_id, timeout);
+
+        // Overrides need their own WASI context, and recorded calls must replay from a fresh
+        // start, so such calls get a fresh instance
+        let tape = self.recording.as_ref().map(|_| Tape::record());
+        let mut poisoned = false;
+        let mut dump = None;
+        let called = if call_context.is_some() || tape.is_some() {
+            let config = match &call_context {
+                Some(call_context) => call_context.apply(component_name, &component.config)?,
+                None => component.config.clone(),
+            };
+            component
+                .call_in_fresh_instance(
+                    &self.context,
+                    &config,
+                    call_id,
+                    &function_info.name,
+                    &args,
+                    &mut results,
+                    tape.clone(),
+                    &mut dump,
+                )
+                .await
         } else {
-            convert_wasm_results_to_json(&results)?
+            let called = component.call_async(&func, &args, &mut results).await;
+            if let Err(WasiMcpError::Component(e)) = &called {
+                dump = coredump::capture(e, &mut component.store, &component.name);
+            }
+            poisoned = poisons_instance(&called);
+            called
         };
+        if let (Some(dump), Err(WasiMcpError::Component(e))) = (dump, &called)
+            && let Some(config) = self
+                .config
+                .engine
+                .as_ref()
+                .and_then(|engine| engine.coredump.as_ref())
+        {
+            save_coredump(config, dump, tool_name, &arguments, call_id, e);
+        }
+        let result = call_result(called, &results, &ConversionOptions::from(&self.config));
+        if poisoned {
+            self.replace_instance(component_name).await;
+        }
 
-        tracing::Span::current().record("duration_ms", start_time.elapsed().as_millis());
-        Ok(result)
+        if let (Some(dir), Some(tape), Some(supplied)) = (&self.recording, tape, supplied) {
+            let recording = tape.finish(tool_name, &supplied, call_id, &result);
+            match recording.save(dir) {
+                Ok(path) => tracing::debug!(
+                    tool = tool_name,
+                    call_id,
+                    "Recorded call to {}",
+                    path.display()
+                ),
+                Err(e) => tracing::error!(tool = tool_name, "Failed to save recording: {e}"),
+            }
+        }
+        result
+    }
+
+    /// The result of a call to `tool_name` as the object its output schema describes, or
+    /// `None` when the tool has no structured output
+    pub fn structured_content(&self, tool_name: &str, value: &Value) -> Option<Value> {
+        let tool_name = self.resolve_tool_name(tool_name);
+        let (component_name, function_name) = tool_name.split_once('.')?;
+        let component = self.components.get(component_name)?;
+        if function_name == RUN_TOOL && component.command {
+            // The run tool's result already is its output object
+            return Some(value.clone());
+        }
+        let function_info = component.get_function_info(function_name)?;
+        structure_results(value, function_info.results.len())
+    }
+
+    /// Resources held and consumed so far by a loaded component
+    pub fn component_usage(&self, component_name: &str) -> Option<ResourceUsage> {
+        self.components
+            .get(component_name)
+            .map(WasmComponent::usage)
+    }
+
+    /// Prompts shipped with the loaded components, by the name they are listed under
+    pub fn component_prompts(&self) -> BTreeMap<String, Prompt> {
+        self.components
+            .iter()
+            .flat_map(|(component, loaded)| {
+                loaded.prompts.iter().map(|(id, prompt)| {
+                    (crate::prompts::prompt_name(component, id), prompt.clone())
+                })
+            })
+            .collect()
     }
 
     /// List all available component names
     pub fn list_components(&self) -> Vec<String> {
         self.components.keys().cloned().collect()
     }
+
+    /// The loaded components with their sources, versions, digests and tool counts
+    pub fn inventory(&self) -> Inventory {
+        let mut components: Vec<_> = self
+            .components
+            .iter()
+            .map(|(name, component)| {
+                let config = self.get_component_config(name);
+                let tools = component_tools(
+                    name,
+                    component.get_tools(None, ConversionOptions::from(&self.config)),
+                    config,
+                    &self.tool_names,
+                );
+                ComponentInventory {
+                    name: name.clone(),
+                    source: component
+                        .config
+                        .oci
+                        .clone()
+                        .or_else(|| component.config.path.clone())
+                        .unwrap_or_default(),
+                    version: component.metadata.version.clone(),
+                    digest: component.digest.clone(),
+                    enabled: !self.is_disabled(name),
+                    tools: tools.len(),
+                }
+            })
+            .collect();
+        components.sort_by(|a, b| a.name.cmp(&b.name));
+        Inventory {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            components,
+        }
+    }
+}
+
+/// Tools of a component as served: filtered by its configuration, with function overrides
+/// applied and names prefixed with the component name
+pub(crate) fn component_tools(
+    name: &str,
+    mut tools: Vec<rmcp::model::Tool>,
+    config: Option<&ComponentConfig>,
+    names: &ToolNames,
+) -> Vec<rmcp::model::Tool> {
+    tools.retain(|tool| config.is_none_or(|config| config.exposes(&tool.name)));
+
+    if let Some(config) = config {
+        for tool in &mut tools {
+            if let Some(overrides) = config.functions.get(tool.name.as_ref()) {
+                apply_function_overrides(tool, overrides);
+            }
+            if let Some(permitted) = &config.call_context {
+                let input_schema = std::sync::Arc::make_mut(&mut tool.input_schema);
+                if let Some(properties) = input_schema
+                    .get_mut("properties")
+                    .and_then(Value::as_object_mut)
+                {
+                    properties.insert(
+                        CTX_ARGUMENT.to_string(),
+                        call_context::ctx_schema(permitted),
+                    );
+                }
+            }
+        }
+    }
+
+    // Prefix tool names with component name to avoid conflicts
+    for tool in &mut tools {
+        tool.name = names.public(name, &tool.name).into();
+    }
+    tools
+}
+
+/// Apply configured description and parameter overrides to an advertised tool
+fn apply_function_overrides(tool: &mut rmcp::model::Tool, overrides: &FunctionConfig) {
+    if let Some(description) = &overrides.description {
+        tool.description = Some(description.clone().into());
+    }
+
+    let input_schema = std::sync::Arc::make_mut(&mut tool.input_schema);
+    let Some(properties) = input_schema
+        .get_mut("properties")
+        .and_then(Value::as_object_mut)
+    else {
+        return;
+    };
+
+    for (name, param) in &overrides.params {
+        let Some(schema) = properties.get_mut(name).and_then(Value::as_object_mut) else {
+            tracing::warn!(
+                "Override for unknown parameter '{}' of '{}'",
+                name,
+                tool.name
+            );
+            continue;
+        };
+        if let Some(description) = &param.description {
+            schema.insert("description".to_string(), Value::from(description.as_str()));
+        }
+        if !param.examples.is_empty() {
+            schema.insert("examples".to_string(), Value::Array(param.examples.clone()));
+        }
+        if let Some(default) = &param.default {
+            schema.insert("default".to_string(), default.clone());
+        }
+    }
+
+    // Pinned parameters are not the caller's to choose, and defaulted ones become optional
+    for (name, param) in &overrides.params {
+        if param.value.is_some() {
+            properties.remove(name);
+        }
+    }
+    if let Some(required) = input_schema
+        .get_mut("required")
+        .and_then(Value::as_array_mut)
+    {
+        required.retain(|name| {
+            name.as_str()
+                .and_then(|name| overrides.params.get(name))
+                .is_none_or(|param| param.value.is_none() && param.default.is_none())
+        });
+    }
+}
+
+/// Merge pinned and default argument values from the configuration into a call
+fn apply_configured_arguments(
+    arguments: &mut HashMap<String, Value>,
+    overrides: &FunctionConfig,
+) -> Result<()> {
+    for (name, param) in &overrides.params {
+        if let Some(value) = &param.value {
+            if arguments.contains_key(name) {
+                return Err(WasiMcpError::InvalidArguments(format!(
+                    "Argument '{name}' is fixed by the server configuration"
+                )));
+            }
+            arguments.insert(name.clone(), value.clone());
+        } else if let Some(default) = &param.default {
+            arguments
+                .entry(name.clone())
+                .or_insert_with(|| default.clone());
+        }
+    }
+    Ok(())
+}
+
+/// Name the exposed tools of `components` as `config` asks, failing on collisions
+fn name_tools<'a>(
+    config: &Config,
+    components: impl IntoIterator<Item = (&'a String, &'a WasmComponent)>,
+) -> Result<ToolNames> {
+    let mut tools = Vec::new();
+    let options = ConversionOptions::from(config);
+    for (name, component) in components {
+        let component_config = config.components.get(name);
+        for tool in component.get_tools(None, options) {
+            if component_config.is_none_or(|config| config.exposes(&tool.name)) {
+                tools.push((name.as_str(), tool.name.to_string()));
+            }
+        }
+    }
+    ToolNames::new(
+        config.tool_names.as_ref(),
+        tools
+            .iter()
+            .map(|(component, function)| (*component, function.as_str())),
+    )
+}
+
+/// Whether a call ended its instance: a guest that called `exit`, even with code 0, has run
+/// its teardown and must not be called again, and wasmtime refuses to re-enter an instance
+/// that trapped, including one interrupted at its timeout
+fn poisons_instance(called: &Result<()>) -> bool {
+    let Err(WasiMcpError::Component(e)) = called else {
+        return false;
+    };
+    e.downcast_ref::<wasmtime_wasi::I32Exit>().is_some()
+        || e.downcast_ref::<wasmtime::Trap>().is_some()
+}
+
+/// The result of a finished call as JSON
+///
+/// Command-style components end with `exit`, which surfaces as an error carrying the exit code
+/// rather than as a trap. Exiting with code 0 only succeeds for functions without results; the
+/// others fail, as they have no value for their output schema.
+fn call_result(called: Result<()>, results: &[Val], options: &ConversionOptions) -> Result<Value> {
+    let exited = match called {
+        Ok(()) => false,
+        Err(WasiMcpError::Component(e)) => match e.downcast_ref::<wasmtime_wasi::I32Exit>() {
+            Some(wasmtime_wasi::I32Exit(0)) if results.is_empty() => true,
+            Some(wasmtime_wasi::I32Exit(code)) => return Err(WasiMcpError::Exit(*code)),
+            None => return Err(WasiMcpError::Component(e)),
+        },
+        Err(e) => return Err(e),
+    };
+    if exited {
+        Ok(Value::String(
+            "Successfully executed (exit code 0)".to_string(),
+        ))
+    } else if results.is_empty() {
+        Ok(Value::String(
+            "Successfully executed (no return value)".to_string(),
+        ))
+    } else {
+        convert_wasm_results_to_json(results, options)
+    }
+}
+
+/// Save the core dump wasmtime attached to a trap in the background, so the executor isn't
+/// held up by the write; failures are only logged
+fn save_coredump(
+    config: &CoreDumpConfig,
+    dump: Vec<u8>,
+    tool_name: &str,
+    arguments: &HashMap<String, Value>,
+    call_id: u64,
+    error: &anyhow::Error,
+) {
+    let metadata = CoreDumpMetadata::new(
+        tool_name,
+        arguments_hash(arguments),
+        call_id,
+        TrapDetails::of(error).trap,
+    );
+    let config = config.clone();
+    tokio::task::spawn_blocking(move || {
+        let saved = coredump::directory(&config)
+            .and_then(|dir| coredump::save(&dir, &metadata, &dump, config.max_dumps));
+        match saved {
+            Ok(path) => tracing::warn!(
+                tool = metadata.tool,
+                call_id,
+                "Saved core dump of trapped guest to {}",
+                path.display()
+            ),
+            Err(e) => tracing::error!(tool = metadata.tool, "Failed to save core dump: {e}"),
+        }
+    });
+}
+
+/// Size of a value serialized as JSON, for the call span
+fn json_size(value: &impl serde::Serialize) -> usize {
+    serde_json::to_vec(value).map_or(0, |bytes| bytes.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{
+        CallContextConfig, InitFailure, ParamConfig, PolicyConfig, ResultCacheConfig,
+        ToolNamingConfig, WarmupCall, WatchdogConfig,
+    };
+    use crate::error::ErrorCode;
+    use std::path::Path;
+    use std::sync::Mutex;
+
+    fn tool() -> rmcp::model::Tool {
+        let input_schema = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "query": {"type": "string"},
+                "limit": {"type": "integer"},
+                "base_url": {"type": "string"},
+            },
+            "required": ["query", "limit", "base_url"],
+        });
+        rmcp::model::Tool {
+            name: "search".into(),
+            title: None,
+            description: Some("Generic component description".into()),
+            input_schema: Arc::new(input_schema.as_object().cloned().unwrap()),
+            output_schema: None,
+            annotations: None,
+            icons: None,
+        }
+    }
+
+    #[test]
+    fn test_function_overrides() {
+        let overrides = FunctionConfig {
+            description: Some("Search the web".to_string()),
+            params: HashMap::from([(
+                "query".to_string(),
+                ParamConfig {
+                    description: Some("Search terms".to_string()),
+                    examples: vec![Value::from("rust wasm")],
+                    ..Default::default()
+                },
+            )]),
+            cache: None,
+        };
+
+        let mut tool = tool();
+        apply_function_overrides(&mut tool, &overrides);
+        assert_eq!(tool.description.as_deref(), Some("Search the web"));
+        assert_eq!(
+            tool.input_schema["properties"]["query"],
+            serde_json::json!({
+                "type": "string",
+                "description": "Search terms",
+                "examples": ["rust wasm"],
+            })
+        );
+    }
+
+    fn pinned_and_defaulted() -> FunctionConfig {
+        FunctionConfig {
+            description: None,
+            params: HashMap::from([
+                (
+                    "limit".to_string(),
+                    ParamConfig {
+                        default: Some(Value::from(20)),
+                        ..Default::default()
+                    },
+                ),
+                (
+                    "base_url".to_string(),
+                    ParamConfig {
+                        value: Some(Value::from("https://api.example.com")),
+                        ..Default::default()
+                    },
+                ),
+            ]),
+            cache: None,
+        }
+    }
+
+    #[test]
+    fn test_pinned_and_default_params_in_schema() {
+        let mut tool = tool();
+        apply_function_overrides(&mut tool, &pinned_and_defaulted());
+
+        let properties = tool.input_schema["properties"].as_object().unwrap();
+        assert!(!properties.contains_key("base_url"));
+        assert_eq!(properties["limit"]["default"], 20);
+        assert_eq!(tool.input_schema["required"], serde_json::json!(["query"]));
+    }
+
+    #[test]
+    fn test_configured_arguments() {
+        let overrides = pinned_and_defaulted();
+
+        let mut arguments = HashMap::from([("query".to_string(), Value::from("wasm"))]);
+        apply_configured_arguments(&mut arguments, &overrides).unwrap();
+        assert_eq!(arguments["limit"], 20);
+        assert_eq!(arguments["base_url"], "https://api.example.com");
+
+        let mut arguments = HashMap::from([("limit".to_string(), Value::from(5))]);
+        apply_configured_arguments(&mut arguments, &overrides).unwrap();
+        assert_eq!(arguments["limit"], 5);
+
+        let mut arguments =
+            HashMap::from([("base_url".to_string(), Value::from("https://evil.example"))]);
+        assert!(apply_configured_arguments(&mut arguments, &overrides).is_err());
+    }
+
+    #[derive(Default)]
+    struct DenySecrets {
+        errors: Mutex<Vec<String>>,
+    }
+
+    impl CallHook for DenySecrets {
+        fn before_call(&self, tool: &str, _arguments: &mut HashMap<String, Value>) -> Result<()> {
+            if tool.starts_with("secrets.") {
+                return Err(WasiMcpError::InvalidArguments(format!(
+                    "'{tool}' is not allowed"
+                )));
+            }
+            Ok(())
+        }
+
+        fn on_error(&self, tool: &str, error: &WasiMcpError) {
+            self.errors.lock().unwrap().push(format!("{tool}: {error}"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hooks() {
+        let hook = Arc::new(DenySecrets::default());
+        let mut executor =
+            WasmExecutor::new(WasmContext::new().unwrap(), Config::default()).unwrap();
+        executor.add_hook(hook.clone());
+
+        let result = executor
+            .execute_function("secrets.get", HashMap::new())
+            .await;
+        assert!(matches!(result, Err(WasiMcpError::InvalidArguments(_))));
+        let result = executor.execute_function("time.now", HashMap::new()).await;
+        assert!(matches!(result, Err(WasiMcpError::ComponentNotFound(_))));
+
+        let errors = hook.errors.lock().unwrap();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].starts_with("secrets.get: "));
+    }
+
+    #[tokio::test]
+    async fn test_disabled_component() {
+        let mut config = Config::default();
+        config.components.insert(
+            "db".to_string(),
+            ComponentConfig {
+                enabled: Some(false),
+                ..Default::default()
+            },
+        );
+        let mut executor = WasmExecutor::new(WasmContext::new().unwrap(), config).unwrap();
+
+        assert!(executor.is_disabled("db"));
+        let result = executor.execute_function("db.query", HashMap::new()).await;
+        assert!(matches!(result, Err(WasiMcpError::ComponentDisabled(_))));
+        // Only loaded components can be toggled at runtime
+        assert!(matches!(
+            executor.set_enabled("db", true),
+            Err(WasiMcpError::InvalidArguments(_))
+        ));
+        assert!(matches!(
+            executor.set_enabled("time", false),
+            Err(WasiMcpError::ComponentNotFound(_))
+        ));
+    }
+
+    /// A component exporting `double: func(n: u64) -> u64`
+    const DOUBLE_WAT: &str = r#"(component
+        (core module $m
+            (func (export "double") (param i64) (result i64)
+                local.get 0
+                local.get 0
+                i64.add))
+        (core instance $i (instantiate $m))
+        (func (export "double") (param "n" u64) (result u64)
+            (canon lift (core func $i "double")))
+    )"#;
+
+    #[tokio::test]
+    async fn test_disabled_component_skips_result_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("math.wat");
+        std::fs::write(&path, DOUBLE_WAT).unwrap();
+        let component = ComponentConfig {
+            path: Some(path.display().to_string()),
+            functions: HashMap::from([(
+                "double".to_string(),
+                FunctionConfig {
+                    cache: Some(ResultCacheConfig {
+                        ttl_secs: 300,
+                        max_entries: 10,
+                    }),
+                    ..Default::default()
+                },
+            )]),
+            ..Default::default()
+        };
+        let mut config = Config::default();
+        config
+            .components
+            .insert("math".to_string(), component.clone());
+        let mut executor = WasmExecutor::new(WasmContext::new().unwrap(), config).unwrap();
+        executor
+            .add_component("math".to_string(), component)
+            .await
+            .unwrap();
+
+        let arguments = HashMap::from([("n".to_string(), Value::from(2))]);
+        let result = executor
+            .execute_function("math.double", arguments.clone())
+            .await;
+        assert_eq!(result.unwrap(), Value::from(4));
+
+        executor.set_enabled("math", false).unwrap();
+        let result = executor
+            .execute_function("math.double", arguments.clone())
+            .await;
+        assert!(matches!(result, Err(WasiMcpError::ComponentDisabled(_))));
+
+        executor.set_enabled("math", true).unwrap();
+        let result = executor
+            .execute_function("math.double", arguments.clone())
+            .await;
+        assert_eq!(result.unwrap(), Value::from(4));
+
+        // Hits count as calls, and separately as hits
+        let result = executor.execute_function("math.double", arguments).await;
+        assert_eq!(result.unwrap(), Value::from(4));
+        let stats = &executor.stats().tools["math.double"];
+        assert_eq!(stats.calls, 4);
+        assert_eq!(stats.errors, 1);
+        assert_eq!(stats.cache_hits, 1);
+    }
+
+    #[tokio::test]
+    async fn test_reload_with_collision_changes_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let double = dir.path().join("math.wat");
+        std::fs::write(&double, DOUBLE_WAT).unwrap();
+        // Exports `b-double`, so component `math` names it like `double` of `math-b`
+        let b_double = dir.path().join("b.wat");
+        std::fs::write(
+            &b_double,
+            DOUBLE_WAT.replace(
+                r#"(export "double") (param "n""#,
+                r#"(export "b-double") (param "n""#,
+            ),
+        )
+        .unwrap();
+        let component = |path: &Path| ComponentConfig {
+            path: Some(path.display().to_string()),
+            ..Default::default()
+        };
+        let mut config = Config {
+            tool_names: Some(ToolNamingConfig {
+                separator: Some("-".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        config
+            .components
+            .insert("math-b".to_string(), component(&double));
+        config
+            .components
+            .insert("old".to_string(), component(&double));
+        let mut executor = WasmExecutor::new(WasmContext::new().unwrap(), config.clone()).unwrap();
+        for name in ["math-b", "old"] {
+            executor
+                .add_component(name.to_string(), component(&double))
+                .await
+                .unwrap();
+        }
+
+        let staged = executor
+            .staging()
+            .unwrap()
+            .stage_component("math", component(&b_double))
+            .await
+            .unwrap();
+        let mut new_config = config.clone();
+        new_config.components.remove("old");
+        new_config
+            .components
+            .insert("math".to_string(), component(&b_double));
+        let result = executor
+            .reload(
+                new_config,
+                &["old".to_string()],
+                vec![("math".to_string(), staged)],
+            )
+            .await;
+        assert!(matches!(result, Err(WasiMcpError::InvalidArguments(_))));
+
+        let mut tools: Vec<_> = executor
+            .tools_by_internal_name()
+            .unwrap()
+            .into_iter()
+            .map(|(internal, _)| internal)
+            .collect();
+        tools.sort();
+        assert_eq!(tools, ["math-b.double", "old.double"]);
+        assert_eq!(executor.config.components.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_policy_with_custom_tool_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("math.wat");
+        std::fs::write(&path, DOUBLE_WAT).unwrap();
+        let config = Config {
+            tool_names: Some(ToolNamingConfig {
+                separator: Some("__".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut executor = WasmExecutor::new(WasmContext::new().unwrap(), config).unwrap();
+        executor
+            .add_component(
+                "math".to_string(),
+                ComponentConfig {
+                    path: Some(path.display().to_string()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        let policy = PolicyConfig {
+            anonymous: vec!["math.*".to_string()],
+            ..Default::default()
+        };
+        let tools = executor.tools_by_internal_name().unwrap();
+        let [(internal, tool)] = tools.as_slice() else {
+            panic!("Expected a single tool, got {tools:?}");
+        };
+        assert_eq!(tool.name, "math__double");
+        assert_eq!(internal, "math.double");
+        assert!(policy.is_allowed(None, internal));
+
+        // Both names of a tool are checked as the same tool
+        for name in ["math__double", "math.double"] {
+            assert!(policy.is_allowed(None, &executor.resolve_tool_name(name)));
+        }
+        let other = PolicyConfig {
+            anonymous: vec!["search.*".to_string()],
+            ..Default::default()
+        };
+        for name in ["math__double", "math.double"] {
+            assert!(!other.is_allowed(None, &executor.resolve_tool_name(name)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_int64_as_string() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("math.wat");
+        std::fs::write(&path, DOUBLE_WAT).unwrap();
+        let config = Config {
+            int64_as_string: true,
+            ..Default::default()
+        };
+        let mut executor = WasmExecutor::new(WasmContext::new().unwrap(), config).unwrap();
+        executor
+            .add_component(
+                "math".to_string(),
+                ComponentConfig {
+                    path: Some(path.display().to_string()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        let tool = executor
+            .get_all_tools()
+            .unwrap()
+            .into_iter()
+            .find(|tool| tool.name == "math.double")
+            .unwrap();
+        assert_eq!(
+            tool.input_schema["properties"]["n"]["type"],
+            serde_json::json!(["integer", "string"])
+        );
+        assert_eq!(
+            tool.output_schema.unwrap()["properties"]["result"],
+            serde_json::json!("string")
+        );
+
+        // Beyond the 2^53 doubles represent exactly
+        let arguments = HashMap::from([("n".to_string(), Value::from("9007199254740993"))]);
+        let result = executor
+            .execute_function("math.double", arguments)
+            .await
+            .unwrap();
+        assert_eq!(result, Value::from("18014398509481986"));
+        let arguments = HashMap::from([("n".to_string(), Value::from(21))]);
+        let result = executor
+            .execute_function("math.double", arguments)
+            .await
+            .unwrap();
+        assert_eq!(result, Value::from("42"));
+    }
+
+    /// A component whose init function `setup` counts its runs, which `runs` returns
+    const COUNTER_WAT: &str = r#"(component
+        (core module $m
+            (global $runs (mut i64) (i64.const 0))
+            (func (export "setup")
+                global.get $runs
+                i64.const 1
+                i64.add
+                global.set $runs)
+            (func (export "runs") (result i64)
+                global.get $runs))
+        (core instance $i (instantiate $m))
+        (func (export "setup") (canon lift (core func $i "setup")))
+        (func (export "runs") (result u64) (canon lift (core func $i "runs")))
+    )"#;
+
+    #[tokio::test]
+    async fn test_fresh_instance_starts_initialized() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("counter.wat");
+        std::fs::write(&path, COUNTER_WAT).unwrap();
+        let mut executor =
+            WasmExecutor::new(WasmContext::new().unwrap(), Config::default()).unwrap();
+        executor
+            .add_component(
+                "counter".to_string(),
+                ComponentConfig {
+                    path: Some(path.display().to_string()),
+                    init: Some("setup".to_string()),
+                    call_context: Some(CallContextConfig {
+                        env: vec!["*".to_string()],
+                        cwd: vec![],
+                    }),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        let result = executor
+            .execute_function("counter.runs", HashMap::new())
+            .await;
+        assert_eq!(result.unwrap(), Value::from(1));
+        // Calls with a context of their own get a fresh instance, initialized exactly once
+        for _ in 0..3 {
+            let arguments = HashMap::from([(
+                "_ctx".to_string(),
+                serde_json::json!({"env": {"PROJECT": "billing"}}),
+            )]);
+            let result = executor.execute_function("counter.runs", arguments).await;
+            assert_eq!(result.unwrap(), Value::from(1));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_init_returning_error_fails_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("broken.wat");
+        std::fs::write(
+            &path,
+            r#"(component
+                (core module $m
+                    (func (export "setup") (result i32)
+                        i32.const 1))
+                (core instance $i (instantiate $m))
+                (func (export "setup") (result (result)) (canon lift (core func $i "setup")))
+            )"#,
+        )
+        .unwrap();
+        let config = ComponentConfig {
+            path: Some(path.display().to_string()),
+            init: Some("setup".to_string()),
+            ..Default::default()
+        };
+        let mut executor =
+            WasmExecutor::new(WasmContext::new().unwrap(), Config::default()).unwrap();
+        let result = executor
+            .add_component("broken".to_string(), config.clone())
+            .await;
+        assert!(result.is_err());
+
+        executor
+            .add_component(
+                "broken".to_string(),
+                ComponentConfig {
+                    init_failure: Some(InitFailure::Warn),
+                    ..config
+                },
+            )
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_call_after_timeout_succeeds() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("spin.wat");
+        std::fs::write(
+            &path,
+            r#"(component
+                (core module $m
+                    (func (export "spin")
+                        (loop $forever (br $forever)))
+                    (func (export "double") (param i64) (result i64)
+                        local.get 0
+                        local.get 0
+                        i64.add))
+                (core instance $i (instantiate $m))
+                (func (export "spin") (canon lift (core func $i "spin")))
+                (func (export "double") (param "n" u64) (result u64)
+                    (canon lift (core func $i "double")))
+            )"#,
+        )
+        .unwrap();
+        let mut executor =
+            WasmExecutor::new(WasmContext::new().unwrap(), Config::default()).unwrap();
+        executor
+            .add_component(
+                "spin".to_string(),
+                ComponentConfig {
+                    path: Some(path.display().to_string()),
+                    timeout_ms: Some(50),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        let result = executor.execute_function("spin.spin", HashMap::new()).await;
+        assert_eq!(result.unwrap_err().code(), ErrorCode::Timeout);
+
+        let arguments = HashMap::from([("n".to_string(), Value::from(21))]);
+        let result = executor.execute_function("spin.double", arguments).await;
+        assert_eq!(result.unwrap(), Value::from(42));
+    }
+
+    #[tokio::test]
+    async fn test_smoke_call_returning_error_fails_staging() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("health.wat");
+        std::fs::write(
+            &path,
+            r#"(component
+                (core module $m
+                    (func (export "check") (result i32)
+                        i32.const 1))
+                (core instance $i (instantiate $m))
+                (func (export "check") (result (result)) (canon lift (core func $i "check")))
+            )"#,
+        )
+        .unwrap();
+        let executor = WasmExecutor::new(WasmContext::new().unwrap(), Config::default()).unwrap();
+        let result = executor
+            .stage_component(
+                "health",
+                ComponentConfig {
+                    path: Some(path.display().to_string()),
+                    smoke_calls: vec![WarmupCall {
+                        function: "check".to_string(),
+                        args: HashMap::new(),
+                    }],
+                    ..Default::default()
+                },
+            )
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_set_config_rebuilds_validators() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("math.wat");
+        std::fs::write(&path, DOUBLE_WAT).unwrap();
+        let mut executor =
+            WasmExecutor::new(WasmContext::new().unwrap(), Config::default()).unwrap();
+        executor
+            .add_component(
+                "math".to_string(),
+                ComponentConfig {
+                    path: Some(path.display().to_string()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        let arguments = HashMap::from([("n".to_string(), Value::from("21"))]);
+        let result = executor
+            .execute_function("math.double", arguments.clone())
+            .await;
+        assert!(result.is_err());
+
+        executor.set_config(Config {
+            int64_as_string: true,
+            ..Default::default()
+        });
+        let result = executor.execute_function("math.double", arguments).await;
+        assert_eq!(result.unwrap(), Value::from("42"));
+    }
+
+    #[tokio::test]
+    async fn test_watchdog_disables_component() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("math.wat");
+        std::fs::write(&path, DOUBLE_WAT).unwrap();
+        let component = ComponentConfig {
+            path: Some(path.display().to_string()),
+            watchdog: Some(WatchdogConfig {
+                max_call_time_secs: Some(0),
+                action: WatchdogAction::Disable,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut config = Config::default();
+        config
+            .components
+            .insert("math".to_string(), component.clone());
+        let mut executor = WasmExecutor::new(WasmContext::new().unwrap(), config).unwrap();
+        executor
+            .add_component("math".to_string(), component)
+            .await
+            .unwrap();
+        let tools_changed = executor.tools_changed();
+
+        let arguments = HashMap::from([("n".to_string(), Value::from(2))]);
+        let result = executor
+            .execute_function("math.double", arguments.clone())
+            .await;
+        assert_eq!(result.unwrap(), Value::from(4));
+        assert!(executor.is_disabled("math"));
+        tokio::time::timeout(Duration::from_secs(1), tools_changed.notified())
+            .await
+            .unwrap();
+
+        // Still loaded, so it can be enabled again without a reload
+        executor.set_enabled("math", true).unwrap();
+        let result = executor.execute_function("math.double", arguments).await;
+        assert_eq!(result.unwrap(), Value::from(4));
+    }
+
+    #[tokio::test]
+    async fn test_profile_calls_are_recorded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("math.wat");
+        std::fs::write(&path, DOUBLE_WAT).unwrap();
+        let recordings = dir.path().join("recordings");
+        let mut root = WasmExecutor::new(WasmContext::new().unwrap(), Config::default()).unwrap();
+        root.set_recording(recordings.clone());
+
+        let mut profile =
+            WasmExecutor::new(root.fork_context().unwrap(), Config::default()).unwrap();
+        profile
+            .add_component(
+                "math".to_string(),
+                ComponentConfig {
+                    path: Some(path.display().to_string()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        root.attach_profile(&mut profile);
+
+        let arguments = HashMap::from([("n".to_string(), Value::from(2))]);
+        let result = profile.execute_function("math.double", arguments).await;
+        assert_eq!(result.unwrap(), Value::from(4));
+        assert_eq!(std::fs::read_dir(&recordings).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_exit_without_results_fails_typed_functions() {
+        let exited =
+            || -> Result<()> { Err(WasiMcpError::Component(wasmtime_wasi::I32Exit(0).into())) };
+        let options = ConversionOptions::default();
+
+        let result = call_result(exited(), &[], &options).unwrap();
+        assert_eq!(result, Value::from("Successfully executed (exit code 0)"));
+
+        let results = [Val::String(String::new())];
+        let error = call_result(exited(), &results, &options).unwrap_err();
+        assert!(matches!(error, WasiMcpError::Exit(0)));
+        assert_eq!(error.code(), ErrorCode::Exit);
+    }
 }
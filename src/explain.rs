@@ -0,0 +1,275 @@
+//! Contracts of single tools, printed by `wasmic explain`
+//!
+//! Puts everything needed to craft a correct call in one place: the WIT signature a tool
+//! comes from, its input and output schemas with the parameter docs, the defaults and fixed
+//! values configured for it, and an example call built from the input schema.
+
+use crate::call_context::CTX_ARGUMENT;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// The contract of one tool
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Explanation {
+    /// Name the tool is advertised under
+    pub tool: String,
+    pub component: String,
+    /// Export implementing the tool
+    pub function: String,
+    /// WIT signature of the export; unset for the `run` tool of commands
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Parameters in the order the export takes them
+    pub parameters: Vec<ParameterContract>,
+    pub input_schema: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<Value>,
+    /// Arguments of an example call
+    pub example: Value,
+}
+
+/// A parameter of a tool and what the configuration does with it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ParameterContract {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wit_type: Option<String>,
+    /// Whether callers must pass the parameter
+    pub required: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Value used when the caller omits the parameter
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>,
+    /// Value always passed, whatever the caller sends; such parameters are not advertised
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+}
+
+impl Explanation {
+    /// The example call as a `wasmic call` command line
+    ///
+    /// Names the function by its internal `component.function` name, which `wasmic call`
+    /// accepts whatever name the tool is advertised under.
+    pub fn invocation(&self) -> String {
+        format!(
+            "wasmic call --function '{}' --args '{}'",
+            shell_quoted(&format!("{}.{}", self.component, self.function)),
+            shell_quoted(&self.example.to_string())
+        )
+    }
+}
+
+/// WIT signature of a function, as `wasmic inspect` prints it
+pub fn signature(params: &[(String, String)], results: &[String]) -> String {
+    let params = params
+        .iter()
+        .map(|(name, ty)| format!("{name}: {ty}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let results = match results {
+        [] => String::new(),
+        [result] => format!(" -> {result}"),
+        results => format!(" -> ({})", results.join(", ")),
+    };
+    format!("func({params}){results}")
+}
+
+/// Example arguments for an input schema, preferring the examples and defaults it declares
+pub fn example_arguments(input_schema: &Value) -> Value {
+    let mut arguments = Map::new();
+    if let Some(properties) = input_schema.get("properties").and_then(Value::as_object) {
+        for (name, schema) in properties {
+            if name != CTX_ARGUMENT {
+                arguments.insert(name.clone(), example_value(schema));
+            }
+        }
+    }
+    Value::Object(arguments)
+}
+
+fn example_value(schema: &Value) -> Value {
+    // Nested types are described by their bare type name
+    let schema = match schema {
+        Value::String(kind) => return example_of_type(kind, &Map::new()),
+        Value::Object(schema) => schema,
+        _ => return Value::Null,
+    };
+    if let Some(example) = schema
+        .get("examples")
+        .and_then(Value::as_array)
+        .and_then(|examples| examples.first())
+    {
+        return example.clone();
+    }
+    for key in ["default", "const"] {
+        if let Some(value) = schema.get(key) {
+            return value.clone();
+        }
+    }
+    if let Some(first) = schema
+        .get("enum")
+        .and_then(Value::as_array)
+        .and_then(|values| values.first())
+    {
+        return first.clone();
+    }
+    if let Some(first) = schema
+        .get("oneOf")
+        .or_else(|| schema.get("anyOf"))
+        .and_then(Value::as_array)
+        .and_then(|alternatives| alternatives.first())
+    {
+        return example_value(first);
+    }
+    match schema.get("type") {
+        Some(Value::String(kind)) => example_of_type(kind, schema),
+        Some(Value::Array(kinds)) => kinds
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|kind| *kind != "null")
+            .map_or(Value::Null, |kind| example_of_type(kind, schema)),
+        _ => Value::Null,
+    }
+}
+
+fn example_of_type(kind: &str, schema: &Map<String, Value>) -> Value {
+    match kind {
+        "string" => Value::from("string"),
+        "integer" | "number" => Value::from(0),
+        "boolean" => Value::Bool(false),
+        "array" => match schema.get("items") {
+            // Tuples list the type of each element
+            Some(Value::Array(items)) => items.iter().map(example_value).collect(),
+            Some(items) => Value::Array(vec![example_value(items)]),
+            None => Value::Array(Vec::new()),
+        },
+        "object" => example_arguments(&Value::Object(schema.clone())),
+        _ => Value::Null,
+    }
+}
+
+/// Escape single quotes for use inside a single-quoted shell word
+fn shell_quoted(text: &str) -> String {
+    text.replace('\'', r"'\''")
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.tool)?;
+        if let Some(description) = &self.description {
+            writeln!(f, "  {description}")?;
+        }
+        writeln!(f)?;
+        match &self.signature {
+            Some(signature) => writeln!(
+                f,
+                "Export: {} of component {}\n  {signature}",
+                self.function, self.component
+            )?,
+            None => writeln!(
+                f,
+                "Export: {} of component {}",
+                self.function, self.component
+            )?,
+        }
+
+        if !self.parameters.is_empty() {
+            writeln!(f, "\nParameters:")?;
+        }
+        for parameter in &self.parameters {
+            write!(f, "  {}", parameter.name)?;
+            if let Some(wit_type) = &parameter.wit_type {
+                write!(f, ": {wit_type}")?;
+            }
+            if let Some(value) = &parameter.value {
+                write!(f, " (always {value})")?;
+            } else if let Some(default) = &parameter.default {
+                write!(f, " (default {default})")?;
+            } else if !parameter.required {
+                write!(f, " (optional)")?;
+            }
+            writeln!(f)?;
+            if let Some(description) = &parameter.description {
+                writeln!(f, "      {description}")?;
+            }
+        }
+
+        writeln!(f, "\nInput schema:\n{:#}", self.input_schema)?;
+        if let Some(output_schema) = &self.output_schema {
+            writeln!(f, "\nOutput schema:\n{output_schema:#}")?;
+        }
+        writeln!(f, "\nExample:\n  {}", self.invocation())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_example_arguments() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "sql": {"type": "string", "examples": ["select 1"]},
+                "limit": {"oneOf": [{"type": "integer"}, {"type": "null"}], "default": 10},
+                "order": {"type": "string", "enum": ["asc", "desc"]},
+                "ids": {"type": "array", "items": "integer"},
+                "point": {"type": "array", "items": ["number", "boolean"]},
+                "filter": {
+                    "type": "object",
+                    "properties": {"column": "string", "kind": {"oneOf": [{"const": "eq"}]}}
+                },
+                "_ctx": {"type": "object"}
+            }
+        });
+        assert_eq!(
+            example_arguments(&schema),
+            json!({
+                "sql": "select 1",
+                "limit": 10,
+                "order": "asc",
+                "ids": [0],
+                "point": [0, false],
+                "filter": {"column": "string", "kind": "eq"}
+            })
+        );
+    }
+
+    #[test]
+    fn test_signature_and_invocation() {
+        assert_eq!(
+            signature(
+                &[
+                    ("sql".to_string(), "string".to_string()),
+                    ("limit".to_string(), "option<u32>".to_string())
+                ],
+                &["result<string, string>".to_string()]
+            ),
+            "func(sql: string, limit: option<u32>) -> result<string, string>"
+        );
+        assert_eq!(signature(&[], &[]), "func()");
+
+        // Advertised under a prefixed name
+        let explanation = Explanation {
+            tool: "acme_db_query".to_string(),
+            component: "db".to_string(),
+            function: "query".to_string(),
+            signature: None,
+            description: None,
+            parameters: Vec::new(),
+            input_schema: json!({}),
+            output_schema: None,
+            example: json!({"sql": "select 'a'"}),
+        };
+        assert_eq!(
+            explanation.invocation(),
+            r#"wasmic call --function 'db.query' --args '{"sql":"select '\''a'\''"}'"#
+        );
+    }
+}
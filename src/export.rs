@@ -0,0 +1,110 @@
+//! Tool catalogs in the function-calling formats of LLM APIs
+//!
+//! Lets applications that talk to an LLM API directly reuse wasmic's tool schemas without an
+//! MCP client. Those APIs only accept names matching `^[a-zA-Z0-9_-]{1,64}$`, so tool names
+//! are rewritten with [`function_name`].
+
+use rmcp::model::Tool;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+/// Longest function name the LLM APIs accept
+const MAX_NAME_LEN: usize = 64;
+
+/// Tools in OpenAI's `tools` request format
+pub fn openai(tools: &[Tool]) -> Value {
+    tools
+        .iter()
+        .map(|tool| {
+            json!({
+                "type": "function",
+                "function": {
+                    "name": function_name(&tool.name),
+                    "description": tool.description.as_deref().unwrap_or_default(),
+                    "parameters": Value::Object((*tool.input_schema).clone()),
+                }
+            })
+        })
+        .collect()
+}
+
+/// Tools in Anthropic's `tools` request format
+pub fn anthropic(tools: &[Tool]) -> Value {
+    tools
+        .iter()
+        .map(|tool| {
+            json!({
+                "name": function_name(&tool.name),
+                "description": tool.description.as_deref().unwrap_or_default(),
+                "input_schema": Value::Object((*tool.input_schema).clone()),
+            })
+        })
+        .collect()
+}
+
+/// A tool name reduced to `[a-zA-Z0-9_-]`, shortened with a hash suffix beyond 64 characters
+///
+/// `time.wasi:clocks/now@0.2.0.get` becomes `time_wasi_clocks_now_0_2_0_get`.
+pub fn function_name(tool: &str) -> String {
+    let name: String = tool
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.len() <= MAX_NAME_LEN {
+        return name;
+    }
+
+    // Keep names distinct after truncation by appending a hash of the full tool name
+    let hash = format!("{:x}", Sha256::digest(tool.as_bytes()));
+    format!("{}_{}", &name[..MAX_NAME_LEN - 9], &hash[..8])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_function_name() {
+        assert_eq!(
+            function_name("time.wasi:clocks/now@0.2.0.get"),
+            "time_wasi_clocks_now_0_2_0_get"
+        );
+        assert_eq!(function_name("fetch.fetch-url"), "fetch_fetch-url");
+
+        let long = format!("component.{}", "x".repeat(80));
+        let name = function_name(&long);
+        assert_eq!(name.len(), MAX_NAME_LEN);
+        assert_ne!(name, function_name(&format!("{long}y")));
+    }
+
+    #[test]
+    fn test_formats() {
+        let input_schema = json!({"type": "object", "properties": {}});
+        let tools = [Tool {
+            name: "time.now".into(),
+            title: None,
+            description: Some("Current time".into()),
+            input_schema: Arc::new(input_schema.as_object().cloned().unwrap()),
+            output_schema: None,
+            annotations: None,
+            icons: None,
+        }];
+
+        let openai = openai(&tools);
+        assert_eq!(openai[0]["type"], "function");
+        assert_eq!(openai[0]["function"]["name"], "time_now");
+        assert_eq!(openai[0]["function"]["parameters"], input_schema);
+
+        let anthropic = anthropic(&tools);
+        assert_eq!(anthropic[0]["name"], "time_now");
+        assert_eq!(anthropic[0]["description"], "Current time");
+        assert_eq!(anthropic[0]["input_schema"], input_schema);
+    }
+}
@@ -0,0 +1,166 @@
+//! Contract testing of component builds against recorded calls
+//!
+//! Recordings double as golden fixtures: replaying them against a new build of a component,
+//! e.g. a release candidate before its OCI tag is promoted, shows every call whose result,
+//! error or outgoing requests changed. `wasmic replay --candidate` swaps the component's
+//! source for the new build while keeping the rest of its configuration.
+
+use crate::config::ComponentConfig;
+use crate::error::{Result, WasiMcpError};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// Recording files named on the command line; directories contribute their `.json` files
+pub fn collect(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if !path.is_dir() {
+            files.push(path.clone());
+            continue;
+        }
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(path)? {
+            let entry = entry?.path();
+            if entry
+                .extension()
+                .is_some_and(|extension| extension == "json")
+            {
+                entries.push(entry);
+            }
+        }
+        entries.sort();
+        files.extend(entries);
+    }
+    if files.is_empty() {
+        return Err(WasiMcpError::InvalidArguments(format!(
+            "No recordings in {}",
+            paths
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )));
+    }
+    Ok(files)
+}
+
+/// Load the component from `candidate`, a local `.wasm` file or an OCI reference, instead of
+/// its configured source
+pub fn use_candidate(config: &mut ComponentConfig, candidate: &str) {
+    // A pinned digest belongs to the configured build
+    config.sha256 = None;
+    if Path::new(candidate).exists() {
+        config.path = Some(candidate.to_string());
+        config.oci = None;
+    } else {
+        config.oci = Some(candidate.to_string());
+        config.path = None;
+    }
+}
+
+/// Differences between a recorded and a replayed result, one per changed JSON pointer
+pub fn diff(expected: &Value, actual: &Value) -> Vec<String> {
+    let mut differences = Vec::new();
+    diff_at("", expected, actual, &mut differences);
+    differences
+}
+
+fn diff_at(path: &str, expected: &Value, actual: &Value, differences: &mut Vec<String>) {
+    let location = if path.is_empty() { "result" } else { path };
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            for (key, expected) in expected {
+                let path = format!("{path}/{}", escape_pointer(key));
+                match actual.get(key) {
+                    Some(actual) => diff_at(&path, expected, actual, differences),
+                    None => differences.push(format!("{path}: {expected} is missing")),
+                }
+            }
+            for (key, actual) in actual {
+                if !expected.contains_key(key) {
+                    let path = format!("{path}/{}", escape_pointer(key));
+                    differences.push(format!("{path}: unexpected {actual}"));
+                }
+            }
+        }
+        (Value::Array(expected_items), Value::Array(actual_items))
+            if expected_items.len() == actual_items.len() =>
+        {
+            for (index, (expected, actual)) in expected_items.iter().zip(actual_items).enumerate() {
+                diff_at(&format!("{path}/{index}"), expected, actual, differences);
+            }
+        }
+        _ if expected != actual => {
+            differences.push(format!("{location}: expected {expected}, got {actual}"));
+        }
+        _ => {}
+    }
+}
+
+/// Escape a key for use in a JSON pointer
+fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_diff() {
+        let expected = json!({"rows": [[1, 2]], "columns": ["id"], "a/b": true, "total": 1});
+        assert!(diff(&expected, &expected).is_empty());
+
+        let actual = json!({"rows": [[1, 3]], "columns": ["id", "name"], "total": 1, "next": null});
+        assert_eq!(
+            diff(&expected, &actual),
+            vec![
+                "/a~1b: true is missing",
+                r#"/columns: expected ["id"], got ["id","name"]"#,
+                "/rows/0/1: expected 2, got 3",
+                "/next: unexpected null",
+            ]
+        );
+        assert_eq!(
+            diff(&json!("ok"), &json!(1)),
+            vec![r#"result: expected "ok", got 1"#]
+        );
+    }
+
+    #[test]
+    fn test_collect_and_candidate() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["2-b.json", "1-a.json", "notes.txt"] {
+            std::fs::write(dir.path().join(name), "{}").unwrap();
+        }
+        let single = dir.path().join("notes.txt");
+        let files = collect(&[dir.path().to_path_buf(), single.clone()]).unwrap();
+        assert_eq!(
+            files,
+            vec![
+                dir.path().join("1-a.json"),
+                dir.path().join("2-b.json"),
+                single.clone()
+            ]
+        );
+        let empty = tempfile::tempdir().unwrap();
+        assert!(collect(&[empty.path().to_path_buf()]).is_err());
+
+        let mut config = ComponentConfig {
+            oci: Some("ghcr.io/example/db:1.0".to_string()),
+            sha256: Some("abc".to_string()),
+            ..Default::default()
+        };
+        use_candidate(&mut config, single.to_str().unwrap());
+        assert_eq!(config.path.as_deref(), single.to_str());
+        assert_eq!(
+            (config.oci.as_deref(), config.sha256.as_deref()),
+            (None, None)
+        );
+
+        use_candidate(&mut config, "ghcr.io/example/db:1.1-rc1");
+        assert_eq!(config.oci.as_deref(), Some("ghcr.io/example/db:1.1-rc1"));
+        assert_eq!(config.path, None);
+    }
+}
@@ -0,0 +1,151 @@
+//! gRPC interface to the tools
+//!
+//! Serves the `wasmic.v1.Tools` service from `proto/wasmic.proto` with server reflection, for
+//! platforms that standardize on gRPC. Calls go through the same executor, policy and drain
+//! as MCP requests; arguments and results travel as JSON strings.
+
+use crate::error::Result;
+use crate::mcp::WasmMcpServer;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use tonic::{Request, Response, Status};
+
+pub mod proto {
+    tonic::include_proto!("wasmic.v1");
+
+    pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("wasmic_descriptor");
+}
+
+use proto::tools_server::{Tools, ToolsServer};
+use proto::{CallToolRequest, CallToolResponse, ListToolsRequest, ListToolsResponse, Tool};
+
+struct ToolsService {
+    server: WasmMcpServer,
+}
+
+/// Bearer token of the `authorization` metadata entry
+fn bearer_token<T>(request: &Request<T>) -> Option<&str> {
+    request
+        .metadata()
+        .get("authorization")?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+fn status_of(error: &crate::WasiMcpError) -> Status {
+    use crate::WasiMcpError;
+
+    match error {
+        WasiMcpError::FunctionNotFound(_)
+        | WasiMcpError::InterfaceNotFound(_)
+        | WasiMcpError::ComponentNotFound(_) => Status::not_found(error.to_string()),
+        WasiMcpError::InvalidArguments(_)
+        | WasiMcpError::ArgumentViolations(_)
+        | WasiMcpError::UnexpectedExpected(_, _) => Status::invalid_argument(error.to_string()),
+        WasiMcpError::ComponentDisabled(_) => Status::unavailable(error.to_string()),
+        _ => Status::internal(error.to_string()),
+    }
+}
+
+#[tonic::async_trait]
+impl Tools for ToolsService {
+    async fn list_tools(
+        &self,
+        request: Request<ListToolsRequest>,
+    ) -> std::result::Result<Response<ListToolsResponse>, Status> {
+        let mut tools = self
+            .server
+            .executor
+            .lock()
+            .await
+            .tools_by_internal_name()
+            .map_err(|e| status_of(&e))?;
+        if let Some(policy) = &self.server.config.read().await.policy {
+            let token = bearer_token(&request);
+            tools.retain(|(internal, _)| policy.is_allowed(token, internal));
+        }
+
+        let tools = tools
+            .into_iter()
+            .map(|(_, tool)| Tool {
+                name: tool.name.to_string(),
+                description: tool.description.as_deref().unwrap_or_default().to_string(),
+                input_schema_json: serde_json::Value::Object((*tool.input_schema).clone())
+                    .to_string(),
+                output_schema_json: tool
+                    .output_schema
+                    .map(|schema| serde_json::Value::Object((*schema).clone()).to_string())
+                    .unwrap_or_default(),
+            })
+            .collect();
+        Ok(Response::new(ListToolsResponse { tools }))
+    }
+
+    async fn call_tool(
+        &self,
+        request: Request<CallToolRequest>,
+    ) -> std::result::Result<Response<CallToolResponse>, Status> {
+        let tool = &request.get_ref().name;
+        let internal = self.server.executor.lock().await.resolve_tool_name(tool);
+        if let Some(policy) = &self.server.config.read().await.policy {
+            let token = bearer_token(&request);
+            if !policy.is_allowed(token, &internal) {
+                let client = policy.client_name(token).unwrap_or("anonymous");
+                tracing::warn!(client, tool = %tool, "Denied gRPC tool call by policy");
+                return Err(Status::permission_denied(format!(
+                    "Client '{client}' is not allowed to call tool '{tool}'"
+                )));
+            }
+        }
+
+        let CallToolRequest {
+            name,
+            arguments_json,
+        } = request.into_inner();
+        let arguments: HashMap<String, serde_json::Value> = if arguments_json.trim().is_empty() {
+            HashMap::new()
+        } else {
+            serde_json::from_str(&arguments_json).map_err(|e| {
+                Status::invalid_argument(format!("arguments_json must be a JSON object: {e}"))
+            })?
+        };
+
+        let Some(_call) = self.server.drain.start_call() else {
+            return Err(Status::unavailable("Server is shutting down"));
+        };
+        let result = {
+            let mut executor = self.server.executor.lock().await;
+            executor
+                .execute_function(&name, arguments)
+                .await
+                .map(|result| {
+                    executor
+                        .structured_content(&name, &result)
+                        .unwrap_or(result)
+                })
+                .map_err(|e| status_of(&e))?
+        };
+        Ok(Response::new(CallToolResponse {
+            result_json: result.to_string(),
+        }))
+    }
+}
+
+/// Serve the gRPC interface on `address` until the server starts draining
+pub async fn serve(server: WasmMcpServer, address: SocketAddr) -> Result<()> {
+    let reflection = tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(proto::FILE_DESCRIPTOR_SET)
+        .build_v1()
+        .map_err(|e| crate::WasiMcpError::Mcp(format!("Failed to build gRPC reflection: {e}")))?;
+
+    let drain = server.drain.clone();
+    tracing::info!(%address, "Starting gRPC server");
+    tonic::transport::Server::builder()
+        .add_service(reflection)
+        .add_service(ToolsServer::new(ToolsService { server }))
+        .serve_with_shutdown(address, async move { drain.started().await })
+        .await
+        .map_err(|e| crate::WasiMcpError::Mcp(format!("gRPC server failed: {e}")))?;
+    Ok(())
+}
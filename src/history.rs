@@ -0,0 +1,290 @@
+//! Persistent history of tool calls
+//!
+//! With a `history` section in the configuration, every call the MCP server executes is
+//! appended to a SQLite database: the time, tool, a hash of the arguments, whether it
+//! succeeded, a short summary of the result or error, and its duration. `wasmic history`
+//! queries it.
+
+use crate::config::HistoryConfig;
+use crate::error::{Result, WasiMcpError};
+use crate::result_cache::canonical_arguments;
+use crate::schedule::civil_from_days;
+use rusqlite::{Connection, params};
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Longest result or error summary stored, in characters
+const SUMMARY_LEN: usize = 200;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS calls (
+        id INTEGER PRIMARY KEY,
+        time INTEGER NOT NULL,
+        tool TEXT NOT NULL,
+        args_hash TEXT NOT NULL,
+        ok INTEGER NOT NULL,
+        code TEXT,
+        summary TEXT NOT NULL,
+        duration_ms INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS calls_time ON calls (time);
+    CREATE INDEX IF NOT EXISTS calls_tool ON calls (tool, time);
+";
+
+/// One recorded call
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HistoryEntry {
+    /// Seconds since the epoch when the call finished
+    pub time: u64,
+    pub tool: String,
+    /// SHA-256 of the arguments with object keys sorted
+    pub args_hash: String,
+    pub ok: bool,
+    /// Error code of a failed call
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    /// Start of the result, or the error message
+    pub summary: String,
+    pub duration_ms: u64,
+}
+
+impl HistoryEntry {
+    /// Entry for a call that just finished
+    pub fn new(tool: &str, args_hash: String, result: &Result<Value>, duration: Duration) -> Self {
+        let (ok, code, summary) = match result {
+            Ok(value) => (true, None, summarize(&value.to_string())),
+            Err(e) => (false, Some(e.code().to_string()), summarize(&e.to_string())),
+        };
+        Self {
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default(),
+            tool: tool.to_string(),
+            args_hash,
+            ok,
+            code,
+            summary,
+            duration_ms: duration.as_millis() as u64,
+        }
+    }
+}
+
+/// SHA-256 of call arguments with object keys sorted, so equal arguments hash the same
+pub fn arguments_hash(arguments: &HashMap<String, Value>) -> String {
+    format!(
+        "{:x}",
+        Sha256::digest(canonical_arguments(arguments).as_bytes())
+    )
+}
+
+/// A timestamp as `YYYY-MM-DD HH:MM:SS` in UTC
+pub fn format_time(unix_secs: u64) -> String {
+    let days = (unix_secs / 86_400) as i64;
+    let (year, month, day) = civil_from_days(days);
+    let secs = unix_secs % 86_400;
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+fn summarize(text: &str) -> String {
+    match text.char_indices().nth(SUMMARY_LEN) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// Filters of a history query
+#[derive(Debug, Clone, Default)]
+pub struct HistoryQuery {
+    /// Only calls of this tool
+    pub tool: Option<String>,
+    /// Only failed calls
+    pub failed: bool,
+    /// Most recent calls returned
+    pub limit: usize,
+}
+
+/// Database file of a history configuration, by default in the user's data directory
+pub fn database_path(config: &HistoryConfig) -> Result<PathBuf> {
+    match &config.path {
+        Some(path) => Ok(path.clone()),
+        None => Ok(dirs::data_dir()
+            .ok_or_else(|| {
+                WasiMcpError::InvalidArguments("Could not determine data directory".to_string())
+            })?
+            .join("wasmic")
+            .join("history.sqlite")),
+    }
+}
+
+fn open(path: &Path) -> Result<Connection> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let connection = Connection::open(path).map_err(database_error)?;
+    connection.execute_batch(SCHEMA).map_err(database_error)?;
+    Ok(connection)
+}
+
+fn database_error(error: rusqlite::Error) -> WasiMcpError {
+    WasiMcpError::Execution(format!("History database error: {error}"))
+}
+
+fn insert(connection: &Connection, entry: &HistoryEntry) -> Result<()> {
+    connection
+        .execute(
+            "INSERT INTO calls (time, tool, args_hash, ok, code, summary, duration_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                entry.time,
+                entry.tool,
+                entry.args_hash,
+                entry.ok,
+                entry.code,
+                entry.summary,
+                entry.duration_ms
+            ],
+        )
+        .map_err(database_error)?;
+    Ok(())
+}
+
+/// Most recent matching calls, newest first
+pub fn query(path: &Path, query: &HistoryQuery) -> Result<Vec<HistoryEntry>> {
+    let connection = open(path)?;
+    let mut statement = connection
+        .prepare(
+            "SELECT time, tool, args_hash, ok, code, summary, duration_ms FROM calls
+             WHERE (?1 IS NULL OR tool = ?1) AND (?2 = 0 OR ok = 0)
+             ORDER BY time DESC, id DESC LIMIT ?3",
+        )
+        .map_err(database_error)?;
+    let rows = statement
+        .query_map(params![query.tool, query.failed, query.limit], |row| {
+            Ok(HistoryEntry {
+                time: row.get(0)?,
+                tool: row.get(1)?,
+                args_hash: row.get(2)?,
+                ok: row.get(3)?,
+                code: row.get(4)?,
+                summary: row.get(5)?,
+                duration_ms: row.get(6)?,
+            })
+        })
+        .map_err(database_error)?;
+    rows.collect::<std::result::Result<_, _>>()
+        .map_err(database_error)
+}
+
+/// Appends entries to the history database from a background thread, so recording never
+/// blocks a call
+#[derive(Clone)]
+pub struct HistoryRecorder {
+    sender: mpsc::Sender<HistoryEntry>,
+}
+
+impl HistoryRecorder {
+    /// Open the database and start the writer thread
+    pub fn open(path: &Path) -> Result<Self> {
+        let connection = open(path)?;
+        let (sender, receiver) = mpsc::channel::<HistoryEntry>();
+        std::thread::Builder::new()
+            .name("wasmic-history".to_string())
+            .spawn(move || {
+                for entry in receiver {
+                    if let Err(e) = insert(&connection, &entry) {
+                        tracing::warn!("Failed to record call history: {}", e);
+                    }
+                }
+            })?;
+        Ok(Self { sender })
+    }
+
+    pub fn record(&self, entry: HistoryEntry) {
+        // The writer only stops when every recorder is gone
+        let _ = self.sender.send(entry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(tool: &str, result: Result<Value>) -> HistoryEntry {
+        let args_hash = arguments_hash(&HashMap::new());
+        HistoryEntry::new(tool, args_hash, &result, Duration::from_millis(5))
+    }
+
+    #[test]
+    fn test_query() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.sqlite");
+        let connection = open(&path).unwrap();
+        insert(&connection, &entry("time.now", Ok(Value::from(1)))).unwrap();
+        insert(
+            &connection,
+            &entry(
+                "fetch.get",
+                Err(WasiMcpError::InvalidArguments("no url".to_string())),
+            ),
+        )
+        .unwrap();
+        insert(&connection, &entry("time.now", Ok(Value::from(2)))).unwrap();
+
+        let all = query(
+            &path,
+            &HistoryQuery {
+                limit: 10,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].summary, "2");
+
+        let failed = query(
+            &path,
+            &HistoryQuery {
+                failed: true,
+                limit: 10,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].code.as_deref(), Some("validation"));
+
+        let time = query(
+            &path,
+            &HistoryQuery {
+                tool: Some("time.now".to_string()),
+                limit: 1,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(time.len(), 1);
+    }
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(1_709_210_096), "2024-02-29 12:34:56");
+    }
+
+    #[test]
+    fn test_summary() {
+        let long = "x".repeat(SUMMARY_LEN + 10);
+        assert_eq!(summarize(&long).chars().count(), SUMMARY_LEN + 1);
+        assert_eq!(summarize("short"), "short");
+    }
+}
@@ -0,0 +1,43 @@
+use crate::error::{Result, WasiMcpError};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Extension point for embedders to observe and alter tool calls
+///
+/// Hooks are registered on a [`crate::executor::WasmExecutor`] and run in registration order
+/// around every call, including calls answered from the result cache. Returning an error
+/// from [`CallHook::before_call`] rejects the call before any component runs.
+///
+/// ```no_run
+/// use std::collections::HashMap;
+/// use wasmic::{CallHook, Result};
+///
+/// struct Redact;
+///
+/// impl CallHook for Redact {
+///     fn after_call(&self, _tool: &str, result: &mut serde_json::Value) -> Result<()> {
+///         if let Some(object) = result.as_object_mut() {
+///             object.remove("api_key");
+///         }
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait CallHook: Send + Sync {
+    /// Inspect or rewrite the arguments of a call to `tool` (`component.function`)
+    fn before_call(&self, tool: &str, arguments: &mut HashMap<String, Value>) -> Result<()> {
+        let _ = (tool, arguments);
+        Ok(())
+    }
+
+    /// Inspect or rewrite the result of a successful call
+    fn after_call(&self, tool: &str, result: &mut Value) -> Result<()> {
+        let _ = (tool, result);
+        Ok(())
+    }
+
+    /// Observe a failed call, including calls rejected by a hook
+    fn on_error(&self, tool: &str, error: &WasiMcpError) {
+        let _ = (tool, error);
+    }
+}
@@ -0,0 +1,70 @@
+use crate::state::ComponentRunStates;
+use wasmtime::component::Linker;
+
+/// Extension point for embedders to provide additional host interfaces to components
+///
+/// Extensions are registered on a [`crate::wasm::WasmContext`] before components are loaded
+/// and are applied to every linker the context creates, including the ones used for
+/// composed components.
+///
+/// ```no_run
+/// use wasmic::ComponentRunStates;
+/// use wasmic::wasm::WasmContext;
+/// use wasmtime::component::Linker;
+///
+/// let mut context = WasmContext::new()?;
+/// context.register_extension(|linker: &mut Linker<ComponentRunStates>| {
+///     linker
+///         .instance("my:app/secrets")?
+///         .func_wrap("get", |_store, (name,): (String,)| Ok((format!("secret-{name}"),)))?;
+///     Ok(())
+/// })?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub trait HostExtension: Send + Sync {
+    /// Define the extension's host interfaces in the linker
+    fn add_to_linker(&self, linker: &mut Linker<ComponentRunStates>) -> wasmtime::Result<()>;
+}
+
+impl<F> HostExtension for F
+where
+    F: Fn(&mut Linker<ComponentRunStates>) -> wasmtime::Result<()> + Send + Sync,
+{
+    fn add_to_linker(&self, linker: &mut Linker<ComponentRunStates>) -> wasmtime::Result<()> {
+        self(linker)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wasm::WasmContext;
+    use wasmtime::component::Component;
+
+    #[test]
+    fn test_extension_satisfies_imports() {
+        let mut context = WasmContext::new().unwrap();
+        let component = Component::new(
+            &context.engine,
+            r#"(component
+                (import "my:app/secrets" (instance (export "get" (func (result string)))))
+            )"#,
+        )
+        .unwrap();
+        assert!(context.linker.instantiate_pre(&component).is_err());
+
+        context
+            .register_extension(|linker: &mut Linker<ComponentRunStates>| {
+                linker
+                    .instance("my:app/secrets")?
+                    .func_wrap("get", |_store, (): ()| Ok(("s3cr3t".to_string(),)))?;
+                Ok(())
+            })
+            .unwrap();
+        assert!(context.linker.instantiate_pre(&component).is_ok());
+
+        // Linkers created later (e.g. for compositions) include the extension too
+        let linker = context.create_linker().unwrap();
+        assert!(linker.instantiate_pre(&component).is_ok());
+    }
+}
@@ -0,0 +1,168 @@
+//! Checking a component's imports against what the host provides
+//!
+//! wasmtime reports only the first import it cannot satisfy, in terms of its linker. Before
+//! instantiating, the component's imports are compared with the interfaces wasmic, its plugs
+//! and the known WASI proposals provide, so a failure names every missing interface along
+//! with what to do about it.
+
+use crate::error::{Result, WasiMcpError};
+use crate::logging::LOGGING_INTERFACE;
+use crate::sql::SQL_INTERFACE;
+use crate::state::ComponentRunStates;
+use wasmtime::Engine;
+use wasmtime::component::{Component, Linker};
+
+/// WASI packages every linker provides
+const BUILTIN_PACKAGES: &[&str] = &[
+    "wasi:cli",
+    "wasi:clocks",
+    "wasi:config",
+    "wasi:filesystem",
+    "wasi:http",
+    "wasi:io",
+    "wasi:random",
+    "wasi:sockets",
+];
+
+/// WASI proposals wasmic doesn't implement, with advice for components importing them
+const UNSUPPORTED_PACKAGES: &[(&str, &str)] = &[
+    (
+        "wasi:keyvalue",
+        "wasmic has no key-value store; provide it with a plug or rebuild the component without it",
+    ),
+    (
+        "wasi:blobstore",
+        "wasmic has no blob store; provide it with a plug or rebuild the component without it",
+    ),
+    (
+        "wasi:messaging",
+        "wasmic has no messaging support; rebuild the component without it",
+    ),
+    (
+        "wasi:nn",
+        "wasmic has no machine learning backend; rebuild the component without it",
+    ),
+];
+
+/// An import no provider is known for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingImport {
+    pub name: String,
+    pub hint: String,
+}
+
+/// Imports of `component` that neither wasmic nor the names in `provided` satisfy
+///
+/// Host extensions are invisible here, so their interfaces are listed as missing; use
+/// [`check_imports`] to only report them when linking actually fails.
+pub fn missing_imports(
+    engine: &Engine,
+    component: &Component,
+    provided: &[String],
+) -> Vec<MissingImport> {
+    component
+        .component_type()
+        .imports(engine)
+        .map(|(name, _)| name)
+        .filter(|name| !is_builtin(name) && !provided.iter().any(|p| same_interface(p, name)))
+        .map(|name| MissingImport {
+            name: name.to_string(),
+            hint: hint(name),
+        })
+        .collect()
+}
+
+/// Fail with every missing import listed when `linker` cannot instantiate `component`
+pub fn check_imports(
+    linker: &Linker<ComponentRunStates>,
+    engine: &Engine,
+    component: &Component,
+    name: &str,
+    provided: &[String],
+) -> Result<()> {
+    let Err(error) = linker.instantiate_pre(component) else {
+        return Ok(());
+    };
+
+    let missing = missing_imports(engine, component, provided);
+    if missing.is_empty() {
+        // Everything is provided by name, so a version or signature doesn't match
+        return Err(WasiMcpError::InvalidArguments(format!(
+            "Component '{name}' cannot be linked: {error:#}"
+        )));
+    }
+    let mut message = format!("Component '{name}' imports interfaces that are not provided:");
+    for import in &missing {
+        message.push_str(&format!(
+            "\n  - {} not provided — {}",
+            import.name, import.hint
+        ));
+    }
+    Err(WasiMcpError::InvalidArguments(message))
+}
+
+fn is_builtin(name: &str) -> bool {
+    same_interface(LOGGING_INTERFACE, name)
+        || same_interface(SQL_INTERFACE, name)
+        || BUILTIN_PACKAGES
+            .iter()
+            .any(|package| package_of(name) == *package)
+}
+
+/// `wasi:io/streams@0.2.3` -> `wasi:io`
+fn package_of(name: &str) -> &str {
+    let name = name.split('@').next().unwrap_or(name);
+    name.split_once('/').map_or(name, |(package, _)| package)
+}
+
+/// Whether two import names refer to the same interface, ignoring versions
+fn same_interface(a: &str, b: &str) -> bool {
+    a.split('@').next() == b.split('@').next()
+}
+
+fn hint(name: &str) -> String {
+    let package = package_of(name);
+    match UNSUPPORTED_PACKAGES.iter().find(|(p, _)| *p == package) {
+        Some((_, hint)) => hint.to_string(),
+        None => "provide it with a plug or a host extension, or rebuild the component without it"
+            .to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wasm::WasmContext;
+
+    #[test]
+    fn test_missing_imports() {
+        let context = WasmContext::new().unwrap();
+        let component = Component::new(
+            &context.engine,
+            r#"(component
+                (import "wasi:random/random@0.2.3" (instance))
+                (import "wasi:keyvalue/store@0.2.0-draft" (instance))
+                (import "my:app/secrets" (instance))
+                (import "my:app/plugged@1.0.0" (instance))
+            )"#,
+        )
+        .unwrap();
+
+        let provided = ["my:app/plugged@1.0.0".to_string()];
+        let missing = missing_imports(&context.engine, &component, &provided);
+        let names: Vec<&str> = missing.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["wasi:keyvalue/store@0.2.0-draft", "my:app/secrets"]);
+        assert!(missing[0].hint.contains("key-value"));
+
+        let error = check_imports(
+            &context.linker,
+            &context.engine,
+            &component,
+            "app",
+            &provided,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(error.contains("wasi:keyvalue/store@0.2.0-draft not provided"));
+    }
+}
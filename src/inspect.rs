@@ -0,0 +1,314 @@
+use crate::WasiMcpError;
+use crate::error::Result;
+use crate::oci::ImageMetadata;
+use serde::Serialize;
+use std::fmt::Write;
+use wasmtime::Engine;
+use wasmtime::component::Component;
+use wasmtime::component::types::{ComponentFunc, ComponentItem, Type};
+
+/// Imports and exports of a component
+#[derive(Debug, Serialize)]
+pub struct WorldInfo {
+    /// Description, license, source and version of a pulled component
+    #[serde(skip_serializing_if = "ImageMetadata::is_empty")]
+    pub metadata: ImageMetadata,
+    pub imports: Vec<ItemInfo>,
+    pub exports: Vec<ItemInfo>,
+}
+
+/// A named import or export and its signature
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum ItemInfo {
+    Function {
+        name: String,
+        params: Vec<ParamInfo>,
+        results: Vec<String>,
+    },
+    Interface {
+        name: String,
+        items: Vec<ItemInfo>,
+    },
+    Component {
+        name: String,
+        items: Vec<ItemInfo>,
+    },
+    Type {
+        name: String,
+        definition: String,
+    },
+    Resource {
+        name: String,
+    },
+    Module {
+        name: String,
+    },
+    CoreFunc {
+        name: String,
+    },
+}
+
+/// A function parameter with its WIT type
+#[derive(Debug, Serialize)]
+pub struct ParamInfo {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+}
+
+impl WorldInfo {
+    /// Describe the imports and exports of a compiled component
+    pub fn new(engine: &Engine, component: &Component) -> Self {
+        let ty = component.component_type();
+        Self {
+            metadata: ImageMetadata::default(),
+            imports: ty
+                .imports(engine)
+                .map(|(name, item)| ItemInfo::new(engine, name, &item))
+                .collect(),
+            exports: ty
+                .exports(engine)
+                .map(|(name, item)| ItemInfo::new(engine, name, &item))
+                .collect(),
+        }
+    }
+
+    /// Render the world in WIT-like syntax
+    ///
+    /// Component types don't retain type names, so records, variants and the like are
+    /// printed inline.
+    pub fn to_wit(&self, world: &str) -> String {
+        let mut wit = String::new();
+        let ImageMetadata {
+            description,
+            licenses,
+            source,
+            version,
+        } = &self.metadata;
+        if let Some(description) = description {
+            for line in description.lines() {
+                let _ = writeln!(wit, "/// {line}");
+            }
+        }
+        for (key, value) in [
+            ("version", version),
+            ("license", licenses),
+            ("source", source),
+        ] {
+            if let Some(value) = value {
+                let _ = writeln!(wit, "// {key}: {value}");
+            }
+        }
+        let _ = writeln!(wit, "world {world} {{");
+        for item in &self.imports {
+            item.write_wit(&mut wit, "import ", 1);
+        }
+        for item in &self.exports {
+            item.write_wit(&mut wit, "export ", 1);
+        }
+        wit.push_str("}\n");
+        wit
+    }
+
+    /// JSON representation for scripting
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).map_err(WasiMcpError::from)
+    }
+}
+
+impl ItemInfo {
+    fn new(engine: &Engine, name: &str, item: &ComponentItem) -> Self {
+        let name = name.to_string();
+        match item {
+            ComponentItem::ComponentFunc(func) => function_info(name, func),
+            ComponentItem::ComponentInstance(instance) => ItemInfo::Interface {
+                name,
+                items: instance
+                    .exports(engine)
+                    .map(|(name, item)| ItemInfo::new(engine, name, &item))
+                    .collect(),
+            },
+            ComponentItem::Component(component) => ItemInfo::Component {
+                name,
+                items: component
+                    .exports(engine)
+                    .map(|(name, item)| ItemInfo::new(engine, name, &item))
+                    .collect(),
+            },
+            ComponentItem::Type(ty) => ItemInfo::Type {
+                name,
+                definition: wit_type(ty),
+            },
+            ComponentItem::Resource(_) => ItemInfo::Resource { name },
+            ComponentItem::Module(_) => ItemInfo::Module { name },
+            ComponentItem::CoreFunc(_) => ItemInfo::CoreFunc { name },
+        }
+    }
+
+    fn write_wit(&self, wit: &mut String, prefix: &str, depth: usize) {
+        let indent = "    ".repeat(depth);
+        // Writing to a String cannot fail
+        let _ = match self {
+            ItemInfo::Function {
+                name,
+                params,
+                results,
+            } => {
+                let params = params
+                    .iter()
+                    .map(|param| format!("{}: {}", param.name, param.ty))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let results = match results.as_slice() {
+                    [] => String::new(),
+                    [result] => format!(" -> {result}"),
+                    results => format!(" -> ({})", results.join(", ")),
+                };
+                writeln!(wit, "{indent}{prefix}{name}: func({params}){results};")
+            }
+            ItemInfo::Interface { name, items } | ItemInfo::Component { name, items } => {
+                let kind = match self {
+                    ItemInfo::Component { .. } => "component",
+                    _ => "interface",
+                };
+                let _ = writeln!(wit, "{indent}{prefix}{name}: {kind} {{");
+                for item in items {
+                    item.write_wit(wit, "", depth + 1);
+                }
+                writeln!(wit, "{indent}}}")
+            }
+            ItemInfo::Type { name, definition } => {
+                writeln!(wit, "{indent}{prefix}type {name} = {definition};")
+            }
+            ItemInfo::Resource { name } => writeln!(wit, "{indent}{prefix}resource {name};"),
+            ItemInfo::Module { name } => writeln!(wit, "{indent}{prefix}{name}: core module;"),
+            ItemInfo::CoreFunc { name } => writeln!(wit, "{indent}{prefix}{name}: core func;"),
+        };
+    }
+}
+
+fn function_info(name: String, func: &ComponentFunc) -> ItemInfo {
+    ItemInfo::Function {
+        name,
+        params: func
+            .params()
+            .map(|(name, ty)| ParamInfo {
+                name: name.to_string(),
+                ty: wit_type(&ty),
+            })
+            .collect(),
+        results: func.results().map(|ty| wit_type(&ty)).collect(),
+    }
+}
+
+/// Render a component value type in WIT syntax
+pub fn wit_type(ty: &Type) -> String {
+    match ty {
+        Type::Bool => "bool".to_string(),
+        Type::S8 => "s8".to_string(),
+        Type::U8 => "u8".to_string(),
+        Type::S16 => "s16".to_string(),
+        Type::U16 => "u16".to_string(),
+        Type::S32 => "s32".to_string(),
+        Type::U32 => "u32".to_string(),
+        Type::S64 => "s64".to_string(),
+        Type::U64 => "u64".to_string(),
+        Type::Float32 => "f32".to_string(),
+        Type::Float64 => "f64".to_string(),
+        Type::Char => "char".to_string(),
+        Type::String => "string".to_string(),
+        Type::List(list) => format!("list<{}>", wit_type(&list.ty())),
+        Type::Record(record) => {
+            let fields = record
+                .fields()
+                .map(|field| format!("{}: {}", field.name, wit_type(&field.ty)))
+                .collect::<Vec<_>>();
+            format!("record {{ {} }}", fields.join(", "))
+        }
+        Type::Tuple(tuple) => {
+            let types = tuple.types().map(|ty| wit_type(&ty)).collect::<Vec<_>>();
+            format!("tuple<{}>", types.join(", "))
+        }
+        Type::Variant(variant) => {
+            let cases = variant
+                .cases()
+                .map(|case| match &case.ty {
+                    Some(ty) => format!("{}({})", case.name, wit_type(ty)),
+                    None => case.name.to_string(),
+                })
+                .collect::<Vec<_>>();
+            format!("variant {{ {} }}", cases.join(", "))
+        }
+        Type::Enum(enum_ty) => {
+            let names = enum_ty.names().collect::<Vec<_>>();
+            format!("enum {{ {} }}", names.join(", "))
+        }
+        Type::Option(option) => format!("option<{}>", wit_type(&option.ty())),
+        Type::Result(result) => match (result.ok(), result.err()) {
+            (None, None) => "result".to_string(),
+            (Some(ok), None) => format!("result<{}>", wit_type(&ok)),
+            (None, Some(err)) => format!("result<_, {}>", wit_type(&err)),
+            (Some(ok), Some(err)) => format!("result<{}, {}>", wit_type(&ok), wit_type(&err)),
+        },
+        Type::Flags(flags) => {
+            let names = flags.names().collect::<Vec<_>>();
+            format!("flags {{ {} }}", names.join(", "))
+        }
+        Type::Own(_) => "own<resource>".to_string(),
+        Type::Borrow(_) => "borrow<resource>".to_string(),
+        Type::Future(future) => match future.ty() {
+            Some(ty) => format!("future<{}>", wit_type(&ty)),
+            None => "future".to_string(),
+        },
+        Type::Stream(stream) => match stream.ty() {
+            Some(ty) => format!("stream<{}>", wit_type(&ty)),
+            None => "stream".to_string(),
+        },
+        Type::ErrorContext => "error-context".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_world_info() {
+        let engine = Engine::default();
+        let component = Component::new(
+            &engine,
+            r#"
+            (component
+                (core module $m
+                    (func (export "add") (param i32 i32) (result i32)
+                        local.get 0
+                        local.get 1
+                        i32.add))
+                (core instance $i (instantiate $m))
+                (func (export "add") (param "a" u32) (param "b" u32) (result u32)
+                    (canon lift (core func $i "add")))
+            )
+            "#,
+        )
+        .unwrap();
+
+        let world = WorldInfo::new(&engine, &component);
+        assert!(world.imports.is_empty());
+        assert_eq!(
+            world.to_wit("calculator"),
+            "world calculator {\n    export add: func(a: u32, b: u32) -> u32;\n}\n"
+        );
+        assert!(world.to_json().unwrap().contains(r#""kind": "function""#));
+        assert!(!world.to_json().unwrap().contains("metadata"));
+
+        let mut world = world;
+        world.metadata.description = Some("Adds numbers".to_string());
+        world.metadata.version = Some("1.0.0".to_string());
+        assert!(
+            world
+                .to_wit("calculator")
+                .starts_with("/// Adds numbers\n// version: 1.0.0\nworld calculator {\n")
+        );
+    }
+}
@@ -0,0 +1,455 @@
+//! Component inspection without instantiation
+//!
+//! [`ComponentInspector`] reads the exported functions of a compiled component and the tool
+//! schemas derived from them from the component's type alone. No store, WASI context or
+//! instance is created, so inspecting is cheap enough for `list`, `inspect` and generating
+//! documentation.
+
+use crate::cache::ComponentCache;
+use crate::command;
+use crate::docs::ComponentDocs;
+use crate::error::Result;
+use crate::inspect::WorldInfo;
+use crate::utils::transform::{ConversionOptions, result_key};
+use crate::utils::wasm::convert_wasm_type_to_json;
+use rmcp::model::Tool;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use wasmtime::Engine;
+use wasmtime::component::{Component, types::ComponentItem};
+
+/// Exported functions of a compiled component, read from its type
+#[derive(Clone)]
+pub struct ComponentInspector {
+    engine: Engine,
+    component: Component,
+    interfaces: HashMap<String, InterfaceInfo>,
+    functions: HashMap<String, FunctionInfo>,
+    docs: ComponentDocs,
+    options: ConversionOptions,
+}
+
+impl ComponentInspector {
+    /// Inspect a compiled component
+    pub fn new(engine: &Engine, component: Component) -> Self {
+        let mut interfaces = HashMap::with_capacity(4); // Pre-allocate with reasonable capacity
+        let mut functions = HashMap::with_capacity(8); // Pre-allocate with reasonable capacity
+
+        // Walk top-level exports and use get_exports to get all information
+        for (name, item) in component.component_type().exports(engine) {
+            let exports = get_exports(engine, name, &item);
+
+            // Process standalone functions (top-level functions not in interfaces)
+            for func in exports.functions {
+                // Only add as standalone function if it's not part of an interface
+                if !exports
+                    .interfaces
+                    .iter()
+                    .any(|interface| interface.functions.contains_key(&func.name))
+                {
+                    functions.insert(func.name.clone(), func);
+                }
+            }
+
+            // Add interfaces to our collections if they have functions
+            for interface in exports.interfaces {
+                if !interface.functions.is_empty() {
+                    interfaces.insert(interface.full_name.clone(), interface);
+                }
+            }
+        }
+
+        Self {
+            engine: engine.clone(),
+            component,
+            interfaces,
+            functions,
+            docs: ComponentDocs::default(),
+            options: ConversionOptions::default(),
+        }
+    }
+
+    /// Describe tools with the WIT docs of the component
+    pub fn with_docs(mut self, docs: ComponentDocs) -> Self {
+        self.docs = docs;
+        self
+    }
+
+    /// Describe the values of tools as they are encoded with `options`
+    pub fn with_conversion_options(mut self, options: ConversionOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Compile or load from the cache the component at `path` and inspect it, after checking
+    /// the file against an expected SHA-256 when one is given
+    pub fn load(engine: &Engine, path: &Path, sha256: Option<&str>) -> Result<Self> {
+        let component = ComponentCache::new()?.load_verified(engine, path, sha256)?;
+        Ok(Self::new(engine, component).with_docs(ComponentDocs::read(path)))
+    }
+
+    pub fn component(&self) -> &Component {
+        &self.component
+    }
+
+    /// Interfaces with at least one function, by full name
+    pub fn interfaces(&self) -> &HashMap<String, InterfaceInfo> {
+        &self.interfaces
+    }
+
+    /// Top-level functions that are not part of an interface
+    pub fn functions(&self) -> &HashMap<String, FunctionInfo> {
+        &self.functions
+    }
+
+    /// Imports and exports with their WIT signatures
+    pub fn world(&self) -> WorldInfo {
+        WorldInfo::new(&self.engine, &self.component)
+    }
+
+    /// Whether the component is a program whose only export is `wasi:cli/run`
+    pub fn is_command(&self) -> bool {
+        self.functions.is_empty()
+            && self.interfaces.len() == 1
+            && self
+                .interfaces
+                .keys()
+                .all(|name| command::is_run_interface(name))
+    }
+
+    /// Tools of every exported function, in export order, described by their WIT docs or
+    /// else by `description`; a command component has a single `run` tool
+    pub fn tools(&self, description: Option<&str>) -> Vec<Tool> {
+        if self.is_command() {
+            return vec![command::run_tool(description)];
+        }
+
+        let mut tools = Vec::new();
+        for (name, item) in self.component.component_type().exports(&self.engine) {
+            let exports = get_exports(&self.engine, name, &item);
+
+            // Process top-level functions
+            for func in &exports.functions {
+                tools.push(self.tool(func, description));
+            }
+
+            // Process interfaces and their functions
+            for interface in &exports.interfaces {
+                for func_info in interface.functions.values() {
+                    tools.push(self.tool(func_info, description));
+                }
+            }
+        }
+
+        tools
+    }
+
+    fn tool(&self, func: &FunctionInfo, description: Option<&str>) -> Tool {
+        let mut tool = Tool::from(&func.with_conversion_options(&self.options));
+        let docs = self.docs.function(&func.name);
+        let description = docs
+            .and_then(|docs| docs.description.as_deref())
+            .or(description)
+            .unwrap_or_default();
+        tool.description = Some(description.to_string().into());
+
+        if let Some(docs) = docs {
+            let input_schema = Arc::make_mut(&mut tool.input_schema);
+            if let Some(serde_json::Value::Object(properties)) = input_schema.get_mut("properties")
+            {
+                for (name, text) in &docs.params {
+                    if let Some(serde_json::Value::Object(schema)) = properties.get_mut(name) {
+                        schema.insert("description".to_string(), text.clone().into());
+                    }
+                }
+            }
+        }
+        tool
+    }
+
+    /// The component and its functions, for instantiation
+    pub(crate) fn into_parts(
+        self,
+    ) -> (
+        Component,
+        HashMap<String, InterfaceInfo>,
+        HashMap<String, FunctionInfo>,
+    ) {
+        (self.component, self.interfaces, self.functions)
+    }
+}
+
+/// Component export information with optimized memory usage
+#[derive(Debug, Clone, Default)]
+pub struct ComponentExports {
+    pub functions: Vec<FunctionInfo>,
+    pub interfaces: Vec<InterfaceInfo>,
+}
+
+/// Interface information containing functions
+#[derive(Debug, Clone)]
+pub struct InterfaceInfo {
+    pub name: String,
+    pub full_name: String,
+    pub functions: HashMap<String, FunctionInfo>,
+}
+
+/// Parameter information combining name, type, and position
+#[derive(Debug, Clone)]
+pub struct ParameterInfo {
+    pub name: String,
+    pub param_json: serde_json::Value, // JSON schema for the type
+    pub wasm_type: wasmtime::component::Type,
+    pub position: usize,
+}
+
+/// Function information with optimized field types
+#[derive(Debug, Clone)]
+pub struct FunctionInfo {
+    pub name: String,
+    pub params: Vec<ParameterInfo>,
+    pub results: Vec<serde_json::Value>, // Function return types/results as JSON
+    pub result_types: Vec<wasmtime::component::Type>,
+    pub func: Option<wasmtime::component::Func>,
+}
+
+impl FunctionInfo {
+    /// The function with schemas describing its values as encoded with `options`
+    pub fn with_conversion_options(&self, options: &ConversionOptions) -> FunctionInfo {
+        let mut function = self.clone();
+        if options.int64_as_string {
+            for param in &mut function.params {
+                param.param_json = options.input_schema(&param.wasm_type);
+            }
+            function.results = function
+                .result_types
+                .iter()
+                .map(|ty| options.output_schema(ty))
+                .collect();
+        }
+        function
+    }
+}
+
+impl From<&FunctionInfo> for Tool {
+    fn from(value: &FunctionInfo) -> Self {
+        let tool_name = value.name.to_string();
+        let description = None;
+        let params = &value.params;
+        let results = &value.results;
+
+        // Create input schema based on function parameters with proper JSON schema types
+        let input_schema = if params.is_empty() {
+            serde_json::json!({
+                "type": "object",
+                "properties": {},
+                "required": [],
+                "additionalProperties": false
+            })
+        } else {
+            let mut properties = serde_json::Map::with_capacity(params.len());
+            let mut required = Vec::with_capacity(params.len());
+
+            for param_info in params.iter() {
+                let mut param_schema = serde_json::Map::new();
+
+                // Use the JSON schema directly from param_json
+                if let Some(obj) = param_info.param_json.as_object() {
+                    param_schema.extend(obj.clone());
+                } else {
+                    // Fallback if it's not an object
+                    param_schema.insert("type".to_string(), param_info.param_json.clone());
+                }
+
+                properties.insert(
+                    param_info.name.clone(),
+                    serde_json::Value::Object(param_schema),
+                );
+                // Option parameters may be omitted and are passed as `none`
+                if !matches!(param_info.wasm_type, wasmtime::component::Type::Option(_)) {
+                    required.push(&param_info.name);
+                }
+            }
+
+            serde_json::json!({
+                "type": "object",
+                "properties": properties,
+                "required": required,
+                "additionalProperties": false
+            })
+        };
+
+        // Results are returned as an object keyed by result name; functions with no return
+        // value only produce a success message and have no structured output
+        let output_schema = (!results.is_empty()).then(|| {
+            let properties: serde_json::Map<String, serde_json::Value> = results
+                .iter()
+                .enumerate()
+                .map(|(i, result_type)| (result_key(i, results.len()), result_type.clone()))
+                .collect();
+            serde_json::json!({
+                "type": "object",
+                "properties": properties,
+                "required": properties.keys().collect::<Vec<_>>(),
+                "additionalProperties": false
+            })
+        });
+
+        Tool {
+            name: tool_name.into(),
+            title: None,
+            description,
+            input_schema: Arc::new(input_schema.as_object().cloned().unwrap_or_default()),
+            output_schema: output_schema
+                .and_then(|schema| schema.as_object().cloned())
+                .map(Arc::new),
+            annotations: None,
+            icons: None,
+        }
+    }
+}
+
+/// Recursively extract exports from a component item with optimized processing and reduced allocations
+pub fn get_exports(engine: &Engine, path: &str, item: &ComponentItem) -> ComponentExports {
+    let mut exports = ComponentExports {
+        functions: Vec::with_capacity(4), // Pre-allocate with reasonable capacity
+        interfaces: Vec::with_capacity(1), // Most components have few interfaces
+    };
+
+    match item {
+        ComponentItem::ComponentFunc(f) => {
+            let results: Vec<serde_json::Value> =
+                f.results().map(|t| convert_wasm_type_to_json(&t)).collect();
+
+            // Create parameter info with position - optimized allocation
+            let params = f
+                .params()
+                .enumerate()
+                .map(|(position, (n, t))| {
+                    let param_json = convert_wasm_type_to_json(&t);
+                    ParameterInfo {
+                        name: n.to_string(),
+                        param_json,
+                        wasm_type: t.clone(),
+                        position,
+                    }
+                })
+                .collect();
+
+            exports.functions.push(FunctionInfo {
+                name: path.to_string(),
+                params,
+                results,
+                result_types: f.results().collect(),
+                func: None,
+            });
+        }
+        ComponentItem::CoreFunc(_ft) => {
+            // todo: improve param/result extraction
+        }
+        ComponentItem::ComponentInstance(inst) => {
+            let mut interface_functions = HashMap::with_capacity(4); // Pre-allocate
+
+            for (name, nested) in inst.exports(engine) {
+                let child = format!("{path}.{name}");
+                let nested_result = get_exports(engine, &child, &nested);
+
+                // Add functions from nested inspection
+                for func in nested_result.functions {
+                    // Keep the original function name, but create the full path for tool execution
+                    let function_key = func.name.clone(); // Original function name
+                    let full_function_path = format!("{path}.{name}"); // Full path for execution
+
+                    // Create a new function info with the proper name for execution
+                    let mut func_for_interface = func.clone();
+                    func_for_interface.name = full_function_path;
+
+                    interface_functions.insert(function_key, func_for_interface);
+                }
+
+                // Add interfaces from nested inspection
+                exports.interfaces.extend(nested_result.interfaces);
+            }
+
+            // Create interface info for this instance if it has functions
+            if !interface_functions.is_empty() {
+                let interface_parts: Vec<&str> = path.split('/').collect();
+                let interface_display_name = interface_parts.last().copied().unwrap_or(path);
+
+                let interface_info = InterfaceInfo {
+                    name: interface_display_name.to_string(),
+                    full_name: path.to_string(),
+                    functions: interface_functions,
+                };
+
+                exports.interfaces.push(interface_info);
+            }
+        }
+        ComponentItem::Component(nested_comp) => {
+            // Nested component defined inside this component
+            for (name, nested) in nested_comp.exports(engine) {
+                let child = format!("{path}.{name}");
+                let nested_result = get_exports(engine, &child, &nested);
+
+                // Add all results from nested inspection
+                exports.functions.extend(nested_result.functions);
+                exports.interfaces.extend(nested_result.interfaces);
+            }
+        }
+        ComponentItem::Module(_) => {
+            // Module types are not currently used, skip collecting them
+        }
+        ComponentItem::Type(_) => {
+            // Type information is not currently used, skip collecting it
+        }
+        ComponentItem::Resource(_) => {
+            // Resource information is not currently used, skip collecting it
+        }
+    }
+
+    exports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inspect_without_instantiating() {
+        let engine = Engine::default();
+        let component = Component::new(
+            &engine,
+            r#"
+            (component
+                (core module $m
+                    (func (export "add") (param i32 i32) (result i32)
+                        local.get 0
+                        local.get 1
+                        i32.add))
+                (core instance $i (instantiate $m))
+                (func (export "add") (param "a" u32) (param "b" u32) (result u32)
+                    (canon lift (core func $i "add")))
+            )
+            "#,
+        )
+        .unwrap();
+
+        let inspector = ComponentInspector::new(&engine, component);
+        assert!(inspector.interfaces().is_empty());
+        let add = &inspector.functions()["add"];
+        assert_eq!(add.params.len(), 2);
+        assert!(add.func.is_none());
+
+        let tools = inspector.tools(Some("Adds numbers"));
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, "add");
+        assert_eq!(tools[0].description.as_deref(), Some("Adds numbers"));
+        assert_eq!(
+            tools[0].input_schema["required"],
+            serde_json::json!(["a", "b"])
+        );
+        let output_schema = tools[0].output_schema.as_ref().unwrap();
+        assert_eq!(output_schema["required"], serde_json::json!(["result"]));
+    }
+}
@@ -2,19 +2,66 @@
 //!
 //! This library provides functionality for managing WASI components and running them as MCP servers.
 
+pub mod bench;
+pub mod cache;
+pub mod call_context;
 pub mod cli;
+pub mod command;
+pub mod componentize;
+pub mod compose;
 pub mod config;
+pub mod coredump;
+pub mod deadline;
+pub mod docs;
+pub mod dry_run;
+pub mod embed;
 pub mod error;
 pub mod executor;
+pub mod explain;
+pub mod export;
+pub mod golden;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod history;
+pub mod hooks;
+pub mod host;
+pub mod imports;
+pub mod inspect;
+pub mod inspector;
 pub mod linker;
+pub mod logging;
 pub mod mcp;
+pub mod naming;
 pub mod oci;
+pub mod openapi;
+pub mod policy;
+pub mod prompts;
+pub mod recovery;
+pub mod replay;
+pub mod resources;
+pub mod rest;
+pub mod result_cache;
+pub mod schedule;
+pub mod secrets;
 pub mod server;
+pub mod shutdown;
+pub mod signature;
+pub mod sql;
 pub mod state;
+pub mod stats;
+pub mod testing;
+pub mod trace;
 mod utils;
+pub mod validation;
 pub mod wasm;
+pub mod watch;
+pub mod watchdog;
+pub mod webhook;
 
 // Re-export commonly used types
-pub use config::{ComponentConfig, Config, VolumeMount};
+pub use config::{ComponentConfig, Config, PlugConfig, VolumeMount};
+pub use embed::{Wasmic, WasmicBuilder};
 pub use error::{Result, WasiMcpError};
+pub use hooks::CallHook;
+pub use host::HostExtension;
 pub use state::ComponentRunStates;
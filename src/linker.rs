@@ -1,16 +1,64 @@
 use crate::WasiMcpError;
-use crate::config::ComponentConfig;
+use crate::config::{ComponentConfig, VolumeMount};
 use crate::error::Result;
+use crate::logging::{GuestStdio, GuestStream};
+use crate::replay::Tape;
 use crate::state::ComponentRunStates;
+use ipnet::IpNet;
+use std::net::IpAddr;
 use std::path::Path;
-use wasmtime_wasi::WasiCtxBuilder;
+use std::sync::Arc;
+use tempfile::TempDir;
+use wasmtime_wasi::{DirPerms, FilePerms, WasiCtxBuilder};
+use wasmtime_wasi_config::WasiConfigVariables;
+
+/// Where the scratch directory is mounted in the guest
+const SCRATCH_PATH: &str = "/tmp";
 
 impl TryFrom<&ComponentConfig> for ComponentRunStates {
     type Error = WasiMcpError;
 
     fn try_from(config: &ComponentConfig) -> std::result::Result<Self, Self::Error> {
+        Self::with_stdio(config, |builder, stdio| {
+            default_stdio(config, builder, stdio)
+        })
+    }
+}
+
+/// Guest output is logged line by line instead of mixing with the host's stdio, unless the
+/// component is granted the host's streams
+fn default_stdio(config: &ComponentConfig, builder: &mut WasiCtxBuilder, stdio: &GuestStdio) {
+    if config.inherit_stdio.unwrap_or_default() {
+        builder.inherit_stdio();
+    } else {
+        builder
+            .stdout(stdio.stream(GuestStream::Stdout))
+            .stderr(stdio.stream(GuestStream::Stderr));
+    }
+    if config.inherit_args.unwrap_or_default() {
+        builder.inherit_args();
+    }
+}
+
+impl ComponentRunStates {
+    /// States for a call whose clocks, random numbers and HTTP responses go through `tape`
+    pub(crate) fn with_tape(config: &ComponentConfig, tape: Arc<Tape>) -> Result<Self> {
+        let mut states = Self::with_stdio(config, |builder, stdio| {
+            default_stdio(config, builder, stdio);
+            tape.install(builder);
+        })?;
+        states.tape = Some(tape);
+        Ok(states)
+    }
+
+    /// States for a component, with arguments and standard streams set up by `configure`
+    pub(crate) fn with_stdio(
+        config: &ComponentConfig,
+        configure: impl FnOnce(&mut WasiCtxBuilder, &GuestStdio),
+    ) -> Result<Self> {
+        let stdio = GuestStdio::default();
         let mut builder = WasiCtxBuilder::new();
-        builder.inherit_stdio().inherit_args();
+        configure(&mut builder, &stdio);
 
         // Determine the working directory
         if let Some(cwd_path) = &config.cwd {
@@ -28,12 +76,8 @@ impl TryFrom<&ComponentConfig> for ComponentRunStates {
                 )));
             }
 
-            builder.preopened_dir(
-                path,
-                ".",
-                wasmtime_wasi::DirPerms::all(),
-                wasmtime_wasi::FilePerms::all(),
-            )?;
+            let (dir_perms, file_perms) = cwd_permissions(path, &config.volumes);
+            builder.preopened_dir(path, ".", dir_perms, file_perms)?;
         }
 
         // Add volume mounts to the WASI context
@@ -61,11 +105,12 @@ impl TryFrom<&ComponentConfig> for ComponentRunStates {
             };
 
             // Add the preopened directory to the WASI context
+            let (dir_perms, file_perms) = mount_permissions(mount);
             builder.preopened_dir(
                 dir_to_mount,
                 mount.guest_path.clone(),
-                wasmtime_wasi::DirPerms::all(),
-                wasmtime_wasi::FilePerms::all(),
+                dir_perms,
+                file_perms,
             )?;
 
             tracing::debug!(
@@ -76,18 +121,163 @@ impl TryFrom<&ComponentConfig> for ComponentRunStates {
             );
         }
 
+        let scratch = if config.scratch.unwrap_or_default() {
+            Some(scratch_dir(config, &mut builder)?)
+        } else {
+            None
+        };
+
+        // Sockets are linked for every component but only usable when networking is enabled
+        if config.network.unwrap_or_default() {
+            let allowed = parse_networks(&config.allowed_networks)?;
+            builder.allow_ip_name_lookup(true);
+            if allowed.is_empty() {
+                builder.inherit_network();
+            } else {
+                let allowed = Arc::new(allowed);
+                builder.socket_addr_check(move |addr, _use| {
+                    let allowed = allowed.clone();
+                    Box::pin(async move { allowed.iter().any(|net| net.contains(&addr.ip())) })
+                });
+            }
+            tracing::debug!(
+                "Enabled networking (allowed: {:?})",
+                config.allowed_networks
+            );
+        }
+
+        // The host's variables are only passed when granted, and `env` takes precedence
+        if config.inherit_env.unwrap_or_default() {
+            for (key, value) in std::env::vars() {
+                // A scratch directory already set TMPDIR
+                if !config.env.contains_key(&key) && !(scratch.is_some() && key == "TMPDIR") {
+                    builder.env(key, value);
+                }
+            }
+        }
+
         // Add environment variables to the WASI context
         for (key, value) in &config.env {
             builder.env(key, value);
-            tracing::debug!("Set environment variable: {}={}", key, value);
+            tracing::debug!("Set environment variable: {}", key);
         }
 
         let wasi_ctx = builder.build();
+        let config_vars: WasiConfigVariables = config_variables(config.config.as_ref())?
+            .into_iter()
+            .collect();
         Ok(ComponentRunStates {
             wasi_ctx,
             resource_table: wasmtime::component::ResourceTable::new(),
             http_ctx: wasmtime_wasi_http::WasiHttpCtx::new(),
+            config_vars,
+            component_name: String::new(),
+            allowed_hosts: config.allowed_hosts.clone(),
+            memory: Default::default(),
+            stdio,
+            scratch,
+            tape: None,
+            databases: config.databases.clone(),
+        })
+    }
+}
+
+/// Create a scratch directory and mount it at `/tmp`, pointing `TMPDIR` at it
+fn scratch_dir(config: &ComponentConfig, builder: &mut WasiCtxBuilder) -> Result<TempDir> {
+    if let Some(mount) = config
+        .volumes
+        .iter()
+        .find(|mount| mount.guest_path.trim_end_matches('/') == SCRATCH_PATH)
+    {
+        return Err(WasiMcpError::InvalidArguments(format!(
+            "scratch conflicts with the volume {} mounted at {SCRATCH_PATH}",
+            mount.host_path
+        )));
+    }
+    let dir = tempfile::Builder::new()
+        .prefix("wasmic-scratch-")
+        .tempdir()?;
+    builder.preopened_dir(dir.path(), SCRATCH_PATH, DirPerms::all(), FilePerms::all())?;
+    if !config.env.contains_key("TMPDIR") {
+        builder.env("TMPDIR", SCRATCH_PATH);
+    }
+    tracing::debug!(
+        "Mounted scratch directory {:?} to {SCRATCH_PATH}",
+        dir.path()
+    );
+    Ok(dir)
+}
+
+/// Directory and file permissions granted to the guest for a volume mount
+pub fn mount_permissions(mount: &VolumeMount) -> (DirPerms, FilePerms) {
+    if mount.read_only {
+        (DirPerms::READ, FilePerms::READ)
+    } else {
+        (DirPerms::all(), FilePerms::all())
+    }
+}
+
+/// Directory and file permissions granted to the guest for its working directory
+///
+/// A working directory inside a read-only volume is read-only too, so it can't be used to
+/// write to the volume.
+pub fn cwd_permissions(cwd: &Path, volumes: &[VolumeMount]) -> (DirPerms, FilePerms) {
+    let cwd = cwd.canonicalize().unwrap_or_else(|_| cwd.to_path_buf());
+    let in_read_only_volume = volumes.iter().filter(|mount| mount.read_only).any(|mount| {
+        let Ok(host_path) = Path::new(&mount.host_path).canonicalize() else {
+            return false;
+        };
+        // Files are mounted by their directory
+        let mounted = if host_path.is_dir() {
+            host_path.as_path()
+        } else {
+            host_path.parent().unwrap_or(&host_path)
+        };
+        cwd.starts_with(mounted)
+    });
+    if in_read_only_volume {
+        (DirPerms::READ, FilePerms::READ)
+    } else {
+        (DirPerms::all(), FilePerms::all())
+    }
+}
+
+/// Parse allowed socket address ranges; single addresses are treated as host networks
+fn parse_networks(networks: &[String]) -> Result<Vec<IpNet>> {
+    networks
+        .iter()
+        .map(|network| {
+            network
+                .parse::<IpNet>()
+                .or_else(|_| network.parse::<IpAddr>().map(IpNet::from))
+                .map_err(|_| {
+                    WasiMcpError::InvalidArguments(format!(
+                        "Invalid network '{network}', expected CIDR (e.g. 10.0.0.0/8) or IP address"
+                    ))
+                })
         })
+        .collect()
+}
+
+/// Flatten the component's `config` mapping into wasi:config key/value pairs
+///
+/// String values are passed through as-is; any other value is encoded as JSON.
+fn config_variables(config: Option<&serde_json::Value>) -> Result<Vec<(String, String)>> {
+    match config {
+        None | Some(serde_json::Value::Null) => Ok(Vec::new()),
+        Some(serde_json::Value::Object(values)) => Ok(values
+            .iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    serde_json::Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                (key.clone(), value)
+            })
+            .collect()),
+        Some(other) => Err(WasiMcpError::InvalidArguments(format!(
+            "Component config must be a mapping of keys to values, got: {other}"
+        ))),
     }
 }
 
@@ -95,3 +285,72 @@ impl TryFrom<&ComponentConfig> for ComponentRunStates {
 pub fn create_wasi_context(config: &ComponentConfig) -> Result<ComponentRunStates> {
     ComponentRunStates::try_from(config)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_config_variables() {
+        let mut vars = config_variables(Some(&json!({
+            "timezone": "UTC",
+            "retries": 3,
+            "verbose": true
+        })))
+        .unwrap();
+        vars.sort();
+        assert_eq!(
+            vars,
+            vec![
+                ("retries".to_string(), "3".to_string()),
+                ("timezone".to_string(), "UTC".to_string()),
+                ("verbose".to_string(), "true".to_string()),
+            ]
+        );
+
+        assert!(config_variables(None).unwrap().is_empty());
+        assert!(config_variables(Some(&json!(["not", "a", "map"]))).is_err());
+    }
+
+    #[test]
+    fn test_scratch_dir() {
+        let config = ComponentConfig {
+            scratch: Some(true),
+            ..ComponentConfig::default()
+        };
+        let states = ComponentRunStates::try_from(&config).unwrap();
+        let path = states.scratch.as_ref().unwrap().path().to_path_buf();
+        assert!(path.is_dir());
+        drop(states);
+        assert!(!path.exists());
+
+        let config = ComponentConfig {
+            scratch: Some(true),
+            volumes: vec![VolumeMount {
+                host_path: std::env::temp_dir().display().to_string(),
+                guest_path: "/tmp".to_string(),
+                read_only: false,
+            }],
+            ..ComponentConfig::default()
+        };
+        assert!(ComponentRunStates::try_from(&config).is_err());
+    }
+
+    #[test]
+    fn test_parse_networks() {
+        let networks = parse_networks(&[
+            "10.0.0.0/8".to_string(),
+            "192.168.1.10".to_string(),
+            "::1".to_string(),
+        ])
+        .unwrap();
+        assert!(networks[0].contains(&"10.1.2.3".parse::<IpAddr>().unwrap()));
+        assert!(!networks[0].contains(&"11.0.0.1".parse::<IpAddr>().unwrap()));
+        assert!(networks[1].contains(&"192.168.1.10".parse::<IpAddr>().unwrap()));
+        assert!(!networks[1].contains(&"192.168.1.11".parse::<IpAddr>().unwrap()));
+        assert!(networks[2].contains(&"::1".parse::<IpAddr>().unwrap()));
+
+        assert!(parse_networks(&["not-a-network".to_string()]).is_err());
+    }
+}
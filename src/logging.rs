@@ -0,0 +1,189 @@
+use crate::state::ComponentRunStates;
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::task::{Context, Poll};
+use tokio::io::AsyncWrite;
+use wasmtime::component::{Linker, Val};
+
+/// Fully qualified name of the wasi-logging interface
+pub const LOGGING_INTERFACE: &str = "wasi:logging/logging@0.1.0-draft";
+
+/// Target used for events emitted by guests, so they can be filtered with `RUST_LOG`
+const GUEST_TARGET: &str = "wasmic::guest";
+
+/// Target of the events logging the component values of arguments and results around their
+/// JSON conversion, enabled with `--debug-conversions`
+pub const CONVERSION_TARGET: &str = "wasmic::conversion";
+
+/// Longest guest output line buffered before it is logged without waiting for a newline
+const MAX_LINE_LEN: usize = 64 * 1024;
+
+/// Implement `wasi:logging/logging` by routing guest log calls into `tracing`
+///
+/// Events carry the component name and the guest-provided context. The `critical` level has
+/// no `tracing` equivalent and is reported as an error.
+pub fn add_to_linker(linker: &mut Linker<ComponentRunStates>) -> wasmtime::Result<()> {
+    let mut instance = linker.instance(LOGGING_INTERFACE)?;
+    instance.func_new("log", |store, params, _results| {
+        let [Val::Enum(level), Val::String(context), Val::String(message)] = params else {
+            anyhow::bail!("Unexpected arguments to {LOGGING_INTERFACE}.log");
+        };
+        let component = store.data().component_name.as_str();
+
+        match level.as_str() {
+            "trace" => {
+                tracing::trace!(target: GUEST_TARGET, component, context = %context, "{message}")
+            }
+            "debug" => {
+                tracing::debug!(target: GUEST_TARGET, component, context = %context, "{message}")
+            }
+            "info" => {
+                tracing::info!(target: GUEST_TARGET, component, context = %context, "{message}")
+            }
+            "warn" => {
+                tracing::warn!(target: GUEST_TARGET, component, context = %context, "{message}")
+            }
+            _ => {
+                tracing::error!(target: GUEST_TARGET, component, context = %context, level = %level, "{message}")
+            }
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Component name and current call ID attached to a store's stdout and stderr lines
+#[derive(Debug, Clone, Default)]
+pub struct GuestStdio {
+    tags: Arc<StdioTags>,
+}
+
+#[derive(Debug, Default)]
+struct StdioTags {
+    component: OnceLock<String>,
+    call_id: AtomicU64,
+}
+
+/// Stream of a guest's output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuestStream {
+    Stdout,
+    Stderr,
+}
+
+impl GuestStdio {
+    /// Name the component the output belongs to; only the first name sticks
+    pub fn set_component(&self, name: &str) {
+        let _ = self.tags.component.set(name.to_string());
+    }
+
+    /// Attribute output written from now on to the call `id`
+    pub fn set_call_id(&self, id: u64) {
+        self.tags.call_id.store(id, Ordering::Relaxed);
+    }
+
+    /// The call output is currently attributed to
+    pub fn call_id(&self) -> u64 {
+        self.tags.call_id.load(Ordering::Relaxed)
+    }
+
+    /// A WASI output stream logging each line written to `stream` as a `tracing` event:
+    /// stdout at info, stderr at warn level
+    pub fn stream(&self, stream: GuestStream) -> wasmtime_wasi::cli::AsyncStdoutStream {
+        wasmtime_wasi::cli::AsyncStdoutStream::new(LineWriter {
+            stdio: self.clone(),
+            stream,
+            buffer: Vec::new(),
+        })
+    }
+
+    fn emit(&self, stream: GuestStream, line: &[u8]) {
+        let line = String::from_utf8_lossy(line.strip_suffix(b"\r").unwrap_or(line));
+        let component = self.tags.component.get().map_or("", String::as_str);
+        let call_id = self.call_id();
+        match stream {
+            GuestStream::Stdout => {
+                tracing::info!(target: GUEST_TARGET, component, call_id, stream = "stdout", "{line}")
+            }
+            GuestStream::Stderr => {
+                tracing::warn!(target: GUEST_TARGET, component, call_id, stream = "stderr", "{line}")
+            }
+        }
+    }
+}
+
+/// Splits guest output into lines for [`GuestStdio::emit`]
+struct LineWriter {
+    stdio: GuestStdio,
+    stream: GuestStream,
+    buffer: Vec<u8>,
+}
+
+impl LineWriter {
+    fn write_lines(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+        while let Some(end) = self.buffer.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            self.stdio.emit(self.stream, &line[..end]);
+        }
+        if self.buffer.len() > MAX_LINE_LEN {
+            self.flush_partial();
+        }
+    }
+
+    fn flush_partial(&mut self) {
+        if !self.buffer.is_empty() {
+            let line = std::mem::take(&mut self.buffer);
+            self.stdio.emit(self.stream, &line);
+        }
+    }
+}
+
+impl AsyncWrite for LineWriter {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        bytes: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.write_lines(bytes);
+        Poll::Ready(Ok(bytes.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.flush_partial();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Drop for LineWriter {
+    fn drop(&mut self) {
+        self.flush_partial();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_splitting() {
+        let mut writer = LineWriter {
+            stdio: GuestStdio::default(),
+            stream: GuestStream::Stdout,
+            buffer: Vec::new(),
+        };
+        writer.write_lines(b"hello\r\nwor");
+        assert_eq!(writer.buffer, b"wor");
+        writer.write_lines(b"ld\n");
+        assert!(writer.buffer.is_empty());
+
+        writer.write_lines(&vec![b'x'; MAX_LINE_LEN + 1]);
+        assert!(writer.buffer.is_empty());
+    }
+}
@@ -1,7 +1,7 @@
 use clap::Parser;
 use tracing::error;
 use wasmic::WasiMcpError;
-use wasmic::cli::{Cli, Commands};
+use wasmic::cli::{Cli, Commands, ConfigCommand};
 use wasmic::config::Config;
 use wasmic::error::Result;
 use wasmic::server::{ServerManager, ServerMode};
@@ -13,22 +13,78 @@ async fn main() -> Result<()> {
     let cli = Cli::parse();
 
     // Configure normal stdout/stderr logging
-    tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-        .init();
-
-    tracing::info!("Starting WASI-MCP");
+    let mut filter = tracing_subscriber::EnvFilter::from_default_env();
+    if cli.debug_conversions {
+        let directive = format!("{}=debug", wasmic::logging::CONVERSION_TARGET);
+        filter = filter.add_directive(directive.parse().expect("valid directive"));
+    }
+    tracing_subscriber::fmt().with_env_filter(filter).init();
 
-    let context = WasmContext::new()?;
     let config_path = cli.config.clone().unwrap_or_else(|| {
         dirs::config_dir()
             .unwrap_or_else(|| std::path::PathBuf::from("."))
             .join("wasmic")
             .join("config.yaml")
     });
-    let config = Config::from_file(&config_path)?;
+
+    // Commands that don't need a configuration file
+    match &cli.command {
+        Commands::Init { force } => {
+            Config::write_starter(&config_path, *force)?;
+            println!("Created {}", config_path.display());
+            return Ok(());
+        }
+        Commands::Config {
+            command: ConfigCommand::Schema {},
+        } => {
+            println!("{:#}", Config::json_schema());
+            return Ok(());
+        }
+        Commands::Stats {
+            server,
+            token,
+            json,
+        } => {
+            let stats = wasmic::stats::fetch(server, token.as_deref()).await?;
+            if *json {
+                println!("{}", serde_json::to_string_pretty(&stats)?);
+            } else {
+                print!("{stats}");
+            }
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    tracing::info!("Starting WASI-MCP");
+
+    let config = match Config::from_file(&config_path) {
+        // Inspecting components and registries works without a configuration file
+        Err(_)
+            if !config_path.exists()
+                && matches!(
+                    cli.command,
+                    Commands::Inspect { .. } | Commands::Tags { .. } | Commands::Run { .. }
+                ) =>
+        {
+            Config::default()
+        }
+        config => config?,
+    };
+    let fuel_metering = matches!(cli.command, Commands::Bench { fuel: true, .. });
+    // `run` exits with the program's exit code, like running it directly
+    let forward_exit_code = matches!(cli.command, Commands::Run { export: None, .. });
+    let context =
+        WasmContext::from_config(&config.engine.clone().unwrap_or_default(), fuel_metering)?;
     let mode = match cli.command {
-        Commands::Mcp { http } => {
+        Commands::Mcp {
+            http,
+            watch,
+            refresh,
+            grace_period,
+            rest,
+            grpc,
+        } => {
             // Parse host:port string
             let (host, port) = if http.contains(':') {
                 let parts: Vec<&str> = http.split(':').collect();
@@ -58,17 +114,149 @@ async fn main() -> Result<()> {
             );
             ServerMode::Mcp {
                 config,
-                transport: wasmic::server::McpTransport::Http { host, port },
+                transport: wasmic::server::McpTransport::Http { host, port, rest },
                 context,
+                watch,
+                refresh,
+                config_path,
+                grace_period: std::time::Duration::from_secs(grace_period),
+                grpc,
             }
         }
-        Commands::Call { function, args } => ServerMode::Call {
+        Commands::Call {
+            batch: true,
+            concurrency,
+            ..
+        } => ServerMode::Batch {
             config,
+            context,
+            concurrency,
+        },
+        Commands::Call {
             function,
             args,
+            output,
+            dry_run,
+            ..
+        } => ServerMode::Call {
+            config,
+            function: function.ok_or_else(|| {
+                WasiMcpError::InvalidArguments("--function is required".to_string())
+            })?,
+            args,
+            output,
+            context,
+            dry_run,
+        },
+        Commands::Run {
+            component,
+            export,
+            args,
+            stdin,
+            output,
+            argv,
+        } => ServerMode::Run {
+            config,
             context,
+            component,
+            export,
+            args,
+            stdin,
+            output,
+            argv,
         },
-        Commands::List {} => ServerMode::List { config, context },
+        Commands::Bench {
+            function,
+            args,
+            iterations,
+            warmup,
+            json,
+            ..
+        } => ServerMode::Bench {
+            config,
+            context,
+            function,
+            args,
+            iterations,
+            warmup,
+            json,
+        },
+        Commands::Export { format, profile } => ServerMode::Export {
+            config,
+            context,
+            format,
+            profile,
+        },
+        Commands::Openapi { profile } => ServerMode::Openapi {
+            config,
+            context,
+            profile,
+        },
+        Commands::History {
+            tool,
+            failed,
+            limit,
+            json,
+        } => ServerMode::History {
+            config,
+            query: wasmic::history::HistoryQuery {
+                tool,
+                failed,
+                limit,
+            },
+            json,
+        },
+        Commands::Replay {
+            recordings,
+            candidate,
+            json,
+        } => ServerMode::Replay {
+            config,
+            context,
+            recordings,
+            candidate,
+            json,
+        },
+        Commands::Test { file, filter, json } => ServerMode::Test {
+            config,
+            context,
+            file,
+            filter,
+            json,
+        },
+        Commands::List { json, component } => ServerMode::List {
+            config,
+            context,
+            json,
+            component,
+        },
+        Commands::Explain { tool, json } => ServerMode::Explain {
+            config,
+            context,
+            tool,
+            json,
+        },
+        Commands::Inspect { component, json } => ServerMode::Inspect {
+            config,
+            context,
+            component,
+            json,
+        },
+        Commands::Pull {} => ServerMode::Pull { config },
+        Commands::Tags { repository, limit } => ServerMode::Tags {
+            config,
+            repository,
+            limit,
+        },
+        Commands::Update {} => ServerMode::Update { config },
+        Commands::Precompile {} => ServerMode::Precompile { config, context },
+        Commands::Cache { command } => ServerMode::Cache { config, command },
+        Commands::Config {
+            command: ConfigCommand::Validate {},
+        } => ServerMode::Validate { config, context },
+        Commands::Init { .. } | Commands::Config { .. } | Commands::Stats { .. } => {
+            unreachable!("handled before loading the configuration")
+        }
     };
 
     match ServerManager::run(mode).await {
@@ -76,6 +264,7 @@ async fn main() -> Result<()> {
             tracing::info!("WASI-MCP completed successfully");
             Ok(())
         }
+        Err(WasiMcpError::Exit(code)) if forward_exit_code => std::process::exit(code),
         Err(e) => {
             tracing::error!("WASI-MCP failed: {}", e);
             Err(e)
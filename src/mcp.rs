@@ -1,42 +1,435 @@
-use crate::config::Config;
-use crate::error::Result;
+use crate::call_context::{CTX_ARGUMENT, CTX_META_KEY};
+use crate::config::{ComponentConfig, Config};
+use crate::deadline::DeadlineManager;
+use crate::dry_run::DRY_RUN_META_KEY;
+use crate::error::{Result, WasiMcpError};
 use crate::executor::WasmExecutor;
+use crate::hooks::CallHook;
+use crate::resources::{self, Subscriptions};
+use crate::schedule::Schedules;
+use crate::shutdown::Drain;
+use crate::stats::{RuntimeStats, StatsHandle};
+use crate::trace::remote_call_span;
+use crate::wasm::{TrapDetails, WasmComponent};
+use axum::response::IntoResponse;
 use rmcp::model::ServerCapabilities;
 use rmcp::transport::streamable_http_server::{
-    StreamableHttpService, session::local::LocalSessionManager,
+    StreamableHttpServerConfig, StreamableHttpService, session::local::LocalSessionManager,
 };
 use rmcp::{
     ErrorData as McpError, ServerHandler,
     model::{
         CallToolRequestParam, CallToolResult, Content, GetPromptRequestParam, GetPromptResult,
-        ListPromptsResult, ListToolsResult, Prompt as McpPrompt, PromptMessage,
-        PromptMessageContent, PromptMessageRole, ServerInfo,
+        ListPromptsResult, ListResourcesResult, ListToolsResult, Prompt as McpPrompt,
+        PromptMessage, PromptMessageContent, PromptMessageRole, ReadResourceRequestParam,
+        ReadResourceResult, ResourceContents, ServerInfo, SubscribeRequestParam,
+        UnsubscribeRequestParam,
     },
-    service::{RequestContext, RoleServer},
+    service::{NotificationContext, Peer, RequestContext, RoleServer},
 };
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
-use std::time::Instant;
-use tokio::sync::Mutex;
-use tracing::debug;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, RwLock};
+use tracing::{Instrument, debug};
+
+/// Time allowed after draining for responses of finished calls to reach their clients
+const RESPONSE_FLUSH_TIMEOUT: Duration = Duration::from_secs(1);
 
 #[derive(Clone)]
 pub struct WasmMcpServer {
     pub executor: Arc<Mutex<WasmExecutor>>,
-    pub config: Arc<Config>,
+    pub config: Arc<RwLock<Config>>,
+    /// Connected clients, used to push list-changed notifications
+    peers: Arc<Mutex<Vec<Peer<RoleServer>>>>,
+    /// Call counters of the executor, readable while it is busy
+    stats: StatsHandle,
+    /// Running tool calls, waited for on shutdown
+    pub drain: Drain,
+    /// Deadlines of running tool calls, readable while the executor is busy
+    deadlines: DeadlineManager,
+    /// Servers of the configured profiles, each with its own executor; replaced on reloads
+    profiles: Arc<std::sync::RwLock<HashMap<String, WasmMcpServer>>>,
+    /// MCP session this copy of the server serves, for its resource subscriptions
+    session: u64,
+    /// Resource subscriptions of all sessions
+    subscriptions: Subscriptions,
+    /// Running schedule tasks, replaced on reloads
+    pub(crate) schedules: Schedules,
+}
+
+/// New versions of a component that passed their checks, for a server and its profiles
+pub struct StagedUpgrade {
+    name: String,
+    components: Vec<(WasmMcpServer, WasmComponent)>,
+}
+
+impl StagedUpgrade {
+    /// Point the staged versions at `path`, where their artifact was moved after loading
+    pub fn relocate(&mut self, path: &Path) {
+        for (_, component) in &mut self.components {
+            component.config.path = Some(path.display().to_string());
+        }
+    }
+
+    /// Swap the staged versions in, each once no call of its server is running
+    pub async fn install(self) -> Result<()> {
+        for (server, component) in self.components {
+            server
+                .executor
+                .lock()
+                .await
+                .install_component(self.name.clone(), component)
+                .await?;
+        }
+        Ok(())
+    }
 }
 
 impl WasmMcpServer {
     /// Create a new WASM MCP server
     pub fn new(executor: WasmExecutor, config: Config) -> Self {
         Self {
+            stats: executor.stats_handle(),
+            deadlines: executor.deadlines(),
             executor: Arc::new(Mutex::new(executor)),
-            config: Arc::new(config),
+            config: Arc::new(RwLock::new(config)),
+            peers: Arc::new(Mutex::new(Vec::new())),
+            drain: Drain::default(),
+            profiles: Arc::default(),
+            session: 0,
+            subscriptions: Subscriptions::default(),
+            schedules: Schedules::default(),
         }
     }
 
+    /// A copy of the server for a new MCP session
+    fn session(&self) -> Self {
+        Self {
+            session: resources::session_id(),
+            ..self.clone()
+        }
+    }
+
+    /// The server whose resources a request sees, if the client may read them
+    async fn resource_server(
+        &self,
+        context: &RequestContext<RoleServer>,
+    ) -> std::result::Result<WasmMcpServer, McpError> {
+        let server = self
+            .profile_server(context)?
+            .unwrap_or_else(|| self.clone());
+        // With a policy, only known clients see the files of the workspace and the inventory
+        if let Some(policy) = &server.config.read().await.policy
+            && policy.client_name(bearer_token(context)).is_none()
+        {
+            return Err(McpError::invalid_request(
+                "Resources need a known client token".to_string(),
+                None,
+            ));
+        }
+        Ok(server)
+    }
+
+    /// Serve `profiles` to requests that select one by path or header
+    ///
+    /// The profile servers share this server's shutdown drain.
+    pub fn with_profiles(self, profiles: HashMap<String, WasmMcpServer>) -> Self {
+        self.set_profiles(profiles);
+        self
+    }
+
+    /// Serve `profiles` from now on, returning the profile servers that are no longer served
+    pub fn set_profiles(&self, profiles: HashMap<String, WasmMcpServer>) -> Vec<WasmMcpServer> {
+        let profiles = profiles
+            .into_iter()
+            .map(|(name, mut profile)| {
+                profile.drain = self.drain.clone();
+                (name, profile)
+            })
+            .collect();
+        let mut current = self.profiles.write().unwrap_or_else(|e| e.into_inner());
+        let previous = std::mem::replace(&mut *current, profiles);
+        previous
+            .into_iter()
+            .filter(|(name, _)| !current.contains_key(name))
+            .map(|(_, profile)| profile)
+            .collect()
+    }
+
+    /// The server of profile `name`, if it is configured
+    pub fn profile(&self, name: &str) -> Option<WasmMcpServer> {
+        self.profiles
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(name)
+            .cloned()
+    }
+
+    /// The servers of every configured profile
+    fn profile_servers(&self) -> Vec<WasmMcpServer> {
+        self.profiles
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// The profile server a request selected, if any
+    fn profile_server(
+        &self,
+        context: &RequestContext<RoleServer>,
+    ) -> std::result::Result<Option<WasmMcpServer>, McpError> {
+        let Some(name) = requested_profile(context) else {
+            return Ok(None);
+        };
+        self.profile(name)
+            .map(Some)
+            .ok_or_else(|| McpError::invalid_request(format!("Unknown profile '{name}'"), None))
+    }
+
+    /// Refuse a call the client holding `token` may not make by this server's policy
+    pub async fn authorize_call(
+        &self,
+        token: Option<&str>,
+        tool_name: &str,
+    ) -> std::result::Result<(), McpError> {
+        // Tools may be called by their advertised or internal name; the policy names the latter
+        let internal = self.executor.lock().await.resolve_tool_name(tool_name);
+        if let Some(policy) = &self.config.read().await.policy
+            && !policy.is_allowed(token, &internal)
+        {
+            let client = policy.client_name(token).unwrap_or("anonymous");
+            tracing::warn!(client, tool = %tool_name, "Denied tool call by policy");
+            return Err(McpError::invalid_request(
+                format!("Client '{client}' is not allowed to call tool '{tool_name}'"),
+                None,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Run the shutdown functions of the components of this server and its profiles
+    pub async fn shutdown_components(&self) {
+        self.executor.lock().await.shutdown().await;
+        for profile in self.profile_servers() {
+            profile.executor.lock().await.shutdown().await;
+        }
+    }
+
+    /// Run `hook` around every call of this server and its profiles
+    pub async fn add_hook(&self, hook: Arc<dyn CallHook>) {
+        self.executor.lock().await.add_hook(hook.clone());
+        for profile in self.profile_servers() {
+            profile.executor.lock().await.add_hook(hook.clone());
+        }
+    }
+
+    /// Re-instantiate a component and notify clients that the tool list changed
+    ///
+    /// The new instance replaces the old one only once it loaded successfully, so a broken
+    /// build leaves the previous version serving calls.
+    pub async fn reload_component(&self, name: &str, config: ComponentConfig) -> Result<()> {
+        self.upgrade_component(name, config).await?;
+        self.notify_tool_list_changed().await;
+        Ok(())
+    }
+
+    /// Blue/green upgrade: the new version is loaded, warmed up and smoke-tested while the
+    /// running one keeps serving calls, then swapped in once no call is running
+    ///
+    /// The profiles serving the component are upgraded too.
+    pub async fn upgrade_component(&self, name: &str, config: ComponentConfig) -> Result<()> {
+        self.stage_upgrade(name, config).await?.install().await
+    }
+
+    /// Load and smoke-test a new version of a component for this server and the profiles
+    /// serving it, leaving the running versions untouched
+    pub async fn stage_upgrade(
+        &self,
+        name: &str,
+        config: ComponentConfig,
+    ) -> Result<StagedUpgrade> {
+        let mut components = vec![(self.clone(), self.stage(name, config.clone()).await?)];
+        for profile in self.profile_servers() {
+            if profile.config.read().await.components.contains_key(name) {
+                let component = profile.stage(name, config.clone()).await?;
+                components.push((profile, component));
+            }
+        }
+        Ok(StagedUpgrade {
+            name: name.to_string(),
+            components,
+        })
+    }
+
+    /// Load a new version of a component with this server's executor
+    async fn stage(&self, name: &str, config: ComponentConfig) -> Result<WasmComponent> {
+        let staging = self.executor.lock().await.staging()?;
+        staging.stage_component(name, config).await
+    }
+
+    /// Watch the volumes a reloaded configuration added, if clients subscribed to files
+    pub async fn rewatch_volumes(&self) {
+        let volumes = resources::volumes(&*self.config.read().await);
+        if let Err(e) = self.subscriptions.rewatch(&volumes) {
+            tracing::warn!("Failed to watch the reloaded volumes: {}", e);
+        }
+    }
+
+    /// Send `notifications/tools/list_changed` to every connected client, and tell the
+    /// subscribers of the inventory that it changed
+    pub async fn notify_tool_list_changed(&self) {
+        self.notify_peers(|peer| async move { peer.notify_tool_list_changed().await })
+            .await;
+        self.subscriptions.notify(resources::INVENTORY_URI).await;
+    }
+
+    /// Notify clients whenever the executors of this server or its profiles change their
+    /// tool lists by themselves, e.g. when the watchdog disables a component
+    pub async fn forward_tool_list_changes(self) {
+        let changed = self.executor.lock().await.tools_changed();
+        loop {
+            changed.notified().await;
+            self.notify_tool_list_changed().await;
+        }
+    }
+
+    /// Send `notifications/prompts/list_changed` to every connected client
+    pub async fn notify_prompt_list_changed(&self) {
+        self.notify_peers(|peer| async move { peer.notify_prompt_list_changed().await })
+            .await;
+    }
+
+    /// Send a notification to every connected client, forgetting clients that have gone away
+    async fn notify_peers<F, Fut, E>(&self, notify: F)
+    where
+        F: Fn(Peer<RoleServer>) -> Fut,
+        Fut: Future<Output = std::result::Result<(), E>>,
+        E: std::fmt::Display,
+    {
+        let mut peers = self.peers.lock().await;
+        let mut connected = Vec::with_capacity(peers.len());
+        for peer in peers.drain(..) {
+            match notify(peer.clone()).await {
+                Ok(()) => connected.push(peer),
+                Err(e) => debug!("Dropping disconnected peer: {}", e),
+            }
+        }
+        *peers = connected;
+    }
+
+    /// Snapshot of the call counters collected so far
+    pub fn stats(&self) -> RuntimeStats {
+        self.stats
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .snapshot()
+    }
+
+    /// Serve the executor's call statistics, restricted to known clients when a policy is set
+    async fn admin_stats(
+        axum::extract::State(server): axum::extract::State<WasmMcpServer>,
+        headers: axum::http::HeaderMap,
+    ) -> axum::response::Response {
+        if let Some(policy) = &server.config.read().await.policy
+            && policy.client_name(header_bearer_token(&headers)).is_none()
+        {
+            return axum::http::StatusCode::UNAUTHORIZED.into_response();
+        }
+        axum::Json(server.stats()).into_response()
+    }
+
+    /// Serve the call statistics in the Prometheus text format, restricted like `/admin/stats`
+    async fn metrics(
+        axum::extract::State(server): axum::extract::State<WasmMcpServer>,
+        headers: axum::http::HeaderMap,
+    ) -> axum::response::Response {
+        if let Some(policy) = &server.config.read().await.policy
+            && policy.client_name(header_bearer_token(&headers)).is_none()
+        {
+            return axum::http::StatusCode::UNAUTHORIZED.into_response();
+        }
+        (
+            [(
+                axum::http::header::CONTENT_TYPE,
+                "text/plain; version=0.0.4; charset=utf-8",
+            )],
+            server.stats().to_prometheus(),
+        )
+            .into_response()
+    }
+
+    /// Enable or disable a loaded component at `/admin/components/<name>/enable|disable`
+    ///
+    /// With a policy only `admin` clients may do so; without one, only if the operator
+    /// allowed unauthenticated administration.
+    async fn admin_toggle_component(
+        axum::extract::State(server): axum::extract::State<WasmMcpServer>,
+        axum::extract::Path((name, action)): axum::extract::Path<(String, String)>,
+        headers: axum::http::HeaderMap,
+    ) -> axum::response::Response {
+        match &*server.config.read().await {
+            Config {
+                policy: Some(policy),
+                ..
+            } => {
+                if !policy.is_admin(header_bearer_token(&headers)) {
+                    return axum::http::StatusCode::UNAUTHORIZED.into_response();
+                }
+            }
+            config if !unauthenticated_admin(config) => {
+                return axum::http::StatusCode::NOT_FOUND.into_response();
+            }
+            _ => {}
+        }
+        let enabled = match action.as_str() {
+            "enable" => true,
+            "disable" => false,
+            _ => return axum::http::StatusCode::NOT_FOUND.into_response(),
+        };
+        match server.set_component_enabled(&name, enabled).await {
+            Ok(()) => {
+                tracing::info!(component = %name, enabled, "Component toggled by an operator");
+                axum::Json(serde_json::json!({ "component": name, "enabled": enabled }))
+                    .into_response()
+            }
+            Err(e) => crate::rest::error_response(&e),
+        }
+    }
+
+    /// Enable or disable a loaded component here and in the profiles serving it, and notify
+    /// clients that the tool list changed
+    pub async fn set_component_enabled(&self, name: &str, enabled: bool) -> Result<()> {
+        self.executor.lock().await.set_enabled(name, enabled)?;
+        let profiles = self.profile_servers();
+        for profile in &profiles {
+            match profile.executor.lock().await.set_enabled(name, enabled) {
+                Ok(()) | Err(WasiMcpError::ComponentNotFound(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        self.notify_tool_list_changed().await;
+        for profile in &profiles {
+            profile.notify_tool_list_changed().await;
+        }
+        Ok(())
+    }
+
     /// Serve the MCP server over HTTP transport using axum
-    pub async fn serve_http(service: WasmMcpServer, host: String, port: u16) -> Result<()> {
+    ///
+    /// With `rest`, the REST gateway is served on the same listener. On Ctrl-C or SIGTERM the
+    /// listener stops accepting requests and running tool calls get up to `grace_period` to
+    /// finish before the server exits.
+    pub async fn serve_http(
+        service: WasmMcpServer,
+        host: String,
+        port: u16,
+        grace_period: Duration,
+        rest: bool,
+    ) -> Result<()> {
         tracing::info!(
             "Starting MCP server with HTTP transport on {}:{}",
             host,
@@ -45,19 +438,92 @@ impl WasmMcpServer {
 
         let start_time = Instant::now();
 
+        let admin = service.clone();
+        let drain = service.drain.clone();
+        let deadlines = service.deadlines.clone();
+        let (http, admin_components) = {
+            let config = service.config.read().await;
+            (
+                config.http.clone().unwrap_or_default(),
+                config.policy.is_some() || unauthenticated_admin(&config),
+            )
+        };
+
+        let mut session_manager = LocalSessionManager::default();
+        if let Some(timeout) = http.session_timeout_secs {
+            session_manager.session_config.keep_alive = Some(Duration::from_secs(timeout));
+        }
+        let mut server_config = StreamableHttpServerConfig {
+            stateful_mode: !http.stateless,
+            ..Default::default()
+        };
+        if let Some(interval) = http.sse_keep_alive_secs {
+            server_config.sse_keep_alive = (interval > 0).then(|| Duration::from_secs(interval));
+        }
+        tracing::debug!(?http, "HTTP transport settings");
+
         let service = StreamableHttpService::new(
-            move || Ok(service.clone()),
-            LocalSessionManager::default().into(),
-            Default::default(),
+            move || Ok(service.session()),
+            session_manager.into(),
+            server_config,
         );
 
-        let router = axum::Router::new().nest_service("/mcp", service);
+        let mut router = axum::Router::new()
+            .nest_service("/mcp", service)
+            .route("/admin/stats", axum::routing::get(Self::admin_stats))
+            .route("/metrics", axum::routing::get(Self::metrics))
+            .merge(crate::webhook::router());
+        if admin_components {
+            router = router.route(
+                "/admin/components/{name}/{action}",
+                axum::routing::post(Self::admin_toggle_component),
+            );
+        } else {
+            tracing::debug!("Not serving /admin/components without a policy");
+        }
+        if rest {
+            router = router.merge(crate::rest::router());
+        }
+        let router = router.with_state(admin);
         let tcp_listener = tokio::net::TcpListener::bind(format!("{host}:{port}")).await?;
-        axum::serve(tcp_listener, router)
-            .with_graceful_shutdown(async { tokio::signal::ctrl_c().await.unwrap() })
-            .await?;
+        let serve = axum::serve(tcp_listener, router)
+            .with_graceful_shutdown({
+                let drain = drain.clone();
+                async move { drain.started().await }
+            })
+            .into_future();
+        tokio::pin!(serve);
 
         tracing::info!("MCP HTTP server listening on {}:{}", host, port);
+        tokio::select! {
+            result = &mut serve => return Ok(result?),
+            () = crate::shutdown::signal() => {}
+        }
+
+        let deadline = tokio::time::Instant::now() + grace_period;
+        tracing::info!(
+            in_flight = drain.in_flight(),
+            "Shutting down, waiting up to {:?} for running tool calls",
+            grace_period
+        );
+        drain.begin();
+        if tokio::time::timeout_at(deadline, drain.idle())
+            .await
+            .is_err()
+        {
+            // Profiles share the context's deadlines, so this reaches their calls too
+            let interrupted = deadlines.cancel_all();
+            tracing::warn!(
+                in_flight = drain.in_flight(),
+                interrupted,
+                "Grace period elapsed with tool calls still running"
+            );
+        }
+
+        // Give finished calls a moment to deliver their responses; open streams are cut after
+        let flush_deadline = deadline.min(tokio::time::Instant::now() + RESPONSE_FLUSH_TIMEOUT);
+        let _ = tokio::time::timeout_at(flush_deadline, &mut serve).await;
+
         let serve_duration = start_time.elapsed();
         tracing::info!("MCP HTTP server service completed in {:?}", serve_duration);
 
@@ -74,6 +540,10 @@ impl ServerHandler for WasmMcpServer {
             capabilities: ServerCapabilities {
                 tools: Some(rmcp::model::ToolsCapability { list_changed: Some(true) }),
                 prompts: Some(rmcp::model::PromptsCapability { list_changed: Some(true) }),
+                resources: Some(rmcp::model::ResourcesCapability {
+                    subscribe: Some(true),
+                    list_changed: None,
+                }),
                 ..Default::default()
             },
             server_info: rmcp::model::Implementation {
@@ -93,19 +563,37 @@ impl ServerHandler for WasmMcpServer {
         }
     }
 
+    /// Remember the client so it can receive list-changed notifications
+    async fn on_initialized(&self, context: NotificationContext<RoleServer>) {
+        self.peers.lock().await.push(context.peer);
+    }
+
     /// List available tools
     async fn list_tools(
         &self,
         _params: Option<rmcp::model::PaginatedRequestParam>,
-        _context: RequestContext<RoleServer>,
+        context: RequestContext<RoleServer>,
     ) -> std::result::Result<ListToolsResult, McpError> {
-        let tools = self.executor.lock().await.get_all_tools().map_err(|e| {
-            tracing::error!("Failed to create tools: {}", e);
-            McpError::internal_error(format!("Failed to create tools: {e}"), None)
-        })?;
+        let server = self
+            .profile_server(&context)?
+            .unwrap_or_else(|| self.clone());
+        let mut tools = server
+            .executor
+            .lock()
+            .await
+            .tools_by_internal_name()
+            .map_err(|e| {
+                tracing::error!("Failed to create tools: {}", e);
+                McpError::internal_error(format!("Failed to create tools: {e}"), None)
+            })?;
+
+        if let Some(policy) = &server.config.read().await.policy {
+            let token = bearer_token(&context);
+            tools.retain(|(internal, _)| policy.is_allowed(token, internal));
+        }
 
         Ok(ListToolsResult {
-            tools,
+            tools: tools.into_iter().map(|(_, tool)| tool).collect(),
             next_cursor: None,
         })
     }
@@ -114,38 +602,133 @@ impl ServerHandler for WasmMcpServer {
     async fn call_tool(
         &self,
         params: CallToolRequestParam,
-        _context: RequestContext<RoleServer>,
+        context: RequestContext<RoleServer>,
     ) -> std::result::Result<CallToolResult, McpError> {
+        let server = self
+            .profile_server(&context)?
+            .unwrap_or_else(|| self.clone());
+        server
+            .authorize_call(bearer_token(&context), &params.name)
+            .await?;
+
+        let Some(_call) = server.drain.start_call() else {
+            return Err(McpError::internal_error(
+                "Server is shutting down".to_string(),
+                None,
+            ));
+        };
+
         let arguments_map = params.arguments.unwrap_or_default();
-        let arguments: HashMap<String, serde_json::Value> = arguments_map.into_iter().collect();
+        let mut arguments: HashMap<String, serde_json::Value> = arguments_map.into_iter().collect();
+        // Overrides may also come in `_meta`, which clients don't show to the model
+        if let Some(call_context) = context.meta.get(CTX_META_KEY) {
+            arguments
+                .entry(CTX_ARGUMENT.to_string())
+                .or_insert_with(|| call_context.clone());
+        }
 
-        let result = self
-            .executor
-            .lock()
-            .await
-            .execute_function(&params.name, arguments)
-            .await
-            .map_err(|e| McpError::internal_error(format!("Failed to execute tool: {e}"), None))?;
+        // A dry run reports what the call would execute instead of running it
+        if context
+            .meta
+            .get(DRY_RUN_META_KEY)
+            .is_some_and(|dry_run| dry_run.as_bool() == Some(true))
+        {
+            let dry_run = server
+                .executor
+                .lock()
+                .await
+                .dry_run(&params.name, arguments);
+            return match dry_run {
+                Ok(dry_run) => {
+                    let structured = serde_json::to_value(&dry_run).map_err(|e| {
+                        McpError::internal_error(format!("Failed to serialize dry run: {e}"), None)
+                    })?;
+                    let mut result =
+                        CallToolResult::success(vec![Content::text(dry_run.to_string())]);
+                    result.structured_content = Some(structured);
+                    Ok(result)
+                }
+                Err(e) => Ok(CallToolResult::structured_error(tool_error(
+                    &params.name,
+                    &e,
+                    false,
+                ))),
+            };
+        }
 
-        let content = serde_json::to_string(&result).map_err(|e| {
-            McpError::internal_error(format!("Failed to serialize result: {e}"), None)
-        })?;
+        // Calls over HTTP join the caller's trace; stdio calls have no request parts
+        let span = context
+            .extensions
+            .get::<axum::http::request::Parts>()
+            .map_or_else(tracing::Span::none, |parts| {
+                remote_call_span("mcp", &parts.headers)
+            });
+        let result = async {
+            let mut executor = server.executor.lock().await;
+            executor
+                .execute_function(&params.name, arguments)
+                .await
+                .map(|result| {
+                    let structured = executor.structured_content(&params.name, &result);
+                    (result, structured)
+                })
+        }
+        .instrument(span)
+        .await;
+        let (result, structured) = match result {
+            Ok(result) => result,
+            // Unknown tools are the client's mistake; everything else is reported to the
+            // model as a failed call it can react to
+            Err(
+                e @ (WasiMcpError::FunctionNotFound(_)
+                | WasiMcpError::InterfaceNotFound(_)
+                | WasiMcpError::ComponentNotFound(_)),
+            ) => {
+                return Err(McpError::invalid_params(
+                    e.to_string(),
+                    Some(serde_json::json!({ "code": e.code() })),
+                ));
+            }
+            Err(e) => {
+                tracing::warn!(tool = %params.name, "Tool call failed: {}", e);
+                let backtrace = server.config.read().await.trap_backtraces;
+                return Ok(CallToolResult::structured_error(tool_error(
+                    &params.name,
+                    &e,
+                    backtrace,
+                )));
+            }
+        };
+
+        // The text content mirrors the structured content, as the MCP specification recommends
+        let content =
+            serde_json::to_string(structured.as_ref().unwrap_or(&result)).map_err(|e| {
+                McpError::internal_error(format!("Failed to serialize result: {e}"), None)
+            })?;
         debug!("Tool result: {}", content);
-        Ok(CallToolResult::success(vec![Content::text(content)]))
+        let mut result = CallToolResult::success(vec![Content::text(content)]);
+        result.structured_content = structured;
+        Ok(result)
     }
 
     /// List available prompts
     async fn list_prompts(
         &self,
         _params: Option<rmcp::model::PaginatedRequestParam>,
-        _context: RequestContext<RoleServer>,
+        context: RequestContext<RoleServer>,
     ) -> std::result::Result<ListPromptsResult, McpError> {
-        let mut prompts = Vec::new();
+        let server = self
+            .profile_server(&context)?
+            .unwrap_or_else(|| self.clone());
+        let mut all_prompts = server.executor.lock().await.component_prompts();
+        // Configured prompts win over component prompts of the same name
+        all_prompts.extend(server.config.read().await.prompts.clone());
 
-        for (prompt_id, prompt) in &self.config.prompts {
+        let mut prompts = Vec::new();
+        for (prompt_id, prompt) in all_prompts {
             prompts.push(McpPrompt {
-                name: prompt_id.clone(),
-                description: Some(prompt.description.clone()),
+                name: prompt_id,
+                description: Some(prompt.description),
                 arguments: Some(Vec::new()), // Static prompts with no arguments
                 title: None,
                 icons: None,
@@ -162,15 +745,34 @@ impl ServerHandler for WasmMcpServer {
     async fn get_prompt(
         &self,
         params: GetPromptRequestParam,
-        _context: RequestContext<RoleServer>,
+        context: RequestContext<RoleServer>,
     ) -> std::result::Result<GetPromptResult, McpError> {
-        if let Some(prompt) = self.config.prompts.get(&params.name) {
+        let server = self
+            .profile_server(&context)?
+            .unwrap_or_else(|| self.clone());
+        let configured = server
+            .config
+            .read()
+            .await
+            .prompts
+            .get(&params.name)
+            .cloned();
+        let prompt = match configured {
+            Some(prompt) => Some(prompt),
+            None => server
+                .executor
+                .lock()
+                .await
+                .component_prompts()
+                .remove(&params.name),
+        };
+        if let Some(prompt) = prompt {
             return Ok(GetPromptResult {
-                description: Some(prompt.description.clone()),
+                description: Some(prompt.description),
                 messages: vec![PromptMessage {
                     role: PromptMessageRole::User,
                     content: PromptMessageContent::Text {
-                        text: prompt.content.clone(),
+                        text: prompt.content,
                     },
                 }],
             });
@@ -181,4 +783,169 @@ impl ServerHandler for WasmMcpServer {
             None,
         ))
     }
+
+    /// List the component inventory and the files of mounted volumes
+    async fn list_resources(
+        &self,
+        _params: Option<rmcp::model::PaginatedRequestParam>,
+        context: RequestContext<RoleServer>,
+    ) -> std::result::Result<ListResourcesResult, McpError> {
+        let server = self.resource_server(&context).await?;
+        let volumes = resources::volumes(&*server.config.read().await);
+        let mut listed = vec![resources::inventory_resource()];
+        listed.extend(resources::list(&volumes));
+        Ok(ListResourcesResult {
+            resources: listed,
+            next_cursor: None,
+        })
+    }
+
+    /// Read the component inventory or a file of a mounted volume
+    async fn read_resource(
+        &self,
+        params: ReadResourceRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> std::result::Result<ReadResourceResult, McpError> {
+        let server = self.resource_server(&context).await?;
+        if params.uri == resources::INVENTORY_URI {
+            let inventory = server.executor.lock().await.inventory();
+            let text = serde_json::to_string_pretty(&inventory).map_err(|e| {
+                McpError::internal_error(format!("Failed to serialize inventory: {e}"), None)
+            })?;
+            return Ok(ReadResourceResult {
+                contents: vec![ResourceContents::text(text, params.uri)],
+            });
+        }
+        let volumes = resources::volumes(&*server.config.read().await);
+        let path = resources::resolve(&volumes, &params.uri)
+            .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+        let bytes = tokio::fs::read(&path).await.map_err(|e| {
+            McpError::invalid_params(format!("Cannot read {}: {e}", params.uri), None)
+        })?;
+        let contents = resources::contents(params.uri, bytes)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        Ok(ReadResourceResult {
+            contents: vec![contents],
+        })
+    }
+
+    /// Notify the client when a file, or any file below a directory, changes, or when the
+    /// inventory does
+    async fn subscribe(
+        &self,
+        params: SubscribeRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> std::result::Result<(), McpError> {
+        let server = self.resource_server(&context).await?;
+        if params.uri == resources::INVENTORY_URI {
+            self.subscriptions
+                .subscribe(self.session, context.peer, params.uri);
+            return Ok(());
+        }
+        let volumes = resources::volumes(&*server.config.read().await);
+        let path = resources::resolve(&volumes, &params.uri)
+            .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+        // Every session watches all volumes, as profiles only narrow down the components
+        let all_volumes = resources::volumes(&*self.config.read().await);
+        self.subscriptions
+            .watch(&all_volumes)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        // Notifications name the canonical path of the changed file
+        self.subscriptions
+            .subscribe(self.session, context.peer, resources::uri(&path));
+        Ok(())
+    }
+
+    async fn unsubscribe(
+        &self,
+        params: UnsubscribeRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> std::result::Result<(), McpError> {
+        // Subscriptions are kept under canonical paths; deleted files can't be canonicalized
+        let uri = resources::path(&params.uri)
+            .ok()
+            .and_then(|path| std::fs::canonicalize(path).ok())
+            .map_or(params.uri.clone(), |path| resources::uri(&path));
+        self.subscriptions.unsubscribe(self.session, &uri);
+        Ok(())
+    }
+}
+
+/// Structured payload of a failed call: the message and error code, the tool's component and
+/// function, and the trap reason (plus the guest backtrace if `backtrace`) when the guest
+/// trapped
+fn tool_error(tool: &str, error: &WasiMcpError, backtrace: bool) -> serde_json::Value {
+    let (component, function) = tool.split_once('.').unwrap_or((tool, ""));
+    let mut payload = error.to_json();
+    payload["component"] = component.into();
+    payload["function"] = function.into();
+    if let WasiMcpError::Component(e) = error {
+        let details = TrapDetails::of(e);
+        if let Some(trap) = details.trap {
+            payload["trap"] = trap.into();
+        }
+        if backtrace && !details.backtrace.is_empty() {
+            payload["backtrace"] = details.backtrace.into();
+        }
+    }
+    payload
+}
+
+/// Bearer token from the `Authorization` header of the HTTP request carrying this MCP request
+fn bearer_token(context: &RequestContext<RoleServer>) -> Option<&str> {
+    let parts = context.extensions.get::<axum::http::request::Parts>()?;
+    header_bearer_token(&parts.headers)
+}
+
+/// Profile named by the `X-Wasmic-Profile` header or the path below `/mcp`
+fn requested_profile(context: &RequestContext<RoleServer>) -> Option<&str> {
+    let parts = context.extensions.get::<axum::http::request::Parts>()?;
+    let header = parts
+        .headers
+        .get("x-wasmic-profile")
+        .and_then(|value| value.to_str().ok());
+    // The service is nested at `/mcp`, so the path it sees starts after that prefix
+    let path = parts.uri.path().trim_matches('/');
+    header.or((!path.is_empty()).then_some(path))
+}
+
+/// Whether the operator allowed `/admin/components` without a policy
+fn unauthenticated_admin(config: &Config) -> bool {
+    config
+        .http
+        .as_ref()
+        .is_some_and(|http| http.unauthenticated_admin)
+}
+
+/// Token of an `Authorization: Bearer` header
+pub(crate) fn header_bearer_token(headers: &axum::http::HeaderMap) -> Option<&str> {
+    headers
+        .get(axum::http::header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_error() {
+        let error =
+            WasiMcpError::Component(wasmtime::Error::new(wasmtime::Trap::UnreachableCodeReached));
+        let payload = tool_error("time.wasi:clocks/now.get", &error, true);
+        assert_eq!(payload["code"], "trap");
+        assert_eq!(payload["component"], "time");
+        assert_eq!(payload["function"], "wasi:clocks/now.get");
+        assert_eq!(
+            payload["trap"],
+            wasmtime::Trap::UnreachableCodeReached.to_string()
+        );
+
+        let error = WasiMcpError::InvalidArguments("Missing required argument: 'zone'".into());
+        let payload = tool_error("time.now", &error, true);
+        assert_eq!(payload["error"], error.to_string());
+        assert!(payload.get("trap").is_none());
+    }
 }
@@ -0,0 +1,252 @@
+//! Tool names
+//!
+//! Tools are named `component.function` by default, where interface functions carry the
+//! interface name (`search.wasi:http/handler@0.2.0.handle`). Some MCP clients only accept
+//! letters, digits, `_` and `-` and limit the length, so the separator, sanitization and a
+//! maximum length are configurable. The executor maps the names back to components and
+//! functions with [`ToolNames`], which also detects tools that end up with the same name.
+
+use crate::config::{CollisionPolicy, ToolNamingConfig};
+use crate::error::{Result, WasiMcpError};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+
+/// Separator used when none is configured
+pub const DEFAULT_SEPARATOR: &str = ".";
+
+/// Hex digits of the hash ending shortened names
+const HASH_LEN: usize = 8;
+
+/// Public name of `function` of `component`
+pub fn tool_name(naming: Option<&ToolNamingConfig>, component: &str, function: &str) -> String {
+    let Some(naming) = naming else {
+        return format!("{component}{DEFAULT_SEPARATOR}{function}");
+    };
+    let separator = naming.separator.as_deref().unwrap_or(DEFAULT_SEPARATOR);
+    let function = match function.rsplit_once('.') {
+        // Only interface functions have a dot, between the interface and the function name
+        Some((_, short)) if naming.short => short,
+        _ => function,
+    };
+    let name = if naming.sanitize {
+        format!("{}{separator}{}", sanitize(component), sanitize(function))
+    } else {
+        format!("{component}{separator}{function}")
+    };
+    match naming.max_length {
+        Some(max_length) if name.chars().count() > max_length => {
+            shorten(&name, &format!("{component}.{function}"), max_length)
+        }
+        _ => name,
+    }
+}
+
+/// Tools sharing one name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Collision {
+    pub name: String,
+    /// The tools as `component.function`, sorted
+    pub tools: Vec<String>,
+}
+
+impl std::fmt::Display for Collision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "'{}' is the name of {}",
+            self.name,
+            self.tools.join(", ")
+        )
+    }
+}
+
+/// Tools whose names collide among `tools`, given as (component, function) pairs
+pub fn collisions<'a>(
+    naming: Option<&ToolNamingConfig>,
+    tools: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> Vec<Collision> {
+    let mut by_name: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (component, function) in tools {
+        by_name
+            .entry(tool_name(naming, component, function))
+            .or_default()
+            .push(format!("{component}.{function}"));
+    }
+    by_name
+        .into_iter()
+        .map(|(name, mut tools)| {
+            tools.sort();
+            tools.dedup();
+            Collision { name, tools }
+        })
+        .filter(|collision| collision.tools.len() > 1)
+        .collect()
+}
+
+/// Public names of a set of tools, with collisions resolved
+#[derive(Debug, Clone, Default)]
+pub struct ToolNames {
+    naming: Option<ToolNamingConfig>,
+    /// Public name by `component.function`, for tools renamed to resolve a collision
+    renamed: HashMap<String, String>,
+    /// `component.function` by public name
+    internal: HashMap<String, String>,
+}
+
+impl ToolNames {
+    /// Name `tools`, given as (component, function) pairs, failing on collisions unless the
+    /// configuration asks to disambiguate them
+    pub fn new<'a>(
+        naming: Option<&ToolNamingConfig>,
+        tools: impl IntoIterator<Item = (&'a str, &'a str)> + Clone,
+    ) -> Result<Self> {
+        let collisions = collisions(naming, tools.clone());
+        let policy = naming
+            .and_then(|naming| naming.collisions)
+            .unwrap_or_default();
+        if !collisions.is_empty() && policy == CollisionPolicy::Error {
+            let collisions: Vec<String> = collisions.iter().map(ToString::to_string).collect();
+            return Err(WasiMcpError::InvalidArguments(format!(
+                "Tool names collide: {}; rename a component or set `tool_names.collisions: disambiguate`",
+                collisions.join("; ")
+            )));
+        }
+
+        let mut names = Self {
+            naming: naming.cloned(),
+            ..Self::default()
+        };
+        for collision in &collisions {
+            // The first tool keeps the name so adding a component renames as little as possible
+            for internal in &collision.tools[1..] {
+                let hash = format!("{:x}", Sha256::digest(internal.as_bytes()));
+                names.renamed.insert(
+                    internal.clone(),
+                    format!("{}_{}", collision.name, &hash[..HASH_LEN]),
+                );
+            }
+        }
+        for (component, function) in tools {
+            let internal = format!("{component}.{function}");
+            names
+                .internal
+                .insert(names.public(component, function), internal);
+        }
+        Ok(names)
+    }
+
+    /// Public name of `function` of `component`
+    pub fn public(&self, component: &str, function: &str) -> String {
+        self.renamed
+            .get(&format!("{component}.{function}"))
+            .cloned()
+            .unwrap_or_else(|| tool_name(self.naming.as_ref(), component, function))
+    }
+
+    /// `component.function` of the tool named `name`
+    pub fn internal(&self, name: &str) -> Option<&str> {
+        self.internal.get(name).map(String::as_str)
+    }
+}
+
+/// Replace characters clients may reject with `_`
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// The start of `name` followed by a hash of the internal name, `max_length` characters long
+fn shorten(name: &str, internal: &str, max_length: usize) -> String {
+    let hash = format!("{:x}", Sha256::digest(internal.as_bytes()));
+    let hash = &hash[..HASH_LEN.min(max_length)];
+    let keep = max_length.saturating_sub(HASH_LEN + 1);
+    if keep == 0 {
+        return hash.to_string();
+    }
+    let prefix: String = name.chars().take(keep).collect();
+    format!("{prefix}_{hash}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FUNCTION: &str = "wasi:http/handler@0.2.0.handle";
+
+    #[test]
+    fn test_default_names() {
+        assert_eq!(tool_name(None, "time", "now"), "time.now");
+        assert_eq!(
+            tool_name(Some(&ToolNamingConfig::default()), "web", FUNCTION),
+            "web.wasi:http/handler@0.2.0.handle"
+        );
+    }
+
+    #[test]
+    fn test_sanitized_names() {
+        let naming = ToolNamingConfig {
+            separator: Some("__".to_string()),
+            sanitize: true,
+            ..ToolNamingConfig::default()
+        };
+        assert_eq!(
+            tool_name(Some(&naming), "web", FUNCTION),
+            "web__wasi_http_handler_0_2_0_handle"
+        );
+    }
+
+    #[test]
+    fn test_shortened_names() {
+        let naming = ToolNamingConfig {
+            sanitize: true,
+            max_length: Some(20),
+            ..ToolNamingConfig::default()
+        };
+        let name = tool_name(Some(&naming), "web", FUNCTION);
+        assert_eq!(name.len(), 20);
+        assert!(name.starts_with("web.wasi_ht_"));
+        assert_ne!(
+            name,
+            tool_name(Some(&naming), "web", "wasi:http/handler@0.2.0.other")
+        );
+        assert_eq!(tool_name(Some(&naming), "time", "now"), "time.now");
+    }
+
+    #[test]
+    fn test_collisions() {
+        let tools = [
+            ("web", "wasi:http/handler@0.2.0.handle"),
+            ("web", "my:app/handler.handle"),
+            ("time", "now"),
+        ];
+        let naming = ToolNamingConfig {
+            short: true,
+            ..ToolNamingConfig::default()
+        };
+        let found = collisions(Some(&naming), tools);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "web.handle");
+        assert!(ToolNames::new(Some(&naming), tools).is_err());
+
+        let naming = ToolNamingConfig {
+            collisions: Some(CollisionPolicy::Disambiguate),
+            ..naming
+        };
+        let names = ToolNames::new(Some(&naming), tools).unwrap();
+        assert_eq!(names.public("web", "my:app/handler.handle"), "web.handle");
+        let renamed = names.public("web", "wasi:http/handler@0.2.0.handle");
+        assert!(renamed.starts_with("web.handle_"));
+        assert_eq!(
+            names.internal(&renamed),
+            Some("web.wasi:http/handler@0.2.0.handle")
+        );
+        assert_eq!(names.internal("time.now"), Some("time.now"));
+    }
+}
@@ -0,0 +1,149 @@
+//! OpenAPI description of the REST gateway
+//!
+//! Generates an OpenAPI 3.1 document with one `POST /tools/{component}/{function}` operation
+//! per tool, reusing the tools' input and output schemas, so client SDKs can be generated for
+//! the gateway.
+
+use rmcp::model::Tool;
+use serde_json::{Map, Value, json};
+
+/// Build an OpenAPI 3.1 document for `tools` as served by the REST gateway
+///
+/// `authenticated` adds a bearer token security scheme, for servers with a `policy`.
+pub fn document(tools: &[Tool], title: &str, authenticated: bool) -> Value {
+    let mut paths = Map::new();
+    for tool in tools {
+        let Some((component, function)) = tool.name.split_once('.') else {
+            continue;
+        };
+        let mut operation = json!({
+            "operationId": operation_id(&tool.name),
+            "tags": [component],
+            "requestBody": {
+                "required": true,
+                "content": {
+                    "application/json": { "schema": Value::Object((*tool.input_schema).clone()) }
+                }
+            },
+            "responses": {
+                "200": {
+                    "description": "Tool result",
+                    "content": {
+                        "application/json": {
+                            "schema": tool
+                                .output_schema
+                                .as_ref()
+                                .map(|schema| Value::Object((**schema).clone()))
+                                .unwrap_or_else(|| json!({}))
+                        }
+                    }
+                },
+                "400": error_response("Invalid arguments"),
+                "403": error_response("Not allowed by the server's policy"),
+                "404": error_response("Unknown tool"),
+                "500": error_response("The tool failed"),
+            }
+        });
+        if let Some(description) = &tool.description {
+            operation["summary"] = Value::from(description.as_ref());
+        }
+        paths.insert(
+            format!("/tools/{component}/{function}"),
+            json!({ "post": operation }),
+        );
+    }
+
+    let mut document = json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": title,
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": paths,
+        "components": {
+            "schemas": {
+                "Error": {
+                    "type": "object",
+                    "properties": {
+                        "error": { "type": "string" },
+                        "code": {
+                            "type": "string",
+                            "enum": [
+                                "validation", "not_found", "trap", "timeout",
+                                "resource_limit", "oci", "exit", "io", "internal"
+                            ]
+                        },
+                        "violations": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "pointer": { "type": "string" },
+                                    "message": { "type": "string" }
+                                },
+                                "required": ["pointer", "message"]
+                            }
+                        }
+                    },
+                    "required": ["error"],
+                }
+            }
+        }
+    });
+    if authenticated {
+        document["components"]["securitySchemes"] =
+            json!({ "bearerAuth": { "type": "http", "scheme": "bearer" } });
+        document["security"] = json!([{ "bearerAuth": [] }]);
+    }
+    document
+}
+
+fn error_response(description: &str) -> Value {
+    json!({
+        "description": description,
+        "content": {
+            "application/json": { "schema": { "$ref": "#/components/schemas/Error" } }
+        }
+    })
+}
+
+/// Tool name reduced to the characters code generators accept in identifiers
+fn operation_id(tool: &str) -> String {
+    tool.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_document() {
+        let input_schema = json!({
+            "type": "object",
+            "properties": { "zone": { "type": "string" } },
+            "required": ["zone"],
+        });
+        let tool = Tool {
+            name: "time.wasi:clocks/now.get".into(),
+            title: None,
+            description: Some("Current time".into()),
+            input_schema: Arc::new(input_schema.as_object().cloned().unwrap()),
+            output_schema: None,
+            annotations: None,
+            icons: None,
+        };
+
+        let document = document(&[tool], "wasmic", true);
+        let operation = &document["paths"]["/tools/time/wasi:clocks/now.get"]["post"];
+        assert_eq!(operation["operationId"], "time_wasi_clocks_now_get");
+        assert_eq!(operation["summary"], "Current time");
+        assert_eq!(
+            operation["requestBody"]["content"]["application/json"]["schema"]["required"],
+            json!(["zone"])
+        );
+        assert_eq!(document["security"], json!([{ "bearerAuth": [] }]));
+    }
+}
@@ -0,0 +1,124 @@
+use crate::config::PolicyConfig;
+use sha2::{Digest, Sha256};
+
+impl PolicyConfig {
+    /// Name of the client presenting `token`, if it is a known client
+    ///
+    /// Every client's token is compared, in constant time, so the time taken doesn't reveal
+    /// how much of a token was right.
+    pub fn client_name(&self, token: Option<&str>) -> Option<&str> {
+        let token = token?;
+        self.clients.iter().fold(None, |found, (name, client)| {
+            let matches = tokens_equal(&client.token, token);
+            found.or(matches.then_some(name.as_str()))
+        })
+    }
+
+    /// Whether the client presenting `token` is a known client marked `admin`
+    pub fn is_admin(&self, token: Option<&str>) -> bool {
+        self.client_name(token)
+            .is_some_and(|name| self.clients[name].admin)
+    }
+
+    /// Check whether the client presenting `token` may call `tool`
+    ///
+    /// Requests without a token, or with an unknown one, fall back to the `anonymous` list.
+    pub fn is_allowed(&self, token: Option<&str>, tool: &str) -> bool {
+        let allow = match self.client_name(token) {
+            Some(name) => &self.clients[name].allow,
+            None => &self.anonymous,
+        };
+        allow.iter().any(|pattern| tool_matches(pattern, tool))
+    }
+}
+
+/// Compare two tokens in constant time; hashing them first hides their lengths too
+fn tokens_equal(expected: &str, presented: &str) -> bool {
+    let expected = Sha256::digest(expected.as_bytes());
+    let presented = Sha256::digest(presented.as_bytes());
+    expected
+        .iter()
+        .zip(presented.iter())
+        .fold(0, |difference, (a, b)| difference | (a ^ b))
+        == 0
+}
+
+/// Match a tool name against `*`, `component.*` or an exact `component.function`
+fn tool_matches(pattern: &str, tool: &str) -> bool {
+    if pattern == "*" {
+        return true;
+    }
+    match pattern.strip_suffix(".*") {
+        Some(component) => tool
+            .split_once('.')
+            .is_some_and(|(tool_component, _)| tool_component == component),
+        None => pattern == tool,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ClientPolicy;
+    use std::collections::HashMap;
+
+    fn policy() -> PolicyConfig {
+        PolicyConfig {
+            clients: HashMap::from([
+                (
+                    "admin".to_string(),
+                    ClientPolicy {
+                        token: "admin-token".to_string(),
+                        allow: vec!["*".to_string()],
+                        admin: true,
+                    },
+                ),
+                (
+                    "reader".to_string(),
+                    ClientPolicy {
+                        token: "reader-token".to_string(),
+                        allow: vec!["search.*".to_string(), "fs.read_file".to_string()],
+                        admin: false,
+                    },
+                ),
+            ]),
+            anonymous: vec!["time.now".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_client_allow_lists() {
+        let policy = policy();
+        assert!(policy.is_allowed(Some("admin-token"), "fs.delete_file"));
+        assert!(policy.is_allowed(Some("reader-token"), "search.query"));
+        assert!(policy.is_allowed(Some("reader-token"), "fs.read_file"));
+        assert!(!policy.is_allowed(Some("reader-token"), "fs.delete_file"));
+        assert!(!policy.is_allowed(Some("reader-token"), "searcher.query"));
+    }
+
+    #[test]
+    fn test_anonymous_fallback() {
+        let policy = policy();
+        assert_eq!(policy.client_name(Some("bogus")), None);
+        assert!(policy.is_allowed(None, "time.now"));
+        assert!(policy.is_allowed(Some("bogus"), "time.now"));
+        assert!(!policy.is_allowed(None, "search.query"));
+    }
+
+    #[test]
+    fn test_tokens_equal() {
+        assert!(tokens_equal("admin-token", "admin-token"));
+        assert!(!tokens_equal("admin-token", "admin-tokem"));
+        assert!(!tokens_equal("admin-token", "admin"));
+        assert!(!tokens_equal("admin-token", ""));
+    }
+
+    #[test]
+    fn test_admin() {
+        let policy = policy();
+        assert!(policy.is_admin(Some("admin-token")));
+        assert!(!policy.is_admin(Some("reader-token")));
+        assert!(!policy.is_admin(Some("bogus")));
+        assert!(!policy.is_admin(None));
+    }
+}
@@ -0,0 +1,113 @@
+//! Prompts shipped with components
+//!
+//! Component authors embed prompts in a `wasmic.prompts` custom section, or publish them in
+//! the `wasmic.prompts` annotation of the component's OCI manifest. Both hold a JSON object
+//! mapping prompt ids to prompts in the configuration's format:
+//!
+//! ```json
+//! {"summarize": {"name": "Summarize", "description": "...", "content": "..."}}
+//! ```
+//!
+//! The server lists them next to the configured prompts as `component.id`.
+
+use crate::config::Prompt;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Custom section carrying a component's prompts
+pub const PROMPTS_SECTION: &str = "wasmic.prompts";
+
+/// OCI manifest annotation carrying a component's prompts
+pub const PROMPTS_ANNOTATION: &str = "wasmic.prompts";
+
+/// Prompts of the component in `bytes`, from its custom section
+pub fn decode(bytes: &[u8]) -> HashMap<String, Prompt> {
+    for payload in wasmparser::Parser::new(0).parse_all(bytes) {
+        match payload {
+            Ok(wasmparser::Payload::CustomSection(section))
+                if section.name() == PROMPTS_SECTION =>
+            {
+                return parse(PROMPTS_SECTION, section.data());
+            }
+            Ok(_) => {}
+            Err(e) => {
+                tracing::debug!("No embedded prompts: {e}");
+                break;
+            }
+        }
+    }
+    HashMap::new()
+}
+
+/// Prompts of the component file at `path`, from its custom section and, for pulled
+/// components, its manifest annotation; the custom section wins for ids in both
+pub fn read(path: &Path) -> HashMap<String, Prompt> {
+    let mut prompts = crate::oci::cached_annotations(path)
+        .get(PROMPTS_ANNOTATION)
+        .map(|annotation| parse(PROMPTS_ANNOTATION, annotation.as_bytes()))
+        .unwrap_or_default();
+    match std::fs::read(path) {
+        Ok(bytes) => prompts.extend(decode(&bytes)),
+        Err(e) => tracing::debug!("No embedded prompts for {path:?}: {e}"),
+    }
+    prompts
+}
+
+/// Name a component's prompt is listed under
+pub fn prompt_name(component: &str, id: &str) -> String {
+    format!("{component}.{id}")
+}
+
+fn parse(source: &str, json: &[u8]) -> HashMap<String, Prompt> {
+    serde_json::from_slice(json).unwrap_or_else(|e| {
+        tracing::warn!("Ignoring invalid prompts in {source}: {e}");
+        HashMap::new()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A core module consisting of one custom section
+    fn module_with_section(name: &str, data: &[u8]) -> Vec<u8> {
+        fn leb128(mut value: usize, out: &mut Vec<u8>) {
+            loop {
+                let byte = (value & 0x7f) as u8;
+                value >>= 7;
+                if value == 0 {
+                    out.push(byte);
+                    return;
+                }
+                out.push(byte | 0x80);
+            }
+        }
+
+        let mut payload = Vec::new();
+        leb128(name.len(), &mut payload);
+        payload.extend_from_slice(name.as_bytes());
+        payload.extend_from_slice(data);
+
+        let mut module = b"\0asm\x01\0\0\0".to_vec();
+        module.push(0);
+        leb128(payload.len(), &mut module);
+        module.extend(payload);
+        module
+    }
+
+    #[test]
+    fn test_decode_prompts_section() {
+        let json = br#"{"summarize": {"name": "Summarize", "description": "Summarize a file", "content": "Read the file, then summarize it."}}"#;
+        let prompts = decode(&module_with_section(PROMPTS_SECTION, json));
+        assert_eq!(prompts.len(), 1);
+        assert_eq!(prompts["summarize"].name, "Summarize");
+        assert_eq!(
+            prompts["summarize"].content,
+            "Read the file, then summarize it."
+        );
+
+        assert!(decode(&module_with_section("other", json)).is_empty());
+        assert!(decode(&module_with_section(PROMPTS_SECTION, b"[1, 2]")).is_empty());
+        assert!(decode(b"not wasm").is_empty());
+    }
+}
@@ -0,0 +1,116 @@
+//! Automatic recovery of components whose calls keep failing
+//!
+//! wasmtime refuses to re-enter an instance that trapped, and a guest that called `exit` has
+//! torn down its state, so the executor re-instantiates a component right after such a call.
+//! Other failures can still leave a guest's state corrupt, e.g. a poisoned allocator or a
+//! lost connection, so after `max_failures` consecutive failed calls the executor
+//! re-instantiates the component too. These restarts back off exponentially until a call
+//! succeeds again, so a component that fails deterministically isn't re-instantiated on
+//! every call.
+
+use crate::config::RecoveryConfig;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Failure streaks and restart backoff of the components of an executor
+#[derive(Debug, Default)]
+pub struct Recovery {
+    components: HashMap<String, Streak>,
+}
+
+#[derive(Debug, Default)]
+struct Streak {
+    failures: u32,
+    backoff: Duration,
+    next_restart: Option<Instant>,
+}
+
+impl Recovery {
+    /// Record the outcome of a call to `component`; returns whether the component should be
+    /// re-instantiated now
+    pub fn record(
+        &mut self,
+        component: &str,
+        failed: bool,
+        config: &RecoveryConfig,
+        now: Instant,
+    ) -> bool {
+        if !failed {
+            self.components.remove(component);
+            return false;
+        }
+
+        let streak = self.components.entry(component.to_string()).or_default();
+        streak.failures += 1;
+        config.max_failures > 0
+            && streak.failures >= config.max_failures
+            && streak.next_restart.is_none_or(|next| now >= next)
+    }
+
+    /// Record a restart attempt of `component`, successful or not, and back off the next one
+    pub fn restarted(
+        &mut self,
+        component: &str,
+        succeeded: bool,
+        config: &RecoveryConfig,
+        now: Instant,
+    ) {
+        let streak = self.components.entry(component.to_string()).or_default();
+        if succeeded {
+            streak.failures = 0;
+        }
+        let max_backoff = Duration::from_millis(config.max_backoff_ms);
+        streak.backoff = if streak.backoff.is_zero() {
+            Duration::from_millis(config.backoff_ms)
+        } else {
+            streak.backoff * 2
+        }
+        .min(max_backoff);
+        streak.next_restart = Some(now + streak.backoff);
+    }
+
+    /// Drop the state of a component that was unloaded or replaced
+    pub fn forget(&mut self, component: &str) {
+        self.components.remove(component);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restart_after_consecutive_failures_with_backoff() {
+        let config = RecoveryConfig {
+            max_failures: 2,
+            backoff_ms: 100,
+            max_backoff_ms: 150,
+        };
+        let mut recovery = Recovery::default();
+        let start = Instant::now();
+
+        // A success resets the streak
+        assert!(!recovery.record("db", true, &config, start));
+        assert!(!recovery.record("db", false, &config, start));
+        assert!(!recovery.record("db", true, &config, start));
+        assert!(recovery.record("db", true, &config, start));
+
+        // The restarted component keeps failing: wait out the backoff
+        recovery.restarted("db", true, &config, start);
+        assert!(!recovery.record("db", true, &config, start));
+        assert!(!recovery.record("db", true, &config, start));
+        let later = start + Duration::from_millis(100);
+        assert!(recovery.record("db", true, &config, later));
+
+        // The backoff doubles up to the maximum
+        recovery.restarted("db", false, &config, later);
+        assert!(!recovery.record("db", true, &config, later + Duration::from_millis(149)));
+        assert!(recovery.record("db", true, &config, later + Duration::from_millis(150)));
+
+        let disabled = RecoveryConfig {
+            max_failures: 0,
+            ..config
+        };
+        assert!(!recovery.record("other", true, &disabled, start));
+    }
+}
@@ -0,0 +1,545 @@
+//! Recording calls for deterministic replay
+//!
+//! With `recording` configured, every call runs in a fresh instance whose sources of
+//! nondeterminism go through a [`Tape`]: the wall and monotonic clocks are read from the host
+//! and written down, random numbers come from a generator seeded per call, and outgoing HTTP
+//! responses are buffered and kept. Each call is saved as a [`Recording`] with its arguments
+//! and result. `wasmic replay <recording>` runs the call again in a fresh instance whose
+//! clocks, random numbers and HTTP responses are served from the recording, so a failure
+//! seen once can be reproduced and debugged locally.
+
+use crate::config::RecordingConfig;
+use crate::error::{Result, WasiMcpError};
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use http_body_util::{BodyExt, Full, LengthLimitError, Limited};
+use hyper::body::Bytes;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use wasmtime_wasi::{HostMonotonicClock, HostWallClock, WasiCtxBuilder};
+use wasmtime_wasi_http::bindings::http::types::ErrorCode;
+use wasmtime_wasi_http::body::{HyperIncomingBody, HyperOutgoingBody};
+use wasmtime_wasi_http::types::{
+    HostFutureIncomingResponse, IncomingResponse, OutgoingRequestConfig,
+    default_send_request_handler,
+};
+
+/// Everything needed to run a call again: its inputs, what the guest observed and its result
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recording {
+    pub tool: String,
+    /// Arguments as the caller sent them, before configured values were applied
+    pub arguments: HashMap<String, Value>,
+    pub call_id: u64,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    /// Seed of the guest's random number generators
+    pub seed: u64,
+    /// Wall clock readings in nanoseconds since the Unix epoch, in the order of reading
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub wall_clock: Vec<u64>,
+    /// Monotonic clock readings in nanoseconds, in the order of reading
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub monotonic_clock: Vec<u64>,
+    /// Outgoing HTTP requests in the order they were sent, with their responses
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub http: Vec<HttpExchange>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Recording {
+    /// Save the recording in `dir`; returns the path of the file
+    pub fn save(&self, dir: &Path) -> Result<PathBuf> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(format!(
+            "{}-{}-{}.json",
+            self.timestamp,
+            self.call_id,
+            crate::utils::file_safe(&self.tool)
+        ));
+        // Recordings hold what the guest received, so only the owner may read them
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options
+            .open(&path)?
+            .write_all(&serde_json::to_vec_pretty(self)?)?;
+        Ok(path)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    }
+
+    /// Component the recorded tool belongs to
+    pub fn component(&self) -> &str {
+        self.tool
+            .split_once('.')
+            .map_or(self.tool.as_str(), |(component, _)| component)
+    }
+
+    /// Whether a replayed outcome is the one that was recorded
+    pub fn matches(&self, outcome: &Result<Value>) -> bool {
+        self.differences(outcome).is_empty()
+    }
+
+    /// How a replayed outcome differs from the recorded one
+    pub fn differences(&self, outcome: &Result<Value>) -> Vec<String> {
+        match (&self.result, &self.error, outcome) {
+            (_, Some(recorded), Err(e)) if *recorded == e.to_string() => Vec::new(),
+            (_, Some(recorded), Err(e)) => {
+                vec![format!("error changed from '{recorded}' to '{e}'")]
+            }
+            (_, Some(recorded), Ok(value)) => {
+                vec![format!("recorded error '{recorded}', now returns {value}")]
+            }
+            (Some(recorded), None, Ok(value)) => crate::golden::diff(recorded, value),
+            (Some(recorded), None, Err(e)) => vec![format!("recorded {recorded}, now fails: {e}")],
+            (None, None, Ok(value)) => vec![format!("recorded no result, now returns {value}")],
+            (None, None, Err(e)) => vec![format!("recorded no result, now fails: {e}")],
+        }
+    }
+}
+
+/// An outgoing HTTP request and the response the guest received
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HttpExchange {
+    pub method: String,
+    pub uri: String,
+    #[serde(default)]
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<(String, String)>,
+    /// Response body, base64 encoded
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub body: String,
+    /// Why the request failed, instead of a response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Largest HTTP response body buffered while recording; larger responses fail the request
+pub const MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+/// Response headers whose values are replaced in recordings, as they carry credentials
+const REDACTED_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+];
+
+/// Directory recordings are saved in, by default in the user's data directory
+pub fn directory(config: &RecordingConfig) -> Result<PathBuf> {
+    match &config.dir {
+        Some(dir) => Ok(dir.clone()),
+        None => Ok(dirs::data_dir()
+            .ok_or_else(|| {
+                WasiMcpError::InvalidArguments("Could not determine data directory".to_string())
+            })?
+            .join("wasmic")
+            .join("recordings")),
+    }
+}
+
+/// Source of the clocks, random numbers and HTTP responses of one call, either recording
+/// them or replaying a recording
+#[derive(Debug)]
+pub struct Tape {
+    seed: u64,
+    replay: bool,
+    started: Instant,
+    state: Mutex<TapeState>,
+}
+
+#[derive(Debug, Default)]
+struct TapeState {
+    wall_clock: Vec<u64>,
+    monotonic_clock: Vec<u64>,
+    http: Vec<HttpExchange>,
+    /// Next reading or exchange to serve when replaying
+    wall_clock_pos: usize,
+    monotonic_clock_pos: usize,
+    http_pos: usize,
+    /// Why the replayed guest stopped doing what the recording says, if it did
+    divergence: Option<String>,
+}
+
+impl Tape {
+    /// A tape recording what a new call observes
+    pub fn record() -> Arc<Self> {
+        Arc::new(Self {
+            seed: rand::random(),
+            replay: false,
+            started: Instant::now(),
+            state: Mutex::default(),
+        })
+    }
+
+    /// A tape serving what `recording` observed
+    pub fn replay(recording: &Recording) -> Arc<Self> {
+        Arc::new(Self {
+            seed: recording.seed,
+            replay: true,
+            started: Instant::now(),
+            state: Mutex::new(TapeState {
+                wall_clock: recording.wall_clock.clone(),
+                monotonic_clock: recording.monotonic_clock.clone(),
+                http: recording.http.clone(),
+                ..Default::default()
+            }),
+        })
+    }
+
+    /// Route the clocks and random number generators of a WASI context through the tape
+    pub fn install(self: &Arc<Self>, builder: &mut WasiCtxBuilder) {
+        builder
+            .wall_clock(TapeWallClock(self.clone()))
+            .monotonic_clock(TapeMonotonicClock(self.clone()))
+            .secure_random(StdRng::seed_from_u64(self.seed))
+            .insecure_random(StdRng::seed_from_u64(self.seed.rotate_left(32)))
+            .insecure_random_seed(u128::from(self.seed));
+    }
+
+    /// Why a replay diverged from its recording, e.g. because the guest read the clock more
+    /// often or sent other requests
+    pub fn divergence(&self) -> Option<String> {
+        self.state().divergence.clone()
+    }
+
+    /// The recording of a finished call
+    pub fn finish(
+        &self,
+        tool: &str,
+        arguments: &HashMap<String, Value>,
+        call_id: u64,
+        outcome: &Result<Value>,
+    ) -> Recording {
+        let state = self.state();
+        Recording {
+            tool: tool.to_string(),
+            arguments: arguments.clone(),
+            call_id,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default(),
+            seed: self.seed,
+            wall_clock: state.wall_clock.clone(),
+            monotonic_clock: state.monotonic_clock.clone(),
+            http: state.http.clone(),
+            result: outcome.as_ref().ok().cloned(),
+            error: outcome.as_ref().err().map(ToString::to_string),
+        }
+    }
+
+    /// Send an outgoing request, or answer it from the recording
+    pub fn send_request(
+        self: &Arc<Self>,
+        request: hyper::Request<HyperOutgoingBody>,
+        config: OutgoingRequestConfig,
+    ) -> HostFutureIncomingResponse {
+        let method = request.method().to_string();
+        let uri = request.uri().to_string();
+        if self.replay {
+            let response =
+                self.replay_exchange(&method, &uri)
+                    .map(|exchange| match exchange.error {
+                        Some(error) => Err(ErrorCode::InternalError(Some(error))),
+                        None => Ok(replayed_response(&exchange, config.between_bytes_timeout)),
+                    });
+            return HostFutureIncomingResponse::ready(Ok(response.unwrap_or_else(|| {
+                Err(ErrorCode::InternalError(Some(format!(
+                    "No recorded response for {method} {uri}"
+                ))))
+            })));
+        }
+
+        // Keep the order requests were sent in, not the order their responses arrive in
+        let index = {
+            let mut state = self.state();
+            state.http.push(HttpExchange {
+                method,
+                uri,
+                status: 0,
+                headers: Vec::new(),
+                body: String::new(),
+                error: None,
+            });
+            state.http.len() - 1
+        };
+        let tape = self.clone();
+        let handle = wasmtime_wasi::runtime::spawn(async move {
+            let between_bytes_timeout = config.between_bytes_timeout;
+            let response = match default_send_request_handler(request, config).await {
+                Ok(response) => response,
+                Err(e) => {
+                    tape.state().http[index].error = Some(format!("{e:?}"));
+                    return Ok(Err(e));
+                }
+            };
+            let (parts, body) = response.resp.into_parts();
+            let body = match Limited::new(body, MAX_BODY_BYTES).collect().await {
+                Ok(collected) => collected.to_bytes(),
+                Err(e) => {
+                    let e = match e.downcast::<ErrorCode>() {
+                        Ok(e) => *e,
+                        Err(e) if e.is::<LengthLimitError>() => {
+                            ErrorCode::HttpResponseBodySize(Some(MAX_BODY_BYTES as u64))
+                        }
+                        Err(e) => ErrorCode::InternalError(Some(e.to_string())),
+                    };
+                    tape.state().http[index].error = Some(format!("{e:?}"));
+                    return Ok(Err(e));
+                }
+            };
+            {
+                let mut state = tape.state();
+                let exchange = &mut state.http[index];
+                exchange.status = parts.status.as_u16();
+                exchange.headers = parts
+                    .headers
+                    .iter()
+                    .map(|(name, value)| {
+                        let value = if REDACTED_HEADERS.contains(&name.as_str()) {
+                            "[redacted]".to_string()
+                        } else {
+                            String::from_utf8_lossy(value.as_bytes()).into_owned()
+                        };
+                        (name.to_string(), value)
+                    })
+                    .collect();
+                exchange.body = BASE64.encode(&body);
+            }
+            Ok(Ok(IncomingResponse {
+                resp: hyper::Response::from_parts(parts, full_body(body)),
+                worker: None,
+                between_bytes_timeout,
+            }))
+        });
+        HostFutureIncomingResponse::pending(handle)
+    }
+
+    fn replay_exchange(&self, method: &str, uri: &str) -> Option<HttpExchange> {
+        let mut state = self.state();
+        let Some(exchange) = state.http.get(state.http_pos).cloned() else {
+            state.diverge(format!("sent an unrecorded request {method} {uri}"));
+            return None;
+        };
+        state.http_pos += 1;
+        if exchange.method != method || exchange.uri != uri {
+            state.diverge(format!(
+                "sent {method} {uri} where the recording has {} {}",
+                exchange.method, exchange.uri
+            ));
+        }
+        Some(exchange)
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, TapeState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Clock {
+    Wall,
+    Monotonic,
+}
+
+impl TapeState {
+    /// Next recorded reading of a clock, or the last one once the guest reads it more often
+    /// than recorded
+    fn next_reading(&mut self, clock: Clock) -> u64 {
+        let (readings, pos, name) = match clock {
+            Clock::Wall => (&self.wall_clock, &mut self.wall_clock_pos, "wall"),
+            Clock::Monotonic => (
+                &self.monotonic_clock,
+                &mut self.monotonic_clock_pos,
+                "monotonic",
+            ),
+        };
+        let reading = readings
+            .get(*pos)
+            .or(readings.last())
+            .copied()
+            .unwrap_or_default();
+        let exhausted = *pos >= readings.len();
+        *pos += 1;
+        if exhausted {
+            self.diverge(format!("read the {name} clock more often than recorded"));
+        }
+        reading
+    }
+
+    fn diverge(&mut self, reason: String) {
+        if self.divergence.is_none() {
+            tracing::warn!("Replay diverged from the recording: the guest {reason}");
+            self.divergence = Some(reason);
+        }
+    }
+}
+
+struct TapeWallClock(Arc<Tape>);
+
+impl HostWallClock for TapeWallClock {
+    fn resolution(&self) -> Duration {
+        Duration::from_nanos(1)
+    }
+
+    fn now(&self) -> Duration {
+        let mut state = self.0.state();
+        let nanos = if self.0.replay {
+            state.next_reading(Clock::Wall)
+        } else {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default();
+            let nanos = u64::try_from(now.as_nanos()).unwrap_or(u64::MAX);
+            state.wall_clock.push(nanos);
+            nanos
+        };
+        Duration::from_nanos(nanos)
+    }
+}
+
+struct TapeMonotonicClock(Arc<Tape>);
+
+impl HostMonotonicClock for TapeMonotonicClock {
+    fn resolution(&self) -> u64 {
+        1
+    }
+
+    fn now(&self) -> u64 {
+        let mut state = self.0.state();
+        if self.0.replay {
+            state.next_reading(Clock::Monotonic)
+        } else {
+            let nanos = u64::try_from(self.0.started.elapsed().as_nanos()).unwrap_or(u64::MAX);
+            state.monotonic_clock.push(nanos);
+            nanos
+        }
+    }
+}
+
+fn full_body(bytes: Bytes) -> HyperIncomingBody {
+    Full::new(bytes).map_err(|never| match never {}).boxed()
+}
+
+fn replayed_response(exchange: &HttpExchange, between_bytes_timeout: Duration) -> IncomingResponse {
+    let mut response = hyper::Response::builder().status(exchange.status);
+    for (name, value) in &exchange.headers {
+        response = response.header(name, value);
+    }
+    let body = BASE64.decode(&exchange.body).unwrap_or_default();
+    IncomingResponse {
+        resp: response
+            .body(full_body(Bytes::from(body)))
+            .unwrap_or_else(|_| hyper::Response::new(full_body(Bytes::new()))),
+        worker: None,
+        between_bytes_timeout,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recording() -> Recording {
+        Recording {
+            tool: "fetch.get".to_string(),
+            arguments: HashMap::from([("url".to_string(), Value::from("https://example.com"))]),
+            call_id: 3,
+            timestamp: 1_700_000_000,
+            seed: 42,
+            wall_clock: vec![1_700_000_000_000_000_000, 1_700_000_000_500_000_000],
+            monotonic_clock: vec![10, 20],
+            http: vec![HttpExchange {
+                method: "GET".to_string(),
+                uri: "https://example.com/".to_string(),
+                status: 200,
+                headers: vec![("content-type".to_string(), "text/plain".to_string())],
+                body: BASE64.encode("hello"),
+                error: None,
+            }],
+            result: Some(Value::from("hello")),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_replay_clocks() {
+        let tape = Tape::replay(&recording());
+        let wall = TapeWallClock(tape.clone());
+        let monotonic = TapeMonotonicClock(tape.clone());
+
+        assert_eq!(wall.now(), Duration::from_secs(1_700_000_000));
+        assert_eq!(monotonic.now(), 10);
+        assert_eq!(monotonic.now(), 20);
+        assert_eq!(tape.divergence(), None);
+
+        // Reading more often than recorded repeats the last reading
+        assert_eq!(monotonic.now(), 20);
+        assert_eq!(
+            tape.divergence().as_deref(),
+            Some("read the monotonic clock more often than recorded")
+        );
+        assert_eq!(wall.now(), Duration::from_millis(1_700_000_000_500));
+    }
+
+    #[test]
+    fn test_record_and_save() {
+        let tape = Tape::record();
+        let monotonic = TapeMonotonicClock(tape.clone());
+        let first = monotonic.now();
+        let second = monotonic.now();
+        assert!(second >= first);
+
+        let arguments = recording().arguments;
+        let outcome = Ok(Value::from("done"));
+        let recorded = tape.finish("fetch.get", &arguments, 9, &outcome);
+        assert_eq!(recorded.monotonic_clock, vec![first, second]);
+        assert!(recorded.matches(&outcome));
+        assert!(!recorded.matches(&Ok(Value::from("other"))));
+        assert!(!recorded.matches(&Err(WasiMcpError::Execution("boom".to_string()))));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = recorded.save(dir.path()).unwrap();
+        assert!(path.ends_with(format!("{}-9-fetch.get.json", recorded.timestamp)));
+        assert_eq!(Recording::load(&path).unwrap(), recorded);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn test_replay_http_in_order() {
+        let tape = Tape::replay(&recording());
+        let exchange = tape.replay_exchange("GET", "https://example.com/").unwrap();
+        assert_eq!(exchange.status, 200);
+        assert_eq!(tape.divergence(), None);
+
+        assert!(
+            tape.replay_exchange("GET", "https://example.com/")
+                .is_none()
+        );
+        assert_eq!(
+            tape.divergence().as_deref(),
+            Some("sent an unrecorded request GET https://example.com/")
+        );
+    }
+}
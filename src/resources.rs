@@ -0,0 +1,489 @@
+//! Files of mounted volumes as MCP resources
+//!
+//! Every file under a component's `volumes` is listed as a `file://` resource with its
+//! percent-encoded host path. Files are read as text, or as base64 blobs when they aren't
+//! UTF-8. Clients subscribing to a file, or to a directory for everything below it, get
+//! `notifications/resources/updated` when it changes. The volumes are watched with `notify`
+//! from the first subscription on, including volumes added by later reloads.
+//!
+//! The `wasmic://components` resource describes the loaded components, so agents can inspect
+//! their own toolbox; its subscribers are notified when components change.
+
+use crate::config::Config;
+use crate::error::{Result, WasiMcpError};
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use rmcp::model::{
+    AnnotateAble, RawResource, Resource, ResourceContents, ResourceUpdatedNotificationParam,
+};
+use rmcp::service::{Peer, RoleServer};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Most files listed, so huge workspaces don't produce huge responses
+const MAX_LISTED_FILES: usize = 1000;
+
+/// Scheme of resource URIs
+const FILE_SCHEME: &str = "file://";
+
+/// URI of the inventory of loaded components
+pub const INVENTORY_URI: &str = "wasmic://components";
+
+/// Source of the ids telling the sessions of the HTTP transport apart
+static NEXT_SESSION_ID: AtomicU64 = AtomicU64::new(1);
+
+/// A new id for an MCP session
+pub fn session_id() -> u64 {
+    NEXT_SESSION_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// A mounted directory and the component it is mounted into
+#[derive(Debug, Clone, PartialEq)]
+pub struct Volume {
+    pub component: String,
+    pub host_path: PathBuf,
+    pub guest_path: String,
+}
+
+/// Volumes of all configured components, with their host paths canonicalized; volumes that
+/// don't exist are skipped
+pub fn volumes(config: &Config) -> Vec<Volume> {
+    let mut volumes = Vec::new();
+    for (component, component_config) in &config.components {
+        for mount in &component_config.volumes {
+            match std::fs::canonicalize(&mount.host_path) {
+                Ok(host_path) => volumes.push(Volume {
+                    component: component.clone(),
+                    host_path,
+                    guest_path: mount.guest_path.clone(),
+                }),
+                Err(e) => tracing::debug!(path = %mount.host_path, "Volume not listed: {}", e),
+            }
+        }
+    }
+    volumes.sort_by(|a, b| a.host_path.cmp(&b.host_path));
+    volumes
+}
+
+/// URI of a host file, with everything but unreserved characters and `/` percent-encoded
+pub fn uri(path: &Path) -> String {
+    let mut uri = FILE_SCHEME.to_string();
+    for byte in path.as_os_str().as_encoded_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                uri.push(char::from(*byte));
+            }
+            _ => uri.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    uri
+}
+
+/// Host path named by a `file://` URI, with percent-encoding decoded
+pub fn path(uri: &str) -> Result<PathBuf> {
+    let invalid = || WasiMcpError::InvalidArguments(format!("Not a file URI: {uri}"));
+    let encoded = uri.strip_prefix(FILE_SCHEME).ok_or_else(invalid)?;
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut rest = encoded.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = tail.get(..2).ok_or_else(invalid)?;
+            let hex = std::str::from_utf8(hex).map_err(|_| invalid())?;
+            bytes.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    Ok(PathBuf::from(
+        String::from_utf8(bytes).map_err(|_| invalid())?,
+    ))
+}
+
+/// Contents of a file as a resource: text when it is UTF-8, a base64 blob otherwise
+pub fn contents(uri: String, bytes: Vec<u8>) -> Result<ResourceContents> {
+    match String::from_utf8(bytes) {
+        Ok(text) => Ok(ResourceContents::text(text, uri)),
+        Err(e) => {
+            let blob = serde_json::json!({
+                "uri": uri,
+                "mimeType": "application/octet-stream",
+                "blob": BASE64.encode(e.as_bytes()),
+            });
+            Ok(serde_json::from_value(blob)?)
+        }
+    }
+}
+
+/// Files of the volumes as resources, named by their path inside the component
+pub fn list(volumes: &[Volume]) -> Vec<Resource> {
+    let mut resources = Vec::new();
+    for volume in volumes {
+        let mut pending = vec![volume.host_path.clone()];
+        while let Some(dir) = pending.pop() {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            let mut entries: Vec<_> = entries.filter_map(|entry| entry.ok()).collect();
+            entries.sort_by_key(|entry| entry.path());
+            for entry in entries {
+                let path = entry.path();
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+                if file_type.is_dir() {
+                    pending.push(path);
+                    continue;
+                }
+                if !file_type.is_file() {
+                    continue;
+                }
+                if resources.len() == MAX_LISTED_FILES {
+                    tracing::debug!("Listing only the first {MAX_LISTED_FILES} mounted files");
+                    return resources;
+                }
+                let relative = path.strip_prefix(&volume.host_path).unwrap_or(&path);
+                let guest_path = Path::new(&volume.guest_path).join(relative);
+                let mut resource = RawResource::new(uri(&path), guest_path.display().to_string());
+                resource.description =
+                    Some(format!("Mounted into component '{}'", volume.component));
+                resource.size = entry
+                    .metadata()
+                    .ok()
+                    .and_then(|metadata| u32::try_from(metadata.len()).ok());
+                resources.push(resource.no_annotation());
+            }
+        }
+    }
+    resources
+}
+
+/// The loaded components, as served at [`INVENTORY_URI`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Inventory {
+    /// Version of wasmic
+    pub version: String,
+    pub components: Vec<ComponentInventory>,
+}
+
+/// A loaded component
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ComponentInventory {
+    pub name: String,
+    /// OCI reference or local path the component was loaded from
+    pub source: String,
+    /// Version annotation of the OCI manifest
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// SHA-256 of the loaded file, as `sha256:<hex>`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+    pub enabled: bool,
+    /// Number of tools the component serves
+    pub tools: usize,
+}
+
+/// The inventory as a resource
+pub fn inventory_resource() -> Resource {
+    let mut resource = RawResource::new(INVENTORY_URI, "components");
+    resource.description =
+        Some("Loaded components with their versions, digests and tool counts".to_string());
+    resource.mime_type = Some("application/json".to_string());
+    resource.no_annotation()
+}
+
+/// SHA-256 of a component file, if it can be read
+pub fn digest(path: &Path) -> Option<String> {
+    let bytes = std::fs::read(path).ok()?;
+    Some(format!("sha256:{:x}", Sha256::digest(&bytes)))
+}
+
+/// Host path of a resource URI, if it names a file or directory inside one of the volumes
+pub fn resolve(volumes: &[Volume], uri: &str) -> Result<PathBuf> {
+    let path = path(uri)?;
+    // Canonicalizing resolves `..` and symlinks that would point out of a volume
+    let path = std::fs::canonicalize(path)
+        .map_err(|e| WasiMcpError::InvalidArguments(format!("Unknown resource {uri}: {e}")))?;
+    if volumes
+        .iter()
+        .any(|volume| path.starts_with(&volume.host_path))
+    {
+        Ok(path)
+    } else {
+        Err(WasiMcpError::InvalidArguments(format!(
+            "{uri} is not in a mounted volume"
+        )))
+    }
+}
+
+/// A session that subscribed to resources
+struct Subscriber {
+    session: u64,
+    peer: Peer<RoleServer>,
+    /// Subscribed URIs; directories stand for everything below them
+    uris: HashSet<String>,
+}
+
+impl Subscriber {
+    fn wants(&self, changed: &str) -> bool {
+        self.uris.iter().any(|uri| {
+            changed == uri
+                || changed
+                    .strip_prefix(uri.as_str())
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+    }
+}
+
+/// The watcher of the volumes, and the host paths it watches
+struct VolumeWatcher {
+    watcher: RecommendedWatcher,
+    watched: HashSet<PathBuf>,
+}
+
+impl VolumeWatcher {
+    /// Watch the volumes not watched yet, and stop watching the ones that are gone
+    fn update(&mut self, volumes: &[Volume]) -> Result<()> {
+        let wanted: HashSet<PathBuf> = volumes
+            .iter()
+            .map(|volume| volume.host_path.clone())
+            .collect();
+        for path in wanted.difference(&self.watched) {
+            self.watcher
+                .watch(path, RecursiveMode::Recursive)
+                .map_err(|e| {
+                    WasiMcpError::Execution(format!("Failed to watch {}: {e}", path.display()))
+                })?;
+            tracing::info!(path = %path.display(), "Watching mounted volume");
+        }
+        for path in self.watched.difference(&wanted) {
+            if let Err(e) = self.watcher.unwatch(path) {
+                tracing::debug!(path = %path.display(), "Failed to stop watching: {}", e);
+            }
+        }
+        self.watched = wanted;
+        Ok(())
+    }
+}
+
+/// Resource subscriptions of all sessions, and the watcher of the volumes
+#[derive(Clone, Default)]
+pub struct Subscriptions {
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+    watcher: Arc<Mutex<Option<VolumeWatcher>>>,
+}
+
+impl Subscriptions {
+    /// Subscribe a session to `uri`
+    pub fn subscribe(&self, session: u64, peer: Peer<RoleServer>, uri: String) {
+        let mut subscribers = self.subscribers.lock().unwrap_or_else(|e| e.into_inner());
+        // Sessions that went away without unsubscribing are forgotten here rather than only
+        // when a notification to them fails
+        subscribers.retain(|subscriber| !subscriber.peer.is_transport_closed());
+        match subscribers
+            .iter_mut()
+            .find(|subscriber| subscriber.session == session)
+        {
+            Some(subscriber) => {
+                subscriber.uris.insert(uri);
+            }
+            None => subscribers.push(Subscriber {
+                session,
+                peer,
+                uris: HashSet::from([uri]),
+            }),
+        }
+    }
+
+    pub fn unsubscribe(&self, session: u64, uri: &str) {
+        let mut subscribers = self.subscribers.lock().unwrap_or_else(|e| e.into_inner());
+        for subscriber in subscribers.iter_mut() {
+            if subscriber.session == session {
+                subscriber.uris.remove(uri);
+            }
+        }
+        subscribers.retain(|subscriber| !subscriber.uris.is_empty());
+    }
+
+    /// Start watching `volumes` for changes, adding the ones not watched yet
+    pub fn watch(&self, volumes: &[Volume]) -> Result<()> {
+        let mut watcher = self.watcher.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(watcher) = watcher.as_mut() {
+            return watcher.update(volumes);
+        }
+
+        // notify calls back on its own thread; notifications are sent from the runtime
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel::<PathBuf>();
+        let new_watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+                Ok(event) if !event.kind.is_access() => {
+                    for path in event.paths {
+                        let _ = sender.send(path);
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Watching volumes failed: {}", e),
+            })
+            .map_err(|e| WasiMcpError::Execution(format!("Failed to watch volumes: {e}")))?;
+        let mut new_watcher = VolumeWatcher {
+            watcher: new_watcher,
+            watched: HashSet::new(),
+        };
+        new_watcher.update(volumes)?;
+        *watcher = Some(new_watcher);
+
+        let subscriptions = self.clone();
+        tokio::spawn(async move {
+            while let Some(path) = receiver.recv().await {
+                subscriptions.notify(&uri(&path)).await;
+            }
+        });
+        Ok(())
+    }
+
+    /// Follow a reloaded configuration's volumes if they are being watched already
+    pub fn rewatch(&self, volumes: &[Volume]) -> Result<()> {
+        match self
+            .watcher
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_mut()
+        {
+            Some(watcher) => watcher.update(volumes),
+            None => Ok(()),
+        }
+    }
+
+    /// Send `notifications/resources/updated` to the sessions subscribed to `changed`,
+    /// forgetting sessions that have gone away
+    pub async fn notify(&self, changed: &str) {
+        let peers: Vec<(u64, Peer<RoleServer>)> = {
+            let mut subscribers = self.subscribers.lock().unwrap_or_else(|e| e.into_inner());
+            subscribers.retain(|subscriber| !subscriber.peer.is_transport_closed());
+            subscribers
+                .iter()
+                .filter(|subscriber| subscriber.wants(changed))
+                .map(|subscriber| (subscriber.session, subscriber.peer.clone()))
+                .collect()
+        };
+        for (session, peer) in peers {
+            let notified = peer
+                .notify_resource_updated(ResourceUpdatedNotificationParam {
+                    uri: changed.to_string(),
+                })
+                .await;
+            if let Err(e) = notified {
+                tracing::debug!("Dropping disconnected subscriber: {}", e);
+                self.subscribers
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .retain(|subscriber| subscriber.session != session);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ComponentConfig, VolumeMount};
+
+    #[test]
+    fn test_list_and_resolve() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("README.md"), "hello").unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "fn main() {}").unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::write(outside.path().join("secret"), "").unwrap();
+
+        let mut config = Config::default();
+        config.components.insert(
+            "editor".to_string(),
+            ComponentConfig {
+                volumes: vec![VolumeMount {
+                    host_path: dir.path().display().to_string(),
+                    guest_path: "/workspace".to_string(),
+                    read_only: true,
+                }],
+                ..Default::default()
+            },
+        );
+        let volumes = volumes(&config);
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+
+        let names: Vec<_> = list(&volumes)
+            .into_iter()
+            .map(|resource| (resource.raw.name, resource.raw.uri))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                (
+                    "/workspace/README.md".to_string(),
+                    uri(&root.join("README.md"))
+                ),
+                (
+                    "/workspace/src/main.rs".to_string(),
+                    uri(&root.join("src/main.rs"))
+                ),
+            ]
+        );
+
+        let readme = uri(&root.join("README.md"));
+        assert_eq!(resolve(&volumes, &readme).unwrap(), root.join("README.md"));
+        let escaped = format!("{}/src/../../{}", uri(&root), "x");
+        assert!(resolve(&volumes, &escaped).is_err());
+        let secret = uri(&std::fs::canonicalize(outside.path().join("secret")).unwrap());
+        assert!(resolve(&volumes, &secret).is_err());
+        assert!(resolve(&volumes, "https://example.com").is_err());
+
+        assert_eq!(
+            digest(&root.join("README.md")).as_deref(),
+            Some("sha256:2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824")
+        );
+        assert_eq!(digest(&root.join("missing")), None);
+    }
+
+    #[test]
+    fn test_uri_encoding() {
+        let path = Path::new("/srv/my notes/50%#1.md");
+        let encoded = uri(path);
+        assert_eq!(encoded, "file:///srv/my%20notes/50%25%231.md");
+        assert_eq!(super::path(&encoded).unwrap(), path);
+        assert!(super::path("file:///srv/%2").is_err());
+        assert!(super::path("https://example.com").is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a b.txt"), "").unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        let volumes = vec![Volume {
+            component: "editor".to_string(),
+            host_path: root.clone(),
+            guest_path: "/workspace".to_string(),
+        }];
+        let file = uri(&root.join("a b.txt"));
+        assert!(file.ends_with("/a%20b.txt"));
+        assert_eq!(resolve(&volumes, &file).unwrap(), root.join("a b.txt"));
+    }
+
+    #[test]
+    fn test_binary_contents() {
+        let text = contents("file:///a.txt".to_string(), b"hello".to_vec()).unwrap();
+        let text = serde_json::to_value(text).unwrap();
+        assert_eq!(text["text"], "hello");
+        assert!(text.get("blob").is_none());
+
+        let blob = contents("file:///a.bin".to_string(), vec![0xff, 0x00]).unwrap();
+        let blob = serde_json::to_value(blob).unwrap();
+        assert_eq!(blob["blob"], "/wA=");
+        assert_eq!(blob["mimeType"], "application/octet-stream");
+        assert!(blob.get("text").is_none());
+    }
+}
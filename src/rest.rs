@@ -0,0 +1,145 @@
+//! REST gateway exposing tools as plain HTTP endpoints
+//!
+//! Every tool is served at `POST /tools/{component}/{function}` with its arguments as a JSON
+//! object body, for services that don't speak MCP. `GET /tools` lists the tools with their
+//! input schemas. Policy, drain and statistics apply exactly as for MCP calls.
+
+use crate::error::WasiMcpError;
+use crate::mcp::{WasmMcpServer, header_bearer_token};
+use crate::trace::remote_call_span;
+use axum::Json;
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde_json::Value;
+use std::collections::HashMap;
+use tracing::Instrument;
+
+/// Routes of the REST gateway, to be merged into the HTTP router
+pub fn router() -> axum::Router<WasmMcpServer> {
+    axum::Router::new()
+        .route("/tools", axum::routing::get(list_tools))
+        // Functions of exported interfaces contain `/`, e.g. `wasi:cli/run@0.2.0.run`
+        .route(
+            "/tools/{component}/{*function}",
+            axum::routing::post(call_tool),
+        )
+}
+
+async fn list_tools(State(server): State<WasmMcpServer>, headers: HeaderMap) -> Response {
+    let mut tools = match server.executor.lock().await.tools_by_internal_name() {
+        Ok(tools) => tools,
+        Err(e) => return error_response(&e),
+    };
+    if let Some(policy) = &server.config.read().await.policy {
+        let token = header_bearer_token(&headers);
+        tools.retain(|(internal, _)| policy.is_allowed(token, internal));
+    }
+    let tools: Vec<_> = tools.into_iter().map(|(_, tool)| tool).collect();
+    Json(tools).into_response()
+}
+
+async fn call_tool(
+    State(server): State<WasmMcpServer>,
+    Path((component, function)): Path<(String, String)>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let tool = format!("{component}.{function}");
+    let internal = server.executor.lock().await.resolve_tool_name(&tool);
+    if let Some(policy) = &server.config.read().await.policy {
+        let token = header_bearer_token(&headers);
+        if !policy.is_allowed(token, &internal) {
+            let client = policy.client_name(token).unwrap_or("anonymous");
+            tracing::warn!(client, tool = %tool, "Denied REST tool call by policy");
+            return message(
+                StatusCode::FORBIDDEN,
+                format!("Client '{client}' is not allowed to call tool '{tool}'"),
+            );
+        }
+    }
+
+    let arguments: HashMap<String, Value> = if body.iter().all(u8::is_ascii_whitespace) {
+        HashMap::new()
+    } else {
+        match serde_json::from_slice(&body) {
+            Ok(arguments) => arguments,
+            Err(e) => {
+                return message(
+                    StatusCode::BAD_REQUEST,
+                    format!("Body must be a JSON object of arguments: {e}"),
+                );
+            }
+        }
+    };
+
+    let Some(_call) = server.drain.start_call() else {
+        return message(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Server is shutting down".to_string(),
+        );
+    };
+
+    let result = async {
+        let mut executor = server.executor.lock().await;
+        executor
+            .execute_function(&tool, arguments)
+            .await
+            .map(|result| {
+                executor
+                    .structured_content(&tool, &result)
+                    .unwrap_or(result)
+            })
+    }
+    .instrument(remote_call_span("rest", &headers))
+    .await;
+    match result {
+        Ok(result) => Json(result).into_response(),
+        Err(e) => error_response(&e),
+    }
+}
+
+/// Status code for an executor error: unknown tools are 404, bad arguments 400, disabled
+/// components 503
+fn status_of(error: &WasiMcpError) -> StatusCode {
+    match error {
+        WasiMcpError::FunctionNotFound(_)
+        | WasiMcpError::InterfaceNotFound(_)
+        | WasiMcpError::ComponentNotFound(_) => StatusCode::NOT_FOUND,
+        WasiMcpError::InvalidArguments(_)
+        | WasiMcpError::ArgumentViolations(_)
+        | WasiMcpError::UnexpectedExpected(_, _) => StatusCode::BAD_REQUEST,
+        WasiMcpError::ComponentDisabled(_) => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+pub(crate) fn error_response(error: &WasiMcpError) -> Response {
+    (status_of(error), Json(error.to_json())).into_response()
+}
+
+pub(crate) fn message(status: StatusCode, error: String) -> Response {
+    (status, Json(serde_json::json!({ "error": error }))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_of() {
+        assert_eq!(
+            status_of(&WasiMcpError::FunctionNotFound("f".to_string())),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            status_of(&WasiMcpError::InvalidArguments("x".to_string())),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            status_of(&WasiMcpError::Execution("trap".to_string())),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+}
@@ -0,0 +1,179 @@
+//! Result caching for deterministic tools
+//!
+//! Results are keyed by tool name and the canonical JSON of the arguments, so repeated calls
+//! that only differ in key order hit the same entry.
+
+use crate::config::ResultCacheConfig;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Cached results of every tool that opted in, keyed by tool name
+#[derive(Debug, Default)]
+pub struct ResultCache {
+    tools: HashMap<String, ToolCache>,
+}
+
+#[derive(Debug)]
+struct ToolCache {
+    ttl: Duration,
+    max_entries: usize,
+    entries: HashMap<String, Entry>,
+    /// Insertion counter, ordering entries for eviction
+    next_sequence: u64,
+}
+
+#[derive(Debug)]
+struct Entry {
+    stored_at: Instant,
+    sequence: u64,
+    value: Value,
+}
+
+impl ResultCache {
+    /// A stored, unexpired result of `tool` called with `arguments`
+    pub fn get(&self, tool: &str, arguments: &HashMap<String, Value>) -> Option<Value> {
+        let cache = self.tools.get(tool)?;
+        let entry = cache.entries.get(&canonical_arguments(arguments))?;
+        (entry.stored_at.elapsed() < cache.ttl).then(|| entry.value.clone())
+    }
+
+    /// Store a result, evicting expired entries and then the oldest when the tool's cache is full
+    pub fn insert(
+        &mut self,
+        tool: &str,
+        arguments: &HashMap<String, Value>,
+        value: Value,
+        config: &ResultCacheConfig,
+    ) {
+        if config.max_entries == 0 {
+            return;
+        }
+        let cache = self
+            .tools
+            .entry(tool.to_string())
+            .or_insert_with(|| ToolCache {
+                ttl: Duration::from_secs(config.ttl_secs),
+                max_entries: config.max_entries,
+                entries: HashMap::new(),
+                next_sequence: 0,
+            });
+
+        let key = canonical_arguments(arguments);
+        if !cache.entries.contains_key(&key) && cache.entries.len() >= cache.max_entries {
+            let ttl = cache.ttl;
+            cache
+                .entries
+                .retain(|_, entry| entry.stored_at.elapsed() < ttl);
+            while cache.entries.len() >= cache.max_entries {
+                let Some(oldest) = cache
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.sequence)
+                    .map(|(key, _)| key.clone())
+                else {
+                    break;
+                };
+                cache.entries.remove(&oldest);
+            }
+        }
+        let entry = Entry {
+            stored_at: Instant::now(),
+            sequence: cache.next_sequence,
+            value,
+        };
+        cache.next_sequence += 1;
+        cache.entries.insert(key, entry);
+    }
+
+    /// Drop every cached result of `component`'s tools
+    pub fn invalidate_component(&mut self, component: &str) {
+        self.tools.retain(|tool, _| {
+            tool.split_once('.')
+                .is_none_or(|(tool_component, _)| tool_component != component)
+        });
+    }
+
+    /// Drop every cached result
+    pub fn clear(&mut self) {
+        self.tools.clear();
+    }
+}
+
+/// Serialize arguments with object keys sorted at every level
+pub(crate) fn canonical_arguments(arguments: &HashMap<String, Value>) -> String {
+    canonical_object(arguments.iter())
+}
+
+fn canonical_object<'a>(fields: impl Iterator<Item = (&'a String, &'a Value)>) -> String {
+    let mut fields: Vec<_> = fields.collect();
+    fields.sort_by_key(|(name, _)| *name);
+    let fields: Vec<String> = fields
+        .into_iter()
+        .map(|(name, value)| format!("{}:{}", Value::from(name.as_str()), canonical(value)))
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+fn canonical(value: &Value) -> String {
+    match value {
+        Value::Object(map) => canonical_object(map.iter()),
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(canonical).collect();
+            format!("[{}]", items.join(","))
+        }
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn args(value: Value) -> HashMap<String, Value> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_key_ignores_argument_order() {
+        let config = ResultCacheConfig::default();
+        let mut cache = ResultCache::default();
+        cache.insert(
+            "convert.run",
+            &args(json!({"a": 1, "b": {"x": 1, "y": [1, 2]}})),
+            json!("done"),
+            &config,
+        );
+
+        let reordered = args(json!({"b": {"y": [1, 2], "x": 1}, "a": 1}));
+        assert_eq!(cache.get("convert.run", &reordered), Some(json!("done")));
+        assert_eq!(cache.get("convert.run", &args(json!({"a": 2}))), None);
+        assert_eq!(cache.get("convert.other", &reordered), None);
+
+        cache.invalidate_component("convert");
+        assert_eq!(cache.get("convert.run", &reordered), None);
+    }
+
+    #[test]
+    fn test_expiry_and_eviction() {
+        let mut cache = ResultCache::default();
+        let expired = ResultCacheConfig {
+            ttl_secs: 0,
+            max_entries: 10,
+        };
+        cache.insert("a.f", &args(json!({})), json!(1), &expired);
+        assert_eq!(cache.get("a.f", &args(json!({}))), None);
+
+        let small = ResultCacheConfig {
+            ttl_secs: 60,
+            max_entries: 2,
+        };
+        for n in 0..3 {
+            cache.insert("b.f", &args(json!({"n": n})), json!(n), &small);
+        }
+        assert_eq!(cache.get("b.f", &args(json!({"n": 0}))), None);
+        assert_eq!(cache.get("b.f", &args(json!({"n": 1}))), Some(json!(1)));
+        assert_eq!(cache.get("b.f", &args(json!({"n": 2}))), Some(json!(2)));
+    }
+}
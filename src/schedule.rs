@@ -0,0 +1,374 @@
+//! Scheduled tool calls
+//!
+//! Entries of the `schedules` section call a tool whenever their cron expression matches,
+//! while the MCP server runs. Results are logged and optionally appended to a JSONL file.
+//! Reloading the configuration replaces the tasks; a call that is running finishes first.
+
+use crate::config::ScheduleConfig;
+use crate::error::{Result, WasiMcpError};
+use crate::mcp::WasmMcpServer;
+use crate::utils::unix_now;
+use std::collections::HashMap;
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Days searched for the next match before a schedule is considered impossible, covering
+/// expressions like `0 0 29 2 *` that only match in leap years
+const SEARCH_DAYS: i64 = 366 * 8;
+
+/// A parsed five-field cron expression, evaluated in UTC
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Whether the day-of-month and day-of-week fields were `*`; when both are restricted a
+    /// day matching either one fires
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl FromStr for CronSchedule {
+    type Err = WasiMcpError;
+
+    fn from_str(expression: &str) -> Result<Self> {
+        let expression = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            expression => expression,
+        };
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(WasiMcpError::InvalidArguments(format!(
+                "Cron expression '{expression}' must have five fields"
+            )));
+        };
+
+        // Sunday may be written as 7
+        let mut weekdays = parse_field(weekday, 0, 7)?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)?,
+            days: parse_field(day, 1, 31)?,
+            months: parse_field(month, 1, 12)?,
+            weekdays,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+}
+
+/// Parse a comma-separated list of `*`, `n`, `a-b`, each optionally with a `/step`, as a bitmask
+fn parse_field(field: &str, min: u64, max: u64) -> Result<u64> {
+    let invalid = || {
+        WasiMcpError::InvalidArguments(format!(
+            "Invalid cron field '{field}' (allowed: {min}-{max})"
+        ))
+    };
+    let number = |value: &str| {
+        value
+            .parse::<u64>()
+            .ok()
+            .filter(|n| (min..=max).contains(n))
+            .ok_or_else(invalid)
+    };
+
+    let mut mask = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<u64>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(invalid)?,
+            ),
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((start, end)) => (number(start)?, number(end)?),
+                // `n/step` runs from n to the end of the range
+                None if step > 1 => (number(range)?, max),
+                None => {
+                    let n = number(range)?;
+                    (n, n)
+                }
+            },
+        };
+        if start > end {
+            return Err(invalid());
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+impl CronSchedule {
+    /// First matching minute strictly after `unix_secs`, as seconds since the epoch
+    pub fn next_after(&self, unix_secs: u64) -> Option<u64> {
+        let mut minute = unix_secs as i64 / 60 + 1;
+        let last_day = minute / 1440 + SEARCH_DAYS;
+
+        while minute / 1440 <= last_day {
+            let day = minute.div_euclid(1440);
+            let (_, month, day_of_month) = civil_from_days(day);
+            let weekday = (day + 4).rem_euclid(7) as u32; // 1970-01-01 was a Thursday
+            if !self.matches_day(month, day_of_month, weekday) {
+                minute = (day + 1) * 1440;
+                continue;
+            }
+
+            let hour = (minute.rem_euclid(1440) / 60) as u32;
+            if self.hours & (1 << hour) == 0 {
+                minute = (minute / 60 + 1) * 60;
+                continue;
+            }
+            if self.minutes & (1 << minute.rem_euclid(60)) != 0 {
+                return Some(minute as u64 * 60);
+            }
+            minute += 1;
+        }
+        None
+    }
+
+    fn matches_day(&self, month: u32, day: u32, weekday: u32) -> bool {
+        if self.months & (1 << month) == 0 {
+            return false;
+        }
+        let day_matches = self.days & (1 << day) != 0;
+        let weekday_matches = self.weekdays & (1 << weekday) != 0;
+        match (self.any_day, self.any_weekday) {
+            (false, false) => day_matches || weekday_matches,
+            _ => day_matches && weekday_matches,
+        }
+    }
+}
+
+/// Convert days since 1970-01-01 to a (year, month, day) civil date
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Generation of a server's schedule tasks; tasks of an older generation stop before their
+/// next call
+#[derive(Debug, Clone, Default)]
+pub struct Schedules(Arc<watch::Sender<u64>>);
+
+/// Schedules whose cron expressions were parsed, ready to [`start`]
+pub struct Parsed(Vec<(String, CronSchedule, ScheduleConfig)>);
+
+/// Parse the cron expressions of `schedules`, so a typo fails startup or a reload instead of
+/// silently never firing
+pub fn parse_all(schedules: &HashMap<String, ScheduleConfig>) -> Result<Parsed> {
+    let mut parsed = Vec::with_capacity(schedules.len());
+    for (name, schedule) in schedules {
+        let cron: CronSchedule = schedule
+            .cron
+            .parse()
+            .map_err(|e| WasiMcpError::InvalidArguments(format!("Schedule '{name}': {e}")))?;
+        parsed.push((name.clone(), cron, schedule.clone()));
+    }
+    Ok(Parsed(parsed))
+}
+
+/// Start a task per schedule that calls its tool until the server drains or the schedules
+/// are started again
+pub fn start(server: &WasmMcpServer, schedules: Parsed) {
+    server
+        .schedules
+        .0
+        .send_modify(|generation| *generation += 1);
+    for (name, cron, schedule) in schedules.0 {
+        tracing::info!(schedule = %name, cron = %schedule.cron, tool = %schedule.tool, "Scheduled tool");
+        let replaced = server.schedules.0.subscribe();
+        tokio::spawn(run(server.clone(), replaced, name, cron, schedule));
+    }
+}
+
+/// Parse and [`start`] `schedules`
+pub fn spawn_all(
+    server: &WasmMcpServer,
+    schedules: &HashMap<String, ScheduleConfig>,
+) -> Result<()> {
+    start(server, parse_all(schedules)?);
+    Ok(())
+}
+
+async fn run(
+    server: WasmMcpServer,
+    mut replaced: watch::Receiver<u64>,
+    name: String,
+    cron: CronSchedule,
+    schedule: ScheduleConfig,
+) {
+    loop {
+        let now = unix_now();
+        let Some(next) = cron.next_after(now) else {
+            tracing::warn!(schedule = %name, "Cron expression never matches, stopping");
+            return;
+        };
+        tokio::select! {
+            () = tokio::time::sleep(Duration::from_secs(next - now)) => {}
+            () = server.drain.started() => return,
+            _ = replaced.changed() => return,
+        }
+
+        let Some(_call) = server.drain.start_call() else {
+            return;
+        };
+        let result = server
+            .executor
+            .lock()
+            .await
+            .execute_function(&schedule.tool, schedule.args.clone())
+            .await;
+        let record = match &result {
+            Ok(value) => {
+                tracing::info!(schedule = %name, tool = %schedule.tool, "Scheduled call finished");
+                serde_json::json!({"schedule": name, "tool": schedule.tool, "time": next, "result": value})
+            }
+            Err(e) => {
+                tracing::error!(schedule = %name, tool = %schedule.tool, "Scheduled call failed: {}", e);
+                serde_json::json!({"schedule": name, "tool": schedule.tool, "time": next, "error": e.to_string(), "code": e.code()})
+            }
+        };
+
+        if let Some(path) = &schedule.output
+            && let Err(e) = append_line(path, &record)
+        {
+            tracing::warn!(schedule = %name, "Failed to store result in {:?}: {}", path, e);
+        }
+    }
+}
+
+fn append_line(path: &std::path::Path, record: &serde_json::Value) -> std::io::Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{record}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JAN_1_2024: u64 = 1_704_067_200; // Monday
+
+    fn next(expression: &str, after: u64) -> Option<u64> {
+        expression
+            .parse::<CronSchedule>()
+            .unwrap()
+            .next_after(after)
+    }
+
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(JAN_1_2024 as i64 / 86_400), (2024, 1, 1));
+        assert_eq!(civil_from_days(1_835_395_200 / 86_400), (2028, 2, 29));
+    }
+
+    #[test]
+    fn test_next_after() {
+        assert_eq!(next("*/15 * * * *", JAN_1_2024), Some(JAN_1_2024 + 15 * 60));
+        assert_eq!(next("* * * * *", JAN_1_2024 + 30), Some(JAN_1_2024 + 60));
+        // Saturday 2024-01-06 to Monday 2024-01-08 09:00
+        assert_eq!(next("0 9 * * 1-5", 1_704_499_200), Some(1_704_704_400));
+        assert_eq!(next("@monthly", 1_706_659_200), Some(1_706_745_600));
+        // Only leap years have a 29th of February
+        assert_eq!(next("0 0 29 2 *", 1_709_251_200), Some(1_835_395_200));
+        // Sunday written as 7
+        assert_eq!(next("0 0 * * 7", JAN_1_2024), Some(JAN_1_2024 + 6 * 86_400));
+        assert_eq!(next("0 0 31 2 *", JAN_1_2024), None);
+    }
+
+    #[test]
+    fn test_day_or_weekday() {
+        // The 15th, or any Monday: Monday 2024-01-08 comes first
+        assert_eq!(
+            next("0 0 15 * 1", JAN_1_2024),
+            Some(JAN_1_2024 + 7 * 86_400)
+        );
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        for expression in [
+            "* * * *",
+            "60 * * * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "a * * * *",
+        ] {
+            assert!(expression.parse::<CronSchedule>().is_err(), "{expression}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_start_replaces_tasks() {
+        let config = crate::config::Config::default();
+        let executor = crate::executor::WasmExecutor::new(
+            crate::wasm::WasmContext::new().unwrap(),
+            config.clone(),
+        )
+        .unwrap();
+        let server = WasmMcpServer::new(executor, config);
+        let schedules = HashMap::from([(
+            "nightly".to_string(),
+            ScheduleConfig {
+                cron: "@daily".to_string(),
+                tool: "reports.build".to_string(),
+                args: HashMap::new(),
+                output: None,
+            },
+        )]);
+
+        spawn_all(&server, &schedules).unwrap();
+        assert_eq!(server.schedules.0.receiver_count(), 1);
+
+        // Starting again stops the sleeping tasks of the previous schedules
+        spawn_all(&server, &HashMap::new()).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while server.schedules.0.receiver_count() > 0 {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .expect("replaced schedule tasks stop");
+
+        let invalid = HashMap::from([(
+            "broken".to_string(),
+            ScheduleConfig {
+                cron: "61 * * * *".to_string(),
+                ..schedules["nightly"].clone()
+            },
+        )]);
+        assert!(parse_all(&invalid).is_err());
+    }
+}
@@ -0,0 +1,242 @@
+use crate::WasiMcpError;
+use crate::config::{ComponentConfig, RegistryConfig, SecretSource};
+use crate::error::Result;
+use std::collections::HashMap;
+use std::process::{Command, Stdio};
+
+/// Prefix of secret references in environment values
+const SECRET_PREFIX: &str = "${secret:";
+
+/// Keyring service under which wasmic secrets are stored
+const KEYRING_SERVICE: &str = "wasmic";
+
+/// Resolve secret-backed environment variables of a component in place
+///
+/// Values from `env_from_keyring` and `env_from_file` are merged into `env` (explicit `env`
+/// entries win, then keyring, then files), and every `${secret:NAME}` reference is replaced
+/// by the value of the named secret.
+pub fn resolve_component_env(
+    component: &mut ComponentConfig,
+    secrets: &HashMap<String, SecretSource>,
+) -> Result<()> {
+    for (key, entry) in &component.env_from_keyring {
+        if !component.env.contains_key(key) {
+            component.env.insert(key.clone(), read_keyring(entry)?);
+        }
+    }
+
+    for path in &component.env_from_file {
+        let entries = dotenvy::from_path_iter(path).map_err(|e| {
+            WasiMcpError::InvalidArguments(format!("Failed to read env file '{path}': {e}"))
+        })?;
+        for entry in entries {
+            let (key, value) = entry.map_err(|e| {
+                WasiMcpError::InvalidArguments(format!("Invalid env file '{path}': {e}"))
+            })?;
+            component.env.entry(key).or_insert(value);
+        }
+    }
+
+    for value in component.env.values_mut() {
+        if value.contains(SECRET_PREFIX) {
+            *value = substitute_secrets(value, secrets)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolve `${secret:NAME}` references in registry passwords
+pub fn resolve_registry_credentials(
+    registries: &HashMap<String, RegistryConfig>,
+    secrets: &HashMap<String, SecretSource>,
+) -> Result<HashMap<String, RegistryConfig>> {
+    registries
+        .iter()
+        .map(|(registry, credentials)| {
+            let password = substitute_secrets(&credentials.password, secrets)?;
+            let credentials = RegistryConfig {
+                username: credentials.username.clone(),
+                password,
+            };
+            Ok((registry.clone(), credentials))
+        })
+        .collect()
+}
+
+/// Replace every `${secret:NAME}` reference in `value`
+pub fn substitute_secrets(value: &str, secrets: &HashMap<String, SecretSource>) -> Result<String> {
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(start) = rest.find(SECRET_PREFIX) {
+        result.push_str(&rest[..start]);
+        let reference = &rest[start + SECRET_PREFIX.len()..];
+        let end = reference.find('}').ok_or_else(|| {
+            WasiMcpError::InvalidArguments(format!("Unterminated secret reference in '{value}'"))
+        })?;
+
+        result.push_str(&resolve_secret(&reference[..end], secrets)?);
+        rest = &reference[end + 1..];
+    }
+    result.push_str(rest);
+
+    Ok(result)
+}
+
+/// Read the value of a named secret from its configured source
+fn resolve_secret(name: &str, secrets: &HashMap<String, SecretSource>) -> Result<String> {
+    let source = secrets.get(name).ok_or_else(|| {
+        WasiMcpError::InvalidArguments(format!("Secret '{name}' is not defined in 'secrets'"))
+    })?;
+
+    tracing::debug!("Resolving secret '{}'", name);
+    match source {
+        SecretSource::Keyring(entry) => read_keyring(entry),
+        SecretSource::File(path) => {
+            let content = std::fs::read_to_string(path).map_err(|e| {
+                WasiMcpError::InvalidArguments(format!(
+                    "Failed to read secret '{name}' from '{path}': {e}"
+                ))
+            })?;
+            Ok(content.trim_end_matches(['\r', '\n']).to_string())
+        }
+        SecretSource::Command(argv) => run_command(argv),
+        SecretSource::Env(var) => std::env::var(var).map_err(|_| {
+            WasiMcpError::InvalidArguments(format!(
+                "Secret '{name}' refers to unset environment variable '{var}'"
+            ))
+        }),
+    }
+}
+
+/// Read an entry from the OS keyring using the platform's command line tool
+fn read_keyring(entry: &str) -> Result<String> {
+    #[cfg(target_os = "macos")]
+    let argv = [
+        "security",
+        "find-generic-password",
+        "-s",
+        KEYRING_SERVICE,
+        "-a",
+        entry,
+        "-w",
+    ];
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let argv = [
+        "secret-tool",
+        "lookup",
+        "service",
+        KEYRING_SERVICE,
+        "account",
+        entry,
+    ];
+    #[cfg(not(unix))]
+    {
+        let _ = (entry, KEYRING_SERVICE);
+        return Err(WasiMcpError::InvalidArguments(
+            "OS keyring secrets are not supported on this platform".to_string(),
+        ));
+    }
+
+    #[cfg(unix)]
+    run_command(&argv.map(str::to_string))
+}
+
+/// Run a command and return its trimmed stdout
+fn run_command(argv: &[String]) -> Result<String> {
+    let (program, args) = argv.split_first().ok_or_else(|| {
+        WasiMcpError::InvalidArguments("Secret command must not be empty".to_string())
+    })?;
+
+    let output = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| {
+            WasiMcpError::InvalidArguments(format!("Failed to run secret command '{program}': {e}"))
+        })?;
+    if !output.status.success() {
+        return Err(WasiMcpError::InvalidArguments(format!(
+            "Secret command '{program}' failed with {}",
+            output.status
+        )));
+    }
+
+    let stdout = String::from_utf8(output.stdout).map_err(|_| {
+        WasiMcpError::InvalidArguments(format!("Secret command '{program}' printed non-UTF-8"))
+    })?;
+    Ok(stdout.trim_end_matches(['\r', '\n']).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_secret_references() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let secret_path = temp_dir.path().join("token");
+        std::fs::write(&secret_path, "s3cr3t\n").unwrap();
+
+        let secrets = HashMap::from([(
+            "token".to_string(),
+            SecretSource::File(secret_path.to_string_lossy().to_string()),
+        )]);
+        let mut component = ComponentConfig {
+            env: HashMap::from([
+                ("API_KEY".to_string(), "${secret:token}".to_string()),
+                ("AUTH".to_string(), "Bearer ${secret:token}".to_string()),
+                ("PLAIN".to_string(), "value".to_string()),
+            ]),
+            ..Default::default()
+        };
+
+        resolve_component_env(&mut component, &secrets).unwrap();
+        assert_eq!(component.env["API_KEY"], "s3cr3t");
+        assert_eq!(component.env["AUTH"], "Bearer s3cr3t");
+        assert_eq!(component.env["PLAIN"], "value");
+    }
+
+    #[test]
+    fn test_undefined_secret() {
+        let mut component = ComponentConfig {
+            env: HashMap::from([("API_KEY".to_string(), "${secret:missing}".to_string())]),
+            ..Default::default()
+        };
+        assert!(resolve_component_env(&mut component, &HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_env_from_file() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let env_path = temp_dir.path().join("component.env");
+        std::fs::write(&env_path, "TOKEN=from-file\nREGION=eu\n").unwrap();
+
+        let mut component = ComponentConfig {
+            env: HashMap::from([("REGION".to_string(), "us".to_string())]),
+            env_from_file: vec![env_path.to_string_lossy().to_string()],
+            ..Default::default()
+        };
+
+        resolve_component_env(&mut component, &HashMap::new()).unwrap();
+        assert_eq!(component.env["TOKEN"], "from-file");
+        // Explicit env entries take precedence over env files
+        assert_eq!(component.env["REGION"], "us");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command_secret() {
+        let secrets = HashMap::from([(
+            "greeting".to_string(),
+            SecretSource::Command(vec!["echo".to_string(), "hello".to_string()]),
+        )]);
+        assert_eq!(
+            substitute_secrets("${secret:greeting}", &secrets).unwrap(),
+            "hello"
+        );
+    }
+}
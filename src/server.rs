@@ -1,19 +1,32 @@
+use crate::cache::{CacheIndex, ComponentCache};
+use crate::cli::{CacheCommand, ExportFormat, OutputFormat};
+use crate::command::RUN_TOOL;
+use crate::config::CollisionPolicy;
 use crate::error::Result;
-use crate::executor::WasmExecutor;
-use crate::mcp::WasmMcpServer;
-use crate::oci::OciManager;
+use crate::executor::{WasmExecutor, component_tools};
+use crate::inspector::ComponentInspector;
+use crate::mcp::{StagedUpgrade, WasmMcpServer};
+use crate::naming::{self, ToolNames};
+use crate::oci::{ImageMetadata, OciManager};
+use crate::replay::{Recording, Tape};
+use crate::secrets;
+use crate::testing::{TestReport, TestResult};
+use crate::utils::transform::ConversionOptions;
 use crate::{ComponentConfig, WasiMcpError};
 use crate::{config::Config, wasm::WasmContext};
-use std::collections::HashMap;
+use rmcp::model::Tool;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tracing::{debug, info, instrument, trace};
 
 /// MCP transport type
 #[derive(Debug, Clone)]
 pub enum McpTransport {
-    /// HTTP transport
-    Http { host: String, port: u16 },
+    /// HTTP transport, optionally with the REST gateway on the same listener
+    Http { host: String, port: u16, rest: bool },
 }
 
 /// Server mode configuration
@@ -23,18 +36,142 @@ pub enum ServerMode {
         config: Config,
         transport: McpTransport,
         context: WasmContext,
+        /// Hot-reload components whose local files change
+        watch: bool,
+        /// Refresh mutable OCI tags before loading components
+        refresh: bool,
+        /// Configuration file, re-read on SIGHUP
+        config_path: PathBuf,
+        /// How long running tool calls may take to finish on shutdown
+        grace_period: Duration,
+        /// Address of the optional gRPC interface
+        grpc: Option<SocketAddr>,
     },
     /// Direct function call
     Call {
         config: Config,
         function: String,
         args: String,
+        output: OutputFormat,
         context: WasmContext,
+        /// Only report what would be called
+        dry_run: bool,
+    },
+    /// Run calls read as JSON lines from stdin
+    Batch {
+        config: Config,
+        context: WasmContext,
+        /// Number of executors running calls side by side
+        concurrency: usize,
+    },
+    /// Run a component once
+    Run {
+        config: Config,
+        context: WasmContext,
+        /// Configured component name, local path or OCI reference
+        component: String,
+        /// Export called instead of `wasi:cli/run`
+        export: Option<String>,
+        args: String,
+        /// File passed on stdin, `-` for wasmic's stdin
+        stdin: Option<PathBuf>,
+        output: OutputFormat,
+        argv: Vec<String>,
+    },
+    /// Measure the latency of repeated calls to a function
+    Bench {
+        config: Config,
+        context: WasmContext,
+        function: String,
+        args: String,
+        iterations: usize,
+        warmup: usize,
+        json: bool,
+    },
+    /// Print the tools as function-calling schemas
+    Export {
+        config: Config,
+        context: WasmContext,
+        format: ExportFormat,
+        profile: Option<String>,
+    },
+    /// Print an OpenAPI document for the REST gateway
+    Openapi {
+        config: Config,
+        context: WasmContext,
+        profile: Option<String>,
+    },
+    /// Print recorded calls
+    History {
+        config: Config,
+        query: crate::history::HistoryQuery,
+        json: bool,
+    },
+    /// Re-execute recorded calls
+    Replay {
+        config: Config,
+        context: WasmContext,
+        recordings: Vec<PathBuf>,
+        /// Build of the recorded component replayed instead of the configured one
+        candidate: Option<String>,
+        json: bool,
+    },
+    /// Run the configured test cases
+    Test {
+        config: Config,
+        context: WasmContext,
+        file: Option<PathBuf>,
+        filter: Option<String>,
+        json: bool,
     },
     /// List available functions
     List {
         config: Config,
         context: WasmContext,
+        /// Print the tool array as JSON
+        json: bool,
+        /// Only list the tools of this component
+        component: Option<String>,
+    },
+    /// Print the contract of one tool
+    Explain {
+        config: Config,
+        context: WasmContext,
+        tool: String,
+        json: bool,
+    },
+    /// Print the imports and exports of a component
+    Inspect {
+        config: Config,
+        context: WasmContext,
+        /// Local path or OCI reference
+        component: String,
+        json: bool,
+    },
+    /// Download every OCI component into the cache
+    Pull { config: Config },
+    /// List the tags of an OCI repository
+    Tags {
+        config: Config,
+        repository: String,
+        limit: usize,
+    },
+    /// Refresh mutable OCI tags in the cache
+    Update { config: Config },
+    /// Compile all components into the compiled-component cache
+    Precompile {
+        config: Config,
+        context: WasmContext,
+    },
+    /// Inspect or clean up the component cache
+    Cache {
+        config: Config,
+        command: CacheCommand,
+    },
+    /// Check the configuration and the names of its tools
+    Validate {
+        config: Config,
+        context: WasmContext,
     },
 }
 
@@ -48,14 +185,127 @@ impl ServerManager {
                 config,
                 transport,
                 context,
-            } => Self::run_mcp_server(config, transport, context).await,
+                watch,
+                refresh,
+                config_path,
+                grace_period,
+                grpc,
+            } => {
+                if refresh {
+                    Self::update(config.clone()).await?;
+                }
+                Self::run_mcp_server(
+                    config,
+                    transport,
+                    context,
+                    watch,
+                    config_path,
+                    grace_period,
+                    grpc,
+                )
+                .await
+            }
             ServerMode::Call {
                 config,
                 function,
                 args,
+                output,
+                context,
+                dry_run,
+            } => {
+                Self::execute_function_call(config, &function, args, output, context, dry_run).await
+            }
+            ServerMode::Batch {
+                config,
                 context,
-            } => Self::execute_function_call(config, &function, args, context).await,
-            ServerMode::List { config, context } => Self::list_functions(config, context).await,
+                concurrency,
+            } => Self::execute_batch(config, context, concurrency).await,
+            ServerMode::Run {
+                config,
+                context,
+                component,
+                export,
+                args,
+                stdin,
+                output,
+                argv,
+            } => match export {
+                Some(export) => {
+                    let (name, config) = Self::run_config(config, &component);
+                    let function = format!("{name}.{export}");
+                    Self::execute_function_call(config, &function, args, output, context).await
+                }
+                None => {
+                    Self::run_command(config, context, &component, stdin.as_deref(), argv).await
+                }
+            },
+            ServerMode::Bench {
+                config,
+                context,
+                function,
+                args,
+                iterations,
+                warmup,
+                json,
+            } => Self::bench(config, context, &function, &args, iterations, warmup, json).await,
+            ServerMode::Export {
+                config,
+                context,
+                format,
+                profile,
+            } => Self::export(config, context, format, profile.as_deref()).await,
+            ServerMode::Openapi {
+                config,
+                context,
+                profile,
+            } => Self::openapi(config, context, profile.as_deref()).await,
+            ServerMode::History {
+                config,
+                query,
+                json,
+            } => Self::history(config, &query, json),
+            ServerMode::Replay {
+                config,
+                context,
+                recordings,
+                candidate,
+                json,
+            } => Self::replay(config, context, &recordings, candidate.as_deref(), json).await,
+            ServerMode::Test {
+                config,
+                context,
+                file,
+                filter,
+                json,
+            } => Self::run_tests(config, context, file.as_deref(), filter.as_deref(), json).await,
+            ServerMode::List {
+                config,
+                context,
+                json,
+                component,
+            } => Self::list_functions(config, context, json, component.as_deref()).await,
+            ServerMode::Explain {
+                config,
+                context,
+                tool,
+                json,
+            } => Self::explain(config, context, &tool, json).await,
+            ServerMode::Inspect {
+                config,
+                context,
+                component,
+                json,
+            } => Self::inspect(config, context, &component, json).await,
+            ServerMode::Pull { config } => Self::pull(config).await,
+            ServerMode::Tags {
+                config,
+                repository,
+                limit,
+            } => Self::list_tags(config, &repository, limit).await,
+            ServerMode::Update { config } => Self::update(config).await,
+            ServerMode::Precompile { config, context } => Self::precompile(config, context).await,
+            ServerMode::Cache { config, command } => Self::cache(config, command),
+            ServerMode::Validate { config, context } => Self::validate(config, context).await,
         }
     }
 
@@ -84,6 +334,22 @@ impl ServerManager {
         Ok(executor)
     }
 
+    /// OCI manager with the registry credentials, proxy and verification policy of a
+    /// configuration
+    fn oci_manager(config: &Config) -> Result<OciManager> {
+        let registries =
+            secrets::resolve_registry_credentials(&config.registries, &config.secrets)?;
+        let mut proxy = config.proxy.clone();
+        if let Some(password) = proxy.as_mut().and_then(|proxy| proxy.password.as_mut()) {
+            *password = secrets::substitute_secrets(password, &config.secrets)?;
+        }
+
+        Ok(OciManager::new()?
+            .with_registries(registries)
+            .with_proxy(proxy.as_ref())
+            .with_verification(config.verification.clone()))
+    }
+
     /// Load all components from a configuration into an executor (parallel and async)
     #[instrument(level = "debug", skip(config), fields(components, duratio_ms))]
     async fn load(config: &Config) -> Result<Vec<(String, ComponentConfig)>> {
@@ -93,17 +359,27 @@ impl ServerManager {
             ));
         }
 
-        let oci_manager = Arc::new(OciManager::new()?);
+        let oci_manager = Arc::new(Self::oci_manager(config)?);
+        let secrets = Arc::new(config.secrets.clone());
         // Prepare component loading tasks for parallel execution
         let load_tasks: Vec<_> = config
             .components
             .iter()
+            .filter(|(name, component_config)| {
+                if !component_config.is_enabled() {
+                    info!("Skipping disabled component '{}'", name);
+                }
+                component_config.is_enabled()
+            })
             .map(|(name, component_config)| {
                 let name = name.clone();
                 let mut component_config = component_config.clone();
                 let oci_manager = oci_manager.clone();
+                let secrets = secrets.clone();
 
                 async move {
+                    secrets::resolve_component_env(&mut component_config, &secrets)?;
+
                     // Resolve the component reference (handle both local and OCI)
                     let resolved_path = oci_manager
                         .resolve_component_reference(
@@ -112,6 +388,13 @@ impl ServerManager {
                         )
                         .await?;
                     component_config.path = Some(resolved_path.to_string_lossy().to_string());
+
+                    for plug in &mut component_config.plugs {
+                        let resolved_path = oci_manager
+                            .resolve_component_reference(plug.path.as_deref(), plug.oci.as_deref())
+                            .await?;
+                        plug.path = Some(resolved_path.to_string_lossy().to_string());
+                    }
                     Ok::<(String, ComponentConfig), WasiMcpError>((name, component_config))
                 }
             })
@@ -139,27 +422,320 @@ impl ServerManager {
         config: Config,
         transport: McpTransport,
         context: WasmContext,
+        watch: bool,
+        config_path: PathBuf,
+        grace_period: Duration,
+        grpc: Option<SocketAddr>,
     ) -> Result<()> {
+        let config_updates = config.updates.clone();
+        let server = Self::build_server(config, context).await?;
+
+        if watch {
+            tokio::spawn(crate::watch::watch_components(server.clone()));
+        }
+        if let Some(updates) = &config_updates {
+            tokio::spawn(Self::update_periodically(
+                server.clone(),
+                Duration::from_secs(updates.interval_secs.max(1)),
+            ));
+        }
+        #[cfg(unix)]
+        tokio::spawn(Self::reload_on_sighup(server.clone(), config_path));
+        #[cfg(not(unix))]
+        let _ = config_path;
+
+        Self::serve(server, transport, grace_period, grpc).await
+    }
+
+    /// Load the components and profiles of a configuration into a server
+    pub(crate) async fn build_server(
+        config: Config,
+        context: WasmContext,
+    ) -> Result<WasmMcpServer> {
+        let mut config = config;
+        Self::resolve_webhook_secrets(&mut config)?;
+
+        // Every profile gets its own instances, isolated from other tenants
+        let mut profiles = HashMap::with_capacity(config.profiles.len());
+        for name in config.profiles.keys() {
+            let profile = Self::build_profile(name, config.profile(name)?, context.fork()?).await?;
+            profiles.insert(name.clone(), profile);
+        }
+
+        let mut executor = Self::init(config.clone(), context).await?;
+        if let Some(history) = &config.history {
+            let path = crate::history::database_path(history)?;
+            info!("Recording call history in {:?}", path);
+            executor.set_history(crate::history::HistoryRecorder::open(&path)?);
+        }
+        if let Some(recording) = &config.recording {
+            let dir = crate::replay::directory(recording)?;
+            info!("Recording calls for replay in {:?}", dir);
+            executor.set_recording(dir);
+        }
+
+        // Evict after loading so the components in use count as recently accessed
+        if let Some(policy) = &config.cache {
+            match CacheIndex::new().and_then(|index| index.prune(policy)) {
+                Ok(evicted) if !evicted.is_empty() => {
+                    info!("Evicted {} stale cached artifacts", evicted.len())
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Failed to prune the component cache: {}", e),
+            }
+        }
+
+        for profile in profiles.values() {
+            executor.attach_profile(&mut *profile.executor.lock().await);
+        }
+        Ok(WasmMcpServer::new(executor, config).with_profiles(profiles))
+    }
+
+    /// Load the components of a profile into a server of its own
+    async fn build_profile(
+        name: &str,
+        config: Config,
+        context: WasmContext,
+    ) -> Result<WasmMcpServer> {
         let executor = Self::init(config.clone(), context).await?;
-        let server = WasmMcpServer::new(executor, config);
+        info!(profile = %name, "Loaded profile");
+        Ok(WasmMcpServer::new(executor, config))
+    }
+
+    /// Serve a loaded server on `transport`, with its schedules and the optional gRPC
+    /// interface, until shutdown
+    pub(crate) async fn serve(
+        server: WasmMcpServer,
+        transport: McpTransport,
+        grace_period: Duration,
+        grpc: Option<SocketAddr>,
+    ) -> Result<()> {
+        let schedules = server.config.read().await.schedules.clone();
+        crate::schedule::spawn_all(&server, &schedules)?;
+        tokio::spawn(server.clone().forward_tool_list_changes());
+        if let Some(address) = grpc {
+            Self::spawn_grpc(server.clone(), address)?;
+        }
 
         match transport {
-            McpTransport::Http { host, port } => {
+            McpTransport::Http { host, port, rest } => {
                 tracing::info!(host, port, "Starting MCP HTTP server",);
-                WasmMcpServer::serve_http(server, host, port).await?;
+                WasmMcpServer::serve_http(server.clone(), host, port, grace_period, rest).await?;
+            }
+        }
+        if tokio::time::timeout(
+            crate::shutdown::SHUTDOWN_FUNCTIONS_TIMEOUT,
+            server.shutdown_components(),
+        )
+        .await
+        .is_err()
+        {
+            tracing::warn!(
+                "Exiting without running all shutdown functions after {:?}",
+                crate::shutdown::SHUTDOWN_FUNCTIONS_TIMEOUT
+            );
+        }
+        Ok(())
+    }
+
+    /// Replace `${secret:NAME}` references in webhook secrets once, instead of on every request
+    fn resolve_webhook_secrets(config: &mut Config) -> Result<()> {
+        for webhook in config.webhooks.values_mut() {
+            if let Some(secret) = &webhook.secret {
+                webhook.secret = Some(secrets::substitute_secrets(secret, &config.secrets)?);
             }
         }
         Ok(())
     }
 
+    /// Serve the gRPC interface next to the HTTP transport
+    #[cfg(feature = "grpc")]
+    fn spawn_grpc(server: WasmMcpServer, address: SocketAddr) -> Result<()> {
+        tokio::spawn(async move {
+            if let Err(e) = crate::grpc::serve(server, address).await {
+                tracing::error!("gRPC server failed: {}", e);
+            }
+        });
+        Ok(())
+    }
+
+    #[cfg(not(feature = "grpc"))]
+    fn spawn_grpc(_server: WasmMcpServer, _address: SocketAddr) -> Result<()> {
+        Err(WasiMcpError::InvalidArguments(
+            "--grpc needs a wasmic built with `--features grpc`".to_string(),
+        ))
+    }
+
+    /// Reload the configuration file whenever the process receives SIGHUP
+    #[cfg(unix)]
+    async fn reload_on_sighup(server: WasmMcpServer, config_path: PathBuf) {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let mut hangup = match signal(SignalKind::hangup()) {
+            Ok(hangup) => hangup,
+            Err(e) => {
+                tracing::warn!("Failed to install SIGHUP handler: {}", e);
+                return;
+            }
+        };
+
+        while hangup.recv().await.is_some() {
+            info!(
+                "Received SIGHUP, reloading configuration from {:?}",
+                config_path
+            );
+            match Self::reload_config(&server, &config_path).await {
+                Ok(()) => info!("Configuration reloaded"),
+                Err(e) => tracing::error!("Failed to reload configuration: {}", e),
+            }
+        }
+    }
+
+    /// Apply a changed configuration to a running server and its profiles
+    ///
+    /// Components that were removed are dropped, new or changed ones are (re)instantiated, and
+    /// unchanged components keep their instances. Profiles are reloaded the same way, added
+    /// ones are loaded and removed ones stop being served. The HTTP listener and sessions are
+    /// untouched.
+    #[instrument(level = "debug", skip(server), fields(duration_ms))]
+    pub async fn reload_config(server: &WasmMcpServer, config_path: &Path) -> Result<()> {
+        let start_time = Instant::now();
+        let mut new_config = Config::from_file(&config_path.to_path_buf())?;
+        Self::resolve_webhook_secrets(&mut new_config)?;
+        let schedules_changed = server.config.read().await.schedules != new_config.schedules;
+        let schedules = crate::schedule::parse_all(&new_config.schedules)?;
+
+        let mut profiles = HashMap::with_capacity(new_config.profiles.len());
+        for name in new_config.profiles.keys() {
+            let profile_config = new_config.profile(name)?;
+            let profile = match server.profile(name) {
+                Some(profile) => {
+                    Self::apply_config(&profile, profile_config).await?;
+                    profile
+                }
+                None => {
+                    let context = server.executor.lock().await.fork_context()?;
+                    let profile = Self::build_profile(name, profile_config, context).await?;
+                    server
+                        .executor
+                        .lock()
+                        .await
+                        .attach_profile(&mut *profile.executor.lock().await);
+                    profile
+                }
+            };
+            profiles.insert(name.clone(), profile);
+        }
+
+        Self::apply_config(server, new_config).await?;
+        for removed in server.set_profiles(profiles) {
+            removed.shutdown_components().await;
+        }
+        if schedules_changed {
+            crate::schedule::start(server, schedules);
+        }
+        server.rewatch_volumes().await;
+
+        tracing::Span::current().record("duration_ms", start_time.elapsed().as_millis());
+        Ok(())
+    }
+
+    /// Apply a changed configuration to the components of one server
+    #[instrument(level = "debug", skip_all, fields(added, removed))]
+    async fn apply_config(server: &WasmMcpServer, new_config: Config) -> Result<()> {
+        let old_config = server.config.read().await.clone();
+        let old_prompts = server.executor.lock().await.component_prompts();
+
+        let removed: Vec<String> = old_config
+            .components
+            .keys()
+            .filter(|name| {
+                new_config
+                    .components
+                    .get(*name)
+                    .is_none_or(|component| !component.is_enabled())
+            })
+            .cloned()
+            .collect();
+
+        let mut changed = new_config.clone();
+        changed
+            .components
+            .retain(|name, component| old_config.components.get(name) != Some(component));
+
+        // Resolve (and pull) everything before touching the running executor
+        let resolved = if changed.components.is_empty() {
+            Vec::new()
+        } else {
+            Self::load(&changed).await?
+        };
+
+        tracing::Span::current().record("added", resolved.len());
+        tracing::Span::current().record("removed", removed.len());
+
+        // Load and check the new versions while the running ones keep serving calls
+        let mut staged = Vec::with_capacity(resolved.len());
+        for (name, component_config) in resolved {
+            info!("Loading component '{}'", name);
+            let staging = server.executor.lock().await.staging()?;
+            let component = staging.stage_component(&name, component_config).await?;
+            staged.push((name, component));
+        }
+
+        let mut prompts_changed = false;
+        {
+            let mut executor = server.executor.lock().await;
+            executor
+                .reload(new_config.clone(), &removed, staged)
+                .await?;
+            prompts_changed |= executor.component_prompts() != old_prompts;
+        }
+
+        prompts_changed |= old_config.prompts != new_config.prompts;
+        *server.config.write().await = new_config;
+
+        server.notify_tool_list_changed().await;
+        if prompts_changed {
+            server.notify_prompt_list_changed().await;
+        }
+        Ok(())
+    }
+
+    fn print_dry_run(
+        executor: &mut WasmExecutor,
+        function: &str,
+        arguments: HashMap<String, serde_json::Value>,
+        output: OutputFormat,
+    ) -> Result<()> {
+        match executor.dry_run(function, arguments) {
+            Ok(dry_run) => {
+                match output {
+                    OutputFormat::Json | OutputFormat::Yaml => {
+                        println!("{}", output.render(&serde_json::to_value(&dry_run)?)?);
+                    }
+                    OutputFormat::Pretty | OutputFormat::Raw => print!("{dry_run}"),
+                }
+                Ok(())
+            }
+            Err(e) => {
+                if output == OutputFormat::Json {
+                    println!("{}", e.to_json());
+                }
+                Err(e)
+            }
+        }
+    }
+
     #[instrument(level = "debug", skip(context, config), fields(function_name, args))]
     async fn execute_function_call(
         config: Config,
         function: &str,
         args: String,
+        output: OutputFormat,
         context: WasmContext,
+        dry_run: bool,
     ) -> Result<()> {
-        tracing::info!(function, args, "Executing function");
+        tracing::info!(function, args, dry_run, "Executing function");
 
         // Parse arguments as named arguments (JSON object)
         let arguments: HashMap<String, serde_json::Value> = serde_json::from_str(&args)
@@ -183,42 +759,960 @@ impl ServerManager {
         let mut config = config.clone();
         config.components.retain(|k, _| k == component_name);
         let mut executor = Self::init(config, context).await?;
+        if dry_run {
+            return Self::print_dry_run(&mut executor, function, arguments, output);
+        }
         let result = executor.execute_function(function, arguments).await;
 
         match result {
             Ok(result) => {
-                let output = serde_json::to_string_pretty(&result).map_err(|e| {
-                    tracing::error!("Failed to serialize result: {}", e);
-                    WasiMcpError::Json(e)
+                let rendered = output.render(&result).inspect_err(|e| {
+                    tracing::error!("Failed to render result: {}", e);
                 })?;
 
-                trace!("{output}",);
+                trace!("{rendered}",);
+                println!("{rendered}");
                 debug!("Function execution completed successfully");
                 Ok(())
             }
             Err(e) => {
                 tracing::error!(error = %e, "Error executing function");
+                if output == OutputFormat::Json {
+                    println!("{}", e.to_json());
+                }
                 return Err(e);
             }
         }
     }
 
+    /// A configuration holding only the component to run, named after the reference when it
+    /// isn't a configured component
+    fn run_config(config: Config, component: &str) -> (String, Config) {
+        let (name, component_config) = match config.components.get(component) {
+            Some(component_config) => (component.to_string(), component_config.clone()),
+            None if Path::new(component).exists() => (
+                run_component_name(component),
+                ComponentConfig {
+                    path: Some(component.to_string()),
+                    ..ComponentConfig::default()
+                },
+            ),
+            None => (
+                run_component_name(component),
+                ComponentConfig {
+                    oci: Some(component.to_string()),
+                    ..ComponentConfig::default()
+                },
+            ),
+        };
+        let mut config = config;
+        config.components = HashMap::from([(name.clone(), component_config)]);
+        (name, config)
+    }
+
+    /// Run a command component's `wasi:cli/run` with `argv` and the contents of `stdin`,
+    /// forwarding its output and failing with its exit code
+    async fn run_command(
+        config: Config,
+        context: WasmContext,
+        component: &str,
+        stdin: Option<&Path>,
+        argv: Vec<String>,
+    ) -> Result<()> {
+        let stdin = match stdin {
+            Some(path) if path == Path::new("-") => std::io::read_to_string(std::io::stdin())?,
+            Some(path) => std::fs::read_to_string(path)?,
+            None => String::new(),
+        };
+        let (name, config) = Self::run_config(config, component);
+        let mut executor = Self::init(config, context).await?;
+        let arguments = HashMap::from([
+            ("args".to_string(), serde_json::json!(argv)),
+            ("stdin".to_string(), serde_json::json!(stdin)),
+        ]);
+        let result = match executor
+            .execute_function(&format!("{name}.{RUN_TOOL}"), arguments)
+            .await
+        {
+            Err(WasiMcpError::FunctionNotFound(_)) => {
+                return Err(WasiMcpError::InvalidArguments(format!(
+                    "Component '{component}' doesn't export wasi:cli/run; call one of its functions with --export"
+                )));
+            }
+            result => result?,
+        };
+
+        print!("{}", result["stdout"].as_str().unwrap_or_default());
+        eprint!("{}", result["stderr"].as_str().unwrap_or_default());
+        match result["exit_code"].as_i64().unwrap_or_default() {
+            0 => Ok(()),
+            code => Err(WasiMcpError::Exit(code as i32)),
+        }
+    }
+
+    /// Execute `{"tool": ..., "args": {...}, "id": ...}` lines from stdin, writing one JSON
+    /// line per call to stdout in completion order
+    ///
+    /// Each line's result carries the caller's `id` (or the line number) so outputs can be
+    /// matched to inputs when `concurrency` is above one. Failed calls produce an `error`
+    /// line instead of aborting the batch.
+    #[instrument(level = "debug", skip(context, config), fields(calls, duration_ms))]
+    async fn execute_batch(config: Config, context: WasmContext, concurrency: usize) -> Result<()> {
+        use tokio::io::AsyncBufReadExt;
+        use tokio::sync::{Mutex, mpsc};
+
+        let start_time = Instant::now();
+        let concurrency = concurrency.max(1);
+        let mut contexts = Vec::with_capacity(concurrency);
+        for _ in 1..concurrency {
+            contexts.push(context.fork()?);
+        }
+        contexts.push(context);
+
+        let (line_tx, line_rx) = mpsc::channel::<(usize, String)>(concurrency * 2);
+        let line_rx = Arc::new(Mutex::new(line_rx));
+        let (output_tx, mut output_rx) = mpsc::unbounded_channel::<serde_json::Value>();
+
+        let mut workers = Vec::with_capacity(concurrency);
+        for context in contexts {
+            let mut executor = Self::init(config.clone(), context).await?;
+            let line_rx = line_rx.clone();
+            let output_tx = output_tx.clone();
+            workers.push(tokio::spawn(async move {
+                loop {
+                    let next = line_rx.lock().await.recv().await;
+                    let Some((line_number, line)) = next else {
+                        break;
+                    };
+                    let output = Self::batch_call(&mut executor, line_number, &line).await;
+                    if output_tx.send(output).is_err() {
+                        break;
+                    }
+                }
+            }));
+        }
+        drop(output_tx);
+
+        let writer = tokio::spawn(async move {
+            while let Some(output) = output_rx.recv().await {
+                println!("{output}");
+            }
+        });
+
+        let mut lines = tokio::io::BufReader::new(tokio::io::stdin()).lines();
+        let mut calls = 0;
+        while let Some(line) = lines.next_line().await? {
+            calls += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if line_tx.send((calls, line)).await.is_err() {
+                break;
+            }
+        }
+        drop(line_tx);
+
+        for worker in workers {
+            worker
+                .await
+                .map_err(|e| WasiMcpError::Execution(format!("Batch worker failed: {e}")))?;
+        }
+        writer
+            .await
+            .map_err(|e| WasiMcpError::Execution(format!("Batch writer failed: {e}")))?;
+
+        tracing::Span::current().record("calls", calls);
+        tracing::Span::current().record("duration_ms", start_time.elapsed().as_millis());
+        Ok(())
+    }
+
+    /// Benchmark a single function and print the report
+    #[instrument(level = "debug", skip(context, config, args))]
+    async fn bench(
+        config: Config,
+        context: WasmContext,
+        function: &str,
+        args: &str,
+        iterations: usize,
+        warmup: usize,
+        json: bool,
+    ) -> Result<()> {
+        let arguments: HashMap<String, serde_json::Value> = serde_json::from_str(args)
+            .map_err(|e| WasiMcpError::InvalidArguments(format!("Invalid JSON arguments: {e}")))?;
+        let (component_name, _) = function.split_once('.').ok_or_else(|| {
+            WasiMcpError::InvalidArguments(format!(
+                "Function name must be in format 'component.function', got: {function}"
+            ))
+        })?;
+
+        let mut config = config;
+        config.components.retain(|k, _| k == component_name);
+        let mut executor = Self::init(config, context).await?;
+        let report =
+            crate::bench::run(&mut executor, function, arguments, iterations, warmup).await?;
+
+        if json {
+            println!("{:#}", report.to_json());
+        } else {
+            println!("{report}");
+        }
+        Ok(())
+    }
+
+    /// Run a single batch line and describe its outcome as JSON
+    async fn batch_call(
+        executor: &mut WasmExecutor,
+        line_number: usize,
+        line: &str,
+    ) -> serde_json::Value {
+        #[derive(serde::Deserialize)]
+        struct BatchCall {
+            tool: String,
+            #[serde(default)]
+            args: HashMap<String, serde_json::Value>,
+            #[serde(default)]
+            id: Option<serde_json::Value>,
+        }
+
+        let call: BatchCall = match serde_json::from_str(line) {
+            Ok(call) => call,
+            Err(e) => {
+                return serde_json::json!({
+                    "id": line_number,
+                    "error": format!("Invalid batch line: {e}"),
+                });
+            }
+        };
+
+        let id = call.id.unwrap_or_else(|| line_number.into());
+        match executor.execute_function(&call.tool, call.args).await {
+            Ok(result) => serde_json::json!({"id": id, "tool": call.tool, "result": result}),
+            Err(e) => {
+                let mut line = e.to_json();
+                line["id"] = id;
+                line["tool"] = call.tool.into();
+                line
+            }
+        }
+    }
+
+    /// Tools of every component, read from the component types; nothing is instantiated
+    async fn inspect_tools(
+        config: &Config,
+        context: &WasmContext,
+    ) -> Result<Vec<InspectedComponent>> {
+        let mut components = Vec::new();
+        for (name, component) in Self::load(config).await? {
+            let path = Path::new(component.path.as_deref().unwrap_or_default());
+            let inspector =
+                ComponentInspector::load(&context.engine, path, component.sha256.as_deref())?
+                    .with_conversion_options(ConversionOptions::from(config));
+            let metadata = ImageMetadata::read(path);
+            let description = component
+                .description
+                .as_deref()
+                .or(metadata.description.as_deref());
+            let tools = inspector.tools(description);
+            components.push(InspectedComponent {
+                name,
+                config: component,
+                metadata,
+                tools,
+            });
+        }
+        Ok(components)
+    }
+
+    /// Check that every component loads and that no two tools share a name
+    async fn validate(config: Config, context: WasmContext) -> Result<()> {
+        let components = Self::inspect_tools(&config, &context).await?;
+        let collisions =
+            naming::collisions(config.tool_names.as_ref(), exposed_functions(&components));
+        for collision in &collisions {
+            println!("Tool name collision: {collision}");
+        }
+
+        let policy = config
+            .tool_names
+            .as_ref()
+            .and_then(|naming| naming.collisions)
+            .unwrap_or_default();
+        if !collisions.is_empty() && policy == CollisionPolicy::Error {
+            return Err(WasiMcpError::InvalidArguments(format!(
+                "{} tool name collision(s); rename a component or set `tool_names.collisions: disambiguate`",
+                collisions.len()
+            )));
+        }
+        let tools = exposed_functions(&components).count();
+        println!(
+            "Configuration is valid: {} component(s), {tools} tool(s)",
+            components.len()
+        );
+        Ok(())
+    }
+
     #[instrument(level = "debug", skip(context, config), fields(functions, components))]
-    async fn list_functions(config: Config, context: WasmContext) -> Result<()> {
-        let executor = Self::init(config.clone(), context).await?;
-        let tools = executor.get_all_tools()?;
+    async fn list_functions(
+        config: Config,
+        context: WasmContext,
+        json: bool,
+        component: Option<&str>,
+    ) -> Result<()> {
+        let mut config = config;
+        if let Some(component) = component {
+            if !config.components.contains_key(component) {
+                return Err(WasiMcpError::ComponentNotFound(component.to_string()));
+            }
+            config.components.retain(|name, _| name == component);
+        }
 
+        let components = Self::inspect_tools(&config, &context).await?;
+        let names = ToolNames::new(config.tool_names.as_ref(), exposed_functions(&components))?;
+        tracing::Span::current().record("components", components.len());
+        let listed: Vec<(String, ImageMetadata, Vec<Tool>)> = components
+            .into_iter()
+            .map(|component| {
+                let tools = component_tools(
+                    &component.name,
+                    component.tools,
+                    Some(&component.config),
+                    &names,
+                );
+                (component.name, component.metadata, tools)
+            })
+            .collect();
+        let tools: Vec<&Tool> = listed.iter().flat_map(|(_, _, tools)| tools).collect();
         tracing::Span::current().record("functions", tools.len());
-        tracing::Span::current().record("components", executor.list_components().len());
 
-        for tool in &tools {
+        if json {
+            println!("{}", serde_json::to_string_pretty(&tools)?);
+            return Ok(());
+        }
+
+        for (name, metadata, tools) in &listed {
+            if let Some(summary) = metadata.summary() {
+                info!("{} ({})", name, summary);
+            }
+            for tool in tools {
+                info!(
+                    "  - {}: {}",
+                    tool.name,
+                    tool.description.as_deref().unwrap_or("No description")
+                );
+                debug!("Function details: {:?}", tool);
+            }
+        }
+        Ok(())
+    }
+
+    /// Print the contract of one tool
+    #[instrument(level = "debug", skip(config, context))]
+    async fn explain(config: Config, context: WasmContext, tool: &str, json: bool) -> Result<()> {
+        let executor = Self::init(config, context).await?;
+        let explanation = executor.explain(tool)?;
+        if json {
+            println!("{}", serde_json::to_string_pretty(&explanation)?);
+        } else {
+            print!("{explanation}");
+        }
+        Ok(())
+    }
+
+    /// Print the tools of the whole configuration or one profile in an LLM API's format
+    #[instrument(level = "debug", skip(config, context))]
+    async fn export(
+        config: Config,
+        context: WasmContext,
+        format: ExportFormat,
+        profile: Option<&str>,
+    ) -> Result<()> {
+        let config = match profile {
+            Some(profile) => config.profile(profile)?,
+            None => config,
+        };
+        let executor = Self::init(config, context).await?;
+        let tools = executor.get_all_tools()?;
+        println!("{}", serde_json::to_string_pretty(&format.convert(&tools))?);
+        Ok(())
+    }
+
+    /// Print an OpenAPI document for the tools of the whole configuration or one profile
+    #[instrument(level = "debug", skip(config, context))]
+    async fn openapi(config: Config, context: WasmContext, profile: Option<&str>) -> Result<()> {
+        let config = match profile {
+            Some(profile) => config.profile(profile)?,
+            None => config,
+        };
+        let title = config
+            .description
+            .clone()
+            .unwrap_or_else(|| "wasmic".to_string());
+        let authenticated = config.policy.is_some();
+
+        let executor = Self::init(config, context).await?;
+        let tools = executor.get_all_tools()?;
+        let document = crate::openapi::document(&tools, &title, authenticated);
+        println!("{}", serde_json::to_string_pretty(&document)?);
+        Ok(())
+    }
+
+    /// Print the calls recorded in the history database
+    fn history(config: Config, query: &crate::history::HistoryQuery, json: bool) -> Result<()> {
+        let path = crate::history::database_path(&config.history.unwrap_or_default())?;
+        if !path.exists() {
+            return Err(WasiMcpError::InvalidArguments(format!(
+                "No call history at {path:?}; add a `history` section to the configuration"
+            )));
+        }
+
+        let entries = crate::history::query(&path, query)?;
+        if json {
+            println!("{}", serde_json::to_string_pretty(&entries)?);
+            return Ok(());
+        }
+        for entry in entries {
+            println!(
+                "{}  {:<14} {:>7}ms  {}  {}",
+                crate::history::format_time(entry.time),
+                entry.code.as_deref().unwrap_or("ok"),
+                entry.duration_ms,
+                entry.tool,
+                entry.summary
+            );
+        }
+        Ok(())
+    }
+
+    /// Re-execute recorded calls, optionally against a candidate build of their component,
+    /// and report those whose outcome differs from the recording
+    async fn replay(
+        config: Config,
+        context: WasmContext,
+        paths: &[PathBuf],
+        candidate: Option<&str>,
+        json: bool,
+    ) -> Result<()> {
+        let mut recordings = Vec::new();
+        for path in crate::golden::collect(paths)? {
+            let recording = Recording::load(&path)?;
+            recordings.push((path, recording));
+        }
+        let components: BTreeSet<&str> = recordings
+            .iter()
+            .map(|(_, recording)| recording.component())
+            .collect();
+
+        let mut config = config;
+        config
+            .components
+            .retain(|name, _| components.contains(name.as_str()));
+        if let Some(candidate) = candidate {
+            let [component] = components.iter().copied().collect::<Vec<_>>()[..] else {
+                return Err(WasiMcpError::InvalidArguments(format!(
+                    "--candidate needs recordings of a single component, got {}",
+                    components.into_iter().collect::<Vec<_>>().join(", ")
+                )));
+            };
+            let component_config = config
+                .components
+                .get_mut(component)
+                .ok_or_else(|| WasiMcpError::ComponentNotFound(component.to_string()))?;
+            crate::golden::use_candidate(component_config, candidate);
             info!(
-                "  - {}: {}",
-                tool.name,
-                tool.description.as_deref().unwrap_or("No description")
+                component,
+                candidate, "Replaying recordings against candidate build"
             );
-            debug!("Function details: {:?}", tool);
+        }
+        let mut executor = Self::init(config, context).await?;
+
+        let mut report = TestReport::default();
+        for (path, recording) in &recordings {
+            let tape = Tape::replay(recording);
+            let start_time = Instant::now();
+            let outcome = executor.replay(recording, tape.clone()).await;
+            let mut failures = recording.differences(&outcome);
+            if let Some(divergence) = tape.divergence() {
+                failures.push(format!("diverged: the guest {divergence}"));
+            }
+            // A single recording is replayed to debug it, so show what it returned
+            if recordings.len() == 1 && !json {
+                match &outcome {
+                    Ok(result) => println!("{}", serde_json::to_string_pretty(result)?),
+                    Err(e) => println!("error: {e}"),
+                }
+            }
+            report.push(TestResult {
+                name: path.file_name().map_or_else(
+                    || path.display().to_string(),
+                    |name| name.to_string_lossy().into_owned(),
+                ),
+                tool: recording.tool.clone(),
+                duration_ms: start_time.elapsed().as_millis() as u64,
+                failures,
+            });
+        }
+
+        if json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            println!("{report}");
+        }
+        if report.failed > 0 {
+            return Err(WasiMcpError::Execution(format!(
+                "{} of {} recordings replayed differently",
+                report.failed,
+                report.tests.len()
+            )));
         }
         Ok(())
     }
+
+    /// Run the test cases of the configuration and of `file`, failing if any test fails
+    async fn run_tests(
+        config: Config,
+        context: WasmContext,
+        file: Option<&Path>,
+        filter: Option<&str>,
+        json: bool,
+    ) -> Result<()> {
+        let mut cases = config.tests.clone();
+        if let Some(file) = file {
+            cases.extend(crate::testing::load(file)?);
+        }
+        if let Some(filter) = filter {
+            cases.retain(|case| case.display_name().contains(filter) || case.tool.contains(filter));
+        }
+        if cases.is_empty() {
+            return Err(WasiMcpError::InvalidArguments(
+                "No test cases; add a `tests` section to the configuration or pass --file"
+                    .to_string(),
+            ));
+        }
+
+        let mut executor = Self::init(config, context).await?;
+        let mut report = TestReport::default();
+        for case in &cases {
+            let start_time = Instant::now();
+            let outcome = executor
+                .execute_function(&case.tool, case.args.clone())
+                .await;
+            report.push(TestResult {
+                name: case.display_name().to_string(),
+                tool: case.tool.clone(),
+                duration_ms: start_time.elapsed().as_millis() as u64,
+                failures: crate::testing::check(case, &outcome),
+            });
+        }
+
+        if json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            println!("{report}");
+        }
+        if report.failed > 0 {
+            return Err(WasiMcpError::Execution(format!(
+                "{} of {} tests failed",
+                report.failed,
+                report.tests.len()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Download every OCI component and plug without instantiating anything
+    ///
+    /// Unlike `precompile` this needs neither secrets nor compilation, so it can warm the cache
+    /// in CI or a container entrypoint and fails as soon as a registry is unreachable.
+    #[instrument(level = "debug", skip(config), fields(artifacts, duration_ms))]
+    async fn pull(config: Config) -> Result<()> {
+        let start_time = Instant::now();
+        let oci_manager = &Self::oci_manager(&config)?;
+
+        let references = Self::oci_references(&config);
+
+        let pulled = futures::future::try_join_all(references.iter().map(|reference| async move {
+            let path = oci_manager.download_wasm_component(reference).await?;
+            Ok::<_, WasiMcpError>((*reference, path))
+        }))
+        .await?;
+
+        for (reference, path) in &pulled {
+            info!("Pulled {} ({:?})", reference, path);
+        }
+
+        tracing::Span::current().record("artifacts", pulled.len());
+        tracing::Span::current().record("duration_ms", start_time.elapsed().as_millis());
+        Ok(())
+    }
+
+    /// Print the world of a local or OCI component as WIT or JSON
+    async fn inspect(
+        config: Config,
+        context: WasmContext,
+        component: &str,
+        json: bool,
+    ) -> Result<()> {
+        let path = if Path::new(component).exists() {
+            PathBuf::from(component)
+        } else {
+            Self::oci_manager(&config)?
+                .download_wasm_component(component)
+                .await?
+        };
+
+        let mut world = ComponentInspector::load(&context.engine, &path, None)?.world();
+        world.metadata = ImageMetadata::read(&path);
+        if json {
+            println!("{}", world.to_json()?);
+        } else {
+            let name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_else(|| "component".to_string());
+            print!("{}", world.to_wit(&name));
+        }
+        Ok(())
+    }
+
+    /// Show the tags of a repository and the description/version annotations of each
+    async fn list_tags(config: Config, repository: &str, limit: usize) -> Result<()> {
+        let tags = Self::oci_manager(&config)?
+            .list_tags(repository, limit)
+            .await?;
+
+        for tag in &tags {
+            let metadata = ImageMetadata::from_annotations(&tag.annotations);
+            let description = metadata.description.as_deref().unwrap_or("No description");
+            match metadata.summary() {
+                Some(summary) => info!("  - {} ({}): {}", tag.tag, summary, description),
+                None => info!("  - {}: {}", tag.tag, description),
+            }
+            for (key, value) in &tag.annotations {
+                debug!("      {} = {}", key, value);
+            }
+        }
+        Ok(())
+    }
+
+    /// Re-resolve every mutable OCI reference and download the ones whose digest changed
+    #[instrument(level = "debug", skip(config), fields(updated, duration_ms))]
+    async fn update(config: Config) -> Result<()> {
+        let start_time = Instant::now();
+        let oci_manager = &Self::oci_manager(&config)?;
+        let references = Self::oci_references(&config);
+
+        let results =
+            futures::future::try_join_all(references.iter().map(|reference| async move {
+                let updated = oci_manager.refresh_wasm_component(reference).await?;
+                Ok::<_, WasiMcpError>((*reference, updated))
+            }))
+            .await?;
+
+        let mut updated = 0;
+        for (reference, was_updated) in &results {
+            if *was_updated {
+                info!("Updated {}", reference);
+                updated += 1;
+            } else {
+                debug!("{} is up to date", reference);
+            }
+        }
+        info!("{} of {} OCI components updated", updated, results.len());
+
+        tracing::Span::current().record("updated", updated);
+        tracing::Span::current().record("duration_ms", start_time.elapsed().as_millis());
+        Ok(())
+    }
+
+    /// Re-resolve the OCI components of a running server every `interval` and upgrade the
+    /// ones whose artifact changed
+    async fn update_periodically(server: WasmMcpServer, interval: Duration) {
+        let mut ticks = tokio::time::interval(interval);
+        // The first tick completes immediately; the components were just loaded
+        ticks.tick().await;
+        loop {
+            ticks.tick().await;
+            if let Err(e) = Self::update_running(&server).await {
+                tracing::error!("Failed to update components: {}", e);
+            }
+        }
+    }
+
+    /// Upgrade the OCI components of a running server whose tag moved to a new artifact
+    ///
+    /// A new artifact is downloaded next to the cached one and only replaces it once every
+    /// component using it loaded and passed its smoke calls, so a broken release neither
+    /// reaches the running server nor the cache a restart would load from.
+    #[instrument(level = "debug", skip(server), fields(updated))]
+    async fn update_running(server: &WasmMcpServer) -> Result<()> {
+        let config = server.config.read().await.clone();
+        let oci_manager = Self::oci_manager(&config)?;
+
+        let mut users: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        for (name, component) in &config.components {
+            if let Some(reference) = component.oci.as_deref() {
+                users.entry(reference).or_default().push(name.clone());
+            }
+        }
+
+        let mut updated = 0;
+        for (reference, names) in users {
+            let artifact = match oci_manager.stage_update(reference).await {
+                Ok(Some(artifact)) => artifact,
+                Ok(None) => continue,
+                Err(e) => {
+                    tracing::warn!("Failed to check {} for updates: {}", reference, e);
+                    continue;
+                }
+            };
+            let mut changed = config.clone();
+            changed.components.retain(|name, _| names.contains(name));
+            match Self::upgrade_to(server, &changed, &artifact.path).await {
+                Ok(upgrades) => {
+                    let path = artifact.promote().await?;
+                    for mut upgrade in upgrades {
+                        upgrade.relocate(&path);
+                        upgrade.install().await?;
+                    }
+                    info!(
+                        "Upgraded {} to a new version of {}",
+                        names.join(", "),
+                        reference
+                    );
+                    updated += 1;
+                }
+                Err(e) => {
+                    tracing::error!("Keeping the running version of {}: {}", names.join(", "), e);
+                    artifact.discard().await;
+                }
+            }
+        }
+        tracing::Span::current().record("updated", updated);
+        if updated > 0 {
+            server.notify_tool_list_changed().await;
+        }
+        Ok(())
+    }
+
+    /// Load and smoke-test the components of `changed` from the staged artifact at `path`
+    async fn upgrade_to(
+        server: &WasmMcpServer,
+        changed: &Config,
+        path: &Path,
+    ) -> Result<Vec<StagedUpgrade>> {
+        let mut upgrades = Vec::new();
+        for (name, mut component_config) in Self::load(changed).await? {
+            info!("Upgrading component '{}'", name);
+            component_config.path = Some(path.display().to_string());
+            upgrades.push(server.stage_upgrade(&name, component_config).await?);
+        }
+        Ok(upgrades)
+    }
+
+    /// Every distinct OCI reference of the configured components and their plugs
+    fn oci_references(config: &Config) -> BTreeSet<&str> {
+        config
+            .components
+            .values()
+            .flat_map(|component| {
+                component
+                    .oci
+                    .iter()
+                    .chain(component.plugs.iter().filter_map(|plug| plug.oci.as_ref()))
+            })
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Resolve every component (pulling OCI artifacts) and store its compiled form in the cache
+    #[instrument(
+        level = "debug",
+        skip(context, config),
+        fields(components, duration_ms)
+    )]
+    async fn precompile(config: Config, context: WasmContext) -> Result<()> {
+        let start_time = Instant::now();
+        let cache = ComponentCache::new()?;
+        let components = Self::load(&config).await?;
+
+        for (name, component_config) in &components {
+            let path = component_config.path.as_deref().ok_or_else(|| {
+                WasiMcpError::InvalidArguments(format!("Component '{name}' has no resolved path"))
+            })?;
+            cache.load_verified(
+                &context.engine,
+                Path::new(path),
+                component_config.sha256.as_deref(),
+            )?;
+            info!("Precompiled component '{}' ({})", name, path);
+        }
+
+        tracing::Span::current().record("components", components.len());
+        tracing::Span::current().record("duration_ms", start_time.elapsed().as_millis());
+        Ok(())
+    }
+
+    /// Run a `cache` subcommand
+    fn cache(config: Config, command: CacheCommand) -> Result<()> {
+        let index = CacheIndex::new()?;
+        match command {
+            CacheCommand::List {} => {
+                let entries = index.entries()?;
+                for entry in &entries {
+                    let age = entry.last_access.elapsed().unwrap_or_default();
+                    info!(
+                        "  - {} ({} bytes, last used {}h ago)",
+                        entry.name,
+                        entry.size,
+                        age.as_secs() / 3600
+                    );
+                }
+                let total: u64 = entries.iter().map(|entry| entry.size).sum();
+                info!("{} cached artifacts, {} bytes", entries.len(), total);
+            }
+            CacheCommand::Prune {
+                max_size,
+                max_age_days,
+            } => {
+                let mut policy = config.cache.unwrap_or_default();
+                if max_size.is_some() {
+                    policy.max_size = max_size;
+                }
+                if max_age_days.is_some() {
+                    policy.max_age_days = max_age_days;
+                }
+                let evicted = index.prune(&policy)?;
+                let freed: u64 = evicted.iter().map(|entry| entry.size).sum();
+                info!(
+                    "Evicted {} cached artifacts ({} bytes)",
+                    evicted.len(),
+                    freed
+                );
+            }
+            CacheCommand::Clear {} => {
+                let removed = index.clear()?;
+                info!("Removed {} cached artifacts", removed.len());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// (component, function) of every exposed tool
+fn exposed_functions(
+    components: &[InspectedComponent],
+) -> impl Iterator<Item = (&str, &str)> + Clone {
+    components.iter().flat_map(|component| {
+        component
+            .tools
+            .iter()
+            .filter(|tool| component.config.exposes(&tool.name))
+            .map(move |tool| (component.name.as_str(), tool.name.as_ref()))
+    })
+}
+
+/// A configured component as read by [`ServerManager::inspect_tools`]
+struct InspectedComponent {
+    name: String,
+    config: ComponentConfig,
+    metadata: ImageMetadata,
+    tools: Vec<Tool>,
+}
+
+/// Component name for a path or OCI reference: the file stem or repository name, without
+/// the dots that separate components from functions in tool names
+fn run_component_name(reference: &str) -> String {
+    let name = reference.rsplit('/').next().unwrap_or(reference);
+    let name = name.split([':', '@']).next().unwrap_or(name);
+    let name = name.strip_suffix(".wasm").unwrap_or(name);
+    name.replace('.', "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A component exporting `double: func(n: u64) -> u64`
+    const DOUBLE_WAT: &str = r#"(component
+        (core module $m
+            (func (export "double") (param i64) (result i64)
+                local.get 0
+                local.get 0
+                i64.add))
+        (core instance $i (instantiate $m))
+        (func (export "double") (param "n" u64) (result u64)
+            (canon lift (core func $i "double")))
+    )"#;
+
+    /// Configuration with a `tenant` profile, letting `clients` (name and token) call any tool
+    fn profile_config(component: &Path, clients: &[(&str, &str)]) -> String {
+        let clients: String = clients
+            .iter()
+            .map(|(name, token)| {
+                format!("    {name}:\n      token: {token}\n      allow: [\"*\"]\n")
+            })
+            .collect();
+        format!(
+            "components:\n  math:\n    path: {}\npolicy:\n  clients:\n{clients}profiles:\n  tenant:\n    components: [math]\n",
+            component.display()
+        )
+    }
+
+    #[tokio::test]
+    async fn test_reload_revokes_tokens_of_profiles() {
+        let dir = tempfile::tempdir().unwrap();
+        let component = dir.path().join("math.wat");
+        std::fs::write(&component, DOUBLE_WAT).unwrap();
+        let config_path = dir.path().join("config.yaml");
+        std::fs::write(
+            &config_path,
+            profile_config(
+                &component,
+                &[("alice", "alice-token"), ("bob", "bob-token")],
+            ),
+        )
+        .unwrap();
+
+        let config = Config::from_file(&config_path).unwrap();
+        let server = ServerManager::build_server(config, WasmContext::new().unwrap())
+            .await
+            .unwrap();
+        let tenant = server.profile("tenant").unwrap();
+        assert!(
+            tenant
+                .authorize_call(Some("bob-token"), "math.double")
+                .await
+                .is_ok()
+        );
+
+        std::fs::write(
+            &config_path,
+            profile_config(&component, &[("alice", "alice-token")]),
+        )
+        .unwrap();
+        ServerManager::reload_config(&server, &config_path)
+            .await
+            .unwrap();
+
+        let tenant = server.profile("tenant").unwrap();
+        assert!(
+            tenant
+                .authorize_call(Some("bob-token"), "math.double")
+                .await
+                .is_err()
+        );
+        tenant
+            .authorize_call(Some("alice-token"), "math.double")
+            .await
+            .unwrap();
+        let arguments = HashMap::from([("n".to_string(), serde_json::Value::from(21))]);
+        let result = tenant
+            .executor
+            .lock()
+            .await
+            .execute_function("math.double", arguments)
+            .await
+            .unwrap();
+        assert_eq!(result, serde_json::Value::from(42));
+    }
 }
@@ -0,0 +1,153 @@
+//! Graceful shutdown of the MCP server
+//!
+//! On SIGINT or SIGTERM the server stops accepting requests and waits, up to a grace period,
+//! for tool calls that are already running before it exits.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::{Notify, watch};
+
+/// Time the components' shutdown functions get once the grace period is over; a call that
+/// ignored its interruption keeps its executor locked, and the server exits without them
+pub const SHUTDOWN_FUNCTIONS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Tracks running tool calls so shutdown can wait for them to finish
+#[derive(Clone)]
+pub struct Drain {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    draining: watch::Sender<bool>,
+    in_flight: AtomicUsize,
+    idle: Notify,
+}
+
+impl Default for Drain {
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                draining: watch::Sender::new(false),
+                in_flight: AtomicUsize::new(0),
+                idle: Notify::new(),
+            }),
+        }
+    }
+}
+
+impl Drain {
+    /// Register a call, or `None` when the server is draining and takes no new calls
+    ///
+    /// The call counts as running until the returned guard is dropped.
+    pub fn start_call(&self) -> Option<CallGuard> {
+        // Count the call before checking, so a drain that saw no calls never misses this one
+        self.inner.in_flight.fetch_add(1, Ordering::SeqCst);
+        let guard = CallGuard {
+            drain: self.clone(),
+        };
+        (!self.is_draining()).then_some(guard)
+    }
+
+    /// Stop accepting calls
+    pub fn begin(&self) {
+        self.inner.draining.send_replace(true);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        *self.inner.draining.borrow()
+    }
+
+    /// Number of calls currently running
+    pub fn in_flight(&self) -> usize {
+        self.inner.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Resolve once draining has begun
+    pub async fn started(&self) {
+        let mut draining = self.inner.draining.subscribe();
+        let _ = draining.wait_for(|draining| *draining).await;
+    }
+
+    /// Resolve once no calls are running
+    pub async fn idle(&self) {
+        loop {
+            let notified = self.inner.idle.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if self.in_flight() == 0 {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// Marks a tool call as running while alive
+pub struct CallGuard {
+    drain: Drain,
+}
+
+impl Drop for CallGuard {
+    fn drop(&mut self) {
+        if self.drain.inner.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.drain.inner.idle.notify_waiters();
+        }
+    }
+}
+
+/// Resolve when the process is asked to stop with Ctrl-C or SIGTERM
+pub async fn signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+                return;
+            }
+            Err(e) => tracing::warn!("Failed to install SIGTERM handler: {}", e),
+        }
+    }
+
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        tracing::warn!("Failed to listen for Ctrl-C: {}", e);
+        std::future::pending::<()>().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_drain_waits_for_running_calls() {
+        let drain = Drain::default();
+        let call = drain
+            .start_call()
+            .expect("calls are accepted before draining");
+        assert_eq!(drain.in_flight(), 1);
+
+        drain.begin();
+        drain.started().await;
+        assert!(drain.start_call().is_none());
+        assert_eq!(drain.in_flight(), 1);
+
+        let idle = tokio::spawn({
+            let drain = drain.clone();
+            async move { drain.idle().await }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(!idle.is_finished());
+
+        drop(call);
+        tokio::time::timeout(Duration::from_secs(1), idle)
+            .await
+            .expect("drain becomes idle once the call finishes")
+            .unwrap();
+    }
+}
@@ -0,0 +1,122 @@
+use crate::WasiMcpError;
+use crate::config::{TrustPolicy, VerificationConfig};
+use crate::error::Result;
+use std::process::Stdio;
+
+impl VerificationConfig {
+    /// Trust policy for a registry, or an error if signed artifacts are required but the
+    /// registry has none. `Ok(None)` means the artifact may be used unverified.
+    pub fn trust_policy(&self, registry: &str) -> Result<Option<&TrustPolicy>> {
+        match self.registries.get(registry) {
+            Some(trust) => Ok(Some(trust)),
+            None if self.require_signed => Err(WasiMcpError::InvalidArguments(format!(
+                "Signed components are required but no trust policy is configured for registry '{registry}'"
+            ))),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Verify the cosign signature of an OCI artifact with the `cosign` CLI
+///
+/// `reference` should be pinned by digest so the verified artifact is the one pulled.
+pub async fn verify(reference: &str, trust: &TrustPolicy) -> Result<()> {
+    let args = cosign_args(reference, trust)?;
+    tracing::info!("Verifying signature of {}", reference);
+
+    let output = tokio::process::Command::new("cosign")
+        .args(&args)
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .map_err(|e| {
+            WasiMcpError::InvalidArguments(format!(
+                "Failed to run cosign to verify '{reference}': {e}"
+            ))
+        })?;
+
+    if !output.status.success() {
+        return Err(WasiMcpError::InvalidArguments(format!(
+            "Signature verification failed for '{reference}': {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Arguments for `cosign verify` under a trust policy
+fn cosign_args(reference: &str, trust: &TrustPolicy) -> Result<Vec<String>> {
+    let mut args = vec!["verify".to_string()];
+    match (&trust.key, &trust.identity, &trust.issuer) {
+        (Some(key), None, None) => {
+            args.extend(["--key".to_string(), key.clone()]);
+        }
+        (None, Some(identity), Some(issuer)) => {
+            args.extend([
+                "--certificate-identity".to_string(),
+                identity.clone(),
+                "--certificate-oidc-issuer".to_string(),
+                issuer.clone(),
+            ]);
+        }
+        _ => {
+            return Err(WasiMcpError::InvalidArguments(
+                "Trust policy must set either 'key', or both 'identity' and 'issuer'".to_string(),
+            ));
+        }
+    }
+    args.push(reference.to_string());
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_cosign_args() {
+        let key = TrustPolicy {
+            key: Some("cosign.pub".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            cosign_args("ghcr.io/a/b@sha256:00", &key).unwrap(),
+            ["verify", "--key", "cosign.pub", "ghcr.io/a/b@sha256:00"]
+        );
+
+        let keyless = TrustPolicy {
+            identity: Some(
+                "https://github.com/a/b/.github/workflows/release.yml@refs/heads/main".to_string(),
+            ),
+            issuer: Some("https://token.actions.githubusercontent.com".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            cosign_args("ghcr.io/a/b@sha256:00", &keyless)
+                .unwrap()
+                .len(),
+            6
+        );
+
+        let incomplete = TrustPolicy {
+            identity: Some("someone".to_string()),
+            ..Default::default()
+        };
+        assert!(cosign_args("ghcr.io/a/b@sha256:00", &incomplete).is_err());
+    }
+
+    #[test]
+    fn test_require_signed() {
+        let mut verification = VerificationConfig {
+            require_signed: false,
+            registries: HashMap::from([("ghcr.io".to_string(), TrustPolicy::default())]),
+        };
+        assert!(verification.trust_policy("ghcr.io").unwrap().is_some());
+        assert!(verification.trust_policy("quay.io").unwrap().is_none());
+
+        verification.require_signed = true;
+        assert!(verification.trust_policy("quay.io").is_err());
+    }
+}
@@ -0,0 +1,346 @@
+//! SQL host interface backed by SQLite
+//!
+//! Components query the SQLite files declared in their `databases` configuration through
+//! `wasmic:sql/sql`, without bundling a database engine or needing network access:
+//!
+//! ```wit
+//! package wasmic:sql@0.1.0;
+//!
+//! interface sql {
+//!     variant value { null, integer(s64), real(f64), text(string), blob(list<u8>) }
+//!     record query-result { columns: list<string>, rows: list<list<value>> }
+//!
+//!     /// Run a statement returning rows
+//!     query: func(database: string, statement: string, params: list<value>) -> result<query-result, string>;
+//!     /// Run a statement and return the number of rows it changed
+//!     execute: func(database: string, statement: string, params: list<value>) -> result<u64, string>;
+//! }
+//! ```
+//!
+//! Databases are named by their key in the configuration; a component can't open any other
+//! file. Statements that would reach other files, `ATTACH` and `VACUUM INTO`, are refused, as
+//! are pragmas. Each call opens its own connection on a blocking thread, so statements of
+//! different components and calls are isolated by SQLite's locking.
+
+use crate::config::DatabaseConfig;
+use crate::state::ComponentRunStates;
+use rusqlite::hooks::{AuthAction, AuthContext, Authorization};
+use rusqlite::limits::Limit;
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{Connection, OpenFlags};
+use std::time::Duration;
+use wasmtime::component::{Linker, Val};
+
+/// Fully qualified name of the SQL interface
+pub const SQL_INTERFACE: &str = "wasmic:sql/sql@0.1.0";
+
+/// How long a statement waits for a database locked by another connection
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Rows and column names returned by a query
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<SqlValue>>,
+}
+
+/// Implement `wasmic:sql/sql` on the databases configured for each store's component
+pub fn add_to_linker(linker: &mut Linker<ComponentRunStates>) -> wasmtime::Result<()> {
+    let mut instance = linker.instance(SQL_INTERFACE)?;
+    instance.func_new_async("query", |store, params, results| {
+        Box::new(async move {
+            let (database, statement, params) = arguments(params, "query")?;
+            let result = match store.data().database(database).cloned() {
+                Ok(config) => blocking(move || query(&config, &statement, &params))
+                    .await?
+                    .map(|result| Box::new(query_result_to_val(result))),
+                Err(e) => Err(e),
+            };
+            results[0] = Val::Result(result.map(Some).map_err(|e| Some(Box::new(Val::String(e)))));
+            Ok(())
+        })
+    })?;
+    instance.func_new_async("execute", |store, params, results| {
+        Box::new(async move {
+            let (database, statement, params) = arguments(params, "execute")?;
+            let result = match store.data().database(database).cloned() {
+                Ok(config) => blocking(move || execute(&config, &statement, &params))
+                    .await?
+                    .map(|changed| Box::new(Val::U64(changed))),
+                Err(e) => Err(e),
+            };
+            results[0] = Val::Result(result.map(Some).map_err(|e| Some(Box::new(Val::String(e)))));
+            Ok(())
+        })
+    })?;
+    Ok(())
+}
+
+/// Run database work off the async workers, as it may wait up to [`BUSY_TIMEOUT`] for locks
+async fn blocking<T: Send + 'static>(
+    work: impl FnOnce() -> T + Send + 'static,
+) -> wasmtime::Result<T> {
+    Ok(tokio::task::spawn_blocking(work).await?)
+}
+
+impl ComponentRunStates {
+    /// Configuration of a database the component may use
+    fn database(&self, name: &str) -> Result<&DatabaseConfig, String> {
+        self.databases.get(name).ok_or_else(|| {
+            format!(
+                "database '{name}' is not configured for component '{}'",
+                self.component_name
+            )
+        })
+    }
+}
+
+/// Database name, statement and parameters of a call
+fn arguments<'a>(
+    params: &'a [Val],
+    function: &str,
+) -> wasmtime::Result<(&'a str, String, Vec<SqlValue>)> {
+    let [
+        Val::String(database),
+        Val::String(statement),
+        Val::List(values),
+    ] = params
+    else {
+        anyhow::bail!("Unexpected arguments to {SQL_INTERFACE}.{function}");
+    };
+    let values = values
+        .iter()
+        .map(val_to_sql)
+        .collect::<wasmtime::Result<_>>()?;
+    Ok((database, statement.clone(), values))
+}
+
+/// Run a statement returning rows
+pub fn query(
+    config: &DatabaseConfig,
+    statement: &str,
+    params: &[SqlValue],
+) -> Result<QueryResult, String> {
+    let connection = open(config)?;
+    let mut statement = connection.prepare(statement).map_err(|e| e.to_string())?;
+    let columns: Vec<String> = statement
+        .column_names()
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    let mut rows = statement
+        .query(rusqlite::params_from_iter(params))
+        .map_err(|e| e.to_string())?;
+    let mut result = Vec::new();
+    while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+        let values = (0..columns.len())
+            .map(|index| row.get_ref(index).map(SqlValue::from))
+            .collect::<rusqlite::Result<_>>()
+            .map_err(|e| e.to_string())?;
+        result.push(values);
+    }
+    Ok(QueryResult {
+        columns,
+        rows: result,
+    })
+}
+
+/// Run a statement and return the number of rows it changed
+pub fn execute(
+    config: &DatabaseConfig,
+    statement: &str,
+    params: &[SqlValue],
+) -> Result<u64, String> {
+    let connection = open(config)?;
+    let changed = connection
+        .execute(statement, rusqlite::params_from_iter(params))
+        .map_err(|e| e.to_string())?;
+    Ok(changed as u64)
+}
+
+/// Open a configured database, confined to that one file
+fn open(config: &DatabaseConfig) -> Result<Connection, String> {
+    let flags = if config.read_only {
+        OpenFlags::SQLITE_OPEN_READ_ONLY
+    } else {
+        OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE
+    } | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+    let connection = Connection::open_with_flags(&config.path, flags)
+        .map_err(|e| format!("cannot open {}: {e}", config.path))?;
+    connection
+        .busy_timeout(BUSY_TIMEOUT)
+        .map_err(|e| e.to_string())?;
+    // `VACUUM INTO` attaches its target too, so no attached database covers both
+    connection.set_limit(Limit::SQLITE_LIMIT_ATTACHED, 0);
+    connection.authorizer(Some(authorize));
+    Ok(connection)
+}
+
+/// Deny statements reaching beyond the configured file or changing how it is accessed
+fn authorize(context: AuthContext<'_>) -> Authorization {
+    match context.action {
+        AuthAction::Attach { .. } | AuthAction::Detach { .. } | AuthAction::Pragma { .. } => {
+            Authorization::Deny
+        }
+        _ => Authorization::Allow,
+    }
+}
+
+/// A `value` variant of the interface as an SQLite value
+fn val_to_sql(val: &Val) -> wasmtime::Result<SqlValue> {
+    let Val::Variant(case, payload) = val else {
+        anyhow::bail!("Expected a {SQL_INTERFACE} value, got {val:?}");
+    };
+    Ok(match (case.as_str(), payload.as_deref()) {
+        ("null", None) => SqlValue::Null,
+        ("integer", Some(Val::S64(n))) => SqlValue::Integer(*n),
+        ("real", Some(Val::Float64(n))) => SqlValue::Real(*n),
+        ("text", Some(Val::String(text))) => SqlValue::Text(text.clone()),
+        ("blob", Some(Val::List(bytes))) => SqlValue::Blob(
+            bytes
+                .iter()
+                .map(|byte| match byte {
+                    Val::U8(byte) => Ok(*byte),
+                    other => Err(anyhow::anyhow!("Expected a byte, got {other:?}")),
+                })
+                .collect::<wasmtime::Result<_>>()?,
+        ),
+        _ => anyhow::bail!("Unexpected {SQL_INTERFACE} value {val:?}"),
+    })
+}
+
+/// An SQLite value as a `value` variant of the interface
+fn sql_to_val(value: SqlValue) -> Val {
+    let (case, payload) = match value {
+        SqlValue::Null => ("null", None),
+        SqlValue::Integer(n) => ("integer", Some(Val::S64(n))),
+        SqlValue::Real(n) => ("real", Some(Val::Float64(n))),
+        SqlValue::Text(text) => ("text", Some(Val::String(text))),
+        SqlValue::Blob(bytes) => (
+            "blob",
+            Some(Val::List(bytes.into_iter().map(Val::U8).collect())),
+        ),
+    };
+    Val::Variant(case.to_string(), payload.map(Box::new))
+}
+
+fn query_result_to_val(result: QueryResult) -> Val {
+    Val::Record(vec![
+        (
+            "columns".to_string(),
+            Val::List(result.columns.into_iter().map(Val::String).collect()),
+        ),
+        (
+            "rows".to_string(),
+            Val::List(
+                result
+                    .rows
+                    .into_iter()
+                    .map(|row| Val::List(row.into_iter().map(sql_to_val).collect()))
+                    .collect(),
+            ),
+        ),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_and_execute() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.db").display().to_string();
+        let writable = DatabaseConfig {
+            path: path.clone(),
+            read_only: false,
+        };
+
+        execute(
+            &writable,
+            "create table items (id integer, name text, price real, data blob)",
+            &[],
+        )
+        .unwrap();
+        let changed = execute(
+            &writable,
+            "insert into items values (?1, ?2, ?3, ?4), (2, null, 0.5, null)",
+            &[
+                SqlValue::Integer(1),
+                SqlValue::Text("pen".to_string()),
+                SqlValue::Real(1.5),
+                SqlValue::Blob(vec![1, 2]),
+            ],
+        )
+        .unwrap();
+        assert_eq!(changed, 2);
+
+        let read_only = DatabaseConfig {
+            path,
+            read_only: true,
+        };
+        let result = query(
+            &read_only,
+            "select id, name, price, data from items where id >= ?1 order by id",
+            &[SqlValue::Integer(1)],
+        )
+        .unwrap();
+        assert_eq!(result.columns, ["id", "name", "price", "data"]);
+        assert_eq!(
+            result.rows,
+            vec![
+                vec![
+                    SqlValue::Integer(1),
+                    SqlValue::Text("pen".to_string()),
+                    SqlValue::Real(1.5),
+                    SqlValue::Blob(vec![1, 2]),
+                ],
+                vec![
+                    SqlValue::Integer(2),
+                    SqlValue::Null,
+                    SqlValue::Real(0.5),
+                    SqlValue::Null,
+                ],
+            ]
+        );
+
+        assert!(execute(&read_only, "delete from items", &[]).is_err());
+        assert!(query(&read_only, "select * from missing", &[]).is_err());
+    }
+
+    #[test]
+    fn test_value_conversion() {
+        let values = [
+            SqlValue::Null,
+            SqlValue::Integer(-3),
+            SqlValue::Real(2.5),
+            SqlValue::Text("a".to_string()),
+            SqlValue::Blob(vec![0, 255]),
+        ];
+        for value in values {
+            assert_eq!(val_to_sql(&sql_to_val(value.clone())).unwrap(), value);
+        }
+        assert!(val_to_sql(&Val::String("x".to_string())).is_err());
+        assert!(val_to_sql(&Val::Variant("integer".to_string(), None)).is_err());
+    }
+
+    #[test]
+    fn test_other_files_are_unreachable() {
+        let dir = tempfile::tempdir().unwrap();
+        let writable = DatabaseConfig {
+            path: dir.path().join("app.db").display().to_string(),
+            read_only: false,
+        };
+        execute(&writable, "create table items (id integer)", &[]).unwrap();
+
+        let other = dir.path().join("other.db");
+        let attach = format!("attach database '{}' as other", other.display());
+        assert!(execute(&writable, &attach, &[]).is_err());
+        let vacuum = format!("vacuum into '{}'", other.display());
+        assert!(execute(&writable, &vacuum, &[]).is_err());
+        assert!(!other.exists());
+
+        assert!(query(&writable, "pragma database_list", &[]).is_err());
+        assert!(execute(&writable, "pragma journal_mode = off", &[]).is_err());
+    }
+}
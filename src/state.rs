@@ -1,6 +1,17 @@
+use crate::config::DatabaseConfig;
+use crate::logging::{GuestStdio, GuestStream};
+use crate::replay::Tape;
+use std::collections::HashMap;
+use std::sync::Arc;
 use wasmtime::component::ResourceTable;
 use wasmtime_wasi::{WasiCtx, WasiCtxView, WasiView};
-use wasmtime_wasi_http::WasiHttpCtx;
+use wasmtime_wasi_config::WasiConfigVariables;
+use wasmtime_wasi_http::bindings::http::types::ErrorCode;
+use wasmtime_wasi_http::body::HyperOutgoingBody;
+use wasmtime_wasi_http::types::{
+    HostFutureIncomingResponse, OutgoingRequestConfig, default_send_request,
+};
+use wasmtime_wasi_http::{HttpResult, WasiHttpCtx};
 
 pub struct ComponentRunStates {
     // These two are required basically as a standard way to enable the impl of IoView and
@@ -10,17 +21,109 @@ pub struct ComponentRunStates {
     pub resource_table: ResourceTable,
     // HTTP context for WASI HTTP support
     pub http_ctx: WasiHttpCtx,
+    // Values served through wasi:config/store
+    pub config_vars: WasiConfigVariables,
+    // Name of the component owning this store, attached to guest log events
+    pub component_name: String,
+    // Hosts outgoing HTTP requests may target, unrestricted when `None`
+    pub allowed_hosts: Option<Vec<String>>,
+    // Linear memory held by the store's instances
+    pub memory: MemoryUsage,
+    // Tags of the guest's stdout and stderr lines in the log
+    pub stdio: GuestStdio,
+    // Scratch directory mounted at /tmp, removed with the store
+    pub scratch: Option<tempfile::TempDir>,
+    // Records or replays the clocks, random numbers and HTTP responses of a call
+    pub tape: Option<Arc<Tape>>,
+    // SQLite databases served through wasmic:sql/sql, by name
+    pub databases: HashMap<String, DatabaseConfig>,
+}
+
+/// Linear memory accounting for a store, installed as its resource limiter
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MemoryUsage {
+    /// Bytes of linear memory across all instances in the store
+    pub bytes: usize,
+}
+
+impl wasmtime::ResourceLimiter for MemoryUsage {
+    fn memory_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> anyhow::Result<bool> {
+        self.bytes += desired.saturating_sub(current);
+        Ok(true)
+    }
+
+    fn table_growing(
+        &mut self,
+        _current: usize,
+        _desired: usize,
+        _maximum: Option<usize>,
+    ) -> anyhow::Result<bool> {
+        Ok(true)
+    }
 }
 
 impl ComponentRunStates {
     pub fn new() -> Self {
-        let wasi_ctx = WasiCtx::builder().inherit_stdio().inherit_args().build();
+        let stdio = GuestStdio::default();
+        let wasi_ctx = WasiCtx::builder()
+            .stdout(stdio.stream(GuestStream::Stdout))
+            .stderr(stdio.stream(GuestStream::Stderr))
+            .build();
         Self {
             wasi_ctx,
             resource_table: ResourceTable::new(),
             http_ctx: WasiHttpCtx::new(),
+            config_vars: WasiConfigVariables::new(),
+            component_name: String::new(),
+            allowed_hosts: None,
+            memory: MemoryUsage::default(),
+            stdio,
+            scratch: None,
+            tape: None,
+            databases: HashMap::new(),
         }
     }
+
+    /// Serve a call with the WASI and HTTP contexts, configuration, tape and output tags of
+    /// `call`, keeping the resources and memory accounting of the instance already in the
+    /// store
+    pub fn adopt(&mut self, call: ComponentRunStates, call_id: u64) {
+        call.stdio.set_component(&self.component_name);
+        call.stdio.set_call_id(call_id);
+        // Streams the guest opened before keep writing through the previous tags
+        self.stdio.set_call_id(call_id);
+        self.wasi_ctx = call.wasi_ctx;
+        self.http_ctx = call.http_ctx;
+        self.config_vars = call.config_vars;
+        self.allowed_hosts = call.allowed_hosts;
+        self.stdio = call.stdio;
+        self.scratch = call.scratch;
+        self.tape = call.tape;
+        self.databases = call.databases;
+    }
+
+    /// Whether outgoing HTTP requests to `host` are permitted
+    pub fn is_host_allowed(&self, host: &str) -> bool {
+        self.allowed_hosts
+            .as_ref()
+            .is_none_or(|allowed| allowed.iter().any(|pattern| host_matches(pattern, host)))
+    }
+}
+
+/// Match a host against an allowlist pattern; `*.example.com` matches any subdomain
+fn host_matches(pattern: &str, host: &str) -> bool {
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host
+            .to_ascii_lowercase()
+            .strip_suffix(&domain.to_ascii_lowercase())
+            .is_some_and(|prefix| prefix.len() > 1 && prefix.ends_with('.')),
+        None => pattern.eq_ignore_ascii_case(host),
+    }
 }
 
 impl WasiView for ComponentRunStates {
@@ -40,6 +143,27 @@ impl wasmtime_wasi_http::WasiHttpView for ComponentRunStates {
     fn table(&mut self) -> &mut ResourceTable {
         &mut self.resource_table
     }
+
+    fn send_request(
+        &mut self,
+        request: hyper::Request<HyperOutgoingBody>,
+        config: OutgoingRequestConfig,
+    ) -> HttpResult<HostFutureIncomingResponse> {
+        let host = request.uri().host().unwrap_or_default();
+        if !self.is_host_allowed(host) {
+            tracing::warn!(
+                component = %self.component_name,
+                host,
+                "Denied outgoing HTTP request"
+            );
+            return Err(ErrorCode::HttpRequestDenied.into());
+        }
+
+        if let Some(tape) = &self.tape {
+            return Ok(tape.send_request(request, config));
+        }
+        Ok(default_send_request(request, config))
+    }
 }
 
 impl Default for ComponentRunStates {
@@ -47,3 +171,33 @@ impl Default for ComponentRunStates {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_matches() {
+        assert!(host_matches("api.example.com", "api.example.com"));
+        assert!(host_matches("api.example.com", "API.Example.com"));
+        assert!(!host_matches("api.example.com", "example.com"));
+
+        assert!(host_matches("*.example.com", "api.example.com"));
+        assert!(host_matches("*.example.com", "a.b.example.com"));
+        assert!(!host_matches("*.example.com", "example.com"));
+        assert!(!host_matches("*.example.com", "badexample.com"));
+    }
+
+    #[test]
+    fn test_allowed_hosts() {
+        let mut state = ComponentRunStates::new();
+        assert!(state.is_host_allowed("anything.test"));
+
+        state.allowed_hosts = Some(vec!["*.github.com".to_string()]);
+        assert!(state.is_host_allowed("api.github.com"));
+        assert!(!state.is_host_allowed("evil.test"));
+
+        state.allowed_hosts = Some(Vec::new());
+        assert!(!state.is_host_allowed("api.github.com"));
+    }
+}
@@ -0,0 +1,442 @@
+//! Runtime call statistics
+
+use crate::error::{Result, WasiMcpError};
+use crate::utils::unix_now;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Upper bounds in milliseconds of the latency histogram buckets; slower calls fall into a
+/// last, unbounded bucket
+pub const LATENCY_BUCKETS_MS: [f64; 12] = [
+    1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10000.0,
+];
+
+/// Seconds covered by the rolling error rate
+const ERROR_RATE_WINDOW_SECS: u64 = 60;
+
+/// Counters for the calls made to a tool or component
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CallStats {
+    pub calls: u64,
+    pub errors: u64,
+    pub total_duration_ms: f64,
+    pub mean_duration_ms: f64,
+    /// Calls answered from the result cache, also counted in `calls`
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub cache_hits: u64,
+    /// Message of the most recent failed call
+    pub last_error: Option<String>,
+    /// Calls per latency bucket, one more than [`LATENCY_BUCKETS_MS`]
+    #[serde(default)]
+    pub latency_buckets: Vec<u64>,
+    /// Share of the calls of the last minute that failed, as of the last snapshot
+    #[serde(default)]
+    pub error_rate_1m: f64,
+    /// `[unix second, calls, errors]` for each second of the last minute with calls
+    #[serde(default, skip_serializing_if = "VecDeque::is_empty")]
+    pub recent: VecDeque<(u64, u64, u64)>,
+}
+
+impl CallStats {
+    fn record(&mut self, duration: Duration, error: Option<&str>, now: u64) {
+        self.calls += 1;
+        let duration_ms = duration.as_secs_f64() * 1000.0;
+        self.total_duration_ms += duration_ms;
+        self.mean_duration_ms = self.total_duration_ms / self.calls as f64;
+        if let Some(error) = error {
+            self.errors += 1;
+            self.last_error = Some(error.to_string());
+        }
+
+        self.latency_buckets.resize(LATENCY_BUCKETS_MS.len() + 1, 0);
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|bound| duration_ms <= *bound)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.latency_buckets[bucket] += 1;
+
+        match self.recent.back_mut() {
+            Some((second, calls, errors)) if *second == now => {
+                *calls += 1;
+                *errors += u64::from(error.is_some());
+            }
+            _ => self.recent.push_back((now, 1, u64::from(error.is_some()))),
+        }
+        self.refresh_error_rate(now);
+    }
+
+    /// Forget calls older than the window and recompute the rolling error rate
+    fn refresh_error_rate(&mut self, now: u64) {
+        let oldest = now.saturating_sub(ERROR_RATE_WINDOW_SECS - 1);
+        while self
+            .recent
+            .front()
+            .is_some_and(|(second, _, _)| *second < oldest)
+        {
+            self.recent.pop_front();
+        }
+        let (calls, errors) = self
+            .recent
+            .iter()
+            .fold((0, 0), |(calls, errors), (_, c, e)| (calls + c, errors + e));
+        self.error_rate_1m = if calls == 0 {
+            0.0
+        } else {
+            errors as f64 / calls as f64
+        };
+    }
+
+    /// Upper bound in milliseconds of the bucket holding the `p`th percentile (0-100) of
+    /// latencies; `None` without calls or when it falls into the unbounded bucket
+    pub fn latency_percentile(&self, p: f64) -> Option<f64> {
+        let total: u64 = self.latency_buckets.iter().sum();
+        if total == 0 {
+            return None;
+        }
+        let rank = ((p / 100.0) * total as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (bucket, count) in self.latency_buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return LATENCY_BUCKETS_MS.get(bucket).copied();
+            }
+        }
+        None
+    }
+}
+
+/// Call counters per component and per tool since the executor was created
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RuntimeStats {
+    pub components: BTreeMap<String, CallStats>,
+    pub tools: BTreeMap<String, CallStats>,
+    /// Times each component was re-instantiated by recovery or the watchdog
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub restarts: BTreeMap<String, u64>,
+}
+
+impl RuntimeStats {
+    /// Record a finished call to `tool` (`component.function`)
+    pub fn record(&mut self, tool: &str, duration: Duration, error: Option<&str>) {
+        self.record_at(tool, duration, error, unix_now());
+    }
+
+    fn record_at(&mut self, tool: &str, duration: Duration, error: Option<&str>, now: u64) {
+        let component = tool
+            .split_once('.')
+            .map_or(tool, |(component, _)| component);
+        self.components
+            .entry(component.to_string())
+            .or_default()
+            .record(duration, error, now);
+        self.tools
+            .entry(tool.to_string())
+            .or_default()
+            .record(duration, error, now);
+    }
+
+    /// A copy with the rolling error rates brought up to date
+    pub fn snapshot(&self) -> Self {
+        self.snapshot_at(unix_now())
+    }
+
+    fn snapshot_at(&self, now: u64) -> Self {
+        let mut snapshot = self.clone();
+        for stats in snapshot
+            .components
+            .values_mut()
+            .chain(snapshot.tools.values_mut())
+        {
+            stats.refresh_error_rate(now);
+        }
+        snapshot
+    }
+
+    /// The statistics in the Prometheus text exposition format
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# HELP wasmic_tool_calls_total Calls made to a tool");
+        let _ = writeln!(out, "# TYPE wasmic_tool_calls_total counter");
+        for (tool, stats) in &self.tools {
+            let _ = writeln!(
+                out,
+                "wasmic_tool_calls_total{{tool=\"{}\"}} {}",
+                escape_label(tool),
+                stats.calls
+            );
+        }
+        let _ = writeln!(
+            out,
+            "# HELP wasmic_tool_errors_total Failed calls of a tool"
+        );
+        let _ = writeln!(out, "# TYPE wasmic_tool_errors_total counter");
+        for (tool, stats) in &self.tools {
+            let _ = writeln!(
+                out,
+                "wasmic_tool_errors_total{{tool=\"{}\"}} {}",
+                escape_label(tool),
+                stats.errors
+            );
+        }
+        let _ = writeln!(
+            out,
+            "# HELP wasmic_tool_error_rate Share of the calls of the last minute that failed"
+        );
+        let _ = writeln!(out, "# TYPE wasmic_tool_error_rate gauge");
+        for (tool, stats) in &self.tools {
+            let _ = writeln!(
+                out,
+                "wasmic_tool_error_rate{{tool=\"{}\"}} {}",
+                escape_label(tool),
+                stats.error_rate_1m
+            );
+        }
+        let _ = writeln!(
+            out,
+            "# HELP wasmic_tool_call_duration_seconds Latency of the calls of a tool"
+        );
+        let _ = writeln!(out, "# TYPE wasmic_tool_call_duration_seconds histogram");
+        for (tool, stats) in &self.tools {
+            let tool = escape_label(tool);
+            let mut cumulative = 0;
+            for (bucket, count) in stats.latency_buckets.iter().enumerate() {
+                cumulative += count;
+                let le = LATENCY_BUCKETS_MS
+                    .get(bucket)
+                    .map_or_else(|| "+Inf".to_string(), |ms| (ms / 1000.0).to_string());
+                let _ = writeln!(
+                    out,
+                    "wasmic_tool_call_duration_seconds_bucket{{tool=\"{tool}\",le=\"{le}\"}} {cumulative}"
+                );
+            }
+            let _ = writeln!(
+                out,
+                "wasmic_tool_call_duration_seconds_sum{{tool=\"{tool}\"}} {}",
+                stats.total_duration_ms / 1000.0
+            );
+            let _ = writeln!(
+                out,
+                "wasmic_tool_call_duration_seconds_count{{tool=\"{tool}\"}} {}",
+                stats.calls
+            );
+        }
+        let _ = writeln!(
+            out,
+            "# HELP wasmic_tool_cache_hits_total Calls of a tool answered from the result cache"
+        );
+        let _ = writeln!(out, "# TYPE wasmic_tool_cache_hits_total counter");
+        for (tool, stats) in &self.tools {
+            let _ = writeln!(
+                out,
+                "wasmic_tool_cache_hits_total{{tool=\"{}\"}} {}",
+                escape_label(tool),
+                stats.cache_hits
+            );
+        }
+        let _ = writeln!(
+            out,
+            "# HELP wasmic_component_restarts_total Re-instantiations of a component"
+        );
+        let _ = writeln!(out, "# TYPE wasmic_component_restarts_total counter");
+        for (component, restarts) in &self.restarts {
+            let _ = writeln!(
+                out,
+                "wasmic_component_restarts_total{{component=\"{}\"}} {}",
+                escape_label(component),
+                restarts
+            );
+        }
+        out
+    }
+
+    /// Record that a call to `tool` was answered from the result cache; the call itself is
+    /// recorded by [`Self::record`]
+    pub fn record_cache_hit(&mut self, tool: &str) {
+        let component = tool
+            .split_once('.')
+            .map_or(tool, |(component, _)| component);
+        self.components
+            .entry(component.to_string())
+            .or_default()
+            .cache_hits += 1;
+        self.tools.entry(tool.to_string()).or_default().cache_hits += 1;
+    }
+
+    /// Record that `component` was re-instantiated
+    pub fn record_restart(&mut self, component: &str) {
+        *self.restarts.entry(component.to_string()).or_default() += 1;
+    }
+}
+
+fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
+
+/// Escape a Prometheus label value
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+impl fmt::Display for RuntimeStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<40} {:>8} {:>8} {:>8} {:>12} {:>10}",
+            "TOOL", "CALLS", "ERRORS", "ERR% 1m", "MEAN (ms)", "P95 (ms)"
+        )?;
+        for (tool, stats) in &self.tools {
+            let p95 = stats
+                .latency_percentile(95.0)
+                .map_or_else(|| "-".to_string(), |ms| format!("<={ms}"));
+            writeln!(
+                f,
+                "{:<40} {:>8} {:>8} {:>8.1} {:>12.2} {:>10}",
+                tool,
+                stats.calls,
+                stats.errors,
+                stats.error_rate_1m * 100.0,
+                stats.mean_duration_ms,
+                p95
+            )?;
+        }
+        for (tool, stats) in &self.tools {
+            if let Some(error) = &stats.last_error {
+                writeln!(f, "\nLast error of {tool}: {error}")?;
+            }
+        }
+        for (component, restarts) in &self.restarts {
+            writeln!(f, "\n{component} was restarted {restarts} time(s)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Shared view of an executor's statistics, readable while calls are running
+pub type StatsHandle = Arc<Mutex<RuntimeStats>>;
+
+/// Fetch the statistics of a running server from its `/admin/stats` endpoint
+pub async fn fetch(address: &str, token: Option<&str>) -> Result<RuntimeStats> {
+    let mut stream = tokio::net::TcpStream::connect(address).await?;
+    let authorization = token
+        .map(|token| format!("Authorization: Bearer {token}\r\n"))
+        .unwrap_or_default();
+    let request = format!(
+        "GET /admin/stats HTTP/1.1\r\nHost: {address}\r\n{authorization}Connection: close\r\n\r\n"
+    );
+    stream.write_all(request.as_bytes()).await?;
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).await?;
+    parse_response(&String::from_utf8_lossy(&response))
+}
+
+/// Extract the statistics from a raw HTTP/1.1 response
+fn parse_response(response: &str) -> Result<RuntimeStats> {
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| WasiMcpError::Execution("Malformed response from server".to_string()))?;
+    let status = head.lines().next().unwrap_or_default();
+    if status.split_whitespace().nth(1) != Some("200") {
+        return Err(WasiMcpError::Execution(format!(
+            "Server refused the stats request: {status}"
+        )));
+    }
+    Ok(serde_json::from_str(body)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let mut stats = RuntimeStats::default();
+        stats.record("time.now", Duration::from_millis(10), None);
+        stats.record("time.now", Duration::from_millis(30), Some("trap"));
+        stats.record("time.zone", Duration::from_millis(20), None);
+
+        let component = &stats.components["time"];
+        assert_eq!(component.calls, 3);
+        assert_eq!(component.errors, 1);
+        assert!((component.mean_duration_ms - 20.0).abs() < 1e-9);
+
+        let tool = &stats.tools["time.now"];
+        assert_eq!(tool.calls, 2);
+        assert_eq!(tool.last_error.as_deref(), Some("trap"));
+        assert_eq!(stats.tools["time.zone"].errors, 0);
+        assert_eq!(tool.latency_buckets[2], 1);
+        assert_eq!(tool.latency_buckets[4], 1);
+        assert_eq!(tool.latency_percentile(50.0), Some(10.0));
+        assert_eq!(tool.latency_percentile(95.0), Some(50.0));
+
+        stats.record("time.now", Duration::from_millis(1), None);
+        stats.record_cache_hit("time.now");
+        assert_eq!(stats.tools["time.now"].calls, 3);
+        assert_eq!(stats.tools["time.now"].cache_hits, 1);
+        assert_eq!(stats.components["time"].cache_hits, 1);
+
+        assert!(stats.restarts.is_empty());
+        stats.record_restart("time");
+        stats.record_restart("time");
+        assert_eq!(stats.restarts["time"], 2);
+    }
+
+    #[test]
+    fn test_rolling_error_rate() {
+        let mut stats = RuntimeStats::default();
+        stats.record_at("time.now", Duration::from_millis(1), Some("trap"), 100);
+        stats.record_at("time.now", Duration::from_millis(1), None, 130);
+        assert_eq!(stats.tools["time.now"].error_rate_1m, 0.5);
+
+        // The failure ages out of the window
+        assert_eq!(stats.snapshot_at(159).tools["time.now"].error_rate_1m, 0.5);
+        assert_eq!(stats.snapshot_at(160).tools["time.now"].error_rate_1m, 0.0);
+        assert_eq!(stats.snapshot_at(500).tools["time.now"].error_rate_1m, 0.0);
+        assert_eq!(stats.tools["time.now"].errors, 1);
+    }
+
+    #[test]
+    fn test_prometheus() {
+        let mut stats = RuntimeStats::default();
+        stats.record("time.now", Duration::from_millis(7), None);
+        stats.record("time.now", Duration::from_secs(20), Some("timeout"));
+        stats.record_cache_hit("time.now");
+        stats.record_restart("time");
+        let text = stats.to_prometheus();
+
+        assert!(text.contains("wasmic_tool_calls_total{tool=\"time.now\"} 2\n"));
+        assert!(text.contains("wasmic_tool_errors_total{tool=\"time.now\"} 1\n"));
+        assert!(text.contains(
+            "wasmic_tool_call_duration_seconds_bucket{tool=\"time.now\",le=\"0.005\"} 0\n"
+        ));
+        assert!(text.contains(
+            "wasmic_tool_call_duration_seconds_bucket{tool=\"time.now\",le=\"0.01\"} 1\n"
+        ));
+        assert!(text.contains(
+            "wasmic_tool_call_duration_seconds_bucket{tool=\"time.now\",le=\"+Inf\"} 2\n"
+        ));
+        assert!(text.contains("wasmic_tool_cache_hits_total{tool=\"time.now\"} 1\n"));
+        assert!(text.contains("wasmic_component_restarts_total{component=\"time\"} 1\n"));
+        assert_eq!(escape_label("a\"b\\"), "a\\\"b\\\\");
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let mut stats = RuntimeStats::default();
+        stats.record("time.now", Duration::from_millis(10), None);
+        let body = serde_json::to_string(&stats).unwrap();
+
+        let response = format!("HTTP/1.1 200 OK\r\ncontent-type: application/json\r\n\r\n{body}");
+        assert_eq!(parse_response(&response).unwrap(), stats);
+
+        let denied = "HTTP/1.1 401 Unauthorized\r\ncontent-length: 0\r\n\r\n";
+        assert!(parse_response(denied).is_err());
+    }
+}
@@ -0,0 +1,303 @@
+//! Example calls with expected outcomes, run by `wasmic test`
+//!
+//! A test case calls a tool and checks the outcome: the whole result with `expect`, parts of
+//! it with `assert`, or the error code with `expect_error`. A case without expectations passes
+//! when the call succeeds; a WIT `result` the guest returned as `err` counts as a failed call.
+//! Cases come from the `tests` section of the configuration or from a separate file.
+
+use crate::config::{Assertion, TestCase};
+use crate::error::{Result, WasiMcpError};
+use crate::utils::transform::result_error;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::path::Path;
+
+/// Test cases of a separate file: either a list of cases or an object with a `tests` list
+pub fn load(path: &Path) -> Result<Vec<TestCase>> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum TestsFile {
+        List(Vec<TestCase>),
+        Section { tests: Vec<TestCase> },
+    }
+
+    let content = std::fs::read_to_string(path)?;
+    match serde_yaml::from_str(&content) {
+        Ok(TestsFile::List(tests) | TestsFile::Section { tests }) => Ok(tests),
+        Err(e) => Err(WasiMcpError::InvalidArguments(format!(
+            "Invalid tests file {}: {e}",
+            path.display()
+        ))),
+    }
+}
+
+/// `expect_error` code matching any error the guest returned, whatever its value
+pub const GUEST_ERROR: &str = "guest";
+
+/// Why the outcome of a call doesn't meet the expectations of a test case; empty if it does
+pub fn check(case: &TestCase, outcome: &Result<Value>) -> Vec<String> {
+    let returned = outcome.as_ref().ok().and_then(result_error);
+    if let Some(error) = returned {
+        return match &case.expect_error {
+            Some(code) if code == GUEST_ERROR || error_name(error) == Some(code.as_str()) => {
+                Vec::new()
+            }
+            Some(code) => vec![format!("expected a '{code}' error, got {error}")],
+            None => vec![format!("call returned an error: {error}")],
+        };
+    }
+
+    let result = match (outcome, &case.expect_error) {
+        (Err(e), Some(code)) if e.code().as_str() == code => return Vec::new(),
+        (Err(e), Some(code)) => {
+            return vec![format!(
+                "expected a '{code}' error, got '{}': {e}",
+                e.code()
+            )];
+        }
+        (Ok(result), Some(code)) => {
+            return vec![format!("expected a '{code}' error, got {result}")];
+        }
+        (Err(e), None) => return vec![format!("call failed: {e}")],
+        (Ok(result), None) => result,
+    };
+
+    let mut failures = Vec::new();
+    if let Some(expected) = &case.expect
+        && result != expected
+    {
+        failures.push(format!("expected {expected}, got {result}"));
+    }
+    for assertion in &case.assertions {
+        failures.extend(check_assertion(assertion, result));
+    }
+    failures
+}
+
+/// Name of an error value the guest returned: a string, enum case or variant tag
+fn error_name(error: &Value) -> Option<&str> {
+    match error {
+        Value::String(name) => Some(name),
+        Value::Object(object) => object.get("tag")?.as_str(),
+        _ => None,
+    }
+}
+
+fn check_assertion(assertion: &Assertion, result: &Value) -> Vec<String> {
+    let path = if assertion.path.is_empty() {
+        "result"
+    } else {
+        assertion.path.as_str()
+    };
+    let value = result.pointer(&assertion.path);
+    let mut failures = Vec::new();
+
+    if let Some(exists) = assertion.exists
+        && exists != value.is_some()
+    {
+        failures.push(if exists {
+            format!("{path}: expected a value")
+        } else {
+            format!("{path}: expected no value")
+        });
+    }
+    let needs_value =
+        assertion.equals.is_some() || assertion.contains.is_some() || assertion.kind.is_some();
+    let Some(value) = value else {
+        if needs_value {
+            failures.push(format!("{path}: no value"));
+        }
+        return failures;
+    };
+
+    if let Some(expected) = &assertion.equals
+        && value != expected
+    {
+        failures.push(format!("{path}: expected {expected}, got {value}"));
+    }
+    if let Some(needle) = &assertion.contains {
+        let contained = match (value, needle) {
+            (Value::String(haystack), Value::String(needle)) => haystack.contains(needle.as_str()),
+            (Value::Array(items), needle) => items.contains(needle),
+            _ => false,
+        };
+        if !contained {
+            failures.push(format!("{path}: {value} does not contain {needle}"));
+        }
+    }
+    if let Some(kind) = &assertion.kind
+        && json_type(value) != kind
+    {
+        failures.push(format!(
+            "{path}: expected a value of type {kind}, got {}",
+            json_type(value)
+        ));
+    }
+    failures
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Outcome of one test case
+#[derive(Debug, Clone, Serialize)]
+pub struct TestResult {
+    pub name: String,
+    pub tool: String,
+    pub duration_ms: u64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub failures: Vec<String>,
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Outcomes of a test run
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TestReport {
+    pub passed: usize,
+    pub failed: usize,
+    pub tests: Vec<TestResult>,
+}
+
+impl TestReport {
+    pub fn push(&mut self, result: TestResult) {
+        if result.passed() {
+            self.passed += 1;
+        } else {
+            self.failed += 1;
+        }
+        self.tests.push(result);
+    }
+}
+
+impl fmt::Display for TestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for test in &self.tests {
+            let status = if test.passed() { "PASS" } else { "FAIL" };
+            writeln!(f, "{status}  {} ({}ms)", test.name, test.duration_ms)?;
+            for failure in &test.failures {
+                writeln!(f, "      {failure}")?;
+            }
+        }
+        write!(f, "\n{} passed, {} failed", self.passed, self.failed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::transform::wasm_to_json;
+    use serde_json::json;
+    use wasmtime::component::Val;
+
+    fn case(yaml: &str) -> TestCase {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_check_outcomes() {
+        let exact = case("tool: hello.greet\nexpect: Hello");
+        assert!(check(&exact, &Ok(json!("Hello"))).is_empty());
+        assert_eq!(
+            check(&exact, &Ok(json!("Bye"))),
+            vec![r#"expected "Hello", got "Bye""#]
+        );
+        assert_eq!(
+            check(&exact, &Err(WasiMcpError::Execution("boom".to_string()))),
+            vec!["call failed: Execution error: boom"]
+        );
+
+        let failing = case("tool: db.query\nexpect_error: validation");
+        let invalid = WasiMcpError::InvalidArguments("missing sql".to_string());
+        assert!(check(&failing, &Err(invalid)).is_empty());
+        assert_eq!(
+            check(&failing, &Ok(json!(1))),
+            vec!["expected a 'validation' error, got 1"]
+        );
+
+        let smoke = case("tool: time.now");
+        assert!(check(&smoke, &Ok(json!(null))).is_empty());
+
+        // Errors the guest returns, in the encoding of the converter
+        let returned = Val::Result(Err(Some(Box::new(Val::Enum("not-found".to_string())))));
+        let returned = Ok(wasm_to_json(&returned).unwrap());
+        assert_eq!(
+            check(&smoke, &returned),
+            vec![r#"call returned an error: "not-found""#]
+        );
+        assert!(check(&case("tool: db.get\nexpect_error: not-found"), &returned).is_empty());
+        assert!(check(&case("tool: db.get\nexpect_error: guest"), &returned).is_empty());
+        assert_eq!(
+            check(&failing, &returned),
+            vec![r#"expected a 'validation' error, got "not-found""#]
+        );
+    }
+
+    #[test]
+    fn test_assertions() {
+        let asserted = case(
+            r#"
+tool: db.query
+assert:
+  - path: /rows/0
+    equals: [1]
+  - path: /columns
+    type: array
+    contains: id
+  - path: /title
+    contains: report
+  - path: /error
+    exists: false
+"#,
+        );
+        let result = json!({"rows": [[1]], "columns": ["id"], "title": "monthly report"});
+        assert!(check(&asserted, &Ok(result)).is_empty());
+
+        let result = json!({"rows": [[2]], "columns": "name", "error": "x"});
+        assert_eq!(
+            check(&asserted, &Ok(result)),
+            vec![
+                "/rows/0: expected [1], got [2]",
+                r#"/columns: "name" does not contain "id""#,
+                "/columns: expected a value of type array, got string",
+                "/title: no value",
+                "/error: expected no value",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_report() {
+        let mut report = TestReport::default();
+        report.push(TestResult {
+            name: "greets".to_string(),
+            tool: "hello.greet".to_string(),
+            duration_ms: 3,
+            failures: Vec::new(),
+        });
+        report.push(TestResult {
+            name: "hello.fail".to_string(),
+            tool: "hello.fail".to_string(),
+            duration_ms: 1,
+            failures: vec!["call failed: boom".to_string()],
+        });
+        assert_eq!((report.passed, report.failed), (1, 1));
+        assert_eq!(
+            report.to_string(),
+            "PASS  greets (3ms)\nFAIL  hello.fail (1ms)\n      call failed: boom\n\n1 passed, 1 failed"
+        );
+    }
+}
@@ -0,0 +1,123 @@
+//! Remote trace context of HTTP requests
+//!
+//! Callers that trace their requests send a W3C `traceparent` header. Tool calls made over
+//! HTTP run in a `remote_call` span carrying the caller's trace and span ids, so the call's
+//! spans and logs can be joined with the caller's trace.
+
+use axum::http::HeaderMap;
+use tracing::Span;
+
+/// Header carrying the trace context, see <https://www.w3.org/TR/trace-context/>
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// Trace context propagated by a caller
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    /// 32 hex digits identifying the whole trace
+    pub trace_id: String,
+    /// 16 hex digits identifying the caller's span
+    pub parent_id: String,
+    pub sampled: bool,
+}
+
+impl TraceContext {
+    /// Parse a `traceparent` header value, `None` if it's malformed
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.trim().split('-');
+        let version = parts.next()?;
+        let trace_id = parts.next()?;
+        let parent_id = parts.next()?;
+        let flags = parts.next()?;
+        // Version 00 has exactly four fields; later versions may append more
+        if version == "ff" || (version == "00" && parts.next().is_some()) {
+            return None;
+        }
+        let is_hex = |s: &str, len: usize| {
+            s.len() == len && s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+        };
+        if !is_hex(version, 2)
+            || !is_hex(trace_id, 32)
+            || !is_hex(parent_id, 16)
+            || !is_hex(flags, 2)
+        {
+            return None;
+        }
+        // All-zero ids are invalid
+        if trace_id.bytes().all(|b| b == b'0') || parent_id.bytes().all(|b| b == b'0') {
+            return None;
+        }
+        let flags = u8::from_str_radix(flags, 16).ok()?;
+        Some(Self {
+            trace_id: trace_id.to_string(),
+            parent_id: parent_id.to_string(),
+            sampled: flags & 1 == 1,
+        })
+    }
+
+    /// Trace context of a request, from its `traceparent` header
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        Self::parse(headers.get(TRACEPARENT_HEADER)?.to_str().ok()?)
+    }
+}
+
+/// Span for a tool call received over HTTP, linked to the caller's trace if it sent one
+pub fn remote_call_span(transport: &'static str, headers: &HeaderMap) -> Span {
+    match TraceContext::from_headers(headers) {
+        Some(context) => tracing::info_span!(
+            "remote_call",
+            transport,
+            trace_id = %context.trace_id,
+            parent_span_id = %context.parent_id,
+            sampled = context.sampled,
+        ),
+        None => tracing::info_span!("remote_call", transport),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_traceparent() {
+        let context =
+            TraceContext::parse("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").unwrap();
+        assert_eq!(context.trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(context.parent_id, "00f067aa0ba902b7");
+        assert!(context.sampled);
+
+        let unsampled =
+            TraceContext::parse("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00").unwrap();
+        assert!(!unsampled.sampled);
+
+        // Future versions may carry more fields
+        assert!(
+            TraceContext::parse("01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-xyz")
+                .is_some()
+        );
+
+        for invalid in [
+            "",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e473-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-1",
+        ] {
+            assert_eq!(TraceContext::parse(invalid), None, "{invalid}");
+        }
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(TraceContext::from_headers(&headers), None);
+        headers.insert(
+            TRACEPARENT_HEADER,
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+                .parse()
+                .unwrap(),
+        );
+        assert_eq!(TraceContext::from_headers(&headers), Some(context));
+    }
+}
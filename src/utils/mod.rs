@@ -1,2 +1,24 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
 pub mod transform;
 pub mod wasm;
+
+/// Seconds since the Unix epoch
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// A tool name usable in file names; tools of exported interfaces contain `:`, `/` and `@`
+pub(crate) fn file_safe(tool: &str) -> String {
+    tool.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
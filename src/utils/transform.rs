@@ -1,7 +1,46 @@
 use crate::error::{Result, WasiMcpError};
+use crate::logging::CONVERSION_TARGET;
+use crate::utils::wasm::{convert_wasm_type_to_json, convert_wasm_type_to_json_with_int64};
 use serde_json::Value;
 use wasmtime::component::Val;
 
+/// Options controlling how WASM values are encoded as JSON
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConversionOptions {
+    /// Encode `u64`/`s64` values as JSON strings instead of numbers
+    pub int64_as_string: bool,
+}
+
+impl ConversionOptions {
+    /// JSON schema of arguments of type `ty`; string-encoded 64-bit integers are accepted
+    /// alongside numbers
+    pub fn input_schema(&self, ty: &wasmtime::component::Type) -> Value {
+        if self.int64_as_string {
+            let int64 = serde_json::json!({"type": ["integer", "string"], "pattern": "^-?[0-9]+$"});
+            convert_wasm_type_to_json_with_int64(ty, &int64)
+        } else {
+            convert_wasm_type_to_json(ty)
+        }
+    }
+
+    /// JSON schema of results of type `ty`
+    pub fn output_schema(&self, ty: &wasmtime::component::Type) -> Value {
+        if self.int64_as_string {
+            convert_wasm_type_to_json_with_int64(ty, &serde_json::json!("string"))
+        } else {
+            convert_wasm_type_to_json(ty)
+        }
+    }
+}
+
+impl From<&crate::config::Config> for ConversionOptions {
+    fn from(config: &crate::config::Config) -> Self {
+        Self {
+            int64_as_string: config.int64_as_string,
+        }
+    }
+}
+
 /// Convert a serde_json::Value to a wasmtime::component::Val
 #[allow(unused)]
 fn to_wasm(json_value: &Value) -> Result<Val> {
@@ -13,6 +52,36 @@ pub fn to_wasm_with_type(
     json_value: &Value,
     wasm_type: Option<&wasmtime::component::Type>,
 ) -> Result<Val> {
+    // Types whose JSON representation differs from the untyped mapping are handled first
+    match wasm_type {
+        Some(wasmtime::component::Type::Variant(variant)) => {
+            return variant_to_wasm(json_value, variant);
+        }
+        Some(wasmtime::component::Type::Enum(enum_ty)) => {
+            return enum_to_wasm(json_value, enum_ty);
+        }
+        Some(wasmtime::component::Type::Flags(flags)) => {
+            return flags_to_wasm(json_value, flags);
+        }
+        Some(wasmtime::component::Type::List(list)) => {
+            return list_to_wasm(json_value, list);
+        }
+        Some(wasmtime::component::Type::Tuple(tuple)) => {
+            return tuple_to_wasm(json_value, tuple);
+        }
+        Some(wasmtime::component::Type::Option(option)) => {
+            // `null` is `none`, anything else is converted as the payload of `some`
+            return match json_value {
+                Value::Null => Ok(Val::Option(None)),
+                value => Ok(Val::Option(Some(Box::new(convert_json_to_wasm_value(
+                    value,
+                    &option.ty(),
+                )?)))),
+            };
+        }
+        _ => {}
+    }
+
     match json_value {
         Value::Null => Ok(Val::String("null".to_string())),
         Value::Bool(b) => Ok(Val::Bool(*b)),
@@ -189,9 +258,8 @@ pub fn to_wasm_with_type(
                 // Add fields in the expected order
                 for field in record_type.fields() {
                     let field_name = field.name;
-                    let field_type = field.ty.clone();
                     if let Some(field_value) = obj_map.get(field_name) {
-                        let wasm_val = to_wasm_with_type(field_value, Some(&field_type))?;
+                        let wasm_val = convert_json_to_wasm_value(field_value, &field.ty)?;
                         record_fields.push((field_name.to_string(), wasm_val));
                     } else {
                         return Err(WasiMcpError::InvalidArguments(format!(
@@ -224,8 +292,190 @@ pub fn to_wasm_with_type(
     }
 }
 
+/// Convert a JSON value to a WASM variant
+///
+/// Accepts `{ "tag": "<case>", "value": <payload> }`, or the bare case name as a string for
+/// cases without a payload.
+fn variant_to_wasm(
+    json_value: &Value,
+    variant: &wasmtime::component::types::Variant,
+) -> Result<Val> {
+    let (tag, payload) = match json_value {
+        Value::String(tag) => (tag.as_str(), None),
+        Value::Object(obj) => {
+            let tag = obj.get("tag").and_then(Value::as_str).ok_or_else(|| {
+                WasiMcpError::InvalidArguments(format!(
+                    "Expected variant object with a string 'tag', got: {json_value}",
+                ))
+            })?;
+
+            if let Some(field_name) = obj.keys().find(|k| *k != "tag" && *k != "value") {
+                return Err(WasiMcpError::InvalidArguments(format!(
+                    "Unexpected field in variant: '{field_name}'",
+                )));
+            }
+
+            (tag, obj.get("value"))
+        }
+        _ => {
+            return Err(WasiMcpError::UnexpectedExpected(
+                "variant".to_string(),
+                json_value.to_string(),
+            ));
+        }
+    };
+
+    let case = variant
+        .cases()
+        .find(|case| case.name == tag)
+        .ok_or_else(|| {
+            let cases: Vec<&str> = variant.cases().map(|case| case.name).collect();
+            WasiMcpError::InvalidArguments(format!(
+                "Unknown variant case '{tag}', expected one of: {}",
+                cases.join(", ")
+            ))
+        })?;
+
+    let payload = match (case.ty, payload) {
+        (Some(ty), Some(value)) => Some(Box::new(convert_json_to_wasm_value(value, &ty)?)),
+        (Some(_), None) => {
+            return Err(WasiMcpError::InvalidArguments(format!(
+                "Variant case '{tag}' requires a 'value'",
+            )));
+        }
+        (None, None | Some(Value::Null)) => None,
+        (None, Some(value)) => {
+            return Err(WasiMcpError::InvalidArguments(format!(
+                "Variant case '{tag}' takes no value, got: {value}",
+            )));
+        }
+    };
+
+    Ok(Val::Variant(tag.to_string(), payload))
+}
+
+/// Convert a JSON array to a WASM list, converting every element with the list's element type
+fn list_to_wasm(json_value: &Value, list: &wasmtime::component::types::List) -> Result<Val> {
+    let Some(entries) = json_value.as_array() else {
+        return Err(WasiMcpError::UnexpectedExpected(
+            "array".to_string(),
+            json_value.to_string(),
+        ));
+    };
+
+    let element_type = list.ty();
+    let elements = entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            convert_json_to_wasm_value(entry, &element_type).map_err(|e| {
+                WasiMcpError::InvalidArguments(format!("Invalid list element at index {i}: {e}"))
+            })
+        })
+        .collect::<Result<Vec<Val>>>()?;
+
+    Ok(Val::List(elements))
+}
+
+/// Convert a JSON array to a WASM tuple, converting each element with its positional type
+fn tuple_to_wasm(json_value: &Value, tuple: &wasmtime::component::types::Tuple) -> Result<Val> {
+    let Some(entries) = json_value.as_array() else {
+        return Err(WasiMcpError::UnexpectedExpected(
+            "array".to_string(),
+            json_value.to_string(),
+        ));
+    };
+
+    let types = tuple.types();
+    if entries.len() != types.len() {
+        return Err(WasiMcpError::InvalidArguments(format!(
+            "Expected tuple of {} elements, got {}",
+            types.len(),
+            entries.len()
+        )));
+    }
+
+    let elements = entries
+        .iter()
+        .zip(types)
+        .enumerate()
+        .map(|(i, (entry, ty))| {
+            convert_json_to_wasm_value(entry, &ty).map_err(|e| {
+                WasiMcpError::InvalidArguments(format!("Invalid tuple element at index {i}: {e}"))
+            })
+        })
+        .collect::<Result<Vec<Val>>>()?;
+
+    Ok(Val::Tuple(elements))
+}
+
+/// Convert a JSON string to a WASM enum, validating it against the declared case names
+fn enum_to_wasm(json_value: &Value, enum_ty: &wasmtime::component::types::Enum) -> Result<Val> {
+    let Some(name) = json_value.as_str() else {
+        return Err(WasiMcpError::UnexpectedExpected(
+            "enum case name".to_string(),
+            json_value.to_string(),
+        ));
+    };
+
+    if !enum_ty.names().any(|case| case == name) {
+        let cases: Vec<&str> = enum_ty.names().collect();
+        return Err(WasiMcpError::InvalidArguments(format!(
+            "Unknown enum case '{name}', expected one of: {}",
+            cases.join(", ")
+        )));
+    }
+
+    Ok(Val::Enum(name.to_string()))
+}
+
+/// Convert a JSON array of flag names to WASM flags
+///
+/// Every entry must be one of the declared flag names and may appear only once.
+fn flags_to_wasm(json_value: &Value, flags: &wasmtime::component::types::Flags) -> Result<Val> {
+    let Some(entries) = json_value.as_array() else {
+        return Err(WasiMcpError::UnexpectedExpected(
+            "array of flag names".to_string(),
+            json_value.to_string(),
+        ));
+    };
+
+    let mut set = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let Some(name) = entry.as_str() else {
+            return Err(WasiMcpError::UnexpectedExpected(
+                "flag name".to_string(),
+                entry.to_string(),
+            ));
+        };
+
+        if !flags.names().any(|flag| flag == name) {
+            let names: Vec<&str> = flags.names().collect();
+            return Err(WasiMcpError::InvalidArguments(format!(
+                "Unknown flag '{name}', expected any of: {}",
+                names.join(", ")
+            )));
+        }
+
+        if set.iter().any(|flag: &String| flag == name) {
+            return Err(WasiMcpError::InvalidArguments(format!(
+                "Duplicate flag '{name}'",
+            )));
+        }
+
+        set.push(name.to_string());
+    }
+
+    Ok(Val::Flags(set))
+}
+
 /// Convert a wasmtime::component::Val to a serde_json::Value
 pub fn wasm_to_json(wasm_value: &Val) -> Result<Value> {
+    wasm_to_json_with_options(wasm_value, &ConversionOptions::default())
+}
+
+/// Convert a wasmtime::component::Val to a serde_json::Value using the given encoding options
+pub fn wasm_to_json_with_options(wasm_value: &Val, options: &ConversionOptions) -> Result<Value> {
     match wasm_value {
         Val::Bool(b) => Ok(Value::Bool(*b)),
         Val::S8(i) => Ok(Value::Number(serde_json::Number::from(*i))),
@@ -234,6 +484,8 @@ pub fn wasm_to_json(wasm_value: &Val) -> Result<Value> {
         Val::U16(u) => Ok(Value::Number(serde_json::Number::from(*u))),
         Val::S32(i) => Ok(Value::Number(serde_json::Number::from(*i))),
         Val::U32(u) => Ok(Value::Number(serde_json::Number::from(*u))),
+        Val::S64(i) if options.int64_as_string => Ok(Value::String(i.to_string())),
+        Val::U64(u) if options.int64_as_string => Ok(Value::String(u.to_string())),
         Val::S64(i) => Ok(Value::Number(serde_json::Number::from(*i))),
         Val::U64(u) => Ok(Value::Number(serde_json::Number::from(*u))),
         Val::Float32(f) => Ok(Value::Number(
@@ -245,33 +497,41 @@ pub fn wasm_to_json(wasm_value: &Val) -> Result<Value> {
         Val::Char(c) => Ok(Value::String(c.to_string())),
         Val::String(s) => Ok(Value::String(s.clone())),
         Val::List(vals) => {
-            let json_values: Result<Vec<Value>> = vals.iter().map(wasm_to_json).collect();
+            let json_values: Result<Vec<Value>> = vals
+                .iter()
+                .map(|val| wasm_to_json_with_options(val, options))
+                .collect();
             Ok(Value::Array(json_values?))
         }
         Val::Record(fields) => {
             let mut obj = serde_json::Map::new();
             for (key, val) in fields {
-                obj.insert(key.clone(), wasm_to_json(val)?);
+                obj.insert(key.clone(), wasm_to_json_with_options(val, options)?);
             }
             Ok(Value::Object(obj))
         }
         Val::Tuple(vals) => {
-            let json_values: Result<Vec<Value>> = vals.iter().map(wasm_to_json).collect();
+            let json_values: Result<Vec<Value>> = vals
+                .iter()
+                .map(|val| wasm_to_json_with_options(val, options))
+                .collect();
             Ok(Value::Array(json_values?))
         }
         Val::Variant(name, value) => {
+            // The shape `variant_to_wasm` parses, so results can be passed back as arguments
             let mut obj = serde_json::Map::new();
-            obj.insert("variant".to_string(), Value::String(name.clone()));
+            obj.insert("tag".to_string(), Value::String(name.clone()));
             if let Some(val) = value {
-                obj.insert("value".to_string(), wasm_to_json(val)?);
-            } else {
-                obj.insert("value".to_string(), Value::Null);
+                obj.insert(
+                    "value".to_string(),
+                    wasm_to_json_with_options(val, options)?,
+                );
             }
             Ok(Value::Object(obj))
         }
         Val::Enum(name) => Ok(Value::String(name.clone())),
         Val::Option(opt_val) => match opt_val {
-            Some(val) => wasm_to_json(val),
+            Some(val) => wasm_to_json_with_options(val, options),
             None => Ok(Value::Null),
         },
         Val::Result(result) => match result {
@@ -279,7 +539,10 @@ pub fn wasm_to_json(wasm_value: &Val) -> Result<Value> {
                 let mut obj = serde_json::Map::new();
                 obj.insert("result".to_string(), Value::String("ok".to_string()));
                 if let Some(val) = ok_val {
-                    obj.insert("value".to_string(), wasm_to_json(val)?);
+                    obj.insert(
+                        "value".to_string(),
+                        wasm_to_json_with_options(val, options)?,
+                    );
                 } else {
                     obj.insert("value".to_string(), Value::Null);
                 }
@@ -289,7 +552,10 @@ pub fn wasm_to_json(wasm_value: &Val) -> Result<Value> {
                 let mut obj = serde_json::Map::new();
                 obj.insert("result".to_string(), Value::String("error".to_string()));
                 if let Some(val) = err_val {
-                    obj.insert("value".to_string(), wasm_to_json(val)?);
+                    obj.insert(
+                        "value".to_string(),
+                        wasm_to_json_with_options(val, options)?,
+                    );
                 } else {
                     obj.insert("value".to_string(), Value::Null);
                 }
@@ -308,19 +574,74 @@ pub fn wasm_to_json(wasm_value: &Val) -> Result<Value> {
 }
 
 /// Convert WASM result values to JSON with proper formatting
-pub fn convert_wasm_results_to_json(wasm_results: &[Val]) -> Result<Value> {
-    match wasm_results.len() {
+pub fn convert_wasm_results_to_json(
+    wasm_results: &[Val],
+    options: &ConversionOptions,
+) -> Result<Value> {
+    for (index, val) in wasm_results.iter().enumerate() {
+        tracing::debug!(target: CONVERSION_TARGET, index, val = ?val, "Result returned");
+    }
+    let converted = match wasm_results.len() {
         0 => Ok(Value::String(
             "Successfully executed (no return value)".to_string(),
         )),
-        1 => wasm_to_json(&wasm_results[0]),
+        1 => wasm_to_json_with_options(&wasm_results[0], options),
         _ => {
-            let json_results: Result<Vec<Value>> = wasm_results.iter().map(wasm_to_json).collect();
+            let json_results: Result<Vec<Value>> = wasm_results
+                .iter()
+                .map(|val| wasm_to_json_with_options(val, options))
+                .collect();
             Ok(Value::Array(json_results?))
         }
+    };
+    if let Ok(json) = &converted {
+        tracing::debug!(target: CONVERSION_TARGET, json = %json, "Results converted");
+    }
+    converted
+}
+
+/// The payload of a call that returned a WIT `result` ending in `err`, from the
+/// `{"result": "error", "value": ...}` encoding of [`wasm_to_json_with_options`]
+pub fn result_error(value: &Value) -> Option<&Value> {
+    let object = value.as_object()?;
+    if object.len() != 2 || object.get("result")?.as_str()? != "error" {
+        return None;
+    }
+    object.get("value")
+}
+
+/// Key of the `index`th of `count` results in a tool's structured output
+///
+/// WIT results are a single anonymous value, named `result`; the values of functions with
+/// several results are numbered from 1.
+pub fn result_key(index: usize, count: usize) -> String {
+    if count == 1 {
+        "result".to_string()
+    } else {
+        format!("result_{}", index + 1)
     }
 }
 
+/// Wrap the JSON form of `count` results into the object the tool's output schema describes
+///
+/// Returns `None` for functions without results, and for values that aren't the results of
+/// the call, such as their success message.
+pub fn structure_results(value: &Value, count: usize) -> Option<Value> {
+    let values = match (count, value) {
+        (0, _) => return None,
+        (1, value) => std::slice::from_ref(value),
+        (_, Value::Array(values)) if values.len() == count => values.as_slice(),
+        _ => return None,
+    };
+    Some(Value::Object(
+        values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| (result_key(index, count), value.clone()))
+            .collect(),
+    ))
+}
+
 /// Convert JSON arguments to WASM values using the transformer
 pub fn convert_args_to_wasm_values(
     arguments: &[serde_json::Value],
@@ -335,6 +656,13 @@ pub fn convert_args_to_wasm_values(
                 param_info.name, i, e
             ))
         })?;
+        tracing::debug!(
+            target: CONVERSION_TARGET,
+            param = %param_info.name,
+            json = %arg,
+            val = ?wasm_val,
+            "Argument converted"
+        );
         wasm_values.push(wasm_val);
     }
     Ok(wasm_values)
@@ -470,6 +798,9 @@ fn convert_json_to_wasm_value(
         wasmtime::component::Type::S64 => {
             if let Some(n) = json_value.as_i64() {
                 Ok(wasmtime::component::Val::S64(n))
+            } else if let Some(n) = json_value.as_str().and_then(|s| s.parse().ok()) {
+                // Accept string-encoded integers for lossless 64-bit transport
+                Ok(wasmtime::component::Val::S64(n))
             } else {
                 Err(WasiMcpError::InvalidArguments(format!(
                     "Expected s64, got: {}",
@@ -480,6 +811,9 @@ fn convert_json_to_wasm_value(
         wasmtime::component::Type::U64 => {
             if let Some(n) = json_value.as_u64() {
                 Ok(wasmtime::component::Val::U64(n))
+            } else if let Some(n) = json_value.as_str().and_then(|s| s.parse().ok()) {
+                // Accept string-encoded integers for lossless 64-bit transport
+                Ok(wasmtime::component::Val::U64(n))
             } else {
                 Err(WasiMcpError::InvalidArguments(format!(
                     "Expected u64, got: {}",
@@ -556,6 +890,60 @@ mod tests {
     use super::*;
     use serde_json::json;
     use wasmtime::component::Type;
+    use wasmtime::component::types::ComponentItem;
+
+    /// Build a component importing `f(x: <param>)` and return the type of `x`
+    fn param_type(types: &str, param: &str) -> Type {
+        let engine = wasmtime::Engine::default();
+        let wat = format!(r#"(component {types} (import "f" (func (param "x" {param}))))"#);
+        let component = wasmtime::component::Component::new(&engine, wat).unwrap();
+        let (_, item) = component
+            .component_type()
+            .imports(&engine)
+            .find(|(name, _)| *name == "f")
+            .unwrap();
+        match item {
+            ComponentItem::ComponentFunc(func) => func.params().next().unwrap().1,
+            _ => panic!("Expected a component function import"),
+        }
+    }
+
+    fn option_type() -> Type {
+        param_type("(type $o (option u32))", "$o")
+    }
+
+    fn enum_type() -> Type {
+        param_type(
+            r#"(type $def (enum "red" "green"))
+               (import "e" (type $e (eq $def)))"#,
+            "$e",
+        )
+    }
+
+    fn flags_type() -> Type {
+        param_type(
+            r#"(type $def (flags "read" "write" "exec"))
+               (import "p" (type $p (eq $def)))"#,
+            "$p",
+        )
+    }
+
+    fn enum_list_type() -> Type {
+        param_type(
+            r#"(type $def (enum "red" "green"))
+               (import "e" (type $e (eq $def)))
+               (type $l (list $e))"#,
+            "$l",
+        )
+    }
+
+    fn variant_type() -> Type {
+        param_type(
+            r#"(type $def (variant (case "none") (case "some" u32)))
+               (import "v" (type $v (eq $def)))"#,
+            "$v",
+        )
+    }
 
     #[test]
     fn test_json_bool_to_wasm() {
@@ -634,6 +1022,20 @@ mod tests {
         assert_eq!(json_val, json!({"name": "test", "value": 42}));
     }
 
+    #[test]
+    fn test_structure_results() {
+        assert_eq!(structure_results(&json!("done"), 0), None);
+        assert_eq!(
+            structure_results(&json!([1, 2]), 1),
+            Some(json!({"result": [1, 2]}))
+        );
+        assert_eq!(
+            structure_results(&json!(["a", true]), 2),
+            Some(json!({"result_1": "a", "result_2": true}))
+        );
+        assert_eq!(structure_results(&json!("exited"), 2), None);
+    }
+
     #[test]
     fn test_type_checked_conversion() {
         let json_val = Value::Number(serde_json::Number::from(100));
@@ -661,5 +1063,182 @@ mod tests {
             }
             _ => panic!("Expected object for result type"),
         }
+
+        let failed = Val::Result(Err(Some(Box::new(Val::String("no such key".to_string())))));
+        let json_val = wasm_to_json(&failed).unwrap();
+        assert_eq!(result_error(&json_val), Some(&json!("no such key")));
+        assert_eq!(result_error(&wasm_to_json(&wasm_val).unwrap()), None);
+    }
+
+    #[test]
+    fn test_variant_conversion() {
+        let ty = variant_type();
+
+        let wasm_val = to_wasm_with_type(&json!({"tag": "some", "value": 7}), Some(&ty)).unwrap();
+        assert_eq!(
+            wasm_val,
+            Val::Variant("some".to_string(), Some(Box::new(Val::U32(7))))
+        );
+
+        let wasm_val = to_wasm_with_type(&json!("none"), Some(&ty)).unwrap();
+        assert_eq!(wasm_val, Val::Variant("none".to_string(), None));
+
+        let wasm_val = to_wasm_with_type(&json!({"tag": "none"}), Some(&ty)).unwrap();
+        assert_eq!(wasm_val, Val::Variant("none".to_string(), None));
+    }
+
+    #[test]
+    fn test_variant_round_trip() {
+        let ty = variant_type();
+        let options = ConversionOptions {
+            int64_as_string: true,
+        };
+        for val in [
+            Val::Variant("some".to_string(), Some(Box::new(Val::U32(7)))),
+            Val::Variant("none".to_string(), None),
+        ] {
+            let json_val = wasm_to_json_with_options(&val, &options).unwrap();
+            assert_eq!(to_wasm_with_type(&json_val, Some(&ty)).unwrap(), val);
+        }
+        assert_eq!(
+            wasm_to_json(&Val::Variant("none".to_string(), None)).unwrap(),
+            json!({"tag": "none"})
+        );
+
+        // Options apply to payloads too
+        let val = Val::Variant("big".to_string(), Some(Box::new(Val::U64(u64::MAX))));
+        assert_eq!(
+            wasm_to_json_with_options(&val, &options).unwrap(),
+            json!({"tag": "big", "value": "18446744073709551615"})
+        );
+    }
+
+    #[test]
+    fn test_variant_conversion_errors() {
+        let ty = variant_type();
+
+        // Unknown case
+        assert!(to_wasm_with_type(&json!({"tag": "other"}), Some(&ty)).is_err());
+        // Missing payload
+        assert!(to_wasm_with_type(&json!({"tag": "some"}), Some(&ty)).is_err());
+        // Payload on a payload-less case
+        assert!(to_wasm_with_type(&json!({"tag": "none", "value": 1}), Some(&ty)).is_err());
+        // Payload of the wrong type
+        assert!(to_wasm_with_type(&json!({"tag": "some", "value": "x"}), Some(&ty)).is_err());
+    }
+
+    #[test]
+    fn test_option_conversion() {
+        let ty = option_type();
+
+        let wasm_val = to_wasm_with_type(&Value::Null, Some(&ty)).unwrap();
+        assert_eq!(wasm_val, Val::Option(None));
+
+        let wasm_val = to_wasm_with_type(&json!(5), Some(&ty)).unwrap();
+        assert_eq!(wasm_val, Val::Option(Some(Box::new(Val::U32(5)))));
+
+        let wasm_val = convert_json_to_wasm_value(&json!(5), &ty).unwrap();
+        assert_eq!(wasm_val, Val::Option(Some(Box::new(Val::U32(5)))));
+
+        assert!(to_wasm_with_type(&json!("five"), Some(&ty)).is_err());
+    }
+
+    #[test]
+    fn test_enum_conversion() {
+        let ty = enum_type();
+
+        let wasm_val = to_wasm_with_type(&json!("green"), Some(&ty)).unwrap();
+        assert_eq!(wasm_val, Val::Enum("green".to_string()));
+
+        let err = to_wasm_with_type(&json!("blue"), Some(&ty)).unwrap_err();
+        assert!(err.to_string().contains("red, green"));
+
+        assert!(to_wasm_with_type(&json!(1), Some(&ty)).is_err());
+    }
+
+    #[test]
+    fn test_flags_conversion() {
+        let ty = flags_type();
+
+        let wasm_val = to_wasm_with_type(&json!(["read", "exec"]), Some(&ty)).unwrap();
+        assert_eq!(
+            wasm_val,
+            Val::Flags(vec!["read".to_string(), "exec".to_string()])
+        );
+
+        let wasm_val = to_wasm_with_type(&json!([]), Some(&ty)).unwrap();
+        assert_eq!(wasm_val, Val::Flags(Vec::new()));
+
+        // Unknown and duplicate flags are rejected
+        assert!(to_wasm_with_type(&json!(["delete"]), Some(&ty)).is_err());
+        assert!(to_wasm_with_type(&json!(["read", "read"]), Some(&ty)).is_err());
+        assert!(to_wasm_with_type(&json!("read"), Some(&ty)).is_err());
+    }
+
+    #[test]
+    fn test_int64_as_string() {
+        let options = ConversionOptions {
+            int64_as_string: true,
+        };
+        let wasm_val = Val::U64(u64::MAX);
+        let json_val = wasm_to_json_with_options(&wasm_val, &options).unwrap();
+        assert_eq!(json_val, json!("18446744073709551615"));
+
+        let wasm_val = Val::List(vec![Val::S64(-1), Val::U32(1)]);
+        let json_val = wasm_to_json_with_options(&wasm_val, &options).unwrap();
+        assert_eq!(json_val, json!(["-1", 1]));
+
+        // Numbers stay numbers by default
+        let json_val = wasm_to_json(&Val::U64(42)).unwrap();
+        assert_eq!(json_val, json!(42));
+    }
+
+    #[test]
+    fn test_string_encoded_int64_input() {
+        let wasm_val = convert_json_to_wasm_value(&json!("18446744073709551615"), &Type::U64);
+        assert_eq!(wasm_val.unwrap(), Val::U64(u64::MAX));
+
+        let wasm_val = convert_json_to_wasm_value(&json!("-9007199254740993"), &Type::S64);
+        assert_eq!(wasm_val.unwrap(), Val::S64(-9007199254740993));
+
+        assert!(convert_json_to_wasm_value(&json!("abc"), &Type::U64).is_err());
+    }
+
+    #[test]
+    fn test_typed_list_conversion() {
+        let ty = param_type("(type $l (list u8))", "$l");
+        let wasm_val = to_wasm_with_type(&json!([1, 2, 255]), Some(&ty)).unwrap();
+        assert_eq!(
+            wasm_val,
+            Val::List(vec![Val::U8(1), Val::U8(2), Val::U8(255)])
+        );
+        assert!(to_wasm_with_type(&json!([256]), Some(&ty)).is_err());
+
+        let ty = enum_list_type();
+        let wasm_val = to_wasm_with_type(&json!(["red", "green"]), Some(&ty)).unwrap();
+        assert_eq!(
+            wasm_val,
+            Val::List(vec![
+                Val::Enum("red".to_string()),
+                Val::Enum("green".to_string())
+            ])
+        );
+        assert!(to_wasm_with_type(&json!(["blue"]), Some(&ty)).is_err());
+    }
+
+    #[test]
+    fn test_typed_tuple_conversion() {
+        let ty = param_type("(type $t (tuple u8 string))", "$t");
+
+        let wasm_val = to_wasm_with_type(&json!([7, "seven"]), Some(&ty)).unwrap();
+        assert_eq!(
+            wasm_val,
+            Val::Tuple(vec![Val::U8(7), Val::String("seven".to_string())])
+        );
+
+        // Wrong arity and wrong element types are rejected
+        assert!(to_wasm_with_type(&json!([7]), Some(&ty)).is_err());
+        assert!(to_wasm_with_type(&json!([7, "seven", 8]), Some(&ty)).is_err());
+        assert!(to_wasm_with_type(&json!(["seven", 7]), Some(&ty)).is_err());
     }
 }
@@ -1,5 +1,13 @@
 /// Convert a wasmtime Type directly to JSON schema type
 pub fn convert_wasm_type_to_json(ty: &wasmtime::component::Type) -> serde_json::Value {
+    convert_wasm_type_to_json_with_int64(ty, &serde_json::json!("integer"))
+}
+
+/// Convert a wasmtime Type to JSON schema type, describing `s64`/`u64` values by `int64`
+pub fn convert_wasm_type_to_json_with_int64(
+    ty: &wasmtime::component::Type,
+    int64: &serde_json::Value,
+) -> serde_json::Value {
     match ty {
         wasmtime::component::Type::Bool => serde_json::json!("boolean"),
         wasmtime::component::Type::Char | wasmtime::component::Type::String => {
@@ -10,14 +18,13 @@ pub fn convert_wasm_type_to_json(ty: &wasmtime::component::Type) -> serde_json::
         | wasmtime::component::Type::S16
         | wasmtime::component::Type::U16
         | wasmtime::component::Type::S32
-        | wasmtime::component::Type::U32
-        | wasmtime::component::Type::S64
-        | wasmtime::component::Type::U64 => serde_json::json!("integer"),
+        | wasmtime::component::Type::U32 => serde_json::json!("integer"),
+        wasmtime::component::Type::S64 | wasmtime::component::Type::U64 => int64.clone(),
         wasmtime::component::Type::Float32 | wasmtime::component::Type::Float64 => {
             serde_json::json!("number")
         }
         wasmtime::component::Type::List(list) => {
-            let element_type = convert_wasm_type_to_json(&list.ty());
+            let element_type = convert_wasm_type_to_json_with_int64(&list.ty(), int64);
             serde_json::json!({
                 "type": "array",
                 "items": element_type
@@ -28,7 +35,7 @@ pub fn convert_wasm_type_to_json(ty: &wasmtime::component::Type) -> serde_json::
             let mut required = Vec::new();
 
             for field in record.fields() {
-                let field_type = convert_wasm_type_to_json(&field.ty);
+                let field_type = convert_wasm_type_to_json_with_int64(&field.ty, int64);
                 properties.insert(field.name.to_string(), field_type);
                 required.push(field.name);
             }
@@ -43,7 +50,7 @@ pub fn convert_wasm_type_to_json(ty: &wasmtime::component::Type) -> serde_json::
         wasmtime::component::Type::Tuple(tuple) => {
             let items: Vec<serde_json::Value> = tuple
                 .types()
-                .map(|t| convert_wasm_type_to_json(&t))
+                .map(|t| convert_wasm_type_to_json_with_int64(&t, int64))
                 .collect();
             serde_json::json!({
                 "type": "array",
@@ -60,14 +67,24 @@ pub fn convert_wasm_type_to_json(ty: &wasmtime::component::Type) -> serde_json::
                         serde_json::json!({
                             "type": "object",
                             "properties": {
-                                case.name: convert_wasm_type_to_json(&ty)
+                                "tag": { "const": case.name },
+                                "value": convert_wasm_type_to_json_with_int64(&ty, int64)
                             },
-                            "required": [case.name],
+                            "required": ["tag", "value"],
                             "additionalProperties": false
                         })
                     } else {
+                        // Cases without payload are passed as their bare name too
                         serde_json::json!({
-                            "const": case.name
+                            "anyOf": [
+                                { "const": case.name },
+                                {
+                                    "type": "object",
+                                    "properties": { "tag": { "const": case.name } },
+                                    "required": ["tag"],
+                                    "additionalProperties": false
+                                }
+                            ]
                         })
                     }
                 })
@@ -85,7 +102,7 @@ pub fn convert_wasm_type_to_json(ty: &wasmtime::component::Type) -> serde_json::
             })
         }
         wasmtime::component::Type::Option(option) => {
-            let inner_type = convert_wasm_type_to_json(&option.ty());
+            let inner_type = convert_wasm_type_to_json_with_int64(&option.ty(), int64);
             serde_json::json!({
                 "oneOf": [
                     inner_type,
@@ -94,8 +111,12 @@ pub fn convert_wasm_type_to_json(ty: &wasmtime::component::Type) -> serde_json::
             })
         }
         wasmtime::component::Type::Result(result) => {
-            let ok_type = result.ok().map(|t| convert_wasm_type_to_json(&t));
-            let err_type = result.err().map(|t| convert_wasm_type_to_json(&t));
+            let ok_type = result
+                .ok()
+                .map(|t| convert_wasm_type_to_json_with_int64(&t, int64));
+            let err_type = result
+                .err()
+                .map(|t| convert_wasm_type_to_json_with_int64(&t, int64));
 
             match (ok_type, err_type) {
                 (Some(ok), Some(err)) => {
@@ -151,7 +172,7 @@ pub fn convert_wasm_type_to_json(ty: &wasmtime::component::Type) -> serde_json::
                     "type": "object",
                     "properties": {
                         "pending": { "type": "boolean" },
-                        "value": convert_wasm_type_to_json(&ty)
+                        "value": convert_wasm_type_to_json_with_int64(&ty, int64)
                     }
                 })
             } else {
@@ -167,7 +188,7 @@ pub fn convert_wasm_type_to_json(ty: &wasmtime::component::Type) -> serde_json::
             if let Some(ty) = stream.ty() {
                 serde_json::json!({
                     "type": "array",
-                    "items": convert_wasm_type_to_json(&ty)
+                    "items": convert_wasm_type_to_json_with_int64(&ty, int64)
                 })
             } else {
                 serde_json::json!({
@@ -0,0 +1,151 @@
+//! Validation of call arguments against a tool's input schema
+//!
+//! Converting arguments to component values stops at the first mismatch. Validating the
+//! arguments against the advertised schema first reports every violation at once, each with
+//! the JSON pointer of the offending value.
+
+use crate::error::{Result, WasiMcpError};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// One way the arguments don't match the schema
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Violation {
+    /// JSON pointer to the offending value, empty for the arguments object itself
+    pub pointer: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.pointer.is_empty() {
+            write!(f, "(root): {}", self.message)
+        } else {
+            write!(f, "{}: {}", self.pointer, self.message)
+        }
+    }
+}
+
+/// Compiled input schema of a tool
+pub struct ArgumentValidator {
+    validator: jsonschema::Validator,
+}
+
+impl ArgumentValidator {
+    /// Compile a tool's input schema
+    pub fn new(schema: &serde_json::Map<String, Value>) -> Result<Self> {
+        let schema = normalize(&Value::Object(schema.clone()));
+        let validator = jsonschema::draft7::new(&schema).map_err(|e| {
+            WasiMcpError::Execution(format!("Input schema cannot be compiled: {e}"))
+        })?;
+        Ok(Self { validator })
+    }
+
+    /// Check the arguments, failing with every violation found
+    pub fn validate(&self, arguments: &HashMap<String, Value>) -> Result<()> {
+        let instance = Value::Object(
+            arguments
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+        );
+        let violations: Vec<Violation> = self
+            .validator
+            .iter_errors(&instance)
+            .map(|error| Violation {
+                pointer: error.instance_path.to_string(),
+                message: error.to_string(),
+            })
+            .collect();
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(WasiMcpError::ArgumentViolations(violations))
+        }
+    }
+}
+
+/// Expand the bare type names the schema generator uses for scalars (`"integer"`) into
+/// schemas (`{"type": "integer"}`)
+fn normalize(schema: &Value) -> Value {
+    let Value::Object(object) = schema else {
+        return match schema {
+            Value::String(ty) => serde_json::json!({ "type": ty }),
+            other => other.clone(),
+        };
+    };
+    object
+        .iter()
+        .map(|(key, value)| {
+            let value = match (key.as_str(), value) {
+                ("properties", Value::Object(properties)) => Value::Object(
+                    properties
+                        .iter()
+                        .map(|(name, schema)| (name.clone(), normalize(schema)))
+                        .collect(),
+                ),
+                ("items" | "oneOf" | "anyOf" | "allOf", Value::Array(schemas)) => {
+                    Value::Array(schemas.iter().map(normalize).collect())
+                }
+                ("items" | "additionalProperties" | "not", schema) => normalize(schema),
+                _ => value.clone(),
+            };
+            (key.clone(), value)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn validator(schema: Value) -> ArgumentValidator {
+        ArgumentValidator::new(schema.as_object().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_all_violations() {
+        let validator = validator(json!({
+            "type": "object",
+            "properties": {
+                "query": {"type": "string"},
+                "limit": {"type": "integer"},
+                "tags": {"type": "array", "items": "string"},
+            },
+            "required": ["query", "limit"],
+            "additionalProperties": false
+        }));
+        let arguments = HashMap::from([
+            ("limit".to_string(), json!("ten")),
+            ("tags".to_string(), json!(["rust", 7])),
+        ]);
+
+        let Err(WasiMcpError::ArgumentViolations(violations)) = validator.validate(&arguments)
+        else {
+            panic!("arguments should be rejected");
+        };
+        let mut pointers: Vec<&str> = violations.iter().map(|v| v.pointer.as_str()).collect();
+        pointers.sort();
+        assert_eq!(pointers, ["", "/limit", "/tags/1"]);
+    }
+
+    #[test]
+    fn test_valid_arguments() {
+        let validator = validator(json!({
+            "type": "object",
+            "properties": {
+                "maybe": {"oneOf": ["integer", {"type": "null"}]},
+                "pair": {"type": "array", "items": ["string", "boolean"]},
+            },
+            "required": [],
+            "additionalProperties": false
+        }));
+        let arguments = HashMap::from([
+            ("maybe".to_string(), Value::Null),
+            ("pair".to_string(), json!(["a", true])),
+        ]);
+        assert!(validator.validate(&arguments).is_ok());
+    }
+}
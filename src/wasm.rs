@@ -1,247 +1,316 @@
+pub use crate::inspector::{
+    ComponentExports, FunctionInfo, InterfaceInfo, ParameterInfo, get_exports,
+};
 use crate::{
-    ComponentRunStates, WasiMcpError, error::Result, utils::wasm::convert_wasm_type_to_json,
+    ComponentRunStates, WasiMcpError,
+    cache::ComponentCache,
+    compose::link_plug,
+    config::{EngineConfig, InitFailure, OptLevel, PoolingConfig, Prompt},
+    deadline::{DEFAULT_TICK, DeadlineManager},
+    docs::ComponentDocs,
+    error::Result,
+    host::HostExtension,
+    imports::check_imports,
+    inspector::ComponentInspector,
+    oci::ImageMetadata,
+    replay::Tape,
+    utils::transform::ConversionOptions,
 };
 use rmcp::model::Tool;
-use std::{collections::HashMap, path::PathBuf, sync::Arc};
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
 use tracing::instrument;
 use wasmtime::{
     Engine, Store,
-    component::{Component, Func, Instance, Linker, Val, types::ComponentItem},
+    component::{Component, Func, Instance, Linker, Val},
 };
 
 pub struct WasmContext {
     pub linker: Linker<ComponentRunStates>,
     pub engine: Engine,
+    extensions: Vec<Arc<dyn HostExtension>>,
+    fuel_metering: bool,
+    deadlines: DeadlineManager,
 }
 
 impl WasmContext {
     pub fn new() -> anyhow::Result<Self> {
+        Self::from_config(&EngineConfig::default(), false)
+    }
+
+    /// Create a context whose stores count the fuel consumed by guest code
+    ///
+    /// Metering slows execution down, so it is only used for measurements.
+    pub fn with_fuel_metering() -> anyhow::Result<Self> {
+        Self::from_config(&EngineConfig::default(), true)
+    }
+
+    /// Create a context whose engine is tuned by the `engine` section of the configuration
+    pub fn from_config(engine_config: &EngineConfig, fuel_metering: bool) -> anyhow::Result<Self> {
         let mut config = wasmtime::Config::new();
         config.async_support(true);
         config.wasm_component_model(true);
+        config.consume_fuel(fuel_metering);
+        apply_engine_config(&mut config, engine_config);
+        config.epoch_interruption(true);
         let engine = Engine::new(&config)?;
-        let mut linker: Linker<ComponentRunStates> = Linker::new(&engine);
-        wasmtime_wasi::p2::add_to_linker_async(&mut linker)?;
-        wasmtime_wasi_http::add_only_http_to_linker_async(&mut linker)?;
+        let linker = Self::base_linker(&engine)?;
+        let deadlines = match engine_config.yield_interval_ms {
+            Some(interval) => {
+                DeadlineManager::start(&engine, Duration::from_millis(interval.max(1)), true)?
+            }
+            None => DeadlineManager::start(&engine, DEFAULT_TICK, false)?,
+        };
 
-        Ok(WasmContext { linker, engine })
+        Ok(WasmContext {
+            linker,
+            engine,
+            extensions: Vec::new(),
+            fuel_metering,
+            deadlines,
+        })
     }
-}
 
-/// Component export information with optimized memory usage
-#[derive(Debug, Clone, Default)]
-pub struct ComponentExports {
-    pub functions: Vec<FunctionInfo>,
-    pub interfaces: Vec<InterfaceInfo>,
-}
+    /// Register additional host interfaces for all components instantiated afterwards
+    pub fn register_extension(
+        &mut self,
+        extension: impl HostExtension + 'static,
+    ) -> anyhow::Result<()> {
+        extension.add_to_linker(&mut self.linker)?;
+        self.extensions.push(Arc::new(extension));
+        Ok(())
+    }
 
-/// Interface information containing functions
-#[derive(Debug, Clone)]
-pub struct InterfaceInfo {
-    pub name: String,
-    pub full_name: String,
-    pub functions: HashMap<String, FunctionInfo>,
-}
+    /// Create an independent context sharing the engine and registered host interfaces, for
+    /// running another executor side by side
+    pub fn fork(&self) -> anyhow::Result<Self> {
+        Ok(WasmContext {
+            linker: self.create_linker()?,
+            engine: self.engine.clone(),
+            extensions: self.extensions.clone(),
+            fuel_metering: self.fuel_metering,
+            deadlines: self.deadlines.clone(),
+        })
+    }
 
-/// Parameter information combining name, type, and position
-#[derive(Debug, Clone)]
-pub struct ParameterInfo {
-    pub name: String,
-    pub param_json: serde_json::Value, // JSON schema for the type
-    pub wasm_type: wasmtime::component::Type,
-    pub position: usize,
-}
+    /// Deadlines of the calls running in stores of this context and its forks
+    pub fn deadlines(&self) -> &DeadlineManager {
+        &self.deadlines
+    }
 
-/// Function information with optimized field types
-#[derive(Debug, Clone)]
-pub struct FunctionInfo {
-    pub name: String,
-    pub params: Vec<ParameterInfo>,
-    pub results: Vec<serde_json::Value>, // Function return types/results as JSON
-    pub func: Option<wasmtime::component::Func>,
-}
+    /// Create a store for a component, with memory accounting, fuel metering and deadlines
+    pub(crate) fn new_store(
+        &self,
+        name: &str,
+        mut state: ComponentRunStates,
+    ) -> Result<Store<ComponentRunStates>> {
+        state.component_name = name.to_string();
+        state.stdio.set_component(name);
+        let mut store = Store::new(&self.engine, state);
+        store.limiter(|state| &mut state.memory);
+        if self.fuel_metering {
+            store.set_fuel(u64::MAX)?;
+        }
+        self.deadlines.configure(&mut store);
+        Ok(store)
+    }
 
-impl From<&FunctionInfo> for Tool {
-    fn from(value: &FunctionInfo) -> Self {
-        let tool_name = value.name.to_string();
-        let description = None;
-        let params = &value.params;
-        let results = &value.results;
-
-        // Create input schema based on function parameters with proper JSON schema types
-        let input_schema = if params.is_empty() {
-            serde_json::json!({
-                "type": "object",
-                "properties": {},
-                "required": [],
-                "additionalProperties": false
-            })
+    /// Instantiate `component` in `store`, linking the plugs of its configuration first
+    pub(crate) async fn instantiate(
+        &self,
+        store: &mut Store<ComponentRunStates>,
+        name: &str,
+        config: &crate::config::ComponentConfig,
+        component: &Component,
+    ) -> Result<Instance> {
+        let engine = &self.engine;
+        let instance = if config.plugs.is_empty() {
+            check_imports(&self.linker, engine, component, name, &[])?;
+            self.linker
+                .instantiate_async(&mut *store, component)
+                .await?
         } else {
-            let mut properties = serde_json::Map::with_capacity(params.len());
-            let mut required = Vec::with_capacity(params.len());
-
-            for param_info in params.iter() {
-                let mut param_schema = serde_json::Map::new();
-
-                // Use the JSON schema directly from param_json
-                if let Some(obj) = param_info.param_json.as_object() {
-                    param_schema.extend(obj.clone());
-                } else {
-                    // Fallback if it's not an object
-                    param_schema.insert("type".to_string(), param_info.param_json.clone());
-                }
-
-                properties.insert(
-                    param_info.name.clone(),
-                    serde_json::Value::Object(param_schema),
+            // Plugs live in the same store and are wired in through a dedicated linker
+            let cache = ComponentCache::new()?;
+            let mut composed = self.create_linker()?;
+            let mut provided = Vec::new();
+            for plug in &config.plugs {
+                let plug_path = plug.path.as_deref().ok_or_else(|| {
+                    WasiMcpError::InvalidArguments(format!("Plug of '{name}' has no resolved path"))
+                })?;
+                let plug_component = cache.load(engine, &PathBuf::from(plug_path))?;
+                link_plug(&mut composed, &mut *store, engine, &plug_component).await?;
+                provided.extend(
+                    plug_component
+                        .component_type()
+                        .exports(engine)
+                        .map(|(export, _)| export.to_string()),
                 );
-                required.push(&param_info.name);
-            }
-
-            serde_json::json!({
-                "type": "object",
-                "properties": properties,
-                "required": required,
-                "additionalProperties": false
-            })
-        };
-
-        let output_schema = if results.is_empty() {
-            // Functions with no return value might still produce a success message
-            serde_json::json!({
-                "type": "string",
-                "description": "Execution status message"
-            })
-        } else {
-            // Multiple return values are returned as an object with positional keys
-            let mut properties = serde_json::Map::with_capacity(results.len());
-            for (i, result_type) in results.iter().enumerate() {
-                properties.insert(format!("result_{}", i + 1), result_type.clone());
             }
-            serde_json::json!({
-                "type": "object",
-                "properties": properties,
-                "required": properties.keys().collect::<Vec<_>>(),
-                "additionalProperties": false
-            })
+            check_imports(&composed, engine, component, name, &provided)?;
+            composed.instantiate_async(&mut *store, component).await?
         };
+        Ok(instance)
+    }
 
-        let mut properties = serde_json::Map::with_capacity(results.len());
-        for (i, result_type) in results.iter().enumerate() {
-            properties.insert(format!("result_{}", i + 1), result_type.clone());
-        }
-        Tool {
-            name: tool_name.into(),
-            title: None,
-            description,
-            input_schema: Arc::new(input_schema.as_object().cloned().unwrap_or_default()),
-            output_schema: Some(Arc::new(
-                output_schema.as_object().cloned().unwrap_or_default(),
-            )),
-            annotations: None,
-            icons: None,
+    /// Create a fresh linker with the built-in and all registered host interfaces
+    pub fn create_linker(&self) -> anyhow::Result<Linker<ComponentRunStates>> {
+        let mut linker = Self::base_linker(&self.engine)?;
+        for extension in &self.extensions {
+            extension.add_to_linker(&mut linker)?;
         }
+        Ok(linker)
     }
-}
-
-/// Recursively extract exports from a component item with optimized processing and reduced allocations
-pub fn get_exports(engine: &Engine, path: &str, item: &ComponentItem) -> ComponentExports {
-    let mut exports = ComponentExports {
-        functions: Vec::with_capacity(4), // Pre-allocate with reasonable capacity
-        interfaces: Vec::with_capacity(1), // Most components have few interfaces
-    };
 
-    match item {
-        ComponentItem::ComponentFunc(f) => {
-            let results: Vec<serde_json::Value> =
-                f.results().map(|t| convert_wasm_type_to_json(&t)).collect();
-
-            // Create parameter info with position - optimized allocation
-            let params = f
-                .params()
-                .enumerate()
-                .map(|(position, (n, t))| {
-                    let param_json = convert_wasm_type_to_json(&t);
-                    ParameterInfo {
-                        name: n.to_string(),
-                        param_json,
-                        wasm_type: t.clone(),
-                        position,
-                    }
-                })
-                .collect();
-
-            exports.functions.push(FunctionInfo {
-                name: path.to_string(),
-                params,
-                results,
-                func: None,
-            });
-        }
-        ComponentItem::CoreFunc(_ft) => {
-            // todo: improve param/result extraction
-        }
-        ComponentItem::ComponentInstance(inst) => {
-            let mut interface_functions = HashMap::with_capacity(4); // Pre-allocate
+    /// Create a linker providing the built-in WASI interfaces
+    fn base_linker(engine: &Engine) -> anyhow::Result<Linker<ComponentRunStates>> {
+        let mut linker: Linker<ComponentRunStates> = Linker::new(engine);
+        wasmtime_wasi::p2::add_to_linker_async(&mut linker)?;
+        wasmtime_wasi_http::add_only_http_to_linker_async(&mut linker)?;
+        wasmtime_wasi_config::add_to_linker(&mut linker, |state: &mut ComponentRunStates| {
+            wasmtime_wasi_config::WasiConfig::from(&state.config_vars)
+        })?;
+        crate::logging::add_to_linker(&mut linker)?;
+        crate::sql::add_to_linker(&mut linker)?;
+        Ok(linker)
+    }
+}
 
-            for (name, nested) in inst.exports(engine) {
-                let child = format!("{path}.{name}");
-                let nested_result = get_exports(engine, &child, &nested);
+/// Host stack kept on top of `max_wasm_stack` for the fibers async calls run on, matching
+/// wasmtime's default difference between the two
+const HOST_STACK_HEADROOM: usize = 1536 * 1024;
 
-                // Add functions from nested inspection
-                for func in nested_result.functions {
-                    // Keep the original function name, but create the full path for tool execution
-                    let function_key = func.name.clone(); // Original function name
-                    let full_function_path = format!("{path}.{name}"); // Full path for execution
+fn apply_engine_config(config: &mut wasmtime::Config, engine: &EngineConfig) {
+    if let Some(parallel) = engine.parallel_compilation {
+        config.parallel_compilation(parallel);
+    }
+    if let Some(level) = engine.opt_level {
+        config.cranelift_opt_level(match level {
+            OptLevel::None => wasmtime::OptLevel::None,
+            OptLevel::Speed => wasmtime::OptLevel::Speed,
+            OptLevel::SpeedAndSize => wasmtime::OptLevel::SpeedAndSize,
+        });
+    }
+    if let Some(debug_info) = engine.debug_info {
+        config.debug_info(debug_info);
+    }
+    if let Some(details) = engine.backtrace_details {
+        config.wasm_backtrace_details(if details {
+            wasmtime::WasmBacktraceDetails::Enable
+        } else {
+            wasmtime::WasmBacktraceDetails::Disable
+        });
+    }
+    if let Some(stack) = engine.max_wasm_stack {
+        // The fiber stack must be larger than the guest stack or the engine is rejected
+        config.max_wasm_stack(stack);
+        config.async_stack_size(stack + HOST_STACK_HEADROOM);
+    }
+    if let Some(relaxed_simd) = engine.relaxed_simd {
+        config.wasm_relaxed_simd(relaxed_simd);
+    }
+    if let Some(deterministic) = engine.relaxed_simd_deterministic {
+        config.relaxed_simd_deterministic(deterministic);
+    }
+    if let Some(canonicalize) = engine.nan_canonicalization {
+        config.cranelift_nan_canonicalization(canonicalize);
+    }
+    if engine.coredump.is_some() {
+        config.coredump_on_trap(true);
+    }
+    if let Some(pooling) = &engine.pooling {
+        config.allocation_strategy(wasmtime::InstanceAllocationStrategy::Pooling(
+            pooling_config(pooling),
+        ));
+    }
+}
 
-                    // Create a new function info with the proper name for execution
-                    let mut func_for_interface = func.clone();
-                    func_for_interface.name = full_function_path;
+fn pooling_config(pooling: &PoolingConfig) -> wasmtime::PoolingAllocationConfig {
+    let mut config = wasmtime::PoolingAllocationConfig::default();
+    if let Some(total) = pooling.total_component_instances {
+        config.total_component_instances(total);
+    }
+    if let Some(total) = pooling.total_core_instances {
+        config.total_core_instances(total);
+    }
+    if let Some(total) = pooling.total_memories {
+        config.total_memories(total);
+    }
+    if let Some(total) = pooling.total_tables {
+        config.total_tables(total);
+    }
+    if let Some(total) = pooling.total_stacks {
+        config.total_stacks(total);
+    }
+    if let Some(size) = pooling.max_memory_size {
+        config.max_memory_size(size);
+    }
+    config
+}
 
-                    interface_functions.insert(function_key, func_for_interface);
-                }
+/// Resource usage of a component's store
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceUsage {
+    /// Fuel consumed by guest code, when the context meters fuel
+    pub fuel_consumed: Option<u64>,
+    /// Bytes of linear memory held by the component's instances
+    pub memory_bytes: usize,
+}
 
-                // Add interfaces from nested inspection
-                exports.interfaces.extend(nested_result.interfaces);
-            }
+/// What is known about a trap: its reason and the guest frames active when it happened
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct TrapDetails {
+    /// Trap reason, e.g. `wasm 'unreachable' instruction executed`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trap: Option<String>,
+    /// Guest frames, innermost first, as `module!function` with source locations when the
+    /// component carries DWARF debug info
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub backtrace: Vec<String>,
+}
 
-            // Create interface info for this instance if it has functions
-            if !interface_functions.is_empty() {
-                let interface_parts: Vec<&str> = path.split('/').collect();
-                let interface_display_name = interface_parts.last().copied().unwrap_or(path);
+impl TrapDetails {
+    /// Details of an error returned by a guest call; empty when it did not trap
+    pub fn of(error: &anyhow::Error) -> Self {
+        let backtrace = error
+            .downcast_ref::<wasmtime::WasmBacktrace>()
+            .map(|backtrace| backtrace.frames().iter().map(format_frame).collect())
+            .unwrap_or_default();
+        Self {
+            trap: error
+                .downcast_ref::<wasmtime::Trap>()
+                .map(ToString::to_string),
+            backtrace,
+        }
+    }
 
-                let interface_info = InterfaceInfo {
-                    name: interface_display_name.to_string(),
-                    full_name: path.to_string(),
-                    functions: interface_functions,
-                };
+    pub fn is_empty(&self) -> bool {
+        self.trap.is_none() && self.backtrace.is_empty()
+    }
+}
 
-                exports.interfaces.push(interface_info);
-            }
-        }
-        ComponentItem::Component(nested_comp) => {
-            // Nested component defined inside this component
-            for (name, nested) in nested_comp.exports(engine) {
-                let child = format!("{path}.{name}");
-                let nested_result = get_exports(engine, &child, &nested);
-
-                // Add all results from nested inspection
-                exports.functions.extend(nested_result.functions);
-                exports.interfaces.extend(nested_result.interfaces);
-            }
-        }
-        ComponentItem::Module(_) => {
-            // Module types are not currently used, skip collecting them
-        }
-        ComponentItem::Type(_) => {
-            // Type information is not currently used, skip collecting it
-        }
-        ComponentItem::Resource(_) => {
-            // Resource information is not currently used, skip collecting it
+fn format_frame(frame: &wasmtime::FrameInfo) -> String {
+    let module = frame.module().name().unwrap_or("<unknown>");
+    let mut line = match frame.func_name() {
+        Some(name) => format!("{module}!{name}"),
+        None => format!("{module}!<wasm function {}>", frame.func_index()),
+    };
+    if let Some(symbol) = frame.symbols().first()
+        && let Some(file) = symbol.file()
+    {
+        line.push_str(&format!(" at {file}"));
+        if let Some(line_number) = symbol.line() {
+            line.push_str(&format!(":{line_number}"));
         }
     }
-
-    exports
+    line
 }
 
 pub struct WasmComponent {
@@ -252,25 +321,141 @@ pub struct WasmComponent {
     pub interfaces: HashMap<String, InterfaceInfo>, // Map of interface name to interface info
     pub functions: HashMap<String, FunctionInfo>, // Map of function name to function info for standalone functions
     pub store: Store<ComponentRunStates>,
+    /// Whether the component only exports `wasi:cli/run` and is called through [`crate::command`]
+    pub command: bool,
+    /// WIT docs describing the component's tools
+    pub docs: ComponentDocs,
+    /// Prompts shipped with the component, by id
+    pub prompts: HashMap<String, Prompt>,
+    /// Standard annotations of the manifest the component was pulled from
+    pub metadata: ImageMetadata,
+    /// SHA-256 of the loaded file, as `sha256:<hex>`
+    pub digest: Option<String>,
+    /// Initialized instances for calls that need a store of their own, when the component
+    /// has an init function
+    spares: Option<Arc<Spares>>,
+}
+
+/// A store whose instance ran the component's init function, ready to serve one call
+struct Initialized {
+    store: Store<ComponentRunStates>,
+    instance: Instance,
+}
+
+/// Snapshots of a component's initialized state, for calls that can't use its long-lived
+/// store because they override its context or are recorded
+///
+/// The next instance is initialized in the background while a call uses the previous one, so
+/// such calls start from the initialized state without waiting for the init function.
+struct Spares {
+    context: WasmContext,
+    name: String,
+    config: crate::config::ComponentConfig,
+    component: Component,
+    init: String,
+    init_results: usize,
+    ready: std::sync::Mutex<Option<Initialized>>,
+    preparing: AtomicBool,
+}
+
+impl Spares {
+    /// The prepared instance, or one initialized now when none is ready; the next one is
+    /// prepared in the background
+    async fn take(self: &Arc<Self>) -> Result<Initialized> {
+        let ready = self.ready.lock().unwrap_or_else(|e| e.into_inner()).take();
+        let initialized = match ready {
+            Some(initialized) => initialized,
+            None => self.initialize().await?,
+        };
+        self.replenish();
+        Ok(initialized)
+    }
+
+    /// Prepare the next instance in the background, unless one is being prepared
+    fn replenish(self: &Arc<Self>) {
+        if self.preparing.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let spares = self.clone();
+        tokio::spawn(async move {
+            match spares.initialize().await {
+                Ok(initialized) => {
+                    *spares.ready.lock().unwrap_or_else(|e| e.into_inner()) = Some(initialized)
+                }
+                Err(e) => tracing::warn!(
+                    "Failed to prepare an initialized instance of '{}': {}",
+                    spares.name,
+                    e
+                ),
+            }
+            spares.preparing.store(false, Ordering::Release);
+        });
+    }
+
+    /// Instantiate the component in a new store and run its init function
+    async fn initialize(&self) -> Result<Initialized> {
+        let states = ComponentRunStates::try_from(&self.config)?;
+        let mut store = self.context.new_store(&self.name, states)?;
+        let instance = self
+            .context
+            .instantiate(&mut store, &self.name, &self.config, &self.component)
+            .await?;
+        let func = WasmComponent::get_function_handle(&mut store, &instance, &self.init)?;
+        let mut results = vec![Val::Bool(false); self.init_results];
+        let initialized = match func.call_async(&mut store, &[], &mut results).await {
+            Ok(()) => returned_error(&results).map_or(Ok(()), |e| Err(init_error(&self.init, e))),
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = initialized {
+            if self.config.init_failure.unwrap_or_default() == InitFailure::Fail {
+                return Err(e);
+            }
+            tracing::warn!("Init function of '{}' failed: {}", self.name, e);
+        }
+        Ok(Initialized { store, instance })
+    }
+}
+
+/// The error a function returned as `result::err`, if it returned one
+fn returned_error(results: &[Val]) -> Option<String> {
+    match results {
+        [Val::Result(Err(error))] => Some(match error.as_deref() {
+            Some(Val::String(message)) => message.clone(),
+            Some(other) => format!("{other:?}"),
+            None => "error".to_string(),
+        }),
+        _ => None,
+    }
+}
+
+fn init_error(function: &str, error: String) -> WasiMcpError {
+    WasiMcpError::Execution(format!("Function '{function}' returned an error: {error}"))
 }
 
 impl WasmComponent {
-    #[instrument(level = "debug", skip(engine, linker), fields(name, duration_ms))]
+    #[instrument(level = "debug", skip(context), fields(name, duration_ms))]
     pub async fn new(
         name: String,
-        engine: Engine,
         config: crate::config::ComponentConfig,
-        linker: &mut Linker<ComponentRunStates>,
+        context: &WasmContext,
     ) -> Result<Self> {
         let start_time = std::time::Instant::now();
+        let engine = context.engine.clone();
         let path = PathBuf::from(config.path.as_deref().expect("path should be provided"));
-        let component = Component::from_file(&engine, &path)?;
-
-        let (interfaces, functions) = Self::extract_component_info(&engine, &component)?;
-
-        let state = ComponentRunStates::try_from(&config)?;
-        let mut store = Store::new(&engine, state);
-        let instance = linker.instantiate_async(&mut store, &component).await?;
+        let cache = ComponentCache::new()?;
+        let component = cache.load_verified(&engine, &path, config.sha256.as_deref())?;
+        let docs = ComponentDocs::read(&path);
+        let prompts = crate::prompts::read(&path);
+        let metadata = ImageMetadata::read(&path);
+        let digest = crate::resources::digest(&path);
+        let inspector = ComponentInspector::new(&engine, component).with_docs(docs.clone());
+        let command = inspector.is_command();
+        let (component, interfaces, functions) = inspector.into_parts();
+
+        let mut store = context.new_store(&name, ComponentRunStates::try_from(&config)?)?;
+        let instance = context
+            .instantiate(&mut store, &name, &config, &component)
+            .await?;
 
         // Populate function handles
         let mut functions_with_handles = functions;
@@ -294,8 +479,7 @@ impl WasmComponent {
             }
         }
 
-        tracing::Span::current().record("duration_ms", start_time.elapsed().as_micros());
-        Ok(Self {
+        let mut component = Self {
             name,
             engine,
             component,
@@ -303,46 +487,122 @@ impl WasmComponent {
             interfaces: interfaces_with_handles,
             functions: functions_with_handles,
             store,
-        })
-    }
-
-    /// Extract component information with optimized processing
-    fn extract_component_info(
-        engine: &Engine,
-        component: &Component,
-    ) -> Result<(
-        HashMap<String, InterfaceInfo>,
-        HashMap<String, FunctionInfo>,
-    )> {
-        let mut interfaces = HashMap::with_capacity(4); // Pre-allocate with reasonable capacity
-        let mut functions = HashMap::with_capacity(8); // Pre-allocate with reasonable capacity
-        let ty = component.component_type();
-
-        // Walk top-level exports and use get_exports to get all information
-        for (name, item) in ty.exports(engine) {
-            let exports = get_exports(engine, name, &item);
-
-            // Process standalone functions (top-level functions not in interfaces)
-            for func in exports.functions {
-                // Only add as standalone function if it's not part of an interface
-                if !exports
-                    .interfaces
-                    .iter()
-                    .any(|interface| interface.functions.contains_key(&func.name))
-                {
-                    functions.insert(func.name.clone(), func);
+            command,
+            docs,
+            prompts,
+            metadata,
+            digest,
+            spares: None,
+        };
+        if let Some(init) = component.config.init.clone() {
+            if let Err(e) = component.call_lifecycle("Init", &init).await {
+                if component.config.init_failure.unwrap_or_default() == InitFailure::Fail {
+                    return Err(e);
                 }
+                tracing::warn!("Init function of '{}' failed: {}", component.name, e);
             }
+            let spares = Arc::new(Spares {
+                context: context.fork()?,
+                name: component.name.clone(),
+                config: component.config.clone(),
+                component: component.component.clone(),
+                init_results: component
+                    .get_function_info(&init)
+                    .map_or(0, |func_info| func_info.results.len()),
+                init,
+                ready: std::sync::Mutex::new(None),
+                preparing: AtomicBool::new(false),
+            });
+            spares.replenish();
+            component.spares = Some(spares);
+        }
 
-            // Process interfaces and their functions
-            for interface in &exports.interfaces {
-                // Add interface to our collections if it has functions
-                if !interface.functions.is_empty() {
-                    interfaces.insert(interface.full_name.clone(), interface.clone());
-                }
+        tracing::Span::current().record("duration_ms", start_time.elapsed().as_micros());
+        Ok(component)
+    }
+
+    /// Run the component's `on_shutdown` function, if any, logging a failure
+    pub async fn shutdown(&mut self) {
+        if let Some(function) = self.config.on_shutdown.clone()
+            && let Err(e) = self.call_lifecycle("Shutdown", &function).await
+        {
+            tracing::warn!("Shutdown function of '{}' failed: {}", self.name, e);
+        }
+    }
+
+    /// Run a lifecycle function such as `init`; the store outlives the call, so whatever it
+    /// sets up is reused by every later call
+    #[instrument(level = "debug", skip(self), fields(component = %self.name, duration_ms))]
+    async fn call_lifecycle(&mut self, kind: &str, function: &str) -> Result<()> {
+        let start_time = std::time::Instant::now();
+        let func_info = self
+            .get_function_info(function)
+            .filter(|func_info| func_info.params.is_empty())
+            .ok_or_else(|| {
+                WasiMcpError::InvalidArguments(format!(
+                    "{kind} function '{function}' of '{}' must be an export without parameters",
+                    self.name
+                ))
+            })?;
+        let func = func_info
+            .func
+            .ok_or_else(|| WasiMcpError::FunctionNotFound(function.to_string()))?;
+        let mut results = vec![Val::Bool(false); func_info.results.len()];
+        self.call_async(&func, &[], &mut results).await?;
+        if let Some(error) = returned_error(&results) {
+            return Err(init_error(function, error));
+        }
+
+        tracing::Span::current().record("duration_ms", start_time.elapsed().as_millis());
+        Ok(())
+    }
+
+    /// Call `func_name` in a fresh instance set up from `config` instead of the component's
+    /// store; with a `tape` the call is recorded or replayed
+    ///
+    /// Components with an init function are called in a snapshot of their initialized state,
+    /// so the init function doesn't run again for the call. When the call traps with a core
+    /// dump attached, the dump is serialized into `dump` before the instance is dropped.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn call_in_fresh_instance(
+        &self,
+        context: &WasmContext,
+        config: &crate::config::ComponentConfig,
+        call_id: u64,
+        func_name: &str,
+        args: &[Val],
+        results: &mut [Val],
+        tape: Option<Arc<Tape>>,
+        dump: &mut Option<Vec<u8>>,
+    ) -> Result<()> {
+        let states = match tape {
+            Some(tape) => ComponentRunStates::with_tape(config, tape)?,
+            None => ComponentRunStates::try_from(config)?,
+        };
+        let (mut store, instance) = match &self.spares {
+            Some(spares) => {
+                let Initialized {
+                    mut store,
+                    instance,
+                } = spares.take().await?;
+                store.data_mut().adopt(states, call_id);
+                (store, instance)
             }
+            None => {
+                let mut store = context.new_store(&self.name, states)?;
+                store.data().stdio.set_call_id(call_id);
+                let instance = context
+                    .instantiate(&mut store, &self.name, config, &self.component)
+                    .await?;
+                (store, instance)
+            }
+        };
+        let func = Self::get_function_handle(&mut store, &instance, func_name)?;
+        let called = func.call_async(&mut store, args, results).await;
+        if let Err(e) = &called {
+            *dump = crate::coredump::capture(e, &mut store, &self.name);
         }
-        Ok((interfaces, functions))
+        called.map_err(Into::into)
     }
 
     fn get_function_handle(
@@ -386,38 +646,29 @@ impl WasmComponent {
         }
     }
 
-    /// Get all tools from the component with component description included
+    /// Get all tools from the component with component description included, with schemas
+    /// describing values encoded with `options`
     pub fn get_tools(
         &self,
-        engine: &Engine,
         component_description: Option<&str>,
-    ) -> Result<Vec<Tool>> {
-        let mut tools = Vec::new();
-        let ty = self.component.component_type();
-        let description = component_description.unwrap_or_default().to_string();
-
-        // Walk top-level exports and use get_exports to get all information
-        for (name, item) in ty.exports(engine) {
-            let exports = get_exports(engine, name, &item);
-
-            // Process top-level functions
-            for func in &exports.functions {
-                let mut tool = Tool::from(func);
-                tool.description = Some(description.clone().into());
-                tools.push(tool);
-            }
+        options: ConversionOptions,
+    ) -> Vec<Tool> {
+        ComponentInspector::new(&self.engine, self.component.clone())
+            .with_docs(self.docs.clone())
+            .with_conversion_options(options)
+            .tools(component_description)
+    }
 
-            // Process interfaces and their functions
-            for interface in &exports.interfaces {
-                for func_info in interface.functions.values() {
-                    let mut tool = Tool::from(func_info);
-                    tool.description = Some(description.clone().into());
-                    tools.push(tool);
-                }
-            }
+    /// Resources held and consumed by the component's store so far
+    pub fn usage(&self) -> ResourceUsage {
+        ResourceUsage {
+            fuel_consumed: self
+                .store
+                .get_fuel()
+                .ok()
+                .map(|remaining| u64::MAX - remaining),
+            memory_bytes: self.store.data().memory.bytes,
         }
-
-        Ok(tools)
     }
 
     /// Get function information by name
@@ -433,13 +684,25 @@ impl WasmComponent {
         self.functions.get(function_name)
     }
 
+    #[instrument(level = "debug", skip_all, fields(component = %self.name, fuel_consumed, trap))]
     pub async fn call_async(
         &mut self,
         func: &Func,
         args: &[Val],
         results: &mut [Val],
     ) -> Result<()> {
-        func.call_async(&mut self.store, args, results).await?;
+        let fuel_before = self.usage().fuel_consumed;
+        let called = func.call_async(&mut self.store, args, results).await;
+        let span = tracing::Span::current();
+        if let (Some(before), Some(after)) = (fuel_before, self.usage().fuel_consumed) {
+            span.record("fuel_consumed", after.saturating_sub(before));
+        }
+        if let Err(e) = &called
+            && let Some(code) = e.downcast_ref::<wasmtime::Trap>()
+        {
+            span.record("trap", code.to_string());
+        }
+        called?;
         Ok(())
     }
 }
@@ -0,0 +1,162 @@
+use crate::config::ComponentConfig;
+use crate::mcp::WasmMcpServer;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How long file events are collected before reloading, so a save that writes several times
+/// reloads once
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// How often the watched directories are brought in line with the configured components
+const RESYNC_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Path of a file with its directory resolved, so configured paths and event paths compare
+/// equal
+fn file_key(path: &Path) -> PathBuf {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    match (path.parent().map(Path::canonicalize), path.file_name()) {
+        (Some(Ok(dir)), Some(name)) => dir.join(name),
+        _ => path,
+    }
+}
+
+/// Components configured with a local `path`, by their file
+///
+/// OCI components are immutable once cached and not included.
+async fn local_components(
+    server: &WasmMcpServer,
+) -> HashMap<PathBuf, Vec<(String, ComponentConfig)>> {
+    let mut components: HashMap<_, Vec<_>> = HashMap::new();
+    for (name, component) in &server.config.read().await.components {
+        if component.oci.is_some() {
+            continue;
+        }
+        if let Some(path) = &component.path {
+            components
+                .entry(file_key(Path::new(path)))
+                .or_default()
+                .push((name.clone(), component.clone()));
+        }
+    }
+    components
+}
+
+/// Watch the directories of `files` and stop watching the others
+///
+/// Directories are watched rather than files since editors often save by replacing the file.
+fn sync_directories<'a>(
+    watcher: &mut RecommendedWatcher,
+    watched: &mut HashSet<PathBuf>,
+    files: impl Iterator<Item = &'a PathBuf>,
+) {
+    let wanted: HashSet<PathBuf> = files
+        .filter_map(|file| file.parent())
+        .map(Path::to_path_buf)
+        .collect();
+    watched.retain(|dir| {
+        if wanted.contains(dir) {
+            return true;
+        }
+        let _ = watcher.unwatch(dir);
+        false
+    });
+    for dir in wanted {
+        if watched.contains(&dir) {
+            continue;
+        }
+        // Missing directories are retried on the next sync
+        match watcher.watch(&dir, RecursiveMode::NonRecursive) {
+            Ok(()) => {
+                watched.insert(dir);
+            }
+            Err(e) => tracing::debug!("Failed to watch {:?}: {}", dir, e),
+        }
+    }
+}
+
+/// Watch local component files and hot-reload components whose file changed
+///
+/// The directories of the component files are watched with `notify`. The watched set is
+/// re-read from the server periodically so configuration reloads are picked up.
+pub async fn watch_components(server: WasmMcpServer) {
+    // notify calls back on its own thread; reloads run on the runtime
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel::<PathBuf>();
+    let watcher =
+        notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+            Ok(event) if !event.kind.is_access() => {
+                for path in event.paths {
+                    let _ = sender.send(path);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Watching local components failed: {}", e),
+        });
+    let mut watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            tracing::error!("Failed to watch local components: {}", e);
+            return;
+        }
+    };
+    let mut watched = HashSet::new();
+    let mut resync = tokio::time::interval(RESYNC_INTERVAL);
+
+    tracing::info!("Watching local components for changes");
+    loop {
+        tokio::select! {
+            _ = resync.tick() => {
+                let components = local_components(&server).await;
+                sync_directories(&mut watcher, &mut watched, components.keys());
+            }
+            Some(path) = receiver.recv() => {
+                tokio::time::sleep(DEBOUNCE).await;
+                let mut changed = HashSet::from([file_key(&path)]);
+                while let Ok(path) = receiver.try_recv() {
+                    changed.insert(file_key(&path));
+                }
+
+                let components = local_components(&server).await;
+                for path in changed {
+                    // A removed file keeps the running version
+                    let Some(components) = components.get(&path).filter(|_| path.exists()) else {
+                        continue;
+                    };
+                    for (name, component_config) in components {
+                        tracing::info!("Component '{}' changed, reloading {:?}", name, path);
+                        match server.reload_component(name, component_config.clone()).await {
+                            Ok(()) => tracing::info!("Reloaded component '{}'", name),
+                            Err(e) => {
+                                tracing::error!("Failed to reload component '{}': {}", name, e)
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_file_key() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let nested = temp_dir.path().join("nested");
+        std::fs::create_dir(&nested).unwrap();
+
+        assert_eq!(
+            file_key(&nested.join("..").join("tool.wasm")),
+            file_key(&temp_dir.path().join("tool.wasm"))
+        );
+        // Files that don't exist yet still resolve through their directory
+        assert_eq!(
+            file_key(&nested.join("missing.wasm")),
+            nested.canonicalize().unwrap().join("missing.wasm")
+        );
+    }
+}
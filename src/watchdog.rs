@@ -0,0 +1,134 @@
+//! Resource watchdog for long-running components
+//!
+//! A component's instance lives as long as the server, so a slow leak in the guest grows its
+//! linear memory until the host runs out. The watchdog checks every instance after each call
+//! against the limits of its `watchdog` configuration: linear memory, and the time spent in
+//! calls since the instance was created. An instance over a limit is evicted, i.e. recycled or
+//! unloaded by the executor.
+
+use crate::config::WatchdogConfig;
+use crate::wasm::ResourceUsage;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+/// Why an instance was evicted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Eviction {
+    Memory { bytes: usize, limit: usize },
+    CallTime { used: Duration, limit: Duration },
+}
+
+impl fmt::Display for Eviction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Eviction::Memory { bytes, limit } => write!(
+                f,
+                "linear memory of {} MiB exceeds {} MiB",
+                bytes >> 20,
+                limit >> 20
+            ),
+            Eviction::CallTime { used, limit } => write!(
+                f,
+                "{:.1}s spent in calls exceeds {}s",
+                used.as_secs_f64(),
+                limit.as_secs()
+            ),
+        }
+    }
+}
+
+/// Time spent in calls by the current instance of each component
+#[derive(Debug, Default)]
+pub struct Watchdog {
+    call_time: HashMap<String, Duration>,
+}
+
+impl Watchdog {
+    /// Account a finished call of `component` and check its instance against the limits
+    pub fn check(
+        &mut self,
+        component: &str,
+        elapsed: Duration,
+        usage: &ResourceUsage,
+        config: &WatchdogConfig,
+    ) -> Option<Eviction> {
+        let used = self.call_time.entry(component.to_string()).or_default();
+        *used += elapsed;
+
+        if let Some(limit_mb) = config.max_memory_mb {
+            let limit = usize::try_from(limit_mb << 20).unwrap_or(usize::MAX);
+            if usage.memory_bytes > limit {
+                return Some(Eviction::Memory {
+                    bytes: usage.memory_bytes,
+                    limit,
+                });
+            }
+        }
+        if let Some(limit_secs) = config.max_call_time_secs {
+            let limit = Duration::from_secs(limit_secs);
+            if *used > limit {
+                return Some(Eviction::CallTime { used: *used, limit });
+            }
+        }
+        None
+    }
+
+    /// Start over for a component whose instance was replaced or unloaded
+    pub fn forget(&mut self, component: &str) {
+        self.call_time.remove(component);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_limits() {
+        let config = WatchdogConfig {
+            max_memory_mb: Some(64),
+            max_call_time_secs: Some(10),
+            ..Default::default()
+        };
+        let mut watchdog = Watchdog::default();
+        let small = ResourceUsage {
+            fuel_consumed: None,
+            memory_bytes: 1 << 20,
+        };
+        let large = ResourceUsage {
+            fuel_consumed: None,
+            memory_bytes: 65 << 20,
+        };
+
+        assert_eq!(
+            watchdog.check("db", Duration::from_secs(4), &small, &config),
+            None
+        );
+        assert_eq!(
+            watchdog.check("db", Duration::from_secs(1), &large, &config),
+            Some(Eviction::Memory {
+                bytes: 65 << 20,
+                limit: 64 << 20
+            })
+        );
+        let eviction = watchdog.check("db", Duration::from_secs(6), &small, &config);
+        assert_eq!(
+            eviction,
+            Some(Eviction::CallTime {
+                used: Duration::from_secs(11),
+                limit: Duration::from_secs(10)
+            })
+        );
+        assert_eq!(
+            eviction.unwrap().to_string(),
+            "11.0s spent in calls exceeds 10s"
+        );
+
+        watchdog.forget("db");
+        assert_eq!(
+            watchdog.check("db", Duration::from_secs(6), &small, &config),
+            None
+        );
+    }
+}
@@ -0,0 +1,308 @@
+//! Webhook triggers for tool calls
+//!
+//! Each entry of the `webhooks` section is served at `POST /hooks/<name>`. The JSON payload
+//! is checked against the sender's HMAC signature, substituted into the configured argument
+//! templates, and the tool is called through the same executor as MCP requests.
+
+use crate::config::{WebhookConfig, WebhookSignature};
+use crate::mcp::WasmMcpServer;
+use crate::rest::{error_response, message};
+use crate::trace::remote_call_span;
+use crate::utils::unix_now;
+use axum::Json;
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::Sha256;
+use std::collections::HashMap;
+use tracing::Instrument;
+
+/// Oldest Stripe signature timestamp accepted, guarding against replayed requests
+const STRIPE_TOLERANCE_SECS: u64 = 300;
+
+/// Routes of the webhook triggers, to be merged into the HTTP router
+pub fn router() -> axum::Router<WasmMcpServer> {
+    axum::Router::new().route("/hooks/{name}", axum::routing::post(trigger))
+}
+
+async fn trigger(
+    State(server): State<WasmMcpServer>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let Some(hook) = server.config.read().await.webhooks.get(&name).cloned() else {
+        return message(StatusCode::NOT_FOUND, format!("Unknown webhook '{name}'"));
+    };
+
+    if let Some(secret) = &hook.secret
+        && !verify_signature(hook.signature, secret, &headers, &body, unix_now())
+    {
+        tracing::warn!(webhook = %name, "Rejected webhook with an invalid signature");
+        return message(StatusCode::UNAUTHORIZED, "Invalid signature".to_string());
+    }
+
+    let payload: Value = if body.is_empty() {
+        Value::Null
+    } else {
+        match serde_json::from_slice(&body) {
+            Ok(payload) => payload,
+            Err(e) => {
+                return message(StatusCode::BAD_REQUEST, format!("Payload is not JSON: {e}"));
+            }
+        }
+    };
+
+    let Some(_call) = server.drain.start_call() else {
+        return message(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Server is shutting down".to_string(),
+        );
+    };
+
+    tracing::info!(webhook = %name, tool = %hook.tool, "Webhook triggered tool call");
+    let result = async {
+        server
+            .executor
+            .lock()
+            .await
+            .execute_function(&hook.tool, render_arguments(&hook, &payload))
+            .await
+    }
+    .instrument(remote_call_span("webhook", &headers))
+    .await;
+    match result {
+        Ok(result) => Json(result).into_response(),
+        Err(e) => error_response(&e),
+    }
+}
+
+/// Check the request's signature of `body` made with `secret`
+fn verify_signature(
+    scheme: WebhookSignature,
+    secret: &str,
+    headers: &HeaderMap,
+    body: &[u8],
+    now: u64,
+) -> bool {
+    match scheme {
+        WebhookSignature::Github => headers
+            .get("x-hub-signature-256")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("sha256="))
+            .is_some_and(|signature| hmac_matches(secret, &[body], signature)),
+        WebhookSignature::Stripe => {
+            let Some(header) = headers
+                .get("stripe-signature")
+                .and_then(|value| value.to_str().ok())
+            else {
+                return false;
+            };
+            let mut timestamp = None;
+            let mut signatures = Vec::new();
+            for (key, value) in header.split(',').filter_map(|part| part.split_once('=')) {
+                match key.trim() {
+                    "t" => timestamp = value.trim().parse::<u64>().ok(),
+                    "v1" => signatures.push(value.trim()),
+                    _ => {}
+                }
+            }
+            let Some(timestamp) = timestamp else {
+                return false;
+            };
+            if now.abs_diff(timestamp) > STRIPE_TOLERANCE_SECS {
+                return false;
+            }
+            let prefix = format!("{timestamp}.");
+            signatures
+                .iter()
+                .any(|signature| hmac_matches(secret, &[prefix.as_bytes(), body], signature))
+        }
+    }
+}
+
+/// Compare the HMAC-SHA256 of `parts` with a hex signature in constant time
+fn hmac_matches(secret: &str, parts: &[&[u8]], signature: &str) -> bool {
+    let Some(expected) = decode_hex(signature) else {
+        return false;
+    };
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    for part in parts {
+        mac.update(part);
+    }
+    mac.verify_slice(&expected).is_ok()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// The webhook's arguments with templates filled in from `payload`
+fn render_arguments(hook: &WebhookConfig, payload: &Value) -> HashMap<String, Value> {
+    hook.args
+        .iter()
+        .map(|(name, template)| (name.clone(), render(template, payload)))
+        .collect()
+}
+
+/// Replace `{{ path }}` references in every string of `template`
+///
+/// A string that is a single reference becomes the referenced value itself, so numbers and
+/// objects keep their type. References to missing fields render as `null` or an empty string.
+fn render(template: &Value, payload: &Value) -> Value {
+    match template {
+        Value::String(string) => {
+            let trimmed = string.trim();
+            if let Some(path) = trimmed
+                .strip_prefix("{{")
+                .and_then(|rest| rest.strip_suffix("}}"))
+                .filter(|path| !path.contains("{{"))
+            {
+                return lookup(payload, path.trim()).cloned().unwrap_or(Value::Null);
+            }
+            Value::String(interpolate(string, payload))
+        }
+        Value::Array(items) => {
+            Value::Array(items.iter().map(|item| render(item, payload)).collect())
+        }
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), render(value, payload)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+fn interpolate(template: &str, payload: &Value) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        result.push_str(&rest[..start]);
+        match lookup(payload, rest[start + 2..start + end].trim()) {
+            Some(Value::String(string)) => result.push_str(string),
+            Some(Value::Null) | None => {}
+            Some(value) => result.push_str(&value.to_string()),
+        }
+        rest = &rest[start + end + 2..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Field of `payload` at a dotted path; `.` is the whole payload and numbers index arrays
+fn lookup<'a>(payload: &'a Value, path: &str) -> Option<&'a Value> {
+    if path == "." {
+        return Some(payload);
+    }
+    path.split('.')
+        .try_fold(payload, |value, segment| match value {
+            Value::Object(map) => map.get(segment),
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_github_signature() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-hub-signature-256",
+            "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17"
+                .parse()
+                .unwrap(),
+        );
+        let secret = "It's a Secret to Everybody";
+        let scheme = WebhookSignature::Github;
+        assert!(verify_signature(
+            scheme,
+            secret,
+            &headers,
+            b"Hello, World!",
+            0
+        ));
+        assert!(!verify_signature(
+            scheme,
+            secret,
+            &headers,
+            b"Hello, World?",
+            0
+        ));
+        assert!(!verify_signature(
+            scheme,
+            "other",
+            &headers,
+            b"Hello, World!",
+            0
+        ));
+        assert!(!verify_signature(scheme, secret, &HeaderMap::new(), b"", 0));
+    }
+
+    #[test]
+    fn test_stripe_signature() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "stripe-signature",
+            "t=1700000000,v1=2f441ba4b3b2d50d28a9ab9d9fd8880376ecd1eb5d0435401553f5d8d0a5dcf8"
+                .parse()
+                .unwrap(),
+        );
+        let scheme = WebhookSignature::Stripe;
+        let body = br#"{"id":1}"#;
+        assert!(verify_signature(
+            scheme,
+            "whsec_test",
+            &headers,
+            body,
+            1_700_000_060
+        ));
+        // Too old: possibly a replayed request
+        assert!(!verify_signature(
+            scheme,
+            "whsec_test",
+            &headers,
+            body,
+            1_700_001_000
+        ));
+    }
+
+    #[test]
+    fn test_render() {
+        let payload = json!({
+            "repository": {"full_name": "dineshdb/wasmic", "stars": 42},
+            "commits": [{"id": "abc"}],
+        });
+        assert_eq!(
+            render(&json!("{{ repository.stars }}"), &payload),
+            json!(42)
+        );
+        assert_eq!(
+            render(
+                &json!("Push to {{repository.full_name}} ({{ commits.0.id }})"),
+                &payload
+            ),
+            json!("Push to dineshdb/wasmic (abc)")
+        );
+        assert_eq!(render(&json!("{{ missing }}"), &payload), Value::Null);
+        assert_eq!(render(&json!({"all": "{{ . }}"}), &payload)["all"], payload);
+        assert_eq!(render(&json!(7), &payload), json!(7));
+    }
+}
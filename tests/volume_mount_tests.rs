@@ -1,7 +1,59 @@
 use std::collections::HashMap;
+use std::path::Path;
 use tempfile::TempDir;
+use wasmic::WasiMcpError;
 use wasmic::config::{ComponentConfig, Config, VolumeMount};
-use wasmic::linker::create_wasi_context;
+use wasmic::executor::WasmExecutor;
+use wasmic::linker::{create_wasi_context, cwd_permissions, mount_permissions};
+use wasmic::wasm::WasmContext;
+use wasmtime_wasi::{DirPerms, FilePerms};
+
+/// A wasip1 command creating `new.txt` in its first preopened directory, exiting with the
+/// errno of `path_open`
+const CREATE_FILE_WAT: &str = r#"(module
+    (import "wasi_snapshot_preview1" "path_open"
+        (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 16) "new.txt")
+    (func (export "_start")
+        (call $proc_exit
+            (call $path_open
+                (i32.const 3)   ;; first preopened directory
+                (i32.const 0)   ;; lookup flags
+                (i32.const 16)  ;; path
+                (i32.const 7)
+                (i32.const 1)   ;; O_CREAT
+                (i64.const 64)  ;; FD_WRITE
+                (i64.const 0)
+                (i32.const 0)   ;; fd flags
+                (i32.const 32)))))  ;; opened fd
+"#;
+
+/// Run the file-creating guest with `volumes` and `cwd`
+async fn create_file(
+    dir: &Path,
+    volumes: Vec<VolumeMount>,
+    cwd: Option<&Path>,
+) -> wasmic::Result<serde_json::Value> {
+    let path = dir.join("create_file.wasm");
+    std::fs::write(&path, wat::parse_str(CREATE_FILE_WAT).unwrap()).unwrap();
+    let mut executor = WasmExecutor::new(WasmContext::new().unwrap(), Config::default()).unwrap();
+    executor
+        .add_component(
+            "writer".to_string(),
+            ComponentConfig {
+                path: Some(path.display().to_string()),
+                volumes,
+                cwd: cwd.map(|cwd| cwd.display().to_string()),
+                ..Default::default()
+            },
+        )
+        .await?;
+    executor
+        .execute_function("writer.run", HashMap::new())
+        .await
+}
 
 #[test]
 fn test_create_wasi_context_with_volume_mounts() {
@@ -38,15 +90,14 @@ fn test_create_wasi_context_with_volume_mounts() {
             volumes: volume_mounts,
             cwd: Some(temp_path.to_string_lossy().to_string()),
             env: HashMap::new(),
-            description: None,
+            ..Default::default()
         },
     );
 
     // Create a config with the component
     let config = Config {
         components,
-        prompts: HashMap::new(),
-        description: None,
+        ..Default::default()
     };
 
     // Test creating WASI context with volume mounts
@@ -84,15 +135,14 @@ fn test_create_wasi_context_with_invalid_path() {
             volumes: volume_mounts,
             cwd: Some("/tmp".to_string()),
             env: HashMap::new(),
-            description: None,
+            ..Default::default()
         },
     );
 
     // Create a config with the component
     let config = Config {
         components,
-        prompts: HashMap::new(),
-        description: None,
+        ..Default::default()
     };
 
     // Test creating WASI context with invalid volume mounts
@@ -117,15 +167,14 @@ fn test_create_wasi_context_with_empty_mounts() {
             volumes: Vec::new(),
             cwd: Some("/tmp".to_string()),
             env: HashMap::new(),
-            description: None,
+            ..Default::default()
         },
     );
 
     // Create a config with the component
     let config = Config {
         components,
-        prompts: HashMap::new(),
-        description: None,
+        ..Default::default()
     };
 
     // Test creating WASI context with no volume mounts
@@ -145,3 +194,118 @@ fn test_create_wasi_context_with_empty_mounts() {
         "WASI context created successfully with empty volume mounts"
     );
 }
+
+#[test]
+fn test_read_only_mount_permissions() {
+    let mount = VolumeMount {
+        host_path: "/tmp".to_string(),
+        guest_path: "/data".to_string(),
+        read_only: true,
+    };
+    let (dir_perms, file_perms) = mount_permissions(&mount);
+    assert!(dir_perms.contains(DirPerms::READ));
+    assert!(!dir_perms.contains(DirPerms::MUTATE));
+    assert!(file_perms.contains(FilePerms::READ));
+    assert!(!file_perms.contains(FilePerms::WRITE));
+
+    let mount = VolumeMount {
+        read_only: false,
+        ..mount
+    };
+    let (dir_perms, file_perms) = mount_permissions(&mount);
+    assert!(dir_perms.contains(DirPerms::MUTATE));
+    assert!(file_perms.contains(FilePerms::WRITE));
+}
+
+#[tokio::test]
+async fn test_guest_cannot_write_to_read_only_mount() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let volume = temp_dir.path().join("data");
+    std::fs::create_dir(&volume).unwrap();
+    let mount = VolumeMount {
+        host_path: volume.display().to_string(),
+        guest_path: "/data".to_string(),
+        read_only: true,
+    };
+
+    let result = create_file(temp_dir.path(), vec![mount.clone()], None).await;
+    assert!(
+        matches!(result, Err(WasiMcpError::Exit(errno)) if errno != 0),
+        "Guest created a file in a read-only mount: {result:?}"
+    );
+    assert!(!volume.join("new.txt").exists());
+
+    // The same guest may write to a writable mount
+    let writable = VolumeMount {
+        read_only: false,
+        ..mount
+    };
+    let result = create_file(temp_dir.path(), vec![writable], None).await;
+    assert!(result.is_ok(), "Guest failed to create a file: {result:?}");
+    assert!(volume.join("new.txt").exists());
+}
+
+#[tokio::test]
+async fn test_guest_cannot_write_to_read_only_mount_through_cwd() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let volume = temp_dir.path().join("data");
+    let cwd = volume.join("project");
+    std::fs::create_dir_all(&cwd).unwrap();
+    let mount = VolumeMount {
+        host_path: volume.display().to_string(),
+        guest_path: "/data".to_string(),
+        read_only: true,
+    };
+
+    // The working directory is the first preopened directory
+    let result = create_file(temp_dir.path(), vec![mount], Some(&cwd)).await;
+    assert!(
+        matches!(result, Err(WasiMcpError::Exit(errno)) if errno != 0),
+        "Guest created a file through its working directory: {result:?}"
+    );
+    assert!(!cwd.join("new.txt").exists());
+}
+
+#[test]
+fn test_cwd_permissions() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let volume = temp_dir.path().join("data");
+    let inside = volume.join("project");
+    let outside = temp_dir.path().join("other");
+    std::fs::create_dir_all(&inside).unwrap();
+    std::fs::create_dir_all(&outside).unwrap();
+    let mounts = vec![VolumeMount {
+        host_path: volume.display().to_string(),
+        guest_path: "/data".to_string(),
+        read_only: true,
+    }];
+
+    let (dir_perms, file_perms) = cwd_permissions(&inside, &mounts);
+    assert!(!dir_perms.contains(DirPerms::MUTATE));
+    assert!(!file_perms.contains(FilePerms::WRITE));
+    let (dir_perms, file_perms) = cwd_permissions(&outside, &mounts);
+    assert!(dir_perms.contains(DirPerms::MUTATE));
+    assert!(file_perms.contains(FilePerms::WRITE));
+}
+
+#[test]
+fn test_create_wasi_context_with_read_only_mount() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+
+    let component_config = ComponentConfig {
+        path: Some("test.wasm".to_string()),
+        volumes: vec![VolumeMount {
+            host_path: temp_dir.path().to_string_lossy().to_string(),
+            guest_path: "/data".to_string(),
+            read_only: true,
+        }],
+        ..Default::default()
+    };
+
+    let result = create_wasi_context(&component_config);
+    assert!(
+        result.is_ok(),
+        "Failed to create WASI context with read-only mount: {:?}",
+        result.err()
+    );
+}